@@ -1,4 +1,4 @@
-import dis, sys, types, json
+import ast, dis, sys, types, json
 
 
 def to_camel_case(str):
@@ -6,28 +6,70 @@ def to_camel_case(str):
 
 
 def parse_code(code, parsed_code={"instructions": [], "constants": []}):
+    parsed_code["co_name"] = code.co_name
+    if hasattr(code, "co_qualname"):
+        parsed_code["co_qualname"] = code.co_qualname
     parsed_code["co_names"] = code.co_names
     parsed_code["co_varnames"] = code.co_varnames
+    parsed_code["co_argcount"] = code.co_argcount
+    if hasattr(code, "co_posonlyargcount"):
+        parsed_code["co_posonlyargcount"] = code.co_posonlyargcount
+    parsed_code["co_kwonlyargcount"] = code.co_kwonlyargcount
+    parsed_code["co_flags"] = code.co_flags
+    parsed_code["co_cellvars"] = code.co_cellvars
+    parsed_code["co_freevars"] = code.co_freevars
 
-    bytecode = dis.Bytecode(code)
+    if sys.version_info >= (3, 11):
+        # Jumps count the inline caches after specializable instructions, so they are kept
+        # as CACHE instructions for the instruction indices to match the code units.
+        bytecode = dis.Bytecode(code, show_caches=True)
+        parsed_code["co_exceptiontable"] = [
+            {
+                "start": entry.start,
+                "end": entry.end,
+                "target": entry.target,
+                "depth": entry.depth,
+                "lasti": entry.lasti,
+            }
+            for entry in dis._parse_exception_table(code)
+        ]
+    else:
+        bytecode = dis.Bytecode(code)
     for instruction in bytecode:
-        parsed_code["instructions"].append(
-            {to_camel_case(instruction.opname): instruction.arg}
-        )
+        arg = instruction.arg if instruction.opcode >= dis.HAVE_ARGUMENT else None
+        parsed_code["instructions"].append({to_camel_case(instruction.opname): arg})
 
     for constant in code.co_consts:
-        if isinstance(constant, types.CodeType):
-            code = {"instructions": [], "constants": []}
-            parse_code(constant, code)
-            parsed_code["constants"].append({"Frame": code})
-        else:
-            parsed_code["constants"].append({to_camel_case(type(constant).__name__): constant})
+        parsed_code["constants"].append(parse_constant(constant))
 
     return parsed_code
 
 
+def parse_constant(constant):
+    if isinstance(constant, types.CodeType):
+        code = {"instructions": [], "constants": []}
+        parse_code(constant, code)
+        return {"Frame": code}
+    elif isinstance(constant, tuple):
+        return {"Tuple": list(map(parse_constant, constant))}
+    elif isinstance(constant, bytes):
+        return {"Bytes": list(constant)}
+    elif isinstance(constant, frozenset):
+        return {"FrozenSet": list(map(parse_constant, constant))}
+    else:
+        return {to_camel_case(type(constant).__name__): constant}
+
+
 if __name__ == "__main__":
-    with open(sys.argv[1]) as source_file:
+    # With --interactive, expression statements print their repr like they do in the REPL.
+    interactive = "--interactive" in sys.argv[1:]
+    path = [arg for arg in sys.argv[1:] if arg != "--interactive"][0]
+    with open(path) as source_file:
         source = source_file.read()
-    code = compile(source, sys.argv[1], "exec")
-    print(json.dumps(parse_code(code)))
+    if interactive:
+        code = compile(ast.Interactive(body=ast.parse(source).body), path, "single")
+    else:
+        code = compile(source, path, "exec")
+    parsed_code = parse_code(code)
+    parsed_code["python_version"] = list(sys.version_info[:2])
+    print(json.dumps(parsed_code))
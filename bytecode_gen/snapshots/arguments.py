@@ -0,0 +1,13 @@
+def f(a, b, c):
+    return a - b - c
+
+
+def g(a, b):
+    x = a - b
+    return x
+
+
+print(f(10, 3, 1))
+print(f(1, 2, 3))
+print(f(3, 2, 1))
+print(g(5, 2))
@@ -0,0 +1,16 @@
+def area(width, height=2):
+    return width * height
+
+
+class Counter:
+    def __init__(self, start):
+        self.count = start
+
+    def add(self, step):
+        self.count += step
+        return self.count
+
+
+counter = Counter(area(3))
+counter.add(area(height=4, width=1))
+print(counter.add(step=area(2, 3)))
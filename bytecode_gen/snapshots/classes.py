@@ -0,0 +1,12 @@
+class Point:
+    def __init__(self, x, y):
+        self.x = x
+        self.y = y
+
+    def __repr__(self):
+        return f"Point({self.x}, {self.y})"
+
+
+point = Point(1, 2)
+point.x = point.x + point.y
+print(point)
@@ -0,0 +1,11 @@
+tenth = 0.1
+total = tenth + 0.2
+one = 1
+third = one / 3
+difference = one - 2.5
+
+print(total == 0.30000000000000004)
+print(third)
+print(difference)
+print(7.5 // 2)
+print(-7.0 // 2)
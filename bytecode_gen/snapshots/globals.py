@@ -0,0 +1,25 @@
+counter = 0
+
+def increment():
+    global counter
+    counter = counter + 1
+
+def define():
+    global created
+    created = "made in a function"
+
+def read():
+    return counter
+
+increment()
+increment()
+print(counter)
+define()
+print(created)
+print(read())
+
+class Config:
+    global mode
+    mode = "set in a class body"
+
+print(mode)
@@ -0,0 +1,11 @@
+big = 2**40
+
+doubled = 1
+exponent = 0
+while exponent < 40:
+    doubled = doubled * 2
+    exponent += 1
+
+print(big)
+print(doubled == big)
+print(-big // 3)
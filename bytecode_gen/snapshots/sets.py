@@ -0,0 +1,10 @@
+primes = {2, 3, 5, 7}
+evens = {2, 4, 6, 8}
+primes.discard(7)
+
+print(primes & evens)
+print(primes - evens)
+print(5 in {1, 5, 9})
+print(4 not in {1, 5, 9})
+print({n for n in (1, 2, 2, 3)})
+print(frozenset({3, 1}) == {1, 3})
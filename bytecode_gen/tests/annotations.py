@@ -0,0 +1,12 @@
+count: int = 3
+name: str
+
+
+class Point:
+    x: int = 0
+    y: "float"
+
+
+module_annotations = __annotations__
+class_annotations = Point.__annotations__
+class_default = Point.x
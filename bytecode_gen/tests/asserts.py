@@ -0,0 +1,9 @@
+checked = 0
+assert checked == 0
+checked = 1
+
+message = None
+try:
+    assert checked == 2, "checked is " + str(checked)
+except AssertionError as error:
+    message = str(error)
@@ -0,0 +1,15 @@
+from math import factorial
+
+largest = 9223372036854775807
+above = largest + 1
+squared = largest * largest
+back = squared // largest
+negated = -(-largest - 1)
+below = -largest - 2
+f25 = factorial(25)
+ratio = factorial(25) // factorial(23)
+divided = f25 / factorial(24)
+bigger = f25 > largest
+narrowed = above - 1 == largest
+looked_up = {f25: 'f25'}[factorial(25)]
+as_text = str(f25)
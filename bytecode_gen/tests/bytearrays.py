@@ -0,0 +1,10 @@
+data = bytearray(b"abc")
+data.append(100)
+data.extend(b"ef")
+data[0] = 65
+data[1:3] = b"XYZ"
+item = data[2]
+copied = data[:2]
+data += b"!"
+as_bytes = bytes(data)
+empty = bytearray()
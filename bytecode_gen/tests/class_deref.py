@@ -0,0 +1,24 @@
+def make(size):
+    class Box:
+        capacity = size
+        doubled = size * 2
+
+        def get(self):
+            return size
+
+    return Box
+
+
+def shadowed(size):
+    class Box:
+        size = "class attribute"
+        seen = size
+
+    return Box
+
+
+Box = make(3)
+capacity = Box.capacity
+doubled = Box.doubled
+from_method = Box().get()
+seen = shadowed(5).seen
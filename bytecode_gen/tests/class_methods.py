@@ -0,0 +1,37 @@
+class Counter:
+    created = 0
+
+    def __init__(self, start):
+        self.value = start
+
+    @classmethod
+    def starting_at_ten(cls):
+        return cls(10)
+
+    @classmethod
+    def kind(cls):
+        return cls.__name__
+
+    @staticmethod
+    def add(first, second):
+        return first + second
+
+
+class LoudCounter(Counter):
+    @classmethod
+    def kind(cls):
+        return "loud " + super().kind()
+
+
+counter = Counter(1)
+from_class = Counter.starting_at_ten().value
+from_instance = counter.starting_at_ten().value
+kind_from_class = Counter.kind()
+kind_from_instance = counter.kind()
+subclass_kind = LoudCounter.kind()
+subclass_instance_kind = LoudCounter.starting_at_ten().kind()
+static_from_class = Counter.add(2, 3)
+static_from_instance = counter.add(4, 5)
+unwrapped = classmethod(Counter.add).__func__(6, 7)
+
+del counter
@@ -0,0 +1,14 @@
+def shadowed():
+    square = "kept"
+    squares = [square * square for square in [1, 2, 3]]
+    return square, squares
+
+
+def unbound():
+    pairs = [(item, other) for item in [1, 2] for other in [3]]
+    return pairs
+
+
+kept, squares = shadowed()
+pairs = unbound()
+del shadowed, unbound
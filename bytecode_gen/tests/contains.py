@@ -0,0 +1,35 @@
+class Bag:
+    def __init__(self, items):
+        self.items = items
+
+    def __contains__(self, item):
+        return item in self.items
+
+
+class Countdown:
+    def __init__(self, start):
+        self.current = start
+
+    def __iter__(self):
+        return self
+
+    def __next__(self):
+        if self.current == 0:
+            raise StopIteration
+        self.current -= 1
+        return self.current
+
+
+class Opaque:
+    pass
+
+
+bag = Bag(["x", "y"])
+in_bag = "x" in bag
+not_in_bag = "z" not in bag
+in_countdown = 1 in Countdown(3)
+past_countdown = 5 in Countdown(3)
+try:
+    1 in Opaque()
+except TypeError as error:
+    not_iterable = str(error)
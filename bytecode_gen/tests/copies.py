@@ -0,0 +1,45 @@
+import copy
+
+
+class Point:
+    def __init__(self, x, tags):
+        self.x = x
+        self.tags = tags
+
+
+inner = [1, 2]
+outer = [inner, {"key": inner}]
+shallow = copy.copy(outer)
+shallow += [3]
+shallow[0] += [3]
+deep = copy.deepcopy(outer)
+deep[0] += [4]
+deep_shares_inner = deep[1]["key"] is deep[0]
+
+counts = {"a": 1}
+counts_copy = copy.copy(counts)
+counts_copy["b"] = 2
+seen = {1, 2}
+seen_copy = copy.copy(seen)
+seen_copy.add(3)
+buffer = bytearray(b"ab")
+buffer_copy = copy.copy(buffer)
+buffer_copy.append(99)
+
+point = Point(1, ["a"])
+point_copy = copy.copy(point)
+point_copy.x = 2
+point_copy.tags += ["b"]
+point_deep = copy.deepcopy(point)
+point_deep.tags += ["c"]
+point_x = point.x
+point_tags = point.tags
+point_deep_tags = point_deep.tags
+
+cycle = [1]
+cycle += [cycle]
+cycle_copy = copy.deepcopy(cycle)
+cycle_is_new = cycle_copy is not cycle
+cycle_copy_contains_itself = cycle_copy[1] is cycle_copy
+# The test compares the repr of the globals, which can't show a list containing itself.
+del cycle, cycle_copy
@@ -0,0 +1,62 @@
+log = []
+numbers = []
+
+
+class Ready:
+    def __await__(self):
+        value = yield "waiting"
+        return value
+
+
+class Manager:
+    async def __aenter__(self):
+        global log
+        log += ["enter"]
+        return "resource"
+
+    async def __aexit__(self, kind, value, traceback):
+        global log
+        log += ["exit " + str(value)]
+        return False
+
+
+class Counter:
+    def __init__(self):
+        self.count = 0
+
+    def __aiter__(self):
+        return self
+
+    async def __anext__(self):
+        if self.count == 3:
+            raise StopAsyncIteration
+        self.count += 1
+        return self.count
+
+
+async def double(value):
+    return value * 2
+
+
+async def main():
+    global log, numbers
+    log += [await double(21)]
+    log += [await Ready()]
+    async with Manager() as resource:
+        log += [resource]
+    try:
+        async with Manager():
+            raise ValueError("inside")
+    except ValueError as error:
+        log += ["caught " + str(error)]
+    async for number in Counter():
+        numbers += [number]
+    return "finished"
+
+
+coroutine = main()
+waiting = coroutine.send(None)
+try:
+    coroutine.send("sent")
+except StopIteration as stop:
+    result = stop.args
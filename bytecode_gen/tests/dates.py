@@ -0,0 +1,14 @@
+from datetime import date, datetime, timedelta, timezone
+
+step = timedelta(days=1, seconds=5)
+week = timedelta(weeks=1, hours=-1)
+start = datetime(2024, 2, 28, hour=23, second=30)
+later = start + step
+elapsed = later - datetime(2024, 1, 1)
+elapsed_days = elapsed.days
+elapsed_seconds = elapsed.seconds
+leap_year = date(2024, 3, 1) - date(year=2023, month=3, day=1)
+day_before = date(2024, 3, 1) - timedelta(days=1)
+fortnight = week * 2 + timedelta(hours=2)
+total_seconds = step.total_seconds()
+noon_utc = datetime(2024, 1, 1, 12, tzinfo=timezone.utc)
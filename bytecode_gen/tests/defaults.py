@@ -0,0 +1,12 @@
+def greet(name, greeting="Hello", *, punctuation="!"):
+    return greeting + ", " + name + punctuation
+
+
+default = greet("a")
+positional = greet("b", "Hi")
+keyword_only = greet("c", punctuation="?")
+keywords = greet(greeting="Yo", name="d")
+try:
+    greet("a", "b", "c")
+except TypeError as error:
+    too_many = str(error)
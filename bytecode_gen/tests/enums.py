@@ -0,0 +1,23 @@
+from enum import Enum
+
+
+class Color(Enum):
+    RED = 1
+    GREEN = 2
+
+    def describe(self):
+        return self.name + "=" + str(self.value)
+
+
+red = Color.RED
+name = red.name
+value = red.value
+same = Color(1) is Color.RED
+different = Color.RED == Color.GREEN
+equal_to_value = Color.RED == 1
+described = Color.GREEN.describe()
+printed = str(Color.GREEN)
+try:
+    Color(3)
+except ValueError as error:
+    missing = str(error)
@@ -0,0 +1,28 @@
+log = ""
+
+
+class Resource:
+    def __init__(self, name):
+        self.name = name
+
+    def __del__(self):
+        global log
+        log += self.name + ";"
+
+
+def scoped():
+    local = Resource("local")
+    return "returned"
+
+
+first = Resource("first")
+second = Resource("second")
+del first
+after_del = log
+returned = scoped()
+after_return = log
+second = None
+after_rebind = log
+held = [Resource("in list")]
+held = []
+after_list = log
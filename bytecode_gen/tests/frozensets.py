@@ -0,0 +1,20 @@
+frozen = frozenset([1, 2, 3])
+keyed = {frozen: "found"}
+found = keyed[frozenset([3, 2, 1])]
+nested = {frozen, frozenset([1, 2, 3])}
+nested_count = 0
+for item in nested:
+    nested_count += 1
+equal_to_set = frozen == {1, 2, 3}
+union = frozen | frozenset([4])
+union_matches = union == frozenset([1, 2, 3, 4])
+intersection = frozen & frozenset([2, 3, 4])
+difference = frozen - frozenset([1])
+difference_matches = difference == frozenset([2, 3])
+symmetric = frozen ^ frozenset([3, 4])
+symmetric_matches = symmetric == {1, 2, 4}
+contains = 2 in frozen
+total = 0
+for number in frozen:
+    total += number
+literal = 5 in frozenset({5})
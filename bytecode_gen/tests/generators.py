@@ -0,0 +1,101 @@
+log = []
+
+
+def echo():
+    global log
+    try:
+        while True:
+            try:
+                value = yield "ready"
+                log += [value]
+            except ValueError as error:
+                log += ["caught " + str(error)]
+    finally:
+        log += ["closed"]
+
+
+gen = echo()
+started = next(gen)
+sent = gen.send(42)
+thrown = gen.throw(ValueError("bad"))
+gen.close()
+echo_log = log
+log = []
+
+
+def inner():
+    received = yield "inner"
+    return received * 2
+
+
+def outer():
+    doubled = yield from inner()
+    yield doubled
+    yield from (0, 1, 2)
+    yield from [4, 5]
+
+
+delegating = outer()
+delegated = [next(delegating), delegating.send(21)]
+for value in delegating:
+    delegated += [value]
+
+
+def catching():
+    try:
+        yield 1
+    except ValueError:
+        return "handled"
+
+
+def catching_outer():
+    result = yield from catching()
+    yield result
+
+
+gen = catching_outer()
+next(gen)
+thrown_through = gen.throw(ValueError)
+
+
+def closing_inner():
+    global log
+    try:
+        yield 1
+    finally:
+        log += ["inner closed"]
+
+
+def closing_outer():
+    global log
+    try:
+        yield from closing_inner()
+    finally:
+        log += ["outer closed"]
+
+
+gen = closing_outer()
+next(gen)
+gen.close()
+closing_log = log
+
+
+def returns():
+    yield 1
+    return "done"
+
+
+gen = returns()
+next(gen)
+try:
+    next(gen)
+except StopIteration as stop:
+    returned = stop.args
+
+
+def plain():
+    yield from [1, 2, 3]
+    yield from "ab"
+
+
+plain_items = [item for item in plain()]
@@ -0,0 +1,16 @@
+class Recorder:
+    def __getitem__(self, key):
+        return key
+
+
+recorder = Recorder()
+index = recorder[3]
+full = recorder[1:5:2]
+parts = (full.start, full.stop, full.step)
+open_ended = recorder[:2]
+open_parts = (open_ended.start, open_ended.stop, open_ended.step)
+items = [1, 2, 3, 4, 5, 6]
+every_other = items[::2]
+backwards = items[::-1]
+tail = items[-2:]
+word = "python"[1:4]
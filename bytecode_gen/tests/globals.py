@@ -0,0 +1,32 @@
+counter = 0
+
+
+def increment():
+    global counter
+    counter += 1
+
+
+def read():
+    return counter
+
+
+def define():
+    global defined
+    defined = "late"
+
+
+def remove():
+    global defined
+    del defined
+
+
+increment()
+increment()
+seen_by_function = read()
+define()
+after_define = defined
+remove()
+try:
+    defined
+except NameError as error:
+    deleted = str(error)
@@ -0,0 +1,16 @@
+try:
+    raise ValueError("outer")
+except ValueError:
+    try:
+        raise KeyError("inner")
+    except KeyError:
+        pass
+    try:
+        raise
+    except ValueError as error:
+        restored = str(error)
+
+try:
+    {}["missing"]
+except (IndexError, LookupError) as error:
+    caught = str(error)
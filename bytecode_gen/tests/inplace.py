@@ -0,0 +1,28 @@
+class Vector:
+    def __init__(self, x, y):
+        self.x = x
+        self.y = y
+
+    def __iadd__(self, other):
+        self.x += other.x
+        self.y += other.y
+        return self
+
+    def __sub__(self, other):
+        return Vector(self.x - other.x, self.y - other.y)
+
+    def __rmul__(self, factor):
+        return Vector(factor * self.x, factor * self.y)
+
+
+vector = Vector(1, 2)
+original = vector
+vector += Vector(10, 20)
+added = (vector.x, vector.y)
+same_object = vector is original
+vector -= Vector(1, 1)
+subtracted = (vector.x, vector.y)
+new_object = vector is not original
+scaled = 3
+scaled *= vector
+multiplied = (scaled.x, scaled.y)
@@ -0,0 +1,7 @@
+from math import *
+
+flag = True
+positive = +flag
+items = [1, 2]
+unpacked = (*items, 3)
+circle = floor(pi * 10)
@@ -0,0 +1,14 @@
+def window(title, *, width, height=10):
+    return title + ":" + str(width) + "x" + str(height)
+
+
+named = window("main", width=80)
+overridden = window("main", height=5, width=80)
+try:
+    window("main", 80)
+except TypeError as error:
+    passed_positionally = str(error)
+try:
+    window("main")
+except TypeError as error:
+    missing = str(error)
@@ -0,0 +1,15 @@
+items = [1, 2, 3]
+first = items[0]
+last = items[-1]
+items[1] = 20
+items[-1] = 30
+joined = items + [4]
+repeated = [0] * 3
+equal = [1, 2] == [1, 2]
+ordered = [1, 2] < [1, 3]
+word = "hello"
+letter = word[-2]
+try:
+    items[3]
+except IndexError as error:
+    out_of_range = str(error)
@@ -0,0 +1,27 @@
+class Point:
+    __match_args__ = ("x", "y")
+
+    def __init__(self, x, y):
+        self.x = x
+        self.y = y
+
+
+def describe(value):
+    match value:
+        case Point(0, 0):
+            return "origin"
+        case Point(x, 0):
+            return "on the x axis at " + str(x)
+        case Point(x=0, y=y):
+            return "on the y axis at " + str(y)
+        case Point():
+            return "a point"
+        case _:
+            return "something else"
+
+
+origin = describe(Point(0, 0))
+on_x = describe(Point(3, 0))
+on_y = describe(Point(0, 4))
+point = describe(Point(1, 2))
+other = describe("text")
@@ -0,0 +1,14 @@
+buffer = bytearray(b"hello world")
+view = memoryview(buffer)
+word = view[6:]
+word[0] = 87
+changed = buffer
+first = view[0]
+sliced = word[1:3].tobytes()
+as_list = view[:5].tolist()
+sizes = (view.nbytes, word.nbytes, view.itemsize, view.format)
+readonly = memoryview(b"fixed").readonly
+try:
+    memoryview(b"fixed")[0] = 70
+except TypeError as error:
+    write_error = str(error)
@@ -0,0 +1,20 @@
+class Plain:
+    pass
+
+
+class Child(Plain):
+    def __init__(self):
+        super().__init__()
+        self.ready = True
+
+
+plain = Plain()
+child = Child()
+bases = Plain.__bases__
+mro = Child.__mro__
+equal_to_itself = plain == plain
+equal_to_another = plain == Plain()
+not_equal = plain != Plain()
+same_hash = hash(plain) == hash(plain)
+described = plain.__repr__()[:28] == "<__main__.Plain object at 0x"
+ready = child.ready
@@ -0,0 +1,15 @@
+def greet(greeting, name, /, punctuation="!"):
+    return greeting + ", " + name + punctuation
+
+
+def echo(name, /, **options):
+    return name + "/" + options["name"]
+
+
+positional = greet("Hi", "Ann")
+by_keyword = greet("Hi", "Ann", punctuation="?")
+shared_name = echo("first", name="second")
+try:
+    greet("Hi", name="Ann")
+except TypeError as error:
+    passed_by_keyword = str(error)
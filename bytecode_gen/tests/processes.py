@@ -0,0 +1,17 @@
+import process
+
+
+def total(limit):
+    result = 0
+    number = 0
+    while number < limit:
+        result += number
+        number += 1
+    return result
+
+
+worker = process.Process(target=total, args=(1000,))
+worker.start()
+worker.join()
+print(worker.exitcode)
+print(worker.result)
@@ -0,0 +1,48 @@
+class Temperature:
+    def __init__(self, celsius):
+        self.celsius = celsius
+        self.deleted = False
+
+    @property
+    def fahrenheit(self):
+        """The temperature in fahrenheit."""
+        return self.celsius * 9 // 5 + 32
+
+    @fahrenheit.setter
+    def fahrenheit(self, value):
+        self.celsius = (value - 32) * 5 // 9
+
+    @fahrenheit.deleter
+    def fahrenheit(self):
+        self.deleted = True
+
+    def get_kelvin(self):
+        return self.celsius + 273
+
+    kelvin = property(get_kelvin, doc="The temperature in kelvin.")
+
+
+temperature = Temperature(100)
+boiling = temperature.fahrenheit
+temperature.fahrenheit = 32
+freezing = temperature.celsius
+del temperature.fahrenheit
+deleted = temperature.deleted
+kelvin = temperature.kelvin
+kelvin_doc = Temperature.kelvin.__doc__
+fahrenheit_doc = Temperature.fahrenheit.__doc__
+has_getter = Temperature.fahrenheit.fget is not None
+
+try:
+    temperature.kelvin = 0
+except AttributeError as error:
+    read_only = str(error)
+
+temperature.extra = 1
+del temperature.extra
+try:
+    temperature.extra
+except AttributeError as error:
+    deleted_extra = str(error)
+
+del temperature
@@ -0,0 +1,17 @@
+class Shape:
+    def area(self):
+        return 0
+
+
+def outer():
+    def inner():
+        pass
+    return inner
+
+
+method = Shape.area.__qualname__
+nested = outer().__qualname__
+try:
+    Shape().area(1)
+except TypeError as error:
+    too_many = str(error)
@@ -0,0 +1,35 @@
+log = ""
+
+try:
+    try:
+        raise ValueError("from the body")
+    finally:
+        log += "finally;"
+except ValueError as error:
+    after_finally = str(error)
+
+try:
+    try:
+        raise ValueError("unmatched")
+    except TypeError:
+        log += "wrong handler;"
+except ValueError as error:
+    unmatched = str(error)
+
+try:
+    try:
+        raise ValueError("bare raise")
+    except ValueError:
+        log += "handled;"
+        raise
+except ValueError as error:
+    bare = str(error)
+
+try:
+    try:
+        raise ValueError("in the handler")
+    except ValueError as inner:
+        log += "named;"
+        raise
+except ValueError as error:
+    named = str(error)
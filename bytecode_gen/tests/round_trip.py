@@ -0,0 +1,20 @@
+def counter(start, step=1):
+    count = start
+
+    def advance():
+        nonlocal count
+        count += step
+        return count
+
+    return advance
+
+
+totals = []
+for limit in (3, 5):
+    advance = counter(0)
+    while advance() < limit:
+        pass
+    totals += [limit]
+names = {"a": b"bytes", "b": frozenset({1}), "c": (1.5, None, True)}
+# Functions are shown with their address, which differs between runs.
+del counter, advance
@@ -0,0 +1,31 @@
+class Point:
+    __slots__ = ("x", "y")
+
+    def __init__(self, x, y):
+        self.x = x
+        self.y = y
+
+
+point = Point(1, 2)
+point.x = 10
+coordinates = (point.x, point.y)
+
+try:
+    point.z = 3
+except AttributeError as error:
+    not_a_slot = str(error)
+
+del point.y
+try:
+    point.y
+except AttributeError as error:
+    unset_slot = str(error)
+
+
+class Point3D(Point):
+    __slots__ = ("z",)
+
+
+point = Point3D(4, 5)
+point.z = 6
+inherited = (point.x, point.y, point.z)
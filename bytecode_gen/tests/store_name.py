@@ -0,0 +1,11 @@
+mode = "unset"
+level = "module"
+
+
+class Config:
+    global mode
+    mode = "set in a class body"
+    level = "class"
+
+
+class_level = Config.level
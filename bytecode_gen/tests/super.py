@@ -0,0 +1,43 @@
+class Shape:
+    def __init__(self, name):
+        self.name = name
+
+    def describe(self):
+        return "shape " + self.name
+
+
+class Polygon(Shape):
+    def __init__(self, name, sides):
+        super().__init__(name)
+        self.sides = sides
+
+    def describe(self):
+        return super().describe() + " with sides"
+
+
+class Square(Polygon):
+    def __init__(self):
+        super(Square, self).__init__("square", 4)
+
+    def describe(self):
+        return "square, " + super().describe()
+
+
+square = Square()
+name = square.name
+sides = square.sides
+description = square.describe()
+polygon_description = super(Square, square).describe()
+shape_description = super(Polygon, square).describe()
+
+try:
+    super(Square, 1)
+except TypeError as error:
+    wrong_object = str(error)
+
+try:
+    super()
+except RuntimeError as error:
+    outside_method = str(error)
+
+del square
@@ -0,0 +1,7 @@
+def sum(n, acc=0):
+    if n == 0:
+        return acc
+    return sum(n - 1, acc + n)
+
+
+total = sum(100_000)
@@ -0,0 +1,32 @@
+import threading
+
+results = []
+
+
+def record(first, second, label="-"):
+    global results
+    results += [(label, first, second)]
+
+
+named = threading.Thread(target=record, name="named", args=(1, 2))
+with_kwargs = threading.Thread(None, record, None, (3, 4), {"label": "kw"})
+daemon = threading.Thread(target=record, args=[5, 6], kwargs={"label": "d"}, daemon=True)
+for thread in [named, with_kwargs, daemon]:
+    thread.start()
+    thread.join()
+name = named.name
+default_name = with_kwargs.name
+is_daemon = daemon.daemon
+try:
+    threading.Thread(target=record, colour="red")
+except TypeError as error:
+    unexpected = str(error)
+try:
+    threading.Thread(None, record, target=record)
+except TypeError as error:
+    twice = str(error)
+try:
+    threading.Lock(blocking=True)
+except TypeError as error:
+    no_keywords = str(error)
+del thread
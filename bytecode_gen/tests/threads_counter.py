@@ -0,0 +1,21 @@
+import threading
+
+counter = 0
+lock = threading.Lock()
+
+
+def increment(times):
+    global counter
+    while times > 0:
+        with lock:
+            counter += 1
+        times -= 1
+
+
+first = threading.Thread(target=increment, args=(1000,))
+second = threading.Thread(target=increment, args=(1000,))
+first.start()
+second.start()
+first.join()
+second.join()
+del first, second
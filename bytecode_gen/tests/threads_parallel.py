@@ -0,0 +1,19 @@
+import threading
+
+lock = threading.Lock()
+
+
+def wait_for_lock(name):
+    with lock:
+        print(name + " acquired the lock")
+
+
+lock.acquire()
+waiting = threading.Thread(target=wait_for_lock, args=("waiting",))
+waiting.start()
+waiting.join(0.05)
+alive_while_locked = waiting.is_alive()
+lock.release()
+waiting.join()
+alive_after_release = waiting.is_alive()
+del waiting
@@ -0,0 +1,31 @@
+class Flag:
+    def __init__(self, value):
+        self.value = value
+
+    def __bool__(self):
+        return self.value
+
+
+class Sized:
+    def __len__(self):
+        return 0
+
+
+class Plain:
+    pass
+
+
+if Flag(False):
+    when_false = "taken"
+else:
+    when_false = "skipped"
+when_true = "taken" if Flag(True) else "skipped"
+chosen = Flag(False) or "fallback"
+both = Flag(True) and "second"
+empty = "taken" if Sized() else "skipped"
+plain = "taken" if Plain() else "skipped"
+count = 0
+flag = Flag(True)
+while flag:
+    count += 1
+    flag = Flag(count < 3)
@@ -0,0 +1,15 @@
+def pair():
+    return 1, 2
+
+
+point = (3, 4, 5)
+x = point[0]
+z = point[-1]
+a, b = pair()
+c, d = [6, 7]
+equal = (1, 2) == pair()
+unequal = (1, 2) == (1, 3)
+try:
+    point[0] = 10
+except TypeError as error:
+    immutable = str(error)
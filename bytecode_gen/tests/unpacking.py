@@ -0,0 +1,14 @@
+first = [1, 2]
+second = (3, 4)
+extended = [*first, *second, 5]
+joined = {*first, *second}
+defaults = {"a": 1, "b": 2}
+merged = {**defaults, "b": 3, **{"c": 4}}
+spread = (*first, *"xy")
+
+
+def collect(*args, **kwargs):
+    return args, kwargs
+
+
+called = collect(*first, 0, *second, **defaults, z=9)
@@ -0,0 +1,15 @@
+def collect(first, *rest):
+    return rest
+
+
+def total(*numbers):
+    result = 0
+    for number in numbers:
+        result += number
+    return result
+
+
+extra = collect(1, 2, 3)
+none_extra = collect(1)
+summed = total(1, 2, 3, 4)
+spread = total(*[5, 6], 7)
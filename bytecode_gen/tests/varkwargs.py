@@ -0,0 +1,16 @@
+def options(name, **rest):
+    return rest
+
+
+def describe(**settings):
+    return settings["color"] + " " + settings["size"]
+
+
+extra = options("box", width=3, depth=4)
+none_extra = options(name="box")
+described = describe(size="large", color="red")
+spread = describe(**{"color": "blue"}, size="small")
+try:
+    options("box", name="again")
+except TypeError as error:
+    twice = str(error)
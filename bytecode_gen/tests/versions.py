@@ -0,0 +1,77 @@
+def greet(name, greeting="Hello"):
+    return greeting + ", " + name + "!"
+
+
+def make_counter(start):
+    count = start
+
+    def increment(step=1):
+        nonlocal count
+        count += step
+        return count
+
+    return increment
+
+
+def squares(limit):
+    value = 0
+    while value < limit:
+        yield value * value
+        value += 1
+
+
+class Animal:
+    def __init__(self, name):
+        self.name = name
+
+    def speak(self):
+        return self.name + " makes a sound"
+
+
+class Dog(Animal):
+    def speak(self):
+        return super().speak() + ", woof"
+
+
+def classify(number):
+    if number < 0:
+        return "negative"
+    elif number == 0:
+        return "zero"
+    elif number // 2 * 2 == number and number > 10:
+        return "big even"
+    return "positive"
+
+
+def safe_divide(first, second):
+    try:
+        return first // second
+    except ZeroDivisionError as error:
+        return str(error)
+    finally:
+        pass
+
+
+greeting = greet("world")
+keyword_greeting = greet(greeting="Hi", name="there")
+counter = make_counter(10)
+counter()
+count = counter(5)
+square_list = [square for square in squares(5)]
+halves = {square // 2 for square in square_list if square < 9}
+speech = Dog("Rex").speak()
+classes = [classify(number) for number in [-1, 0, 12, 7]]
+quotient = safe_divide(7, 2)
+division_error = safe_divide(1, 0)
+total = 0
+for index, number in [(0, 1), (1, 2), (2, 3)]:
+    if number == 2:
+        continue
+    total += index * number
+first, second = (1, 2)
+formatted = f"{total:>4}|{count!r}"
+negated = -total
+chained = 1 < total < 10
+ternary = "yes" if total > 3 else "no"
+sliced = square_list[1:3]
+del counter, greet, make_counter, squares, Animal, Dog, classify, safe_divide
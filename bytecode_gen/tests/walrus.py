@@ -0,0 +1,17 @@
+values = [3, 8, 1, 9]
+if (first := values[0]) > 2:
+    size = "big first"
+doubled = [double for value in values if (double := value * 2) > 5]
+last_double = double
+total = 0
+index = 0
+while (item := values[index]) != 1:
+    total += item
+    index += 1
+
+
+def scoped():
+    return [(inner := value) for value in (1, 2)], inner
+
+
+in_function = scoped()
@@ -0,0 +1,20 @@
+import weakref
+
+
+class Node:
+    def __init__(self, name):
+        self.name = name
+
+
+node = Node("first")
+reference = weakref.ref(node)
+proxy = weakref.proxy(node)
+alive = reference() is node
+through_proxy = proxy.name
+node = Node("second")
+dead = reference()
+try:
+    proxy.name
+    proxy_error = None
+except ReferenceError as error:
+    proxy_error = str(error)
@@ -0,0 +1,29 @@
+try:
+    with open("<path>", "w") as file:
+        file.write("before")
+        raise ValueError("inside")
+except ValueError as error:
+    caught = str(error)
+closed = file.closed
+
+try:
+    file.write("after")
+except ValueError as error:
+    write_error = str(error)
+
+with open("<path>") as file:
+    contents = file.read()
+
+
+class Suppress:
+    def __enter__(self):
+        return "entered"
+
+    def __exit__(self, kind, value, traceback):
+        global exited
+        exited = str(value)
+        return True
+
+
+with Suppress() as entered:
+    raise ValueError("suppressed")
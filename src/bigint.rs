@@ -0,0 +1,116 @@
+//! Ints too big for an `i64`. Results that fit in one are always narrowed back to
+//! `Value::Int`, so a `Value::BigInt` is never equal to a `Value::Int`.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+use crate::{Value, VmError};
+
+/// `number` as an `Int` when it fits in one, and as a `BigInt` otherwise.
+pub(crate) fn narrow(number: BigInt) -> Value {
+    match i64::try_from(&number) {
+        Ok(number) => Value::Int(number),
+        Err(_) => Value::BigInt(Rc::new(number)),
+    }
+}
+
+/// `value` as a big int when it is an int or a bool.
+fn widen(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Int(number) => Some(BigInt::from(*number)),
+        Value::Bool(value) => Some(BigInt::from(*value as i64)),
+        Value::BigInt(number) => Some((**number).clone()),
+        _ => None,
+    }
+}
+
+pub(crate) fn to_f64(number: &BigInt) -> Result<f64, VmError> {
+    number.to_f64().filter(|number| number.is_finite())
+        .ok_or_else(|| VmError::OverflowError(String::from("int too large to convert to float")))
+}
+
+/// `first // second`, rounding towards negative infinity like ints do.
+pub(crate) fn floor_divide(first: BigInt, second: BigInt) -> BigInt {
+    let quotient = &first / &second;
+    if !(&first % &second).is_zero() && first.is_negative() != second.is_negative() {
+        quotient - 1
+    } else {
+        quotient
+    }
+}
+
+/// The binary operator `name` when an operand is a big int, `None` when neither is.
+pub(crate) fn operation(name: &str, first: &Value, second: &Value) -> Option<Result<Value, VmError>> {
+    if !matches!(first, Value::BigInt(_)) && !matches!(second, Value::BigInt(_)) {
+        return None;
+    }
+    let unsupported = || VmError::TypeError(format!(
+        "unsupported operand type(s) for {}: '{}' and '{}'", symbol(name), first.type_name(), second.type_name()
+    ));
+
+    Some(match (widen(first), widen(second), first, second) {
+        (Some(first), Some(second), ..) => match name {
+            "add" => Ok(narrow(first + second)),
+            "sub" => Ok(narrow(first - second)),
+            "mul" => Ok(narrow(first * second)),
+            "truediv" if second.is_zero() => Err(VmError::ZeroDivisionError(String::from("division by zero"))),
+            "truediv" => to_f64(&first).and_then(|first| Ok(Value::Float(first / to_f64(&second)?))),
+            "floordiv" if second.is_zero() => Err(VmError::ZeroDivisionError(String::from("integer division or modulo by zero"))),
+            "floordiv" => Ok(narrow(floor_divide(first, second))),
+            "and" => Ok(narrow(first & second)),
+            "or" => Ok(narrow(first | second)),
+            "xor" => Ok(narrow(first ^ second)),
+            _ => Err(unsupported()),
+        },
+        (Some(number), None, _, Value::Float(float)) | (None, Some(number), Value::Float(float), _) => {
+            let number = match to_f64(&number) {
+                Ok(number) => number,
+                Err(error) => return Some(Err(error)),
+            };
+            let (first, second) = if matches!(first, Value::Float(_)) { (*float, number) } else { (number, *float) };
+            match name {
+                "add" => Ok(Value::Float(first + second)),
+                "sub" => Ok(Value::Float(first - second)),
+                "mul" => Ok(Value::Float(first * second)),
+                "truediv" if second == 0.0 => Err(VmError::ZeroDivisionError(String::from("float division by zero"))),
+                "truediv" => Ok(Value::Float(first / second)),
+                "floordiv" if second == 0.0 => Err(VmError::ZeroDivisionError(String::from("float divmod()"))),
+                "floordiv" => Ok(Value::Float((first / second).floor())),
+                _ => Err(unsupported()),
+            }
+        },
+        _ => Err(unsupported()),
+    })
+}
+
+fn symbol(name: &str) -> &str {
+    match name {
+        "add" => "+",
+        "sub" => "-",
+        "mul" => "*",
+        "truediv" => "/",
+        "floordiv" => "//",
+        "and" => "&",
+        "or" => "|",
+        _ => "^",
+    }
+}
+
+/// Orders a big int against another int or a float, `None` when `second` is neither.
+pub(crate) fn compare(first: &BigInt, second: &Value) -> Option<Ordering> {
+    match second {
+        Value::Float(second) if second.is_nan() => None,
+        Value::Float(second) if second.is_infinite() => Some(if *second > 0.0 { Ordering::Less } else { Ordering::Greater }),
+        // Every float that isn't a whole number lies between two ints, which compare the same.
+        Value::Float(second) => Some(first.cmp(&BigInt::from_f64(second.floor()).unwrap()).then(if second.fract() == 0.0 {
+            Ordering::Equal
+        } else {
+            Ordering::Less
+        })),
+        _ => widen(second).map(|second| first.cmp(&second)),
+    }
+}
@@ -0,0 +1,157 @@
+//! `bytearray`, the mutable `bytes`. Like lists, copies of a bytearray value share it, so a
+//! change made through one is seen through all of them and through memoryviews of it.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::modules::{bound_method, expect_args};
+use crate::slice::Slice;
+use crate::{byte_items, byte_value, bytes_from, check_encoding, decode_utf8, NativeFn, Value, Vm, VmError};
+
+type Bytes = Rc<RefCell<Vec<u8>>>;
+
+/// The `bytearray([source])` built-in, which takes the same sources as `bytes()`.
+pub(crate) fn builtin_bytearray(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("bytearray", &args, 0, 1)?;
+    Ok(Value::ByteArray(Rc::new(RefCell::new(bytes_from(args.first())?))))
+}
+
+/// `bytearray[index] = value`, a byte for an int index and the bytes of a bytes-like object or
+/// a sequence of ints for a slice. Slices with a step have to be given as many bytes as they
+/// select, others are replaced by however many there are.
+pub(crate) fn set_item(bytes: &Bytes, index: &Value, value: &Value) -> Result<(), VmError> {
+    let slice = match index {
+        Value::Slice(slice) => slice,
+        index => {
+            let position = position(bytes.borrow().len(), index)?;
+            bytes.borrow_mut()[position] = byte(value)?;
+            return Ok(());
+        },
+    };
+    let items = byte_items(value).unwrap_or_else(|| Err(VmError::TypeError(format!(
+        "can assign only bytes, buffers, or iterables of ints in range(0, 256), not {}", value.python_repr()
+    ))))?;
+    replace_slice(&mut bytes.borrow_mut(), slice, items)
+}
+
+fn replace_slice(bytes: &mut Vec<u8>, slice: &Slice, items: Vec<u8>) -> Result<(), VmError> {
+    let (start, stop, step) = slice.indices(bytes.len() as i64)?;
+    if step == 1 {
+        let stop = stop.max(start);
+        bytes.splice(start as usize..stop as usize, items);
+        return Ok(());
+    }
+
+    let positions = slice.select(&(0..bytes.len()).collect::<Vec<_>>())?;
+    if positions.len() != items.len() {
+        return Err(VmError::ValueError(format!(
+            "attempt to assign bytes of size {} to extended slice of size {}", items.len(), positions.len()
+        )));
+    }
+    for (position, item) in positions.into_iter().zip(items) {
+        bytes[position] = item;
+    }
+    Ok(())
+}
+
+/// A single byte given as an int, for `append()` and the other methods that take one.
+fn byte(value: &Value) -> Result<u8, VmError> {
+    byte_value(value).map_err(|error| match error {
+        VmError::ValueError(_) => VmError::ValueError(String::from("byte must be in range(0, 256)")),
+        error => error,
+    })
+}
+
+/// Where `index` is in a bytearray of `len` bytes, counting negative indices from the end.
+fn position(len: usize, index: &Value) -> Result<usize, VmError> {
+    let index = match index {
+        Value::Int(index) => *index,
+        Value::Bool(index) => i64::from(*index),
+        other => return Err(VmError::TypeError(format!("bytearray indices must be integers or slices, not {}", other.type_name()))),
+    };
+    let position = if index < 0 { index + len as i64 } else { index };
+    if position < 0 || position >= len as i64 {
+        return Err(VmError::IndexError(String::from("bytearray index out of range")));
+    }
+    Ok(position as usize)
+}
+
+pub(crate) fn get_attr(bytes: &Bytes, name: &str) -> Option<Value> {
+    let method: NativeFn = match name {
+        "append" => |_, args| {
+            expect_args("append", &args[1..], 1, 1)?;
+            let byte = byte(&args[1])?;
+            receiver(&args).borrow_mut().push(byte);
+            Ok(Value::Nonetype)
+        },
+        "extend" => |_, args| {
+            expect_args("extend", &args[1..], 1, 1)?;
+            let items = byte_items(&args[1]).unwrap_or_else(|| {
+                Err(VmError::TypeError(format!("can't extend bytearray with {}", args[1].python_repr())))
+            })?;
+            receiver(&args).borrow_mut().extend(items);
+            Ok(Value::Nonetype)
+        },
+        "pop" => |_, args| {
+            expect_args("pop", &args[1..], 0, 1)?;
+            let bytes = receiver(&args);
+            let len = bytes.borrow().len();
+            if len == 0 {
+                return Err(VmError::IndexError(String::from("pop from empty bytearray")));
+            }
+            let position = match args.get(1) {
+                Some(index) => position(len, index).map_err(|_| VmError::IndexError(String::from("pop index out of range")))?,
+                None => len - 1,
+            };
+            let byte = bytes.borrow_mut().remove(position);
+            Ok(Value::Int(i64::from(byte)))
+        },
+        "insert" => |_, args| {
+            expect_args("insert", &args[1..], 2, 2)?;
+            let bytes = receiver(&args);
+            let len = bytes.borrow().len() as i64;
+            let index = match &args[1] {
+                Value::Int(index) => *index,
+                Value::Bool(index) => i64::from(*index),
+                other => return Err(VmError::TypeError(format!("\'{}\' object cannot be interpreted as an integer", other.type_name()))),
+            };
+            // Like `list.insert`, indices past either end insert at that end.
+            let position = if index < 0 { (index + len).max(0) } else { index.min(len) };
+            let byte = byte(&args[2])?;
+            bytes.borrow_mut().insert(position as usize, byte);
+            Ok(Value::Nonetype)
+        },
+        "remove" => |_, args| {
+            expect_args("remove", &args[1..], 1, 1)?;
+            let byte = byte(&args[1])?;
+            let bytes = receiver(&args);
+            let position = bytes.borrow().iter().position(|item| *item == byte)
+                .ok_or_else(|| VmError::ValueError(String::from("value not found in bytearray")))?;
+            bytes.borrow_mut().remove(position);
+            Ok(Value::Nonetype)
+        },
+        "clear" => |_, args| {
+            expect_args("clear", &args[1..], 0, 0)?;
+            receiver(&args).borrow_mut().clear();
+            Ok(Value::Nonetype)
+        },
+        "copy" => |_, args| {
+            expect_args("copy", &args[1..], 0, 0)?;
+            Ok(Value::ByteArray(Rc::new(RefCell::new(receiver(&args).borrow().clone()))))
+        },
+        "decode" => |_, args| {
+            check_encoding("decode", &args)?;
+            decode_utf8(&receiver(&args).borrow())
+        },
+        _ => return None,
+    };
+
+    Some(bound_method(Value::ByteArray(Rc::clone(bytes)), name, method))
+}
+
+fn receiver(args: &[Value]) -> &Bytes {
+    match &args[0] {
+        Value::ByteArray(bytes) => bytes,
+        _ => unreachable!("bytearray methods are only reachable through a bound bytearray"),
+    }
+}
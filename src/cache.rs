@@ -0,0 +1,50 @@
+//! `--cache-dir`: keeps decoded bytecode files, so later runs of an unchanged file skip reading
+//! the header and rewriting its jumps. Entries are named after the SHA-256 of the bytecode file.
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Frame, PythonVersion};
+
+/// A cached frame, which is a `&Frame` when it is written.
+#[derive(Deserialize, Serialize)]
+struct Entry<F> {
+    /// The `--version` the frame was decoded with, `None` if it used the file's own.
+    python_version: Option<PythonVersion>,
+    frame: F,
+}
+
+pub(crate) struct Cache {
+    path: PathBuf,
+}
+
+impl Cache {
+    /// The cache entry of the bytecode file `source` in `dir`.
+    pub(crate) fn new(dir: &str, source: &str) -> Cache {
+        let hash = Sha256::digest(source.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        Cache { path: [dir, &format!("{}.cache", hash)].iter().collect() }
+    }
+
+    /// The decoded frame, if it was cached for the same `--version`. Unreadable entries are
+    /// treated as missing and replaced by `store`.
+    pub(crate) fn load(&self, python_version: Option<PythonVersion>) -> Option<Frame> {
+        let contents = fs::read_to_string(&self.path).ok()?;
+        let entry: Entry<Frame> = serde_json::from_str(&contents).ok()?;
+        if entry.python_version != python_version {
+            return None;
+        }
+        Some(entry.frame)
+    }
+
+    pub(crate) fn store(&self, python_version: Option<PythonVersion>, frame: &Frame) -> Result<(), String> {
+        let entry = Entry { python_version, frame };
+        let contents = serde_json::to_string(&entry).map_err(|error| error.to_string())?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|error| format!("Can't create {}: {}", dir.display(), error))?;
+        }
+        fs::write(&self.path, contents).map_err(|error| format!("Can't write {}: {}", self.path.display(), error))
+    }
+}
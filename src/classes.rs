@@ -0,0 +1,849 @@
+//! Classes defined by Python code, their instances, and `object`, the class at the root of
+//! every class hierarchy.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+use crate::modules::{bound_method, expect_args, r#enum};
+use crate::exceptions::Exception;
+use crate::{bind_cells, Frame, FrameState, FrameType, NativeFn, Value, Vm, VmError};
+
+thread_local! {
+    /// Instances whose `__del__` is waiting to run, see `Instance::drop`.
+    static FINALIZERS: RefCell<Vec<Rc<Instance>>> = const { RefCell::new(Vec::new()) };
+
+    static OBJECT_CLASS: Rc<Class> = {
+        let functions: Vec<(&str, NativeFn)> = vec![
+            ("__init__", object_init),
+            ("__repr__", |_, args| Ok(Value::Str(args[0].python_repr()))),
+            ("__str__", |vm, args| vm.python_repr(&args[0]).map(Value::Str)),
+            ("__eq__", |_, args| Ok(Value::Bool(is_same_object(&args[0], &args[1])))),
+            ("__ne__", |_, args| Ok(Value::Bool(!is_same_object(&args[0], &args[1])))),
+            ("__hash__", |_, args| args[0].python_hash().map(|hash| Value::Int(i64::from(hash)))),
+        ];
+        let mut dict = HashMap::new();
+        for (name, function) in functions {
+            let name = Rc::new(String::from(name));
+            dict.insert(Rc::clone(&name), Value::NativeFunction(name, function, &[]));
+        }
+
+        Rc::new(Class {
+            name: Rc::new(String::from("object")),
+            module: Rc::new(String::from("builtins")),
+            bases: vec![],
+            mro: vec![],
+            dict: RefCell::new(dict),
+            layout: vec![],
+            has_dict: false,
+        })
+    };
+}
+
+/// The binary operators instances can define, by the name of their methods.
+const BINARY_OPERATORS: [(&str, &str); 8] = [
+    ("add", "+"), ("sub", "-"), ("mul", "*"), ("truediv", "/"), ("floordiv", "//"), ("and", "&"), ("or", "|"), ("xor", "^"),
+];
+
+/// `object`, the last class of every method resolution order.
+pub(crate) fn object_class() -> Rc<Class> {
+    OBJECT_CLASS.with(Rc::clone)
+}
+
+/// An empty class deriving from `object`, for classes of built-in modules that Python code
+/// subclasses.
+pub(crate) fn native_class(name: &str, module: &str) -> Rc<Class> {
+    Rc::new(Class {
+        name: Rc::new(String::from(name)),
+        module: Rc::new(String::from(module)),
+        bases: vec![object_class()],
+        mro: vec![object_class()],
+        dict: RefCell::new(HashMap::new()),
+        layout: vec![],
+        has_dict: true,
+    })
+}
+
+pub(crate) struct Class {
+    pub(crate) name: Rc<String>,
+    /// The `__module__` of the class, `builtins` for `object`.
+    module: Rc<String>,
+    bases: Vec<Rc<Class>>,
+    /// The method resolution order after the class itself, which always ends with `object`.
+    mro: Vec<Rc<Class>>,
+    pub(crate) dict: RefCell<HashMap<Rc<String>, Value>>,
+    /// Every slot of the instances, the ones of the bases first.
+    layout: Vec<Rc<String>>,
+    /// Whether instances have a `__dict__`, which they do unless every class in the MRO
+    /// declares `__slots__`.
+    has_dict: bool,
+}
+
+impl Class {
+    /// The name `repr()` shows, qualified with the module unless it is a built-in.
+    fn qualified_name(&self) -> String {
+        match self.module.as_str() {
+            "builtins" => self.name.to_string(),
+            module => format!("{}.{}", module, self.name),
+        }
+    }
+
+    /// The class itself followed by `mro`, `__mro__` in Python.
+    fn full_mro(self: &Rc<Self>) -> Vec<Rc<Class>> {
+        let mut mro = vec![Rc::clone(self)];
+        mro.extend(self.mro.iter().cloned());
+        mro
+    }
+
+    /// `name` looked up on the class and then on its bases, in method resolution order.
+    pub(crate) fn lookup(self: &Rc<Self>, name: &str) -> Option<Value> {
+        self.full_mro().iter().find_map(|class| class.dict.borrow().get(&String::from(name)).cloned())
+    }
+
+    /// Whether the class is `base` or derives from it.
+    pub(crate) fn is_subclass(self: &Rc<Self>, base: &Rc<Class>) -> bool {
+        self.full_mro().iter().any(|class| Rc::ptr_eq(class, base))
+    }
+
+    /// Whether the class defines `__del__`. Instances can be dropped while a class dict is
+    /// being assigned to, so a dict that is borrowed is skipped rather than waited for.
+    fn has_finalizer(self: &Rc<Self>) -> bool {
+        self.full_mro().iter().any(|class| {
+            class.dict.try_borrow().is_ok_and(|dict| dict.contains_key(&String::from("__del__")))
+        })
+    }
+
+    pub(crate) fn get_attr(self: &Rc<Self>, name: &Rc<String>) -> Option<Value> {
+        match name.as_str() {
+            "__name__" => Some(Value::Str(self.name.to_string())),
+            "__module__" => Some(Value::Str(self.module.to_string())),
+            "__bases__" => Some(Value::Tuple(self.bases.iter().cloned().map(Value::Class).collect())),
+            "__mro__" => Some(Value::Tuple(self.full_mro().into_iter().map(Value::Class).collect())),
+            _ => self.lookup(name).map(|value| bind(value, &Value::Class(Rc::clone(self)), self)),
+        }
+    }
+}
+
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<class '{}'>", self.qualified_name())
+    }
+}
+
+/// The C3 linearization of `bases`: every class comes before its bases, and the bases of each
+/// class keep their order.
+fn linearize(name: &str, bases: &[Rc<Class>]) -> Result<Vec<Rc<Class>>, VmError> {
+    let mut sequences = bases.iter().map(Class::full_mro).collect::<Vec<_>>();
+    sequences.push(bases.to_vec());
+
+    let mut mro: Vec<Rc<Class>> = vec![];
+    loop {
+        sequences.retain(|sequence| !sequence.is_empty());
+        if sequences.is_empty() {
+            return Ok(mro);
+        }
+        let in_tail = |candidate: &Rc<Class>| sequences.iter().any(|sequence| sequence[1..].iter().any(|class| Rc::ptr_eq(class, candidate)));
+        let next = sequences.iter().map(|sequence| &sequence[0]).find(|candidate| !in_tail(candidate)).cloned()
+            .ok_or_else(|| VmError::TypeError(format!(
+                "Cannot create a consistent method resolution order (MRO) for bases {}",
+                bases.iter().map(|base| base.name.to_string()).collect::<Vec<_>>().join(", "),
+            )))?;
+        for sequence in sequences.iter_mut() {
+            if Rc::ptr_eq(&sequence[0], &next) {
+                sequence.remove(0);
+            }
+        }
+        if mro.iter().any(|class| Rc::ptr_eq(class, &next)) {
+            return Err(VmError::TypeError(format!("duplicate base class {} in {}", next.name, name)));
+        }
+        mro.push(next);
+    }
+}
+
+/// The names in `__slots__`, which is a string or a tuple of them.
+fn slot_names(slots: &Value, dict: &HashMap<Rc<String>, Value>) -> Result<Vec<Rc<String>>, VmError> {
+    let slots = match slots {
+        Value::Str(_) => vec![slots.clone()],
+        Value::Tuple(slots) => slots.clone(),
+        other => return Err(VmError::Unimplemented(format!("__slots__ = {}", other.python_repr()))),
+    };
+
+    let mut names: Vec<Rc<String>> = vec![];
+    for slot in slots {
+        let name = match slot {
+            Value::Str(name) if name.chars().next().is_some_and(|first| first.is_alphabetic() || first == '_')
+                && name.chars().all(|character| character.is_alphanumeric() || character == '_') => Rc::new(name),
+            Value::Str(_) => return Err(VmError::TypeError(String::from("__slots__ must be identifiers"))),
+            other => return Err(VmError::TypeError(format!("__slots__ items must be strings, not {}", other.python_repr()))),
+        };
+        if dict.contains_key(&name) {
+            return Err(VmError::ValueError(format!("'{}' in __slots__ conflicts with class variable", name)));
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// The slots of instances of a class with `bases` that declares `slots`. Only one base can
+/// bring slots, as in CPython.
+fn slot_layout(bases: &[Rc<Class>], slots: &Option<Vec<Rc<String>>>) -> Result<Vec<Rc<String>>, VmError> {
+    let mut with_slots = bases.iter().filter(|base| !base.layout.is_empty());
+    let mut layout = with_slots.next().map(|base| base.layout.clone()).unwrap_or_default();
+    if with_slots.next().is_some() {
+        return Err(VmError::TypeError(String::from("multiple bases have instance lay-out conflict")));
+    }
+    for name in slots.iter().flatten() {
+        if name.as_str() != "__dict__" && !layout.contains(name) {
+            layout.push(Rc::clone(name));
+        }
+    }
+    Ok(layout)
+}
+
+/// An object created by calling a `Class`.
+pub(crate) struct Instance {
+    pub(crate) class: Rc<Class>,
+    /// The `__dict__` of the instance, which instances of classes with `__slots__` don't have.
+    dict: Option<RefCell<HashMap<Rc<String>, Value>>>,
+    /// The values of the class' slot layout, `None` until they are assigned.
+    slots: RefCell<Vec<Option<Value>>>,
+    /// Whether `__del__` has been queued for the instance, it only ever runs once.
+    finalized: Cell<bool>,
+}
+
+impl Instance {
+    fn new(class: Rc<Class>) -> Instance {
+        let dict = if class.has_dict { Some(RefCell::new(HashMap::new())) } else { None };
+        let slots = RefCell::new(vec![None; class.layout.len()]);
+        Instance { class, dict, slots, finalized: Cell::new(false) }
+    }
+
+    fn slot(&self, name: &str) -> Option<usize> {
+        self.class.layout.iter().position(|slot| slot.as_str() == name)
+    }
+
+    /// Attributes of the instance, then of its class. Functions found on the class are bound
+    /// to the instance.
+    pub(crate) fn get_attr(self: &Rc<Self>, name: &Rc<String>) -> Option<Value> {
+        if name.as_str() == "__class__" {
+            return Some(Value::Class(Rc::clone(&self.class)));
+        }
+        if let Some(slot) = self.slot(name) {
+            return self.slots.borrow()[slot].clone();
+        }
+        if let Some(value) = self.dict.as_ref().and_then(|dict| dict.borrow().get(name).cloned()) {
+            return Some(value);
+        }
+
+        self.class.lookup(name).map(|value| bind(value, &Value::Instance(Rc::clone(self)), &self.class))
+    }
+
+    /// A new instance of the same class with the same attribute values, for `copy.copy`.
+    pub(crate) fn shallow_copy(&self) -> Instance {
+        Instance {
+            class: Rc::clone(&self.class),
+            dict: self.dict.clone(),
+            slots: self.slots.clone(),
+            finalized: Cell::new(false),
+        }
+    }
+
+    /// Replaces the value of every attribute with what `replace` gives for it, for `copy.deepcopy`.
+    pub(crate) fn replace_values(&self, mut replace: impl FnMut(&Value) -> Result<Value, VmError>) -> Result<(), VmError> {
+        if let Some(dict) = &self.dict {
+            // `replace` can reach this instance again, so the dict isn't borrowed while it runs.
+            let items = dict.borrow().iter().map(|(name, value)| (Rc::clone(name), value.clone())).collect::<Vec<_>>();
+            for (name, value) in items {
+                let value = replace(&value)?;
+                dict.borrow_mut().insert(name, value);
+            }
+        }
+        let slots = self.slots.borrow().clone();
+        for (position, value) in slots.into_iter().enumerate() {
+            if let Some(value) = value {
+                let value = replace(&value)?;
+                self.slots.borrow_mut()[position] = Some(value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Unbinds a slot, or removes an entry of the `__dict__`.
+    pub(crate) fn delete_attr(&self, name: &Rc<String>) -> Result<(), VmError> {
+        let deleted = match (self.slot(name), &self.dict) {
+            (Some(slot), _) => self.slots.borrow_mut()[slot].take().is_some(),
+            (None, Some(dict)) => dict.borrow_mut().remove(name).is_some(),
+            (None, None) => false,
+        };
+        if !deleted {
+            return Err(VmError::AttributeError(format!("'{}' object has no attribute '{}'", self.class.name, name)));
+        }
+        Ok(())
+    }
+
+    /// Assigns a slot, or an entry of the `__dict__`. Instances without one can only set their slots.
+    pub(crate) fn set_attr(&self, name: Rc<String>, value: Value) -> Result<(), VmError> {
+        if let Some(slot) = self.slot(&name) {
+            self.slots.borrow_mut()[slot] = Some(value);
+            return Ok(());
+        }
+        match &self.dict {
+            Some(dict) => {
+                dict.borrow_mut().insert(name, value);
+                Ok(())
+            },
+            None => Err(VmError::AttributeError(format!("'{}' object has no attribute '{}'", self.class.name, name))),
+        }
+    }
+}
+
+/// A class attribute as it is seen through `object`, an instance of `owner` or `owner` itself:
+/// functions are bound to instances, class methods to `owner`, and static methods to nothing.
+fn bind(value: Value, object: &Value, owner: &Rc<Class>) -> Value {
+    match (value, object) {
+        (value @ (Value::Frame(_) | Value::NativeFunction(..)), Value::Instance(_)) => {
+            Value::BoundMethod(Box::new(object.clone()), Box::new(value))
+        },
+        (Value::ClassMethod(function), _) => Value::BoundMethod(Box::new(Value::Class(Rc::clone(owner))), function),
+        (Value::StaticMethod(function), _) => *function,
+        (value, _) => value,
+    }
+}
+
+/// What `super()` gives: the attributes of the classes that come after `class` in the method
+/// resolution order of `object`, bound to `object`. `object` is an instance of `class`, or a
+/// subclass of it in class methods.
+pub(crate) struct Super {
+    class: Rc<Class>,
+    object: Value,
+}
+
+impl Super {
+    fn new(class: Rc<Class>, object: Value) -> Result<Super, VmError> {
+        let is_derived = match &object {
+            Value::Instance(instance) => instance.class.is_subclass(&class),
+            Value::Class(subclass) => subclass.is_subclass(&class),
+            _ => false,
+        };
+        if !is_derived {
+            return Err(VmError::TypeError(String::from("super(type, obj): obj must be an instance or subtype of type")));
+        }
+        Ok(Super { class, object })
+    }
+
+    /// The class whose method resolution order is searched, the one of `object`.
+    fn owner(&self) -> Rc<Class> {
+        match &self.object {
+            Value::Instance(instance) => Rc::clone(&instance.class),
+            Value::Class(class) => Rc::clone(class),
+            _ => unreachable!("super objects are only made for instances and classes"),
+        }
+    }
+
+    /// `name` looked up on the classes after `class`, without binding it.
+    fn lookup(&self, name: &str) -> Option<Value> {
+        let mro = self.owner().full_mro();
+        let position = mro.iter().position(|class| Rc::ptr_eq(class, &self.class))?;
+        mro[position + 1..].iter().find_map(|class| class.dict.borrow().get(&String::from(name)).cloned())
+    }
+
+    pub(crate) fn get_attr(&self, name: &Rc<String>) -> Option<Value> {
+        match name.as_str() {
+            "__thisclass__" => Some(Value::Class(Rc::clone(&self.class))),
+            "__self__" => Some(self.object.clone()),
+            _ => self.lookup(name).map(|value| bind(value, &self.object, &self.owner())),
+        }
+    }
+}
+
+impl fmt::Debug for Super {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let object = match &self.object {
+            Value::Instance(instance) => format!("<{} object>", instance.class.name),
+            object => object.python_repr(),
+        };
+        write!(f, "<super: <class '{}'>, {}>", self.class.name, object)
+    }
+}
+
+/// `property(fget, fset, fdel, doc)`: a class attribute whose value is what `fget` gives for
+/// the instance it is read from. Assigning and deleting it on an instance call `fset` and
+/// `fdel`, and without them raise `AttributeError`.
+#[derive(Clone)]
+pub(crate) struct Property {
+    fget: Option<Value>,
+    fset: Option<Value>,
+    fdel: Option<Value>,
+    doc: Option<String>,
+}
+
+impl Property {
+    pub(crate) fn get_attr(property: &Rc<Property>, name: &str) -> Option<Value> {
+        let accessor = |accessor: &Option<Value>| Some(accessor.clone().unwrap_or(Value::Nonetype));
+        let method: NativeFn = match name {
+            "fget" => return accessor(&property.fget),
+            "fset" => return accessor(&property.fset),
+            "fdel" => return accessor(&property.fdel),
+            "__doc__" => {
+                let doc = property.doc.clone().or_else(|| property.fget.as_ref().and_then(docstring));
+                return Some(doc.map_or(Value::Nonetype, Value::Str));
+            },
+            // `@x.setter` and the like give a copy of the property with the decorated function.
+            "getter" => |_, args| with_accessor("getter", args, |property, function| property.fget = function),
+            "setter" => |_, args| with_accessor("setter", args, |property, function| property.fset = function),
+            "deleter" => |_, args| with_accessor("deleter", args, |property, function| property.fdel = function),
+            _ => return None,
+        };
+        Some(bound_method(Value::Property(Rc::clone(property)), name, method))
+    }
+}
+
+/// The docstring of a function, which the compiler makes the first constant of its code.
+fn docstring(function: &Value) -> Option<String> {
+    match function {
+        Value::Frame(frame) => match frame.constants.first() {
+            Some(Value::Str(doc)) => Some(doc.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn with_accessor(name: &str, args: Vec<Value>, set: impl FnOnce(&mut Property, Option<Value>)) -> Result<Value, VmError> {
+    expect_args(name, &args[1..], 1, 1)?;
+    let mut property = match &args[0] {
+        Value::Property(property) => Property::clone(property),
+        _ => unreachable!("property methods are only reachable through a bound property"),
+    };
+    set(&mut property, Some(args[1].clone()));
+    Ok(Value::Property(Rc::new(property)))
+}
+
+impl fmt::Debug for Property {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<property object at {:#x}>", self as *const Property as usize)
+    }
+}
+
+/// The `property(fget=None, fset=None, fdel=None, doc=None)` built-in type.
+pub(crate) fn builtin_property(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("property", &args, 0, 4)?;
+    let mut args = args.into_iter().map(|arg| match arg {
+        Value::Nonetype => None,
+        arg => Some(arg),
+    });
+    let (fget, fset, fdel) = (args.next().flatten(), args.next().flatten(), args.next().flatten());
+    let doc = match args.next().flatten() {
+        Some(Value::Str(doc)) => Some(doc),
+        Some(other) => return Err(VmError::Unimplemented(format!("property doc {}", other.python_repr()))),
+        None => None,
+    };
+    Ok(Value::Property(Rc::new(Property { fget, fset, fdel, doc })))
+}
+
+/// The `classmethod(function)` built-in type, whose function is called with the class it is
+/// looked up on, see `bind`.
+pub(crate) fn builtin_classmethod(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("classmethod", &args, 1, 1)?;
+    Ok(Value::ClassMethod(Box::new(args.into_iter().next().unwrap())))
+}
+
+/// The `staticmethod(function)` built-in type, whose function is called without the instance
+/// or class it is looked up on.
+pub(crate) fn builtin_staticmethod(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("staticmethod", &args, 1, 1)?;
+    Ok(Value::StaticMethod(Box::new(args.into_iter().next().unwrap())))
+}
+
+fn runtime_error(message: &str) -> VmError {
+    VmError::Exception(Exception::new("RuntimeError", String::from(message)))
+}
+
+/// `super(class, object)`. `super()` without arguments is given them by the call, see
+/// `Vm::implicit_super_arguments`.
+pub(crate) fn builtin_super(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    match args.len() {
+        0 => return Err(runtime_error("super(): no arguments")),
+        1 => return Err(VmError::Unimplemented(String::from("unbound super objects"))),
+        2 => {},
+        count => return Err(VmError::TypeError(format!("super() takes at most 2 arguments ({} given)", count))),
+    }
+    let mut args = args.into_iter();
+    let class = match args.next().unwrap() {
+        Value::Class(class) => class,
+        other => return Err(VmError::TypeError(format!("super() argument 1 must be type, not {}", other.type_name()))),
+    };
+    Super::new(class, args.next().unwrap()).map(|value| Value::Super(Rc::new(value)))
+}
+
+/// Runs `__del__` when the last reference to an instance goes away.
+///
+/// `__del__` is Python code, which can't run in the middle of whatever dropped the instance,
+/// so the instance's contents are moved into a new instance that is queued. The VM calls
+/// `__del__` on it between two instructions, see `Vm::run_finalizers`. That means:
+/// - `__del__` runs after the instruction that dropped the last reference, not during it
+/// - a `__del__` that stores `self` somewhere keeps the new instance alive, whose `id()`
+///   differs from the old one
+/// - `__del__` runs once per instance, and finalizers don't run while another one is running,
+///   so instances dropped by a `__del__` wait for it to return
+/// - instances in reference cycles are never dropped, so their `__del__` never runs
+impl Drop for Instance {
+    fn drop(&mut self) {
+        if self.finalized.get() || !self.class.has_finalizer() {
+            return;
+        }
+
+        let instance = Rc::new(Instance {
+            class: Rc::clone(&self.class),
+            dict: self.dict.take(),
+            slots: RefCell::new(mem::take(self.slots.get_mut())),
+            finalized: Cell::new(true),
+        });
+        // The queue is gone when the thread is exiting, and then there is no VM to run `__del__`.
+        let _ = FINALIZERS.try_with(|finalizers| finalizers.borrow_mut().push(instance));
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} object at {:#x}>", self.class.qualified_name(), self as *const Instance as usize)
+    }
+}
+
+/// Instances, classes and the other objects behind an `Rc`, compared by identity.
+pub(crate) fn is_same_object(first: &Value, second: &Value) -> bool {
+    match (first, second) {
+        (Value::Instance(first), Value::Instance(second)) => Rc::ptr_eq(first, second),
+        (Value::Class(first), Value::Class(second)) => Rc::ptr_eq(first, second),
+        (Value::WeakRef(first), Value::WeakRef(second)) => first.ptr_eq(second),
+        (Value::EnumMember(first), Value::EnumMember(second)) => Rc::ptr_eq(first, second),
+        (Value::Super(first), Value::Super(second)) => Rc::ptr_eq(first, second),
+        (Value::Property(first), Value::Property(second)) => Rc::ptr_eq(first, second),
+        _ => false,
+    }
+}
+
+fn object_init(_vm: &mut Vm, _args: Vec<Value>) -> Result<Value, VmError> {
+    Ok(Value::Nonetype)
+}
+
+/// `MATCH_CLASS`: the attributes a class pattern like `Point(x, y=0)` binds, positional
+/// ones named by `__match_args__` followed by `keywords`, or `None` if `subject` doesn't match.
+pub(crate) fn match_class(subject: &Value, class: &Value, count: usize, keywords: &[Value]) -> Result<Option<Vec<Value>>, VmError> {
+    let class = match class {
+        Value::Class(class) => class,
+        _ => return Err(VmError::TypeError(String::from("called match pattern must be a type"))),
+    };
+    let instance = match subject {
+        Value::Instance(instance) if instance.class.is_subclass(class) => instance,
+        _ => return Ok(None),
+    };
+
+    let mut names = vec![];
+    if count > 0 {
+        let match_args = match class.lookup("__match_args__") {
+            Some(Value::Tuple(match_args)) => match_args,
+            Some(other) => {
+                return Err(VmError::TypeError(format!("{}.__match_args__ must be a tuple (got {})", class.name, other.python_repr())));
+            },
+            None => vec![],
+        };
+        if count > match_args.len() {
+            let plural = if match_args.len() == 1 { "" } else { "s" };
+            return Err(VmError::TypeError(format!(
+                "{}() accepts {} positional sub-pattern{} ({} given)", class.name, match_args.len(), plural, count
+            )));
+        }
+        names.extend(match_args.into_iter().take(count));
+    }
+    names.extend(keywords.iter().cloned());
+
+    let mut attributes = vec![];
+    for (position, name) in names.iter().enumerate() {
+        let name = match name {
+            Value::Str(name) => Rc::new(name.clone()),
+            other => return Err(VmError::TypeError(format!("__match_args__ elements must be strings (got {})", other.python_repr()))),
+        };
+        if names[..position].iter().any(|seen| matches!(seen, Value::Str(seen) if seen == name.as_str())) {
+            return Err(VmError::TypeError(format!("{}() got multiple sub-patterns for attribute '{}'", class.name, name)));
+        }
+        match instance.get_attr(&name) {
+            Some(attribute) => attributes.push(attribute),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(attributes))
+}
+
+/// `__build_class__(body, name, *bases)`, which `LOAD_BUILD_CLASS` pushes for a `class` statement.
+pub(crate) fn build_class(vm: &mut Vm, mut args: Vec<Value>) -> Result<Value, VmError> {
+    if args.len() < 2 {
+        return Err(VmError::TypeError(String::from("__build_class__: not enough arguments")));
+    }
+    let bases = args.split_off(2);
+    let (body, name) = match (&args[0], &args[1]) {
+        (Value::Frame(body), Value::Str(name)) => (body.clone(), Rc::new(name.clone())),
+        (Value::Frame(_), _) => return Err(VmError::TypeError(String::from("__build_class__: name is not a string"))),
+        _ => return Err(VmError::TypeError(String::from("__build_class__: func must be a function"))),
+    };
+    let mut bases = bases.into_iter().map(|base| match base {
+        Value::Class(base) => Ok(base),
+        Value::ExceptionType(_) => Err(VmError::Unimplemented(String::from("classes deriving from exceptions"))),
+        base => Err(VmError::TypeError(format!("bases must be types, not {}", base.python_repr()))),
+    }).collect::<Result<Vec<_>, _>>()?;
+    if bases.is_empty() {
+        bases.push(object_class());
+    }
+
+    let mut namespace = HashMap::new();
+    bind_cells(&body, &mut namespace);
+    let state = FrameState::new(Rc::new(RefCell::new(namespace)), FrameType::Class);
+    let namespace = vm.run_state(&body, state, None)?.locals;
+    // The body gives the `__class__` cell of methods calling `super()` back as `__classcell__`,
+    // it is filled once the class exists.
+    let class_cell = match namespace.borrow().get(&String::from("__classcell__")) {
+        Some(Value::Cell(cell)) => Some(Rc::clone(cell)),
+        _ => None,
+    };
+    // Cells only hold the class body's variables for the methods closing over them.
+    let dict: HashMap<_, _> = namespace.borrow().iter()
+        .filter(|(_, value)| !matches!(value, Value::Cell(_)))
+        .map(|(name, value)| (Rc::clone(name), value.clone()))
+        .collect();
+    let slots = match dict.get(&String::from("__slots__")) {
+        Some(slots) => Some(slot_names(slots, &dict)?),
+        None => None,
+    };
+    let module = match dict.get(&String::from("__module__")) {
+        Some(Value::Str(module)) => Rc::new(module.clone()),
+        _ => Rc::new(String::from("__main__")),
+    };
+
+    let is_enum = r#enum::is_enum(&bases);
+    let class = Rc::new(Class {
+        mro: linearize(&name, &bases)?,
+        layout: slot_layout(&bases, &slots)?,
+        has_dict: bases.iter().any(|base| base.has_dict)
+            || slots.as_ref().is_none_or(|slots| slots.iter().any(|slot| slot.as_str() == "__dict__")),
+        name,
+        module,
+        bases,
+        dict: RefCell::new(dict),
+    });
+    if is_enum {
+        r#enum::make_enum(&class);
+    }
+    if let Some(cell) = class_cell {
+        *cell.borrow_mut() = Value::Class(Rc::clone(&class));
+    }
+    Ok(Value::Class(class))
+}
+
+/// Calling a class: creates an instance and runs `__init__` on it.
+pub(crate) fn instantiate(vm: &mut Vm, class: Rc<Class>, mut args: Vec<Value>, kwargs: Vec<(Rc<String>, Value)>) -> Result<Value, VmError> {
+    if r#enum::is_enum(&[Rc::clone(&class)]) {
+        if !kwargs.is_empty() {
+            return Err(VmError::TypeError(format!("{}() takes no keyword arguments", class.name)));
+        }
+        return r#enum::lookup_member(&class, &args);
+    }
+    let init = class.lookup("__init__").unwrap();
+    let defined_by_object = class.full_mro().iter()
+        .find(|class| class.dict.borrow().contains_key(&String::from("__init__")))
+        .is_some_and(|class| class.bases.is_empty());
+    if (!args.is_empty() || !kwargs.is_empty()) && defined_by_object {
+        return Err(VmError::TypeError(format!("{}() takes no arguments", class.name)));
+    }
+
+    let instance = Value::Instance(Rc::new(Instance::new(class)));
+    args.insert(0, instance.clone());
+    match vm.call_value_kw(init, args, kwargs)? {
+        Value::Nonetype => Ok(instance),
+        other => Err(VmError::TypeError(format!("__init__() should return None, not {}", other.python_repr()))),
+    }
+}
+
+impl Vm {
+    /// `object.name`. A property found on the class of an instance gives what its getter
+    /// returns for the instance, even when the instance has an attribute of the same name.
+    pub(crate) fn get_attribute(&mut self, object: Value, name: &Rc<String>) -> Result<Value, VmError> {
+        let descriptor = match &object {
+            Value::Instance(instance) => instance.class.lookup(name),
+            Value::Super(value) => value.lookup(name),
+            _ => None,
+        };
+        match descriptor {
+            Some(Value::Property(property)) => {
+                let instance = match object {
+                    Value::Super(value) => value.object.clone(),
+                    object => object,
+                };
+                let fget = property.fget.clone().ok_or_else(|| VmError::AttributeError(String::from("unreadable attribute")))?;
+                self.call_value(fget, vec![instance])
+            },
+            _ => object.get_attr(name),
+        }
+    }
+
+    /// `instance.name = value`, which calls the setter of a property.
+    pub(crate) fn set_attribute(&mut self, instance: &Rc<Instance>, name: Rc<String>, value: Value) -> Result<(), VmError> {
+        match instance.class.lookup(&name) {
+            Some(Value::Property(property)) => {
+                let fset = property.fset.clone().ok_or_else(|| VmError::AttributeError(String::from("can't set attribute")))?;
+                self.call_value(fset, vec![Value::Instance(Rc::clone(instance)), value]).map(|_| ())
+            },
+            _ => instance.set_attr(name, value),
+        }
+    }
+
+    /// `del instance.name`, which calls the deleter of a property.
+    pub(crate) fn delete_attribute(&mut self, instance: &Rc<Instance>, name: &Rc<String>) -> Result<(), VmError> {
+        match instance.class.lookup(name) {
+            Some(Value::Property(property)) => {
+                let fdel = property.fdel.clone().ok_or_else(|| VmError::AttributeError(String::from("can't delete attribute")))?;
+                self.call_value(fdel, vec![Value::Instance(Rc::clone(instance))]).map(|_| ())
+            },
+            _ => instance.delete_attr(name),
+        }
+    }
+
+    /// The arguments `super()` is called with when it is given none in a method of `frame`: the
+    /// class the method is defined in, from its `__class__` cell, and the first argument.
+    pub(crate) fn implicit_super_arguments(&mut self, frame: &Frame) -> Result<Vec<Value>, VmError> {
+        let first = match frame.co_varnames.first() {
+            Some(name) if self.state().frame_type == FrameType::Function && frame.co_argcount != Some(0) => name,
+            _ => return Err(runtime_error("super(): no arguments")),
+        };
+        let locals = self.state().locals.borrow();
+        let class = match locals.get(&String::from("__class__")) {
+            Some(Value::Cell(cell)) => match &*cell.borrow() {
+                class @ Value::Class(_) => class.clone(),
+                _ => return Err(runtime_error("super(): empty __class__ cell")),
+            },
+            _ => return Err(runtime_error("super(): __class__ cell not found")),
+        };
+        // A first argument that a nested function closes over is in a cell.
+        let object = match locals.get(first) {
+            Some(Value::Cell(cell)) => cell.borrow().clone(),
+            Some(value) => value.clone(),
+            None => Value::Null,
+        };
+        if let Value::Null = object {
+            return Err(runtime_error("super(): arg[0] deleted"));
+        }
+        Ok(vec![class, object])
+    }
+
+    /// `str(value)`, which calls `__str__` on instances.
+    pub(crate) fn python_str(&mut self, value: &Value) -> Result<String, VmError> {
+        self.call_text_method(value, "__str__").unwrap_or_else(|| Ok(value.to_string()))
+    }
+
+    /// `repr(value)`, which calls `__repr__` on instances.
+    pub(crate) fn python_repr(&mut self, value: &Value) -> Result<String, VmError> {
+        self.call_text_method(value, "__repr__").unwrap_or_else(|| Ok(value.python_repr()))
+    }
+
+    /// `bool(value)`, which calls `__bool__` on instances, or `__len__` if the class has no
+    /// `__bool__`. Instances with neither are true.
+    pub(crate) fn python_bool(&mut self, value: &Value) -> Result<bool, VmError> {
+        let instance = match value {
+            Value::Instance(instance) => instance,
+            _ => return Ok(value.is_truthy()),
+        };
+        if let Some(method) = instance.get_attr(&Rc::new(String::from("__bool__"))) {
+            return match self.call_value(method, vec![])? {
+                Value::Bool(value) => Ok(value),
+                other => Err(VmError::TypeError(format!("__bool__ should return bool, returned {}", other.python_repr()))),
+            };
+        }
+        if let Some(method) = instance.get_attr(&Rc::new(String::from("__len__"))) {
+            return match self.call_value(method, vec![])? {
+                Value::Int(length) if length < 0 => Err(VmError::ValueError(String::from("__len__() should return >= 0"))),
+                Value::Int(length) => Ok(length != 0),
+                Value::Bool(value) => Ok(value),
+                other => Err(VmError::TypeError(format!("\'{}\' object cannot be interpreted as an integer", other.type_name()))),
+            };
+        }
+        Ok(true)
+    }
+
+    /// `format(value, spec)`, which calls `__format__` on instances.
+    pub(crate) fn python_format(&mut self, value: &Value, spec: &str) -> Result<String, VmError> {
+        let method = match value {
+            Value::Instance(instance) => instance.get_attr(&Rc::new(String::from("__format__"))),
+            _ => return value.format_with_spec(spec),
+        };
+        match method {
+            Some(method) => match self.call_value(method, vec![Value::Str(String::from(spec))])? {
+                Value::Str(text) => Ok(text),
+                other => Err(VmError::TypeError(format!("__format__ must return a str, not {}", other.python_repr()))),
+            },
+            None if spec.is_empty() => self.python_str(value),
+            None => Err(VmError::TypeError(format!("unsupported format string passed to {}.__format__", value.python_repr()))),
+        }
+    }
+
+    /// `first <operator> second` when either is an instance: the in-place method like
+    /// `__iadd__` for augmented assignments, then `__add__` of `first`, then `__radd__` of
+    /// `second`. `None` if neither operand is an instance.
+    pub(crate) fn binary_method(&mut self, name: &str, inplace: bool, first: &Value, second: &Value) -> Option<Result<Value, VmError>> {
+        if !matches!(first, Value::Instance(_)) && !matches!(second, Value::Instance(_)) {
+            return None;
+        }
+        let mut candidates = vec![];
+        if let Value::Instance(instance) = first {
+            if inplace {
+                candidates.push((instance.get_attr(&Rc::new(format!("__i{}__", name))), second));
+            }
+            candidates.push((instance.get_attr(&Rc::new(format!("__{}__", name))), second));
+        }
+        if let Value::Instance(instance) = second {
+            candidates.push((instance.get_attr(&Rc::new(format!("__r{}__", name))), first));
+        }
+        if let Some((Some(method), other)) = candidates.into_iter().find(|(method, _)| method.is_some()) {
+            return Some(self.call_value(method, vec![other.clone()]));
+        }
+
+        let symbol = BINARY_OPERATORS.iter().find(|(method, _)| *method == name).map_or(name, |(_, symbol)| symbol);
+        Some(Err(VmError::TypeError(format!(
+            "unsupported operand type(s) for {}{}: '{}' and '{}'", symbol, if inplace { "=" } else { "" }, first.type_name(), second.type_name()
+        ))))
+    }
+
+    /// Calls `__del__` on the instances that were dropped since the last call. Exceptions
+    /// raised by `__del__` are printed and ignored, like CPython does.
+    pub(crate) fn run_finalizers(&mut self) -> Result<(), VmError> {
+        if self.finalizing {
+            return Ok(());
+        }
+        self.finalizing = true;
+        while let Some(instance) = FINALIZERS.with(|finalizers| finalizers.borrow_mut().pop()) {
+            let finalizer = instance.get_attr(&Rc::new(String::from("__del__"))).unwrap();
+            match self.call_value(finalizer, vec![]) {
+                Err(error @ VmError::Unimplemented(_)) => {
+                    self.finalizing = false;
+                    return Err(error);
+                },
+                Err(error) => eprintln!("Exception ignored in: <function {}.__del__>\n{}", instance.class.name, error),
+                Ok(_) => {},
+            }
+        }
+        self.finalizing = false;
+        Ok(())
+    }
+
+    fn call_text_method(&mut self, value: &Value, name: &str) -> Option<Result<String, VmError>> {
+        let method = match value {
+            Value::Instance(instance) => instance.get_attr(&Rc::new(String::from(name)))?,
+            _ => return None,
+        };
+        Some(match self.call_value(method, vec![]) {
+            Ok(Value::Str(text)) => Ok(text),
+            Ok(_) => Err(VmError::TypeError(format!("{} returned non-string", name))),
+            Err(error) => Err(error),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+//! `dict`, which keeps its keys in the order they were first inserted, like CPython's. Keys are
+//! looked up by their hash and then compared, so `1`, `1.0` and `True` are the same key.
+
+use crate::{Value, VmError};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Dict {
+    /// The hash, key and value of each item, in insertion order.
+    entries: Vec<(i32, Value, Value)>,
+}
+
+impl Dict {
+    pub(crate) fn new() -> Dict {
+        Dict::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Where `key` is in `entries`, or a `TypeError` if it is unhashable.
+    fn position(&self, key: &Value) -> Result<Option<usize>, VmError> {
+        let hash = key.python_hash()?;
+        Ok(self.entries.iter().position(|(entry_hash, entry_key, _)| *entry_hash == hash && entry_key == key))
+    }
+
+    /// `dict[key]`, `None` if the key isn't in the dict.
+    pub(crate) fn get(&self, key: &Value) -> Result<Option<Value>, VmError> {
+        Ok(self.position(key)?.map(|position| self.entries[position].2.clone()))
+    }
+
+    /// `dict[key] = value`. Replacing the value of a key keeps the key where it was and the key
+    /// object it was first inserted with.
+    pub(crate) fn insert(&mut self, key: Value, value: Value) -> Result<(), VmError> {
+        match self.position(&key)? {
+            Some(position) => self.entries[position].2 = value,
+            None => self.entries.push((key.python_hash()?, key, value)),
+        }
+        Ok(())
+    }
+
+    /// `del dict[key]`, which gives the value back, `None` if the key isn't in the dict.
+    pub(crate) fn remove(&mut self, key: &Value) -> Result<Option<Value>, VmError> {
+        Ok(self.position(key)?.map(|position| self.entries.remove(position).2))
+    }
+
+    pub(crate) fn items(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.entries.iter().map(|(_, key, value)| (key, value))
+    }
+
+    /// The keys, which iterating over a dict gives.
+    pub(crate) fn keys(&self) -> Vec<Value> {
+        self.entries.iter().map(|(_, key, _)| key.clone()).collect()
+    }
+
+    pub(crate) fn repr(&self) -> String {
+        let items = self.items().map(|(key, value)| format!("{}: {}", key.python_repr(), value.python_repr()));
+        format!("{{{}}}", items.collect::<Vec<_>>().join(", "))
+    }
+}
+
+/// Dicts are equal when they have the same keys with equal values, whatever their order.
+impl PartialEq for Dict {
+    fn eq(&self, other: &Dict) -> bool {
+        self.len() == other.len() && self.items().all(|(key, value)| match other.get(key) {
+            Ok(Some(other_value)) => *value == other_value,
+            _ => false,
+        })
+    }
+}
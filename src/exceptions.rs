@@ -0,0 +1,272 @@
+//! Built-in exception types and the exceptions raised with them.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::modules::{bound_method, expect_args};
+use crate::{Value, VmError};
+
+/// Every built-in exception type and its base, bases come before the types deriving from them.
+const HIERARCHY: &[(&str, Option<&str>)] = &[
+    ("BaseException", None),
+    ("SystemExit", Some("BaseException")),
+    ("KeyboardInterrupt", Some("BaseException")),
+    ("GeneratorExit", Some("BaseException")),
+    ("BaseExceptionGroup", Some("BaseException")),
+    ("Exception", Some("BaseException")),
+    ("ExceptionGroup", Some("Exception")),
+    ("StopIteration", Some("Exception")),
+    ("StopAsyncIteration", Some("Exception")),
+    ("ArithmeticError", Some("Exception")),
+    ("FloatingPointError", Some("ArithmeticError")),
+    ("OverflowError", Some("ArithmeticError")),
+    ("ZeroDivisionError", Some("ArithmeticError")),
+    ("AssertionError", Some("Exception")),
+    ("AttributeError", Some("Exception")),
+    ("EOFError", Some("Exception")),
+    ("ImportError", Some("Exception")),
+    ("ModuleNotFoundError", Some("ImportError")),
+    ("LookupError", Some("Exception")),
+    ("IndexError", Some("LookupError")),
+    ("KeyError", Some("LookupError")),
+    ("NameError", Some("Exception")),
+    ("UnboundLocalError", Some("NameError")),
+    ("OSError", Some("Exception")),
+    ("FileExistsError", Some("OSError")),
+    ("FileNotFoundError", Some("OSError")),
+    ("IsADirectoryError", Some("OSError")),
+    ("NotADirectoryError", Some("OSError")),
+    ("PermissionError", Some("OSError")),
+    ("io.UnsupportedOperation", Some("OSError")),
+    ("ReferenceError", Some("Exception")),
+    ("RuntimeError", Some("Exception")),
+    ("NotImplementedError", Some("RuntimeError")),
+    ("RecursionError", Some("RuntimeError")),
+    ("TypeError", Some("Exception")),
+    ("ValueError", Some("Exception")),
+    ("UnicodeError", Some("ValueError")),
+    ("re.error", Some("Exception")),
+    ("struct.error", Some("Exception")),
+    ("_pickle.PickleError", Some("Exception")),
+    ("_pickle.PicklingError", Some("_pickle.PickleError")),
+    ("_pickle.UnpicklingError", Some("_pickle.PickleError")),
+    ("Warning", Some("Exception")),
+];
+
+thread_local! {
+    static TYPES: HashMap<&'static str, Rc<ExceptionType>> = {
+        let mut types: HashMap<&str, Rc<ExceptionType>> = HashMap::new();
+        for (name, base) in HIERARCHY.iter() {
+            let base = base.map(|base| Rc::clone(&types[base]));
+            types.insert(name, Rc::new(ExceptionType { name: Rc::new(String::from(*name)), base }));
+        }
+        types
+    };
+}
+
+/// The built-in exception type called `name`, modules' exceptions are qualified like `re.error`.
+pub(crate) fn exception_type(name: &str) -> Rc<ExceptionType> {
+    TYPES.with(|types| Rc::clone(types.get(name).unwrap_or_else(|| panic!("{} isn't a built-in exception", name))))
+}
+
+/// The exception types that live in the `builtins` module.
+pub(crate) fn builtin_types() -> Vec<Rc<ExceptionType>> {
+    HIERARCHY.iter().filter(|(name, _)| !name.contains('.')).map(|(name, _)| exception_type(name)).collect()
+}
+
+#[derive(Debug)]
+pub(crate) struct ExceptionType {
+    /// The name tracebacks show, which includes the module for exceptions outside `builtins`.
+    pub(crate) name: Rc<String>,
+    base: Option<Rc<ExceptionType>>,
+}
+
+impl ExceptionType {
+    /// Whether an `except other:` clause catches exceptions of this type.
+    pub(crate) fn is_subclass(self: &Rc<Self>, other: &Rc<ExceptionType>) -> bool {
+        let mut current = Some(self);
+        while let Some(kind) = current {
+            if Rc::ptr_eq(kind, other) {
+                return true;
+            }
+            current = kind.base.as_ref();
+        }
+        false
+    }
+
+    /// The attribute name of the type, without its module.
+    pub(crate) fn short_name(&self) -> &str {
+        self.name.rsplit('.').next().unwrap()
+    }
+}
+
+/// A raised or raisable exception, an instance of one of the built-in exception types.
+pub(crate) struct Exception {
+    pub(crate) kind: Rc<ExceptionType>,
+    pub(crate) args: Vec<Value>,
+}
+
+impl Exception {
+    pub(crate) fn new(name: &str, message: String) -> Rc<Exception> {
+        Rc::new(Exception { kind: exception_type(name), args: vec![Value::Str(message)] })
+    }
+
+    /// Calling exception type `kind`. Exception groups check that they get a message and a
+    /// non-empty sequence of exceptions.
+    pub(crate) fn instantiate(kind: Rc<ExceptionType>, args: Vec<Value>) -> Result<Value, VmError> {
+        let exception = Exception { kind, args };
+        if exception.is_group() {
+            exception.check_group()?;
+        }
+        Ok(Value::Exception(Rc::new(exception)))
+    }
+
+    /// Whether this is an `ExceptionGroup` or `BaseExceptionGroup`, whose arguments are a
+    /// message and the exceptions in the group.
+    pub(crate) fn is_group(&self) -> bool {
+        matches!(self.kind.name.as_str(), "ExceptionGroup" | "BaseExceptionGroup")
+    }
+
+    fn group_exceptions(&self) -> Vec<Value> {
+        self.args.get(1).and_then(Value::sequence_items).unwrap_or_default()
+    }
+
+    fn check_group(&self) -> Result<(), VmError> {
+        let name = self.kind.short_name();
+        match self.args.as_slice() {
+            [Value::Str(_), exceptions] => match exceptions.sequence_items() {
+                Some(items) if !items.is_empty() => {
+                    let base_exception = exception_type("Exception");
+                    for (index, item) in items.iter().enumerate() {
+                        match item {
+                            Value::Exception(item) if name == "ExceptionGroup" && !item.kind.is_subclass(&base_exception) => {
+                                return Err(VmError::TypeError(String::from("Cannot nest BaseExceptions in an ExceptionGroup")));
+                            },
+                            Value::Exception(_) => {},
+                            _ => return Err(VmError::ValueError(format!("Item {} of second argument (exceptions) is not an exception", index))),
+                        }
+                    }
+                    Ok(())
+                },
+                _ => Err(VmError::ValueError(String::from("second argument (exceptions) must be a non-empty sequence"))),
+            },
+            [message, _] => Err(VmError::TypeError(format!("{}() argument 1 must be str, not {}", name, message.type_name()))),
+            args => Err(VmError::TypeError(format!("{}() takes exactly 2 arguments ({} given)", name, args.len()))),
+        }
+    }
+
+    /// `str()` of the exception, its only argument or the tuple of all of them. Groups give
+    /// their message and how many exceptions they hold.
+    pub(crate) fn message(&self) -> String {
+        if self.is_group() {
+            let count = self.group_exceptions().len();
+            return format!("{} ({} sub-exception{})", self.args[0], count, if count == 1 { "" } else { "s" });
+        }
+        match self.args.as_slice() {
+            [] => String::new(),
+            [arg] => arg.to_string(),
+            args => Value::Tuple(args.to_vec()).python_repr(),
+        }
+    }
+
+    pub(crate) fn get_attr(self: &Rc<Self>, name: &str) -> Option<Value> {
+        match name {
+            "args" => Some(Value::Tuple(self.args.clone())),
+            "value" if self.kind.name.as_str() == "StopIteration" => Some(self.args.first().cloned().unwrap_or(Value::Nonetype)),
+            "message" if self.is_group() => Some(self.args[0].clone()),
+            "exceptions" if self.is_group() => Some(Value::Tuple(self.group_exceptions())),
+            "split" if self.is_group() => Some(bound_method(Value::Exception(Rc::clone(self)), "split", |_, args| {
+                expect_args("split", &args[1..], 1, 1)?;
+                let group = match &args[0] {
+                    Value::Exception(group) => group,
+                    _ => unreachable!("split is only reachable through a bound exception group"),
+                };
+                let (matched, rest) = split_exception_group(group, &args[1])?;
+                Ok(Value::Tuple(vec![matched, rest]))
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// Splits `exception` into the part `except* clause:` handles and the rest, each `None` when it
+/// is empty. Groups are split recursively, and the parts keep their message and nesting. A
+/// group that isn't split is given back as it is.
+pub(crate) fn split_exception_group(exception: &Rc<Exception>, clause: &Value) -> Result<(Value, Value), VmError> {
+    if crate::exception_matches(&Value::ExceptionType(Rc::clone(&exception.kind)), clause)? {
+        return Ok((Value::Exception(Rc::clone(exception)), Value::Nonetype));
+    }
+    if !exception.is_group() {
+        return Ok((Value::Nonetype, Value::Exception(Rc::clone(exception))));
+    }
+
+    let (mut matched, mut rest) = (vec![], vec![]);
+    for item in exception.group_exceptions() {
+        if let Value::Exception(item) = item {
+            let (item_matched, item_rest) = split_exception_group(&item, clause)?;
+            matched.extend(Some(item_matched).filter(|part| !matches!(part, Value::Nonetype)));
+            rest.extend(Some(item_rest).filter(|part| !matches!(part, Value::Nonetype)));
+        }
+    }
+    if matched.is_empty() {
+        return Ok((Value::Nonetype, Value::Exception(Rc::clone(exception))));
+    }
+    if rest.is_empty() {
+        return Ok((Value::Exception(Rc::clone(exception)), Value::Nonetype));
+    }
+    let derive = |exceptions: Vec<Value>| Value::Exception(Rc::new(Exception {
+        kind: Rc::clone(&exception.kind),
+        args: vec![exception.args[0].clone(), Value::List(Rc::new(RefCell::new(exceptions)))],
+    }));
+    Ok((derive(matched), derive(rest)))
+}
+
+impl fmt::Debug for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args = self.args.iter().map(Value::python_repr).collect::<Vec<_>>();
+        write!(f, "{}({})", self.kind.short_name(), args.join(", "))
+    }
+}
+
+/// The last line of a traceback, `ValueError: message`.
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.message().as_str() {
+            "" => write!(f, "{}", self.kind.name),
+            message => write!(f, "{}: {}", self.kind.name, message),
+        }
+    }
+}
+
+impl VmError {
+    /// The exception Python code sees for the error. Features the VM doesn't implement can't be
+    /// caught, so they are given back as the error.
+    pub(crate) fn into_exception(self) -> Result<Rc<Exception>, VmError> {
+        Ok(match self {
+            VmError::TypeError(message) => Exception::new("TypeError", message),
+            VmError::AttributeError(message) => Exception::new("AttributeError", message),
+            VmError::ImportError(message) => Exception::new("ImportError", message),
+            VmError::ModuleNotFoundError(name) => Exception::new("ModuleNotFoundError", format!("No module named '{}'", name)),
+            VmError::NameError(name) => Exception::new("NameError", format!("name '{}' is not defined", name)),
+            VmError::UnboundLocalError(name) => {
+                Exception::new("UnboundLocalError", format!("local variable '{}' referenced before assignment", name))
+            },
+            VmError::ValueError(message) => Exception::new("ValueError", message),
+            VmError::IndexError(message) => Exception::new("IndexError", message),
+            VmError::KeyError(key) => Exception::new("KeyError", key),
+            VmError::OverflowError(message) => Exception::new("OverflowError", message),
+            VmError::ZeroDivisionError(message) => Exception::new("ZeroDivisionError", message),
+            VmError::ReferenceError(message) => Exception::new("ReferenceError", message),
+            VmError::OSError(name, message) => Exception::new(name, message),
+            VmError::RecursionError => Exception::new("RecursionError", String::from("maximum recursion depth exceeded")),
+            VmError::ReError(message) => Exception::new("re.error", message),
+            VmError::StructError(message) => Exception::new("struct.error", message),
+            VmError::PicklingError(message) => Exception::new("_pickle.PicklingError", message),
+            VmError::UnpicklingError(message) => Exception::new("_pickle.UnpicklingError", message),
+            VmError::Exception(exception) => exception,
+            VmError::Unimplemented(_) | VmError::InternalError(_) => return Err(self),
+        })
+    }
+}
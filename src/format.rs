@@ -0,0 +1,278 @@
+//! The format spec mini-language used by `format()` and f-strings.
+
+use std::convert::TryFrom;
+
+use crate::{Value, Vm, VmError};
+
+/// A parsed `[[fill]align][sign][#][0][width][grouping][.precision][type]`.
+struct FormatSpec {
+    fill: char,
+    align: Option<char>,
+    sign: Option<char>,
+    alternate: bool,
+    width: usize,
+    grouping: Option<char>,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+impl FormatSpec {
+    fn parse(spec: &str) -> Result<FormatSpec, VmError> {
+        let characters = spec.chars().collect::<Vec<_>>();
+        let mut position = 0;
+        let is_align = |character: Option<&char>| matches!(character, Some('<') | Some('>') | Some('^') | Some('='));
+
+        let (mut fill, mut align) = (' ', None);
+        if is_align(characters.get(1)) {
+            fill = characters[0];
+            align = Some(characters[1]);
+            position = 2;
+        } else if is_align(characters.first()) {
+            align = Some(characters[0]);
+            position = 1;
+        }
+
+        let mut sign = None;
+        if let Some(character @ ('+' | '-' | ' ')) = characters.get(position) {
+            sign = Some(*character);
+            position += 1;
+        }
+        let alternate = characters.get(position) == Some(&'#');
+        if alternate {
+            position += 1;
+        }
+        // A leading zero in the width pads with zeros after the sign.
+        if characters.get(position) == Some(&'0') && align.is_none() {
+            fill = '0';
+            align = Some('=');
+        }
+
+        let digits = |position: &mut usize| {
+            let start = *position;
+            while characters.get(*position).is_some_and(char::is_ascii_digit) {
+                *position += 1;
+            }
+            characters[start..*position].iter().collect::<String>()
+        };
+        let width = digits(&mut position).parse().unwrap_or(0);
+
+        let mut grouping = None;
+        if let Some(character @ (',' | '_')) = characters.get(position) {
+            grouping = Some(*character);
+            position += 1;
+        }
+
+        let mut precision = None;
+        if characters.get(position) == Some(&'.') {
+            position += 1;
+            match digits(&mut position).parse() {
+                Ok(digits) => precision = Some(digits),
+                Err(_) => return Err(VmError::ValueError(String::from("Format specifier missing precision"))),
+            }
+        }
+
+        let kind = characters.get(position).copied();
+        if characters.len() > position + 1 {
+            return Err(VmError::ValueError(String::from("Invalid format specifier")));
+        }
+
+        Ok(FormatSpec { fill, align, sign, alternate, width, grouping, precision, kind })
+    }
+
+    /// Pads `body` to the width. `prefix` is the sign and base prefix that `=` pads after.
+    fn pad(&self, prefix: &str, body: &str, default_align: char) -> String {
+        let length = prefix.chars().count() + body.chars().count();
+        let padding = self.width.saturating_sub(length);
+        let fill = |count: usize| self.fill.to_string().repeat(count);
+        match self.align.unwrap_or(default_align) {
+            '<' => format!("{}{}{}", prefix, body, fill(padding)),
+            '^' => format!("{}{}{}{}", fill(padding / 2), prefix, body, fill(padding - padding / 2)),
+            '=' => format!("{}{}{}", prefix, fill(padding), body),
+            _ => format!("{}{}{}", fill(padding), prefix, body),
+        }
+    }
+
+    fn sign(&self, negative: bool) -> &'static str {
+        match (negative, self.sign) {
+            (true, _) => "-",
+            (false, Some('+')) => "+",
+            (false, Some(' ')) => " ",
+            _ => "",
+        }
+    }
+
+    /// Formats a number whose integer digits (without sign) are `whole`, grouping them and
+    /// padding. `rest` is the fraction, exponent or `%` that follows, which isn't grouped.
+    fn number(&self, negative: bool, base_prefix: &str, whole: &str, rest: &str, group_size: usize) -> String {
+        let prefix = format!("{}{}", self.sign(negative), base_prefix);
+        let mut whole = whole.to_string();
+
+        let grouped = |whole: &str| match self.grouping {
+            Some(separator) => group(whole, separator, group_size),
+            None => whole.to_string(),
+        };
+        // Zero padding is grouped like the digits are, so it is added before grouping.
+        if self.fill == '0' && self.align == Some('=') && self.grouping.is_some() {
+            while prefix.len() + grouped(&whole).len() + rest.len() < self.width {
+                whole.insert(0, '0');
+            }
+        }
+
+        self.pad(&prefix, &format!("{}{}", grouped(&whole), rest), '>')
+    }
+}
+
+fn group(digits: &str, separator: char, size: usize) -> String {
+    let digits = digits.chars().collect::<Vec<_>>();
+    let mut grouped = String::new();
+    for (index, digit) in digits.iter().enumerate() {
+        if index != 0 && (digits.len() - index) % size == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(*digit);
+    }
+    grouped
+}
+
+/// Rust's `{:e}` writes `1.5e3`, Python writes `1.5e+03`.
+fn python_exponent(formatted: &str, upper: bool) -> String {
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    let exponent = exponent.parse::<i32>().unwrap();
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}{}{}{:02}", mantissa, if upper { 'E' } else { 'e' }, sign, exponent.abs())
+}
+
+/// `{:.precision$g}`, which Rust doesn't have: fixed point for moderate exponents, scientific
+/// otherwise, without trailing zeros unless `alternate` asks for them. `keep_point` is the
+/// form floats take without a type, which keeps a `.0` and switches to scientific one
+/// exponent sooner.
+fn general(number: f64, precision: usize, upper: bool, alternate: bool, keep_point: bool) -> String {
+    let precision = precision.max(1);
+    let scientific = format!("{:.*e}", precision - 1, number);
+    let exponent = scientific.split_once('e').unwrap().1.parse::<i32>().unwrap();
+    let limit = if keep_point { precision as i32 - 1 } else { precision as i32 };
+    let formatted = if -4 <= exponent && exponent < limit {
+        format!("{:.*}", (precision as i32 - 1 - exponent) as usize, number)
+    } else {
+        python_exponent(&scientific, upper)
+    };
+    if alternate {
+        return formatted;
+    }
+
+    let (mantissa, exponent) = match formatted.find(['e', 'E']) {
+        Some(split) => formatted.split_at(split),
+        None => (formatted.as_str(), ""),
+    };
+    let mantissa = if mantissa.contains('.') { mantissa.trim_end_matches('0').trim_end_matches('.') } else { mantissa };
+    if keep_point && !mantissa.contains('.') && exponent.is_empty() {
+        return format!("{}.0", mantissa);
+    }
+    format!("{}{}", mantissa, exponent)
+}
+
+fn unknown_code(kind: char, type_name: &str) -> VmError {
+    VmError::ValueError(format!("Unknown format code '{}' for object of type '{}'", kind, type_name))
+}
+
+fn format_int(number: i64, spec: &FormatSpec) -> Result<String, VmError> {
+    let magnitude = number.unsigned_abs();
+    let (prefix, digits, group_size) = match spec.kind {
+        None | Some('d') | Some('n') => ("", magnitude.to_string(), 3),
+        Some('b') => ("0b", format!("{:b}", magnitude), 4),
+        Some('o') => ("0o", format!("{:o}", magnitude), 4),
+        Some('x') => ("0x", format!("{:x}", magnitude), 4),
+        Some('X') => ("0X", format!("{:X}", magnitude), 4),
+        Some('c') => {
+            let character = u32::try_from(number).ok().and_then(char::from_u32)
+                .ok_or_else(|| VmError::OverflowError(String::from("%c arg not in range(0x110000)")))?;
+            return Ok(spec.pad("", &character.to_string(), '<'));
+        },
+        Some('e' | 'E' | 'f' | 'F' | 'g' | 'G' | '%') => return format_float(number as f64, spec),
+        Some(kind) => return Err(unknown_code(kind, "int")),
+    };
+    if spec.precision.is_some() {
+        return Err(VmError::ValueError(String::from("Precision not allowed in integer format specifier")));
+    }
+    if spec.grouping == Some(',') && group_size != 3 {
+        return Err(VmError::ValueError(format!("Cannot specify ',' with '{}'.", spec.kind.unwrap())));
+    }
+
+    let prefix = if spec.alternate { prefix } else { "" };
+    Ok(spec.number(number < 0, prefix, &digits, "", group_size))
+}
+
+/// Formats a float. Without a type, floats are formatted like `repr()`, or like `g` but with
+/// at least one digit after the point when a precision is given.
+fn format_float(number: f64, spec: &FormatSpec) -> Result<String, VmError> {
+    let upper = spec.kind.is_some_and(|kind| kind.is_ascii_uppercase());
+    let magnitude = number.abs();
+    let precision = spec.precision.unwrap_or(6);
+    let digits = if number.is_nan() {
+        String::from("nan")
+    } else if number.is_infinite() {
+        String::from("inf")
+    } else {
+        match spec.kind {
+            Some('e' | 'E') => python_exponent(&format!("{:.*e}", precision, magnitude), upper),
+            Some('f' | 'F') => format!("{:.*}", precision, magnitude),
+            Some('g' | 'G' | 'n') => general(magnitude, precision, upper, spec.alternate, false),
+            Some('%') => format!("{:.*}%", precision, magnitude * 100.0),
+            None if spec.precision.is_none() => Value::Float(magnitude).python_repr(),
+            None => general(magnitude, precision, false, spec.alternate, true),
+            Some(kind) => return Err(unknown_code(kind, "float")),
+        }
+    };
+    let digits = if upper { digits.to_uppercase() } else { digits };
+
+    let split = digits.find(|character: char| !character.is_ascii_digit()).unwrap_or(digits.len());
+    let (whole, rest) = digits.split_at(split);
+    Ok(spec.number(number.is_sign_negative() && !number.is_nan(), "", whole, rest, 3))
+}
+
+impl Value {
+    /// `format(value, spec)`.
+    pub(crate) fn format_with_spec(&self, spec: &str) -> Result<String, VmError> {
+        if spec.is_empty() {
+            return Ok(self.to_string());
+        }
+        let spec = FormatSpec::parse(spec)?;
+        match self {
+            Value::Int(number) => format_int(*number, &spec),
+            Value::Bool(value) if spec.kind.is_some() => format_int(i64::from(*value), &spec),
+            Value::Float(number) => format_float(*number, &spec),
+            Value::Str(_) | Value::Bool(_) => {
+                let string = self.to_string();
+                match spec.kind {
+                    None | Some('s') => {},
+                    Some(kind) => return Err(unknown_code(kind, "str")),
+                }
+                if spec.sign.is_some() {
+                    return Err(VmError::ValueError(String::from("Sign not allowed in string format specifier")));
+                }
+                if spec.alternate {
+                    return Err(VmError::ValueError(String::from("Alternate form (#) not allowed in string format specifier")));
+                }
+                if spec.align == Some('=') {
+                    return Err(VmError::ValueError(String::from("'=' alignment not allowed in string format specifier")));
+                }
+                let truncated = match spec.precision {
+                    Some(precision) => string.chars().take(precision).collect(),
+                    None => string,
+                };
+                Ok(spec.pad("", &truncated, '<'))
+            },
+            _ => Err(VmError::TypeError(String::from("unsupported format string passed to object.__format__"))),
+        }
+    }
+}
+
+/// The `format(value[, format_spec])` built-in.
+pub(crate) fn builtin_format(vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    match args.as_slice() {
+        [value] => vm.python_format(value, "").map(Value::Str),
+        [value, Value::Str(spec)] => vm.python_format(value, spec).map(Value::Str),
+        [_, spec] => Err(VmError::TypeError(format!("format() argument 2 must be str, not {}", spec.python_repr()))),
+        _ => Err(VmError::TypeError(format!("format expected at most 2 arguments, got {}", args.len()))),
+    }
+}
@@ -0,0 +1,434 @@
+//! Building a `Frame` in Rust, for code objects the VM provides itself rather than loading
+//! from a bytecode file.
+
+use std::rc::Rc;
+
+use crate::{Frame, Instruction, Value};
+
+pub(crate) struct FrameBuilder {
+    frame: Frame,
+}
+
+impl FrameBuilder {
+    pub(crate) fn new(name: &str) -> FrameBuilder {
+        FrameBuilder {
+            frame: Frame {
+                co_name: Rc::new(String::from(name)),
+                co_qualname: Rc::default(),
+                instructions: vec![],
+                constants: vec![],
+                co_names: vec![],
+                co_varnames: vec![],
+                co_argcount: Some(0),
+                co_posonlyargcount: 0,
+                co_kwonlyargcount: 0,
+                co_flags: 0,
+                co_cellvars: vec![],
+                co_freevars: vec![],
+                closure: vec![],
+                defaults: vec![],
+                kwdefaults: vec![],
+                co_exceptiontable: vec![],
+            },
+        }
+    }
+
+    /// Adds a positional parameter. Parameters have to be added before other locals, as they
+    /// come first in `co_varnames`.
+    pub(crate) fn parameter(mut self, name: &str) -> FrameBuilder {
+        self.frame.co_varnames.push(Rc::new(String::from(name)));
+        self.frame.co_argcount = Some(self.frame.co_argcount.unwrap_or(0) + 1);
+        self
+    }
+
+    pub(crate) fn constant(mut self, value: Value) -> FrameBuilder {
+        self.frame.constants.push(value);
+        self
+    }
+
+    pub(crate) fn instruction(mut self, instruction: Instruction) -> FrameBuilder {
+        self.frame.instructions.push(instruction);
+        self
+    }
+
+    /// Loads the local `name`, adding it to `co_varnames` if it isn't a parameter or local yet.
+    pub(crate) fn load_fast(mut self, name: &str) -> FrameBuilder {
+        let index = self.varname_index(name);
+        self.instruction(Instruction::LoadFast(index))
+    }
+
+    pub(crate) fn build(self) -> Frame {
+        self.frame
+    }
+
+    fn varname_index(&mut self, name: &str) -> usize {
+        index_of(&mut self.frame.co_varnames, name)
+    }
+}
+
+/// The position of `name` in `names`, which it is added to if it isn't in them yet.
+fn index_of(names: &mut Vec<Rc<String>>, name: &str) -> usize {
+    names.iter().position(|existing| existing.as_str() == name).unwrap_or_else(|| {
+        names.push(Rc::new(String::from(name)));
+        names.len() - 1
+    })
+}
+
+/// Methods only tests use, to build frames that exercise instructions.
+#[cfg(test)]
+impl FrameBuilder {
+    /// Adds a name to `co_names`, for the instructions that take global, attribute and
+    /// module names.
+    pub(crate) fn name(mut self, name: &str) -> FrameBuilder {
+        self.frame.co_names.push(Rc::new(String::from(name)));
+        self
+    }
+
+    /// Adds `values` as constants, for frames whose instructions load constants by index.
+    pub(crate) fn constants(self, values: Vec<Value>) -> FrameBuilder {
+        values.into_iter().fold(self, FrameBuilder::constant)
+    }
+
+    /// Adds `names` to `co_names`, in order.
+    pub(crate) fn names(self, names: Vec<&str>) -> FrameBuilder {
+        names.into_iter().fold(self, FrameBuilder::name)
+    }
+
+    /// Adds `value` as a new constant and loads it.
+    pub(crate) fn push_const(self, value: Value) -> FrameBuilder {
+        let index = self.frame.constants.len();
+        self.constant(value).instruction(Instruction::LoadConst(index))
+    }
+
+    pub(crate) fn load_name(mut self, name: &str) -> FrameBuilder {
+        let index = index_of(&mut self.frame.co_names, name);
+        self.instruction(Instruction::LoadName(index))
+    }
+
+    pub(crate) fn store_name(mut self, name: &str) -> FrameBuilder {
+        let index = index_of(&mut self.frame.co_names, name);
+        self.instruction(Instruction::StoreName(index))
+    }
+
+    pub(crate) fn load_global(mut self, name: &str) -> FrameBuilder {
+        let index = index_of(&mut self.frame.co_names, name);
+        self.instruction(Instruction::LoadGlobal(index))
+    }
+
+    pub(crate) fn store_global(mut self, name: &str) -> FrameBuilder {
+        let index = index_of(&mut self.frame.co_names, name);
+        self.instruction(Instruction::StoreGlobal(index))
+    }
+
+    pub(crate) fn load_attr(mut self, name: &str) -> FrameBuilder {
+        let index = index_of(&mut self.frame.co_names, name);
+        self.instruction(Instruction::LoadAttr(index))
+    }
+
+    pub(crate) fn store_fast(mut self, name: &str) -> FrameBuilder {
+        let index = self.varname_index(name);
+        self.instruction(Instruction::StoreFast(index))
+    }
+
+    pub(crate) fn pop_top(self) -> FrameBuilder {
+        self.instruction(Instruction::PopTop)
+    }
+
+    pub(crate) fn dup_top(self) -> FrameBuilder {
+        self.instruction(Instruction::DupTop)
+    }
+
+    pub(crate) fn rot_two(self) -> FrameBuilder {
+        self.instruction(Instruction::RotTwo)
+    }
+
+    pub(crate) fn rot_three(self) -> FrameBuilder {
+        self.instruction(Instruction::RotThree)
+    }
+
+    /// Pushes the item `index` places down the stack, the top being 1.
+    pub(crate) fn copy(self, index: usize) -> FrameBuilder {
+        self.instruction(Instruction::Copy(index))
+    }
+
+    /// Swaps the top item with the one `index` places down, the top being 1.
+    pub(crate) fn swap(self, index: usize) -> FrameBuilder {
+        self.instruction(Instruction::Swap(index))
+    }
+
+    pub(crate) fn binary_add(self) -> FrameBuilder {
+        self.instruction(Instruction::BinaryAdd)
+    }
+
+    pub(crate) fn binary_subscr(self) -> FrameBuilder {
+        self.instruction(Instruction::BinarySubscr)
+    }
+
+    pub(crate) fn compare_op(self, op: usize) -> FrameBuilder {
+        self.instruction(Instruction::CompareOp(op))
+    }
+
+    pub(crate) fn build_tuple(self, count: usize) -> FrameBuilder {
+        self.instruction(Instruction::BuildTuple(count))
+    }
+
+    pub(crate) fn build_list(self, count: usize) -> FrameBuilder {
+        self.instruction(Instruction::BuildList(count))
+    }
+
+    pub(crate) fn build_map(self, count: usize) -> FrameBuilder {
+        self.instruction(Instruction::BuildMap(count))
+    }
+
+    pub(crate) fn unpack_sequence(self, count: usize) -> FrameBuilder {
+        self.instruction(Instruction::UnpackSequence(count))
+    }
+
+    pub(crate) fn call_function(self, argc: usize) -> FrameBuilder {
+        self.instruction(Instruction::CallFunction(argc))
+    }
+
+    pub(crate) fn return_value(self) -> FrameBuilder {
+        self.instruction(Instruction::ReturnValue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::FrameBuilder;
+    use crate::tests::{run, stack_reprs};
+    use crate::{Instruction, Value, Vm, VmConfig};
+
+    fn module() -> FrameBuilder {
+        FrameBuilder::new("<module>")
+    }
+
+    fn string(value: &str) -> Value {
+        Value::Str(String::from(value))
+    }
+
+    #[test]
+    fn push_const_pushes_the_constant() {
+        let frame = module().push_const(Value::Int(1)).push_const(string("a")).build();
+        assert_eq!(stack_reprs(&frame), ["1", "'a'"]);
+        assert_eq!(frame.constants.len(), 2);
+    }
+
+    #[test]
+    fn store_name_pops_the_value() {
+        let frame = module().push_const(Value::Int(1)).store_name("x").build();
+        assert!(run(&frame).unwrap().is_empty());
+    }
+
+    #[test]
+    fn load_name_pushes_what_store_name_stored() {
+        let frame = module().push_const(Value::Int(5)).store_name("x").load_name("x").load_name("x").build();
+        assert_eq!(stack_reprs(&frame), ["5", "5"]);
+        assert_eq!(frame.co_names.len(), 1);
+    }
+
+    #[test]
+    fn load_global_pushes_what_store_global_stored() {
+        let frame = module().push_const(Value::Float(2.5)).store_global("x").load_global("x").build();
+        assert_eq!(stack_reprs(&frame), ["2.5"]);
+    }
+
+    #[test]
+    fn load_fast_pushes_what_store_fast_stored() {
+        let frame = module().push_const(string("local")).store_fast("x").load_fast("x").build();
+        assert_eq!(stack_reprs(&frame), ["'local'"]);
+        assert_eq!(frame.co_varnames.len(), 1);
+    }
+
+    #[test]
+    fn pop_top_removes_the_top_item() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).pop_top().build();
+        assert_eq!(stack_reprs(&frame), ["1"]);
+    }
+
+    #[test]
+    fn dup_top_pushes_the_top_item_again() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).dup_top().build();
+        assert_eq!(stack_reprs(&frame), ["1", "2", "2"]);
+    }
+
+    #[test]
+    fn dup_top_shares_lists() {
+        let frame = module()
+            .build_list(0)
+            .dup_top()
+            .push_const(Value::Int(1))
+            .instruction(Instruction::ListAppend(2))
+            .build();
+        // The item is appended to the list below the copy, which shows through the copy too.
+        assert_eq!(stack_reprs(&frame), ["[1]", "[1]"]);
+    }
+
+    #[test]
+    fn rot_two_swaps_the_top_items() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).rot_two().build();
+        assert_eq!(stack_reprs(&frame), ["2", "1"]);
+    }
+
+    #[test]
+    fn rot_three_moves_the_top_item_below_the_next_two() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).push_const(Value::Int(3)).rot_three().build();
+        assert_eq!(stack_reprs(&frame), ["3", "1", "2"]);
+    }
+
+    #[test]
+    fn copy_pushes_an_item_from_down_the_stack() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).copy(2).build();
+        assert_eq!(stack_reprs(&frame), ["1", "2", "1"]);
+    }
+
+    #[test]
+    fn swap_exchanges_the_top_item_with_one_down_the_stack() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).push_const(Value::Int(3)).swap(3).build();
+        assert_eq!(stack_reprs(&frame), ["3", "2", "1"]);
+    }
+
+    #[test]
+    fn constants_and_names_are_indexed_in_order() {
+        let frame = module()
+            .constants(vec![Value::Int(42), string("unused")])
+            .names(vec!["x"])
+            .instruction(Instruction::LoadConst(0))
+            .instruction(Instruction::StoreName(0))
+            .load_name("x")
+            .build();
+        assert_eq!(stack_reprs(&frame), ["42"]);
+        assert_eq!(frame.co_names.len(), 1);
+    }
+
+    #[test]
+    fn build_tuple_replaces_the_items_with_a_tuple() {
+        let frame = module().push_const(Value::Int(0)).push_const(Value::Int(1)).push_const(string("a")).build_tuple(2).build();
+        assert_eq!(stack_reprs(&frame), ["0", "(1, 'a')"]);
+    }
+
+    #[test]
+    fn build_tuple_of_no_items_is_empty() {
+        let frame = module().build_tuple(0).build();
+        assert_eq!(stack_reprs(&frame), ["()"]);
+    }
+
+    #[test]
+    fn build_list_keeps_the_order_of_the_items() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).push_const(Value::Int(3)).build_list(3).build();
+        assert_eq!(stack_reprs(&frame), ["[1, 2, 3]"]);
+    }
+
+    #[test]
+    fn build_map_pairs_keys_and_values() {
+        let frame = module()
+            .push_const(string("a"))
+            .push_const(Value::Int(1))
+            .push_const(string("b"))
+            .push_const(Value::Int(2))
+            .build_map(2)
+            .build();
+        assert_eq!(stack_reprs(&frame), ["{'a': 1, 'b': 2}"]);
+    }
+
+    #[test]
+    fn unpack_sequence_pushes_the_first_item_last() {
+        let frame = module()
+            .push_const(Value::Tuple(vec![Value::Int(1), Value::Int(2), Value::Int(3)]))
+            .unpack_sequence(3)
+            .build();
+        assert_eq!(stack_reprs(&frame), ["3", "2", "1"]);
+    }
+
+    #[test]
+    fn binary_add_replaces_both_operands() {
+        let frame = module().push_const(Value::Int(7)).push_const(Value::Int(2)).binary_add().build();
+        assert_eq!(stack_reprs(&frame), ["9"]);
+    }
+
+    #[test]
+    fn binary_subtract_subtracts_the_top_from_the_one_below() {
+        let frame = module().push_const(Value::Int(7)).push_const(Value::Int(2)).instruction(Instruction::BinarySubtract).build();
+        assert_eq!(stack_reprs(&frame), ["5"]);
+    }
+
+    #[test]
+    fn binary_multiply_repeats_strings() {
+        let frame = module().push_const(string("ab")).push_const(Value::Int(2)).instruction(Instruction::BinaryMultiply).build();
+        assert_eq!(stack_reprs(&frame), ["'abab'"]);
+    }
+
+    #[test]
+    fn compare_op_pushes_a_bool() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).compare_op(0).build();
+        assert_eq!(stack_reprs(&frame), ["True"]);
+    }
+
+    #[test]
+    fn contains_op_looks_in_the_top_item() {
+        let frame = module()
+            .push_const(Value::Int(2))
+            .push_const(Value::Tuple(vec![Value::Int(1), Value::Int(2)]))
+            .instruction(Instruction::ContainsOp(1))
+            .build();
+        assert_eq!(stack_reprs(&frame), ["False"]);
+    }
+
+    #[test]
+    fn build_slice_and_binary_subscr_select_items() {
+        let frame = module()
+            .push_const(Value::Tuple(vec![Value::Int(1), Value::Int(2), Value::Int(3)]))
+            .push_const(Value::Int(1))
+            .push_const(Value::Nonetype)
+            .instruction(Instruction::BuildSlice(2))
+            .binary_subscr()
+            .build();
+        assert_eq!(stack_reprs(&frame), ["(2, 3)"]);
+    }
+
+    #[test]
+    fn build_string_joins_the_strings() {
+        let frame = module().push_const(string("a")).push_const(string("b")).instruction(Instruction::BuildString(2)).build();
+        assert_eq!(stack_reprs(&frame), ["'ab'"]);
+    }
+
+    #[test]
+    fn call_function_replaces_the_function_and_arguments_with_the_result() {
+        let double = FrameBuilder::new("double")
+            .parameter("x")
+            .load_fast("x")
+            .push_const(Value::Int(2))
+            .instruction(Instruction::BinaryMultiply)
+            .return_value()
+            .build();
+        let frame = module()
+            .push_const(Value::Int(0))
+            .push_const(Value::Frame(Rc::new(double)))
+            .push_const(Value::Int(21))
+            .call_function(1)
+            .build();
+        assert_eq!(stack_reprs(&frame), ["0", "42"]);
+    }
+
+    #[test]
+    fn load_attr_replaces_the_object_with_its_attribute() {
+        let frame = module()
+            .push_const(Value::Int(0))
+            .push_const(Value::Nonetype)
+            .name("math")
+            .instruction(Instruction::ImportName(0))
+            .load_attr("pi")
+            .build();
+        assert_eq!(stack_reprs(&frame), ["3.141592653589793"]);
+    }
+
+    #[test]
+    fn return_value_ends_the_frame_with_the_top_item() {
+        let frame = module().push_const(Value::Int(1)).push_const(Value::Int(2)).return_value().push_const(Value::Int(3)).build();
+        let mut vm = Vm::new(VmConfig::default());
+        assert_eq!(vm.run_frame(&frame).unwrap().python_repr(), "2");
+        assert_eq!(stack_reprs(&frame), ["1"]);
+    }
+}
@@ -0,0 +1,338 @@
+//! Generators. Calling a function that contains `yield` gives a generator holding the
+//! function's suspended frame, which `YIELD_VALUE` gives back control from and `send()`,
+//! `throw()`, `close()` and `for` loops resume. Coroutines, made by calling an `async def`
+//! function, are generators that `await` resumes instead of `for` loops.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+use crate::exceptions::{self, Exception};
+use crate::modules::{bound_method, expect_args};
+use crate::{relative_target, Frame, FrameState, Instruction, NativeFn, Value, Vm, VmError};
+
+pub(crate) struct Generator {
+    frame: Rc<Frame>,
+    /// The suspended frame, `None` once the generator returned or raised.
+    state: RefCell<Option<FrameState>>,
+    started: Cell<bool>,
+    running: Cell<bool>,
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.is_coroutine() { "coroutine" } else { "generator" };
+        write!(f, "<{} object {} at {:#x}>", kind, self.frame.co_name, self as *const Generator as usize)
+    }
+}
+
+/// How a resumed generator gave control back.
+pub(crate) enum Resumed {
+    Yielded(Value),
+    Returned(Value),
+}
+
+/// `CO_COROUTINE`, set for `async def` functions.
+const CO_COROUTINE: u32 = 0x80;
+
+impl Frame {
+    /// Whether calling the function makes a generator instead of running it. CPython marks
+    /// these with `CO_GENERATOR`, which is set for code that yields, and coroutines with
+    /// `CO_COROUTINE`, which is set even if they never await.
+    pub(crate) fn is_generator(&self) -> bool {
+        self.is_coroutine()
+            || self.instructions.iter().any(|instruction| matches!(instruction, Instruction::YieldValue(_) | Instruction::YieldFrom | Instruction::GenStart(_)))
+    }
+
+    pub(crate) fn is_coroutine(&self) -> bool {
+        self.co_flags & CO_COROUTINE != 0
+    }
+}
+
+impl Generator {
+    pub(crate) fn new(frame: Rc<Frame>, state: FrameState) -> Generator {
+        Generator { frame, state: RefCell::new(Some(state)), started: Cell::new(false), running: Cell::new(false) }
+    }
+
+    pub(crate) fn is_coroutine(&self) -> bool {
+        self.frame.is_coroutine()
+    }
+
+    pub(crate) fn get_attr(generator: &Rc<Generator>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "__next__" => |vm, args| {
+                expect_args("__next__", &args[1..], 0, 0)?;
+                returned_value(vm.resume(receiver(&args), Value::Nonetype, None)?)
+            },
+            "send" => |vm, mut args| {
+                expect_args("send", &args[1..], 1, 1)?;
+                let value = args.pop().unwrap();
+                returned_value(vm.resume(receiver(&args), value, None)?)
+            },
+            "throw" => |vm, args| {
+                expect_args("throw", &args[1..], 1, 2)?;
+                let exception = match (&args[1], args.get(2)) {
+                    (Value::ExceptionType(kind), value) => Rc::new(Exception { kind: Rc::clone(kind), args: value.cloned().into_iter().collect() }),
+                    (Value::Exception(exception), None) => Rc::clone(exception),
+                    (Value::Exception(_), Some(_)) => {
+                        return Err(VmError::TypeError(String::from("instance exception may not have a separate value")));
+                    },
+                    (other, _) => return Err(VmError::TypeError(format!(
+                        "exceptions must be classes or instances deriving from BaseException, not {}", other.python_repr()
+                    ))),
+                };
+                returned_value(vm.resume(receiver(&args), Value::Nonetype, Some(VmError::Exception(exception)))?)
+            },
+            "close" => |vm, args| {
+                expect_args("close", &args[1..], 0, 0)?;
+                vm.close_generator(receiver(&args))?;
+                Ok(Value::Nonetype)
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Generator(Rc::clone(generator)), name, method))
+    }
+}
+
+/// What `send()` and `next()` give for a resumed generator: the value it yielded, or
+/// `StopIteration` with the value it returned.
+fn returned_value(resumed: Resumed) -> Result<Value, VmError> {
+    match resumed {
+        Resumed::Yielded(value) => Ok(value),
+        Resumed::Returned(value) => Err(VmError::Exception(stop_iteration(value))),
+    }
+}
+
+/// `StopIteration(value)`, without arguments for `None` like CPython raises it.
+pub(crate) fn stop_iteration(value: Value) -> Rc<Exception> {
+    let args = match value {
+        Value::Nonetype => vec![],
+        value => vec![value],
+    };
+    Rc::new(Exception { kind: exceptions::exception_type("StopIteration"), args })
+}
+
+fn receiver(args: &[Value]) -> &Rc<Generator> {
+    match &args[0] {
+        Value::Generator(generator) => generator,
+        _ => unreachable!("generator methods are only reachable through a bound generator"),
+    }
+}
+
+impl Vm {
+    /// Runs the generator until it yields or finishes. `sent` becomes the value of the `yield`
+    /// it is suspended at, or `thrown` is raised there instead.
+    pub(crate) fn resume(&mut self, generator: &Rc<Generator>, sent: Value, thrown: Option<VmError>) -> Result<Resumed, VmError> {
+        if generator.running.get() {
+            return Err(VmError::ValueError(String::from("generator already executing")));
+        }
+        let mut state = match generator.state.borrow_mut().take() {
+            Some(state) => state,
+            None => return thrown.map_or(Ok(Resumed::Returned(Value::Nonetype)), Err),
+        };
+
+        let mut raised = None;
+        if let Some(error) = thrown {
+            match self.throw_to_delegate(&generator.frame, &state, error) {
+                Ok(Resumed::Yielded(value)) => {
+                    generator.state.replace(Some(state));
+                    return Ok(Resumed::Yielded(value));
+                },
+                Ok(Resumed::Returned(value)) => end_delegation(&generator.frame, &mut state, value),
+                Err(error) => {
+                    // Like CPython, the exception is raised at the `YIELD_VALUE` the generator
+                    // is suspended after, as if it was resumed with `None`. Python 3.12's
+                    // `CLEANUP_THROW` expects that `None` on the stack.
+                    if state.index > 0 && matches!(generator.frame.instructions[state.index - 1], Instruction::YieldValue(_)) {
+                        state.index -= 1;
+                        state.stack.push(Value::Nonetype);
+                    }
+                    raised = Some(error);
+                },
+            }
+        } else if generator.started.get() {
+            state.stack.push(sent);
+        } else if !matches!(sent, Value::Nonetype) {
+            generator.state.replace(Some(state));
+            return Err(VmError::TypeError(String::from("can't send non-None value to a just-started generator")));
+        } else if let Some(Instruction::GenStart(_)) = generator.frame.instructions.first() {
+            // Python 3.10 pops the value the generator is started with.
+            state.stack.push(sent);
+        }
+        generator.started.set(true);
+
+        generator.running.set(true);
+        let result = self.run_state(&generator.frame, state, raised);
+        generator.running.set(false);
+        match result {
+            Ok(mut state) if state.yielded => {
+                state.yielded = false;
+                let value = mem::replace(&mut state.return_value, Value::Nonetype);
+                generator.state.replace(Some(state));
+                Ok(Resumed::Yielded(value))
+            },
+            Ok(state) => Ok(Resumed::Returned(state.return_value)),
+            // PEP 479, a `StopIteration` leaking out of the generator would look like its end.
+            Err(VmError::Exception(exception)) if exception.kind.name.as_str() == "StopIteration" => {
+                Err(VmError::Exception(Exception::new("RuntimeError", String::from("generator raised StopIteration"))))
+            },
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Passes an exception thrown into a generator suspended at `yield from` on to the generator
+    /// it is running, which can handle it. `close()` closes that generator and then raises
+    /// `GeneratorExit` in this one. An `Err` is the exception to raise in this generator.
+    fn throw_to_delegate(&mut self, frame: &Frame, state: &FrameState, error: VmError) -> Result<Resumed, VmError> {
+        let delegating = match frame.instructions.get(state.index) {
+            Some(Instruction::YieldFrom) => true,
+            _ => send_loop_end(frame, state.index).is_some(),
+        };
+        let delegate = match state.stack.last() {
+            Some(Value::Generator(delegate)) if delegating => Rc::clone(delegate),
+            _ => return Err(error),
+        };
+        match error {
+            VmError::Exception(exception) if exception.kind.name.as_str() == "GeneratorExit" => {
+                self.close_generator(&delegate)?;
+                Err(VmError::Exception(exception))
+            },
+            error => self.resume(&delegate, Value::Nonetype, Some(error)),
+        }
+    }
+
+    /// `YIELD_FROM`: sends the value on top of the stack to the iterator below it, and yields
+    /// what that gives without moving past the instruction, so the next value sent to this
+    /// generator goes to the iterator too. Once the iterator is exhausted its return value
+    /// replaces it.
+    pub(crate) fn yield_from(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let sent = state.stack.pop().unwrap();
+        let receiver = state.stack.last().unwrap().clone();
+        let resumed = self.send_to(receiver, sent)?;
+
+        let state = self.state();
+        match resumed {
+            Resumed::Yielded(value) => {
+                state.return_value = value;
+                state.yielded = true;
+            },
+            Resumed::Returned(value) => {
+                state.stack.pop();
+                state.stack.push(value);
+                state.index += 1;
+            },
+        }
+        Ok(())
+    }
+
+    /// `SEND`: like `YIELD_FROM`, but the `YIELD_VALUE` after it yields what the iterator gives,
+    /// and the jump back to it sends the next value.
+    pub(crate) fn send(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let sent = state.stack.pop().unwrap();
+        let receiver = state.stack.last().unwrap().clone();
+        let resumed = self.send_to(receiver, sent)?;
+
+        let state = self.state();
+        match resumed {
+            Resumed::Yielded(value) => {
+                state.stack.push(value);
+                state.index += 1;
+            },
+            Resumed::Returned(value) => end_send_loop(frame, state, relative_target(state.index, arg), value),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn end_send(&mut self) {
+        let state = self.state();
+        let value = state.stack.pop().unwrap();
+        state.stack.pop();
+        state.stack.push(value);
+
+        state.index += 1;
+    }
+
+    pub(crate) fn cleanup_throw(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let exception = match state.stack.pop().unwrap() {
+            Value::Exception(exception) if exception.kind.name.as_str() == "StopIteration" => exception,
+            Value::Exception(exception) => return Err(VmError::Exception(exception)),
+            other => return Err(VmError::InternalError(format!("CLEANUP_THROW of {}", other.python_repr()))),
+        };
+        state.stack.truncate(state.stack.len() - 2);
+        state.stack.push(Value::Nonetype);
+        state.stack.push(exception.args.first().cloned().unwrap_or(Value::Nonetype));
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// Sends `sent` to the iterator of a `yield from` or `await`. Iterators that aren't
+    /// generators can only be sent `None`, which gets their next item.
+    fn send_to(&mut self, receiver: Value, sent: Value) -> Result<Resumed, VmError> {
+        match receiver {
+            Value::Generator(delegate) => self.resume(&delegate, sent, None),
+            Value::Iterator(iterator) if matches!(sent, Value::Nonetype) => {
+                Ok(iterator.next_item().map_or(Resumed::Returned(Value::Nonetype), Resumed::Yielded))
+            },
+            Value::File(file) if matches!(sent, Value::Nonetype) => {
+                Ok(file.next_line()?.map_or(Resumed::Returned(Value::Nonetype), Resumed::Yielded))
+            },
+            other => Err(VmError::AttributeError(format!("{} has no attribute 'send'", other.python_repr()))),
+        }
+    }
+
+    /// `close()`: raises `GeneratorExit` at the `yield` the generator is suspended at, which
+    /// has to end it.
+    pub(crate) fn close_generator(&mut self, generator: &Rc<Generator>) -> Result<(), VmError> {
+        if !generator.started.get() {
+            generator.state.replace(None);
+            return Ok(());
+        }
+        let exit = Rc::new(Exception { kind: exceptions::exception_type("GeneratorExit"), args: vec![] });
+        match self.resume(generator, Value::Nonetype, Some(VmError::Exception(exit))) {
+            Ok(Resumed::Yielded(_)) => Err(VmError::Exception(Exception::new("RuntimeError", String::from("generator ignored GeneratorExit")))),
+            Ok(Resumed::Returned(_)) => Ok(()),
+            Err(VmError::Exception(exception))
+                if matches!(exception.kind.name.as_str(), "GeneratorExit" | "StopIteration") => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// The instruction the `SEND` loop ends at when a generator suspended at `index` yielded from
+/// the `YIELD_VALUE` of one, `None` when it didn't.
+fn send_loop_end(frame: &Frame, index: usize) -> Option<usize> {
+    let send = frame.instructions[..index].iter().rposition(|instruction| !matches!(instruction, Instruction::YieldValue(_) | Instruction::Cache))?;
+    match frame.instructions[send] {
+        Instruction::Send(arg) if matches!(frame.instructions[index - 1], Instruction::YieldValue(_)) => Some(relative_target(send, arg)),
+        _ => None,
+    }
+}
+
+/// Continues a generator whose delegate returned `value` after the `yield from` or `await` it
+/// is suspended in.
+fn end_delegation(frame: &Frame, state: &mut FrameState, value: Value) {
+    match send_loop_end(frame, state.index) {
+        Some(end) => end_send_loop(frame, state, end, value),
+        None => {
+            state.stack.pop();
+            state.stack.push(value);
+            state.index += 1;
+        },
+    }
+}
+
+/// Jumps to `end` with the value a `SEND` loop ended with. Python 3.12's `END_SEND` there pops
+/// the iterator, before that the value replaces it.
+fn end_send_loop(frame: &Frame, state: &mut FrameState, end: usize, value: Value) {
+    if !matches!(frame.instructions.get(end), Some(Instruction::EndSend)) {
+        state.stack.pop();
+    }
+    state.stack.push(value);
+    state.index = end;
+}
@@ -0,0 +1,184 @@
+//! `hash()`, with the values a 32-bit CPython build gives, since ints are 32 bits.
+
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::rc::Rc;
+
+use num_bigint::{BigInt, Sign};
+
+use crate::{set, Value, Vm, VmError};
+
+/// `sys.hash_info.modulus`, the prime numbers are hashed modulo.
+const MODULUS: u32 = (1 << 31) - 1;
+const MODULUS_BITS: i32 = 31;
+const INFINITY_HASH: i32 = 314_159;
+/// CPython's fixed hash of `None`.
+const NONE_HASH: u32 = 0xFCA8_6420;
+
+thread_local! {
+    /// The per-process key strings are hashed with, like `PYTHONHASHSEED=random`.
+    static STRING_KEYS: RandomState = RandomState::new();
+}
+
+/// -1 is the error return of `tp_hash` in CPython, so no hash is ever -1.
+fn fix_minus_one(hash: i32) -> i32 {
+    if hash == -1 { -2 } else { hash }
+}
+
+fn hash_int(number: i64) -> i32 {
+    let magnitude = (number.unsigned_abs() % u64::from(MODULUS)) as i32;
+    fix_minus_one(if number < 0 { -magnitude } else { magnitude })
+}
+
+fn hash_big_int(number: &BigInt) -> i32 {
+    let magnitude = i32::try_from(number.magnitude() % MODULUS).unwrap();
+    fix_minus_one(if number.sign() == Sign::Minus { -magnitude } else { magnitude })
+}
+
+/// `(mantissa, exponent)` with `number == mantissa * 2 ** exponent` and `0.5 <= mantissa < 1`.
+fn frexp(number: f64) -> (f64, i32) {
+    if number == 0.0 {
+        return (number, 0);
+    }
+    // Subnormals are scaled up first so their exponent bits mean something.
+    let (number, offset) = if number.abs() < f64::MIN_POSITIVE { (number * 2f64.powi(54), -54) } else { (number, 0) };
+    let bits = number.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32 - 1022;
+    let mantissa = f64::from_bits((bits & !(0x7ff << 52)) | (1022 << 52));
+    (mantissa, exponent + offset)
+}
+
+/// `_Py_HashDouble`: the hash of the rational the float equals, so `hash(2.0) == hash(2)`.
+fn hash_float(number: f64) -> i32 {
+    if number.is_infinite() {
+        return if number > 0.0 { INFINITY_HASH } else { -INFINITY_HASH };
+    }
+    if number.is_nan() {
+        return 0;
+    }
+
+    let (mut mantissa, mut exponent) = frexp(number.abs());
+    let mut hash: u32 = 0;
+    while mantissa != 0.0 {
+        hash = ((hash << 28) & MODULUS) | hash >> (MODULUS_BITS - 28);
+        mantissa *= 268_435_456.0;
+        exponent -= 28;
+        let digits = mantissa as u32;
+        mantissa -= f64::from(digits);
+        hash += digits;
+        if hash >= MODULUS {
+            hash -= MODULUS;
+        }
+    }
+
+    let exponent = if exponent >= 0 {
+        exponent % MODULUS_BITS
+    } else {
+        MODULUS_BITS - 1 - ((-1 - exponent) % MODULUS_BITS)
+    } as u32;
+    hash = ((hash << exponent) & MODULUS) | hash >> (MODULUS_BITS as u32 - exponent);
+
+    let hash = hash as i32;
+    fix_minus_one(if number < 0.0 { -hash } else { hash })
+}
+
+/// SipHash of the bytes with the process' keys, truncated like a 32-bit build does.
+fn hash_bytes(bytes: &[u8]) -> i32 {
+    if bytes.is_empty() {
+        return 0;
+    }
+    let mut hasher = STRING_KEYS.with(RandomState::build_hasher);
+    hasher.write(bytes);
+    fix_minus_one(hasher.finish() as i32)
+}
+
+/// The xxHash based tuple hash of CPython 3.8 and later.
+fn hash_tuple(values: &[Value]) -> Result<i32, VmError> {
+    const PRIME_1: u32 = 2_654_435_761;
+    const PRIME_2: u32 = 2_246_822_519;
+    const PRIME_5: u32 = 374_761_393;
+
+    let mut hash = PRIME_5;
+    for value in values {
+        let lane = value.python_hash()? as u32;
+        hash = hash.wrapping_add(lane.wrapping_mul(PRIME_2)).rotate_left(13).wrapping_mul(PRIME_1);
+    }
+    hash = hash.wrapping_add(values.len() as u32 ^ (PRIME_5 ^ 3_527_539));
+
+    Ok(if hash == u32::MAX { 1_546_275_796 } else { hash as i32 })
+}
+
+/// `_Py_HashPointer`, used for objects that are only equal to themselves.
+fn hash_pointer<T: ?Sized>(pointer: &Rc<T>) -> i32 {
+    let address = Rc::as_ptr(pointer) as *const u8 as usize;
+    fix_minus_one(address.rotate_right(4) as i32)
+}
+
+impl Value {
+    /// `hash(value)`. Values that compare equal hash equal, including `1`, `1.0` and `True`.
+    pub(crate) fn python_hash(&self) -> Result<i32, VmError> {
+        match self {
+            Value::Int(number) => Ok(hash_int(*number)),
+            Value::BigInt(number) => Ok(hash_big_int(number)),
+            Value::Bool(value) => Ok(i32::from(*value)),
+            Value::Float(number) => Ok(hash_float(*number)),
+            Value::Str(string) => Ok(hash_bytes(string.as_bytes())),
+            Value::Bytes(bytes) => Ok(hash_bytes(bytes)),
+            Value::Tuple(values) => hash_tuple(values),
+            Value::FrozenSet(set) => set::hash(set),
+            Value::Nonetype => Ok(NONE_HASH as i32),
+            Value::NativeFunction(name, ..) => Ok(hash_pointer(name)),
+            Value::Module(module) => Ok(hash_pointer(module)),
+            Value::File(file) => Ok(hash_pointer(file)),
+            Value::Regex(pattern) => Ok(hash_pointer(pattern)),
+            Value::Match(matched) => Ok(hash_pointer(matched)),
+            Value::NativeType(native_type) => Ok(hash_pointer(native_type)),
+            Value::Hash(hash) => Ok(hash_pointer(hash)),
+            Value::Cell(cell) => Ok(hash_pointer(cell)),
+            Value::List(_) => Err(VmError::TypeError(String::from("unhashable type: 'list'"))),
+            Value::Dict(_) => Err(VmError::TypeError(String::from("unhashable type: 'dict'"))),
+            Value::Set(_) => Err(VmError::TypeError(String::from("unhashable type: 'set'"))),
+            Value::ByteArray(_) => Err(VmError::TypeError(String::from("unhashable type: 'bytearray'"))),
+            Value::MemoryView(view) if view.is_readonly() => Ok(hash_bytes(&view.to_bytes())),
+            Value::MemoryView(_) => Err(VmError::ValueError(String::from("cannot hash writable memoryview object"))),
+            Value::Slice(_) => Err(VmError::TypeError(String::from("unhashable type: 'slice'"))),
+            Value::Iterator(iterator) => Ok(hash_pointer(iterator)),
+            Value::Generator(generator) => Ok(hash_pointer(generator)),
+            Value::Thread(thread) => Ok(hash_pointer(thread)),
+            Value::Lock(lock) => Ok(hash_pointer(lock)),
+            Value::Process(process) => Ok(hash_pointer(process)),
+            Value::ExceptionType(kind) => Ok(hash_pointer(kind)),
+            Value::Exception(exception) => Ok(hash_pointer(exception)),
+            Value::Class(class) => Ok(hash_pointer(class)),
+            Value::Instance(instance) => Ok(hash_pointer(instance)),
+            Value::EnumMember(member) => Ok(hash_pointer(member)),
+            Value::Super(value) => Ok(hash_pointer(value)),
+            Value::Property(property) => Ok(hash_pointer(property)),
+            Value::WeakRef(reference) => match reference.upgrade() {
+                Some(instance) => Ok(hash_pointer(&instance)),
+                None => Err(VmError::TypeError(String::from("weak object has gone away"))),
+            },
+            _ => Err(VmError::Unimplemented(format!("hash() of {}", self.python_repr()))),
+        }
+    }
+}
+
+/// Hashes the way `hash()` does. Callers check `python_hash` first, values it rejects can't be keys.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_i32(self.python_hash().expect("unhashable values are rejected before hashing"));
+    }
+}
+
+/// Sets need their items to be `Eq`. Like in CPython, a NaN in a set is only found again by
+/// identity, which values here don't have, so it is never found.
+impl Eq for Value {}
+
+/// The `hash(object)` built-in.
+pub(crate) fn builtin_hash(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    match args.as_slice() {
+        [value] => value.python_hash().map(|hash| Value::Int(i64::from(hash))),
+        _ => Err(VmError::TypeError(format!("hash() takes exactly one argument ({} given)", args.len()))),
+    }
+}
@@ -0,0 +1,87 @@
+//! `id()`. Ids are numbers handed out in order instead of addresses, since values that are copied
+//! rather than shared have no lasting address.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::{Value, Vm, VmError};
+
+/// What an id is given to: an object behind an `Rc`, or the repr of an immutable value.
+#[derive(PartialEq, Eq, Hash)]
+enum Identity {
+    Address(usize),
+    Interned(String),
+}
+
+struct Ids {
+    ids: HashMap<Identity, i64>,
+    next: i64,
+}
+
+thread_local! {
+    static IDS: RefCell<Ids> = RefCell::new(Ids { ids: HashMap::new(), next: 1 });
+}
+
+fn address<T: ?Sized>(pointer: &Rc<T>) -> Identity {
+    Identity::Address(Rc::as_ptr(pointer) as *const u8 as usize)
+}
+
+impl Value {
+    /// `id(value)`. Values are copied rather than shared, so immutable values are interned
+    /// by their repr, like CPython's small ints, and equal ones share an id. An object's id
+    /// can be reused once it is freed, as in CPython.
+    pub(crate) fn python_id(&self) -> Result<i64, VmError> {
+        let identity = match self {
+            Value::Int(_) | Value::BigInt(_) | Value::Bool(_) | Value::Float(_) | Value::Str(_) | Value::Bytes(_) | Value::Tuple(_)
+                | Value::Nonetype => Identity::Interned(self.python_repr()),
+            Value::FrozenSet(set) => address(set),
+            Value::Set(set) => address(set),
+            Value::Frame(frame) => address(frame),
+            Value::NativeFunction(name, ..) => address(name),
+            Value::Module(module) => address(module),
+            Value::File(file) => address(file),
+            Value::Regex(pattern) => address(pattern),
+            Value::Match(matched) => address(matched),
+            Value::NativeType(native_type) => address(native_type),
+            Value::List(values) => address(values),
+            Value::Dict(dict) => address(dict),
+            Value::Cell(cell) => address(cell),
+            Value::Hash(hash) => address(hash),
+            Value::ByteArray(bytes) => address(bytes),
+            Value::MemoryView(view) => address(view),
+            Value::Slice(slice) => address(slice),
+            Value::Iterator(iterator) => address(iterator),
+            Value::Generator(generator) => address(generator),
+            Value::Thread(thread) => address(thread),
+            Value::Lock(lock) => address(lock),
+            Value::Process(process) => address(process),
+            Value::ExceptionType(kind) => address(kind),
+            Value::Exception(exception) => address(exception),
+            Value::Class(class) => address(class),
+            Value::Instance(instance) => address(instance),
+            Value::EnumMember(member) => address(member),
+            Value::Super(value) => address(value),
+            Value::Property(property) => address(property),
+            _ => return Err(VmError::Unimplemented(format!("id() of {}", self.python_repr()))),
+        };
+
+        IDS.with(|ids| {
+            let mut ids = ids.borrow_mut();
+            let next = ids.next;
+            let id = *ids.ids.entry(identity).or_insert(next);
+            if id == next {
+                ids.next += 1;
+            }
+            Ok(id)
+        })
+    }
+}
+
+/// The `id(object)` built-in.
+pub(crate) fn builtin_id(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    match args.as_slice() {
+        [value] => value.python_id().map(Value::Int),
+        _ => Err(VmError::TypeError(format!("id() takes exactly one argument ({} given)", args.len()))),
+    }
+}
@@ -0,0 +1,62 @@
+//! The intrinsic functions Python 3.12's `CALL_INTRINSIC_1` calls, which replace instructions
+//! that earlier versions had for rare operations like `PRINT_EXPR` and `IMPORT_STAR`.
+
+use crate::exceptions::Exception;
+use crate::{Value, Vm, VmError};
+
+type Intrinsic1 = fn(&mut Vm, Value) -> Result<Value, VmError>;
+
+/// The index of `INTRINSIC_LIST_TO_TUPLE`, which Python 3.9's `LIST_TO_TUPLE` also runs.
+pub(crate) const LIST_TO_TUPLE: usize = 6;
+
+/// CPython's `_PyIntrinsics_UnaryFunctions`, by index. The ones without an implementation
+/// raise `Unimplemented` with their name.
+const INTRINSICS_1: [(&str, Option<Intrinsic1>); 12] = [
+    ("INTRINSIC_1_INVALID", None),
+    ("INTRINSIC_PRINT", Some(|vm, value| {
+        vm.display_value(value)?;
+        Ok(Value::Nonetype)
+    })),
+    ("INTRINSIC_IMPORT_STAR", Some(|vm, module| {
+        vm.import_public_names(module)?;
+        Ok(Value::Nonetype)
+    })),
+    ("INTRINSIC_STOPITERATION_ERROR", Some(|_, exception| match exception {
+        Value::Exception(exception) if exception.kind.name.as_str() == "StopIteration" => {
+            Ok(Value::Exception(Exception::new("RuntimeError", String::from("generator raised StopIteration"))))
+        },
+        exception => Ok(exception),
+    })),
+    ("INTRINSIC_ASYNC_GEN_WRAP", None),
+    ("INTRINSIC_UNARY_POSITIVE", Some(|_, value| match value {
+        Value::Bool(value) => Ok(Value::Int(i64::from(value))),
+        value @ (Value::Int(_) | Value::Float(_)) => Ok(value),
+        other => Err(VmError::TypeError(format!("bad operand type for unary +: \'{}\'", other.type_name()))),
+    })),
+    ("INTRINSIC_LIST_TO_TUPLE", Some(|_, list| match list {
+        Value::List(values) => Ok(Value::Tuple(values.borrow().clone())),
+        other => Err(VmError::InternalError(format!("INTRINSIC_LIST_TO_TUPLE of {}", other.python_repr()))),
+    })),
+    ("INTRINSIC_TYPEVAR", None),
+    ("INTRINSIC_PARAMSPEC", None),
+    ("INTRINSIC_TYPEVARTUPLE", None),
+    ("INTRINSIC_SUBSCRIPT_GENERIC", None),
+    ("INTRINSIC_TYPEALIAS", None),
+];
+
+impl Vm {
+    pub(crate) fn call_intrinsic_1(&mut self, arg: usize) -> Result<(), VmError> {
+        let function = match INTRINSICS_1.get(arg) {
+            Some((_, Some(function))) => *function,
+            Some((name, None)) => return Err(VmError::Unimplemented(format!("CALL_INTRINSIC_1 {}", name))),
+            None => return Err(VmError::InternalError(format!("CALL_INTRINSIC_1 with unknown intrinsic {}", arg))),
+        };
+        let value = self.state().stack.pop().unwrap();
+        let result = function(self, value)?;
+
+        let state = self.state();
+        state.stack.push(result);
+        state.index += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+//! Iterators over the built-in sequences, which `GET_ITER` makes for `for` loops and
+//! comprehensions.
+
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::generator::{self, Resumed};
+use crate::modules::expect_args;
+use crate::{Value, Vm, VmError};
+
+pub(crate) struct SequenceIterator {
+    /// A list, which is read as it is iterated so appends during the loop are seen, or a
+    /// tuple of the items of any other sequence.
+    items: Value,
+    kind: &'static str,
+    index: Cell<usize>,
+}
+
+impl fmt::Debug for SequenceIterator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} object at {:#x}>", self.kind, self as *const SequenceIterator as usize)
+    }
+}
+
+impl SequenceIterator {
+    /// An iterator over `sequence`, `None` if it isn't a built-in sequence.
+    pub(crate) fn new(sequence: &Value) -> Option<SequenceIterator> {
+        let (items, kind) = match sequence {
+            Value::List(_) => (sequence.clone(), "list_iterator"),
+            Value::Tuple(_) => (sequence.clone(), "tuple_iterator"),
+            Value::Str(_) => (Value::Tuple(sequence.sequence_items()?), "str_iterator"),
+            Value::Bytes(_) => (Value::Tuple(sequence.sequence_items()?), "bytes_iterator"),
+            Value::ByteArray(_) => (Value::Tuple(sequence.sequence_items()?), "bytearray_iterator"),
+            Value::MemoryView(_) => (Value::Tuple(sequence.sequence_items()?), "memory_iterator"),
+            Value::FrozenSet(_) | Value::Set(_) => (Value::Tuple(sequence.sequence_items()?), "set_iterator"),
+            Value::Dict(_) => (Value::Tuple(sequence.sequence_items()?), "dict_keyiterator"),
+            _ => return None,
+        };
+        Some(SequenceIterator { items, kind, index: Cell::new(0) })
+    }
+
+    pub(crate) fn type_name(&self) -> &'static str {
+        self.kind
+    }
+
+    pub(crate) fn next_item(&self) -> Option<Value> {
+        let index = self.index.get();
+        let item = match &self.items {
+            Value::List(values) => values.borrow().get(index).cloned(),
+            Value::Tuple(values) => values.get(index).cloned(),
+            _ => unreachable!("iterators only hold lists and tuples"),
+        };
+        if item.is_some() {
+            self.index.set(index + 1);
+        }
+        item
+    }
+}
+
+impl Vm {
+    /// The next item of `iterator`, `None` once it is exhausted. Instances are iterators
+    /// when they have `__next__`, which raises `StopIteration` at the end.
+    pub(crate) fn next_value(&mut self, iterator: &Value) -> Result<Option<Value>, VmError> {
+        match iterator {
+            Value::Iterator(iterator) => Ok(iterator.next_item()),
+            Value::File(file) => file.next_line(),
+            Value::Generator(generator) => match self.resume(&Rc::clone(generator), Value::Nonetype, None)? {
+                Resumed::Yielded(value) => Ok(Some(value)),
+                Resumed::Returned(_) => Ok(None),
+            },
+            Value::Instance(instance) => {
+                let next = instance.get_attr(&Rc::new(String::from("__next__"))).ok_or_else(|| {
+                    VmError::TypeError(format!("'{}' object is not an iterator", instance.class.name))
+                })?;
+                match self.call_value(next, vec![]) {
+                    Ok(value) => Ok(Some(value)),
+                    Err(VmError::Exception(exception)) if exception.kind.name.as_str() == "StopIteration" => Ok(None),
+                    Err(error) => Err(error),
+                }
+            },
+            other => Err(VmError::TypeError(format!("{} is not an iterator", other.python_repr()))),
+        }
+    }
+}
+
+/// The `next(iterator[, default])` built-in.
+pub(crate) fn builtin_next(vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("next", &args, 1, 2)?;
+    let mut args = args.into_iter();
+    let iterator = args.next().unwrap();
+    let default = args.next();
+    let next = match &iterator {
+        Value::Iterator(iterator) => iterator.next_item(),
+        Value::File(file) => file.next_line()?,
+        Value::Generator(generator) => match vm.resume(&Rc::clone(generator), Value::Nonetype, None)? {
+            Resumed::Yielded(value) => Some(value),
+            Resumed::Returned(value) => return default.ok_or_else(|| VmError::Exception(generator::stop_iteration(value))),
+        },
+        other => return Err(VmError::TypeError(format!("{} is not an iterator", other.python_repr()))),
+    };
+    next.or(default).ok_or_else(|| VmError::Exception(generator::stop_iteration(Value::Nonetype)))
+}
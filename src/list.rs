@@ -0,0 +1,79 @@
+//! `list`, the mutable sequence. Copies of a list value share it, so a change made through one,
+//! like `a[0] = 1` or `a += [2]`, is seen through all of them.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::slice::Slice;
+use crate::{Value, VmError};
+
+pub(crate) type List = Rc<RefCell<Vec<Value>>>;
+
+/// `list[index] = value`, an item for an int index and the items of an iterable for a slice.
+/// Slices with a step have to be given as many items as they select.
+pub(crate) fn set_item(list: &List, index: &Value, value: &Value) -> Result<(), VmError> {
+    let slice = match index {
+        Value::Slice(slice) => slice,
+        index => {
+            let position = position(list.borrow().len(), index)?;
+            list.borrow_mut()[position] = value.clone();
+            return Ok(());
+        },
+    };
+    let items = value.sequence_items().ok_or_else(|| VmError::TypeError(String::from("can only assign an iterable")))?;
+    replace_slice(&mut list.borrow_mut(), slice, items)
+}
+
+fn replace_slice(values: &mut Vec<Value>, slice: &Slice, items: Vec<Value>) -> Result<(), VmError> {
+    let (start, stop, step) = slice.indices(values.len() as i64)?;
+    if step == 1 {
+        let stop = stop.max(start);
+        values.splice(start as usize..stop as usize, items);
+        return Ok(());
+    }
+
+    let positions = slice.select(&(0..values.len()).collect::<Vec<_>>())?;
+    if positions.len() != items.len() {
+        return Err(VmError::ValueError(format!(
+            "attempt to assign sequence of size {} to extended slice of size {}", items.len(), positions.len()
+        )));
+    }
+    for (position, item) in positions.into_iter().zip(items) {
+        values[position] = item;
+    }
+    Ok(())
+}
+
+/// Where `index` is in a list of `len` items, counting negative indices from the end.
+fn position(len: usize, index: &Value) -> Result<usize, VmError> {
+    let index = match index {
+        Value::Int(index) => *index,
+        Value::Bool(index) => i64::from(*index),
+        other => return Err(VmError::TypeError(format!("list indices must be integers or slices, not {}", other.type_name()))),
+    };
+    let position = if index < 0 { index + len as i64 } else { index };
+    if position < 0 || position >= len as i64 {
+        return Err(VmError::IndexError(String::from("list assignment index out of range")));
+    }
+    Ok(position as usize)
+}
+
+/// `items * count`, no items for a count below one.
+pub(crate) fn repeat(items: &[Value], count: i64) -> Vec<Value> {
+    (0..count.max(0)).flat_map(|_| items.iter().cloned()).collect()
+}
+
+/// `list += iterable`, which extends the list in place and gives the same list back.
+pub(crate) fn extend(list: &List, iterable: &Value) -> Result<(), VmError> {
+    let items = iterable.sequence_items().ok_or_else(|| {
+        VmError::TypeError(format!("\'{}\' object is not iterable", iterable.type_name()))
+    })?;
+    list.borrow_mut().extend(items);
+    Ok(())
+}
+
+/// `list *= count`, which repeats the items in place.
+pub(crate) fn repeat_in_place(list: &List, count: i64) {
+    let items = repeat(&list.borrow(), count);
+    *list.borrow_mut() = items;
+}
@@ -1,13 +1,64 @@
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::env;
+use std::fmt;
 use std::fs;
+use std::mem;
 use std::ops::{Add, Sub, Mul, Div};
-use std::rc::Rc;
-use std::time::Instant;
+use std::process;
+use std::rc::{Rc, Weak};
+use std::str::FromStr;
+use std::thread;
 
-use serde::Deserialize;
-use serde_json;
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+
+mod bigint;
+mod bytearray;
+mod cache;
+mod classes;
+mod dict;
+mod exceptions;
+mod format;
+mod frame_builder;
+mod generator;
+mod hash;
+mod id;
+mod intrinsics;
+mod iterator;
+mod list;
+mod memoryview;
+mod modules;
+mod profile;
+mod set;
+mod slice;
+mod snapshot;
+mod verify;
+
+#[cfg(test)]
+mod tests;
+
+use cache::Cache;
+use classes::{Class, Instance, Property, Super};
+use dict::Dict;
+use exceptions::{Exception, ExceptionType};
+use frame_builder::FrameBuilder;
+use generator::{Generator, Resumed};
+use iterator::SequenceIterator;
+use memoryview::MemoryView;
+use modules::datetime::{self, Date, DateTime, TimeDelta, TimeZone};
+use modules::r#enum::EnumMember;
+use modules::hashlib::Hash;
+use modules::io::File;
+use modules::process::Process;
+use modules::re::{Match, Pattern};
+use modules::threading::{Lock, SharedGlobals, Thread};
+use profile::Profiler;
+use set::Set;
+use slice::Slice;
+use snapshot::Snapshot;
 
 enum CompareOps {
     LessThan,
@@ -15,7 +66,13 @@ enum CompareOps {
     Equal,
     NotEqual,
     GreaterThan,
-    GreaterThanOrEqual
+    GreaterThanOrEqual,
+    In,
+    NotIn,
+    Is,
+    IsNot,
+    /// Whether the exception type below matches an `except` clause.
+    ExceptionMatch,
 }
 
 impl From<usize> for CompareOps {
@@ -27,12 +84,17 @@ impl From<usize> for CompareOps {
             3 => Self::NotEqual,
             4 => Self::GreaterThan,
             5 => Self::GreaterThanOrEqual,
+            6 => Self::In,
+            7 => Self::NotIn,
+            8 => Self::Is,
+            9 => Self::IsNot,
+            10 => Self::ExceptionMatch,
             _ => panic!("Unimplemented cmp_op")
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 enum Instruction {
     LoadConst(usize),
     StoreName(usize),
@@ -41,30 +103,269 @@ enum Instruction {
     StoreFast(usize),
     LoadFast(usize),
     DeleteFast(usize),
+    /// Python 3.12's `LOAD_FAST_AND_CLEAR`, which saves a local before an inlined comprehension
+    /// reuses its name. It pushes the local, or `Null` when it is unbound, and unbinds it.
+    LoadFastAndClear(usize),
+    /// Python 3.12's `LOAD_FAST` of a local that may be unbound.
+    LoadFastCheck(usize),
     StoreGlobal(usize),
     LoadGlobal(usize),
+    /// Python 3.11's `LOAD_GLOBAL` with the low bit of its argument set, which pushes `Null`
+    /// below the global for the `CALL` that follows. Decoding gives it the name index.
+    LoadGlobalWithNull(usize),
     DeleteGlobal(usize),
+    /// Pushes the cell of cell or free variable `arg`, counting `co_cellvars` and then
+    /// `co_freevars`, for `MAKE_FUNCTION` to close over.
+    LoadClosure(usize),
+    LoadDeref(usize),
+    /// `LOAD_DEREF` in a class body, which looks in the class namespace before the cell.
+    /// `recursive_dis.py` spells `LOAD_CLASSDEREF` as one word.
+    #[serde(rename = "LoadClassderef")]
+    LoadClassDeref(usize),
+    /// Python 3.12's `LOAD_LOCALS`, which pushes the class namespace for the
+    /// `LOAD_FROM_DICT_OR_DEREF` after it. Decoding turns the pair into `LOAD_CLASSDEREF`.
+    LoadLocals,
+    LoadFromDictOrDeref(usize),
+    StoreDeref(usize),
+    DeleteDeref(usize),
+    /// Python 3.11's `MAKE_CELL`, puts the value of local `arg` in a new cell. Python 3.11
+    /// counts the cell variables that aren't parameters after `co_varnames`.
+    MakeCell(usize),
+    /// Python 3.11's `COPY_FREE_VARS`, the first instruction of a closure, binds the first
+    /// `arg` free variables to the cells of the function's closure.
+    CopyFreeVars(usize),
     CompareOp(usize),
+    /// Python 3.9's `IS_OP`, `is` or with `arg` 1 `is not`.
+    IsOp(usize),
+    /// Python 3.9's `CONTAINS_OP`, `in` or with `arg` 1 `not in`.
+    ContainsOp(usize),
     JumpForward(usize),
     PopJumpIfTrue(usize),
     PopJumpIfFalse(usize),
     JumpIfTrueOrPop(usize),
     JumpIfFalseOrPop(usize),
+    /// Python 3.9's `JUMP_IF_NOT_EXC_MATCH`, pops an except clause and the exception type
+    /// below it, and jumps when the type doesn't match the clause.
+    JumpIfNotExcMatch(usize),
+    /// Python 3.12's jumps for `is None` and `is not None` tests, which pop TOS. Decoding
+    /// makes their targets absolute like those of `PopJumpIfTrue`.
+    PopJumpIfNone(usize),
+    PopJumpIfNotNone(usize),
+    /// Python 3.11's relative conditional jumps, which decoding turns into `PopJumpIfTrue`,
+    /// `PopJumpIfFalse`, `PopJumpIfNone` and `PopJumpIfNotNone`.
+    PopJumpForwardIfTrue(usize),
+    PopJumpForwardIfFalse(usize),
+    PopJumpForwardIfNone(usize),
+    PopJumpForwardIfNotNone(usize),
+    PopJumpBackwardIfTrue(usize),
+    PopJumpBackwardIfFalse(usize),
+    PopJumpBackwardIfNone(usize),
+    PopJumpBackwardIfNotNone(usize),
+    /// Python 3.11's backward jumps, `arg` code units back from the next instruction, which
+    /// decoding turns into `JumpAbsolute`.
+    JumpBackward(usize),
+    JumpBackwardNoInterrupt(usize),
     MakeFunction(usize),
     CallFunction(usize),
+    /// Python 3.6 to 3.10's call with keyword arguments. TOS is the tuple of their names, and
+    /// the last of the `arg` arguments below it are their values.
+    CallFunctionKw(usize),
+    /// Python 3.11's `KW_NAMES`, keeps the tuple of names in constant `arg` for the `CALL`
+    /// that follows, which passes its last arguments by those names.
+    KwNames(usize),
+    /// Python 3.11's `CALL`, calls the function below the `arg` arguments on top of the stack.
+    /// The `NULL` that Python 3.11 pushes below functions that aren't methods is popped too.
+    /// Without it the function is one item lower, and the item above it is its first argument
+    /// like the `self` of a method.
+    Call(usize),
+    /// Python 3.11's `PRECALL`, which comes before every `CALL` so the adaptive interpreter can
+    /// specialize the call. It does nothing here, an inline cache for `CALL` could be kept in it.
+    Precall(usize),
+    /// Python 3.11's `PUSH_NULL`, pushes the `Null` that `CALL` pops below a function that
+    /// isn't a method.
+    PushNull,
+    /// Python 3.11's first instruction of every function, where the interpreter checks for
+    /// signals and tracing. It does nothing here.
+    Resume(usize),
     JumpAbsolute(usize),
+    ForIter(usize),
+    SetupFinally(usize),
+    SetupWith(usize),
+    SetupAsyncWith(usize),
+    CallFinally(usize),
+    PopFinally(usize),
+    RaiseVarargs(usize),
+    /// Python 3.9's `RERAISE`, raises the exception whose type, value and traceback are on top
+    /// of the stack again at the end of a `finally` or unmatched `except`. Python 3.10 gives it
+    /// an argument, which only sets the line tracebacks show.
+    Reraise(Option<usize>),
+    LoadAssertionError,
+    ImportName(usize),
+    ImportFrom(usize),
+    LoadAttr(usize),
+    StoreAttr(usize),
+    DeleteAttr(usize),
+    /// Pushes `Null` and then attribute `arg` of TOS, like Python 3.11 does for attributes
+    /// that aren't methods defined in a class, so `CallMethod` and Python 3.11's `CALL` can
+    /// call the attribute the same way.
+    LoadMethod(usize),
+    CallMethod(usize),
+    /// Python 3.12's `LOAD_SUPER_ATTR` for `super().name`, which pops `super`, the class and
+    /// `self`. With bit 2 of `arg` set `super` was called with the class and `self`, and bit 1
+    /// makes it push `Null` too like `LoadMethod`. The name is `arg >> 2`.
+    LoadSuperAttr(usize),
+    FormatValue(usize),
+    BuildString(usize),
+    BuildSlice(usize),
+    /// Python 3.12's `container[start:stop]`, with the container, start and stop on the stack.
+    BinarySlice,
+    /// Python 3.12's `container[start:stop] = value`.
+    StoreSlice,
+    BuildTuple(usize),
+    /// Replaces the iterable on top of the stack with its `arg` items, the first on top.
+    UnpackSequence(usize),
+    BuildList(usize),
+    BuildSet(usize),
+    /// Adds TOS to the set `arg` positions from the top, after popping TOS.
+    SetAdd(usize),
+    /// Python 3.9's `SET_UPDATE` for `{*a, *b}`, updates the set `arg` positions from the top
+    /// with the items of TOS, after popping it.
+    SetUpdate(usize),
+    /// Python 3.6 to 3.8's `{*a, *b}`, replaces the `arg` iterables on top of the stack with a
+    /// set of their items.
+    BuildSetUnpack(usize),
+    /// Builds a dict of the `arg` key and value pairs on top of the stack, the first deepest.
+    BuildMap(usize),
+    /// Builds a dict of the keys in the tuple on top of the stack and the `arg` values below it.
+    BuildConstKeyMap(usize),
+    /// Python 3.9's `DICT_UPDATE` for `{**a, **b}`, updates the dict `arg` positions from the
+    /// top with the mapping on top of the stack, after popping it.
+    DictUpdate(usize),
+    /// Python 3.9's `DICT_MERGE` for `f(**a, **b)`, like `DictUpdate` but a key that is
+    /// already in the dict is an argument given twice.
+    DictMerge(usize),
+    /// Python 3.6 to 3.8's `{**a, **b}`, replaces the `arg` mappings on top of the stack with a
+    /// dict of their items.
+    BuildMapUnpack(usize),
+    /// Python 3.6 to 3.8's `f(**a, **b)`, like `BuildMapUnpack` but a key in two of the
+    /// mappings is an argument given twice.
+    BuildMapUnpackWithCall(usize),
+    /// Python 3.9's `LIST_EXTEND`, extends the list `arg` positions from the top with TOS.
+    ListExtend(usize),
+    /// Appends TOS to the list `arg` positions from the top, after popping TOS.
+    ListAppend(usize),
+    /// Python 3.9's `LIST_TO_TUPLE`, which replaces the list on top of the stack with a tuple
+    /// of its items, like the intrinsic of Python 3.12.
+    ListToTuple,
+    /// Python 3.6 to 3.8's `f(*a, *b)`, which pushes a tuple of the items of the `arg`
+    /// iterables on top of the stack.
+    BuildTupleUnpackWithCall(usize),
+    /// Calls the function below the iterable of positional arguments on top of the stack, or
+    /// below it and a dict of keyword arguments when `arg & 1` is set.
+    CallFunctionEx(usize),
+    /// Python 3.11's `COPY`, pushes a copy of the item `arg` positions from the top, 1 being TOS.
+    Copy(usize),
+    /// Python 3.11's `SWAP`, swaps TOS with the item `arg` positions from the top.
+    Swap(usize),
+    /// Python 3.11's `PUSH_EXC_INFO`, starts handling the exception on top of the stack and
+    /// pushes the one that was being handled below it.
+    PushExcInfo,
+    /// Python 3.11's `CHECK_EXC_MATCH`, replaces the exception type on top of the stack with
+    /// whether the exception below it matches it.
+    CheckExcMatch,
+    /// Python 3.11's `CHECK_EG_MATCH` for `except*`, splits the exception below the clause on
+    /// top of the stack into the rest, which replaces it, and the part the clause handles,
+    /// pushed as a group. When nothing matches the exception stays and `None` is pushed.
+    CheckEgMatch,
+    /// Python 3.12's `CALL_INTRINSIC_1`, replaces TOS with what intrinsic `arg` returns for it.
+    CallIntrinsic1(usize),
+    /// Python 3.10's first instruction of generators, pops the `None` they are started with.
+    GenStart(usize),
+    /// Class patterns in `match`, `arg` is the number of positional sub-patterns.
+    MatchClass(usize),
     ReturnValue,
+    /// Python 3.12's `return` of constant `arg`.
+    ReturnConst(usize),
+    /// Python 3.11's first instruction of generators after the cells are made, which makes
+    /// the generator in CPython. Calls make the generator here, so it only pushes the `None`
+    /// the `POP_TOP` after it pops.
+    ReturnGenerator,
+    /// Python 3.12's `INTERPRETER_EXIT`, which ends the outermost frame the interpreter runs
+    /// with the value on top of the stack. It ends the frame like `RETURN_VALUE`.
+    InterpreterExit,
+    /// Python 3.12 gives `YIELD_VALUE` an argument, the depth of the exception handlers it
+    /// yields from.
+    YieldValue(Option<usize>),
+    YieldFrom,
+    /// Python 3.11's `SEND`, which replaces `YIELD_FROM`. It sends TOS to the iterator below it
+    /// and replaces TOS with what that yields, for the `YIELD_VALUE` after it. Once the iterator
+    /// is exhausted it jumps `arg` bytes forward with the value it returned, which replaces the
+    /// iterator too before Python 3.12.
+    Send(usize),
+    /// Python 3.12's `END_SEND`, pops the iterator below the value a `SEND` loop ended with.
+    EndSend,
+    /// Python 3.12's `CLEANUP_THROW`, the handler of the `YIELD_VALUE` in a `SEND` loop.
+    /// `StopIteration` from the iterator ends the loop with its value, which replaces the
+    /// iterator and the value sent to it, and other exceptions are raised again.
+    CleanupThrow,
+    ImportStar,
+    LoadBuildClass,
+    /// Python 3.6's `STORE_ANNOTATION`, stores TOS in `__annotations__` as the annotation of
+    /// name `arg`. Later versions use `STORE_SUBSCR`.
+    StoreAnnotation(usize),
+    /// Binds `__annotations__` to an empty dict in module and class bodies with variable
+    /// annotations, unless it is already bound.
+    SetupAnnotations,
+    PopBlock,
+    PopExcept,
+    BeginFinally,
+    EndFinally,
+    WithCleanupStart,
+    WithCleanupFinish,
+    /// Python 3.9's `WITH_EXCEPT_START`, which replaces `WITH_CLEANUP_START` in the handler of
+    /// a `with` body. It calls `__exit__` with the exception being handled and pushes what it
+    /// returns, for the jump after it to swallow the exception or reraise it.
+    WithExceptStart,
+    /// Python 3.11's `BEFORE_WITH`, which replaces `SETUP_WITH` now that the exception table
+    /// has the handler of the body.
+    BeforeWith,
+    GetIter,
+    GetYieldFromIter,
+    /// Python 3.11 gives `GET_AWAITABLE` an argument, which only says where the awaitable
+    /// comes from.
+    GetAwaitable(Option<usize>),
+    GetAiter,
+    GetAnext,
+    EndAsyncFor,
+    BeforeAsyncWith,
     InplaceAdd,
     InplaceSubtract,
     InplaceMultiply,
     InplaceTrueDivide,
     InplaceFloorDivide,
+    InplaceAnd,
+    InplaceOr,
+    InplaceXor,
     BinaryAdd,
     BinarySubtract,
     BinaryMultiply,
     BinaryTrueDivide,
     BinaryFloorDivide,
+    BinaryAnd,
+    BinaryOr,
+    BinaryXor,
+    /// Python 3.11's `BINARY_OP`, which replaces the binary and in-place operator
+    /// instructions. `arg` is the operator, `NB_ADD` and the others.
+    BinaryOp(usize),
+    BinarySubscr,
+    StoreSubscr,
     Nop,
+    /// Python 3.11's inline cache entries after specializable instructions, which do nothing
+    /// when they are run.
+    Cache,
+    /// `dis` already adds the bits of `EXTENDED_ARG` to the argument of the instruction after
+    /// it, so it does nothing either. It is kept so the instructions line up with jump targets.
+    ExtendedArg(usize),
     PopTop,
     RotTwo,
     RotThree,
@@ -73,22 +374,649 @@ enum Instruction {
     DupTopTwo,
     UnaryPositive,
     UnaryNegative,
+    PrintExpr,
+    /// Python 3.12's end of `for` loops, which pops the iterator and the last item. `FOR_ITER`
+    /// jumps past it when the iterator is exhausted.
+    EndFor,
 
     Print,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// Variants marked `#[serde(skip)]` only exist at run time, serializing one of them is an error.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 enum Value {
-    Int(i32),
+    Int(i64),
+    /// An int that doesn't fit in `Int`, see `bigint`.
+    #[serde(skip)]
+    BigInt(Rc<BigInt>),
     Bool(bool),
-    Float(f32),
+    Float(f64),
     Str(String),
+    Bytes(Vec<u8>),
+    /// Bytearrays are mutable and shared, like lists.
+    ByteArray(Rc<RefCell<Vec<u8>>>),
+    Tuple(Vec<Value>),
+    /// Sets and frozensets are written as a list of their items, checked to be hashable when
+    /// they are loaded.
+    #[serde(serialize_with = "set::serialize_frozen", deserialize_with = "set::deserialize_frozen")]
+    FrozenSet(Rc<Set>),
+    /// Sets are shared like lists.
+    #[serde(serialize_with = "set::serialize", deserialize_with = "set::deserialize")]
+    Set(Rc<RefCell<Set>>),
+    /// Lists are mutable and shared, a copy refers to the same list.
+    List(Rc<RefCell<Vec<Value>>>),
+    /// Dicts are shared like lists.
+    #[serde(skip)]
+    Dict(Rc<RefCell<Dict>>),
     Nonetype,
-    Frame(Frame)
+    /// A code object or function. The code never changes once it is loaded, so calls share it.
+    Frame(Rc<Frame>),
+    /// A variable shared between a function and the functions nested in it. `Null` while the
+    /// variable is unbound.
+    #[serde(skip)]
+    Cell(Rc<RefCell<Value>>),
+    /// A function implemented in Rust, with the parameters it takes by keyword.
+    #[serde(skip)]
+    NativeFunction(Rc<String>, NativeFn, Keywords),
+    #[serde(skip)]
+    Module(Rc<Module>),
+    /// A function and the value it was looked up on, which is passed as its first argument.
+    #[serde(skip)]
+    BoundMethod(Box<Value>, Box<Value>),
+    #[serde(skip)]
+    File(Rc<File>),
+    #[serde(skip)]
+    Regex(Rc<Pattern>),
+    #[serde(skip)]
+    Match(Rc<Match>),
+    #[serde(skip)]
+    NativeType(Rc<NativeType>),
+    #[serde(skip)]
+    Date(Date),
+    #[serde(skip)]
+    DateTime(DateTime),
+    #[serde(skip)]
+    TimeDelta(TimeDelta),
+    #[serde(skip)]
+    TimeZone(TimeZone),
+    #[serde(skip)]
+    Hash(Rc<Hash>),
+    #[serde(skip)]
+    MemoryView(Rc<MemoryView>),
+    #[serde(skip)]
+    Slice(Rc<Slice>),
+    #[serde(skip)]
+    Iterator(Rc<SequenceIterator>),
+    #[serde(skip)]
+    Generator(Rc<Generator>),
+    #[serde(skip)]
+    Thread(Rc<Thread>),
+    /// Locks can only be serialized into the job of a thread, which shares them.
+    #[serde(serialize_with = "modules::threading::serialize_lock", deserialize_with = "modules::threading::deserialize_lock")]
+    Lock(Rc<Lock>),
+    #[serde(skip)]
+    Process(Rc<Process>),
+    #[serde(skip)]
+    ExceptionType(Rc<ExceptionType>),
+    #[serde(skip)]
+    Exception(Rc<Exception>),
+    #[serde(skip)]
+    Class(Rc<Class>),
+    #[serde(skip)]
+    Instance(Rc<Instance>),
+    #[serde(skip)]
+    EnumMember(Rc<EnumMember>),
+    #[serde(skip)]
+    Super(Rc<Super>),
+    #[serde(skip)]
+    Property(Rc<Property>),
+    /// `classmethod(function)`, see `classes::bind`.
+    #[serde(skip)]
+    ClassMethod(Box<Value>),
+    /// `staticmethod(function)`, see `classes::bind`.
+    #[serde(skip)]
+    StaticMethod(Box<Value>),
+    /// `weakref.ref(instance)`, calling it gives the instance or None once it is gone.
+    #[serde(skip)]
+    WeakRef(Weak<Instance>),
+    /// `weakref.proxy(instance)`, which forwards attribute access to the instance.
+    #[serde(skip)]
+    WeakProxy(Weak<Instance>),
+    /// The NULL CPython pushes for `BEGIN_FINALLY`. Python code never sees it.
+    #[serde(skip)]
+    Null,
+}
+
+type NativeFn = fn(&mut Vm, Vec<Value>) -> Result<Value, VmError>;
+
+/// The parameters of a native function or type that can be given by keyword, in order. Empty
+/// for the ones that only take positional arguments.
+type Keywords = &'static [&'static str];
+
+impl Value {
+    /// The text `repr()` gives for the value.
+    fn python_repr(&self) -> String {
+        match self {
+            Value::Int(val) => val.to_string(),
+            Value::BigInt(val) => val.to_string(),
+            Value::Bool(true) => String::from("True"),
+            Value::Bool(false) => String::from("False"),
+            Value::Float(val) => format!("{:?}", val),
+            Value::Str(val) => str_repr(val),
+            Value::Bytes(bytes) => bytes_repr(bytes),
+            Value::ByteArray(bytes) => format!("bytearray({})", bytes_repr(&bytes.borrow())),
+            Value::Tuple(values) if values.len() == 1 => format!("({},)", values[0].python_repr()),
+            Value::Tuple(values) => format!("({})", values.iter().map(Value::python_repr).collect::<Vec<_>>().join(", ")),
+            Value::List(values) => format!("[{}]", values.borrow().iter().map(Value::python_repr).collect::<Vec<_>>().join(", ")),
+            Value::Dict(dict) => dict.borrow().repr(),
+            Value::FrozenSet(set) => set::repr(set, true),
+            Value::Set(set) => set::repr(&set.borrow(), false),
+            Value::Nonetype => String::from("None"),
+            Value::Frame(frame) => format!("<function {} at {:#x}>", frame.qualname(), &**frame as *const Frame as usize),
+            Value::Cell(cell) => match &*cell.borrow() {
+                Value::Null => format!("<cell at {:#x}: empty>", Rc::as_ptr(cell) as usize),
+                value => format!("<cell at {:#x}: {}>", Rc::as_ptr(cell) as usize, value.python_repr()),
+            },
+            Value::NativeFunction(name, ..) => format!("<built-in function {}>", name),
+            Value::Module(module) => format!("<module '{}' (built-in)>", module.name),
+            Value::BoundMethod(receiver, function) => match function.as_ref() {
+                Value::NativeFunction(name, ..) => format!("<built-in method {} of {}>", name, receiver.python_repr()),
+                _ => format!("<bound method of {}>", receiver.python_repr()),
+            },
+            Value::File(file) => format!("{:?}", file),
+            Value::Regex(pattern) => format!("{:?}", pattern),
+            Value::Match(matched) => format!("{:?}", matched),
+            Value::NativeType(native_type) => format!("<class '{}'>", native_type.name),
+            Value::Date(date) => format!("{:?}", date),
+            Value::DateTime(date_time) => format!("{:?}", date_time),
+            Value::TimeDelta(delta) => format!("{:?}", delta),
+            Value::TimeZone(tz) => format!("{:?}", tz),
+            Value::Hash(hash) => format!("{:?}", hash),
+            Value::MemoryView(view) => format!("{:?}", view),
+            Value::Slice(slice) => format!("{:?}", slice),
+            Value::Iterator(iterator) => format!("{:?}", iterator),
+            Value::Generator(generator) => format!("{:?}", generator),
+            Value::Thread(thread) => format!("{:?}", thread),
+            Value::Lock(lock) => format!("{:?}", lock),
+            Value::Process(process) => format!("{:?}", process),
+            Value::ExceptionType(kind) => format!("<class '{}'>", kind.name),
+            Value::Exception(exception) => format!("{:?}", exception),
+            Value::Class(class) => format!("{:?}", class),
+            Value::Instance(instance) => format!("{:?}", instance),
+            Value::EnumMember(member) => format!("{:?}", member),
+            Value::Super(value) => format!("{:?}", value),
+            Value::Property(property) => format!("{:?}", property),
+            Value::ClassMethod(function) | Value::StaticMethod(function) => {
+                format!("<{} object at {:#x}>", self.type_name(), &**function as *const Value as usize)
+            },
+            Value::WeakRef(reference) => modules::weakref::repr(reference, false),
+            Value::WeakProxy(proxy) => modules::weakref::repr(proxy, true),
+            Value::Null => String::from("<NULL>"),
+        }
+    }
+
+    /// The name of the value's type, as `type(value).__name__` gives it and error messages
+    /// show it.
+    fn type_name(&self) -> &str {
+        match self {
+            Value::Int(_) | Value::BigInt(_) => "int",
+            Value::Float(_) => "float",
+            Value::Str(_) => "str",
+            Value::Bool(_) => "bool",
+            Value::Bytes(_) => "bytes",
+            Value::ByteArray(_) => "bytearray",
+            Value::Tuple(_) => "tuple",
+            Value::FrozenSet(_) => "frozenset",
+            Value::Set(_) => "set",
+            Value::List(_) => "list",
+            Value::Dict(_) => "dict",
+            Value::Nonetype => "NoneType",
+            Value::Frame(_) => "function",
+            Value::Cell(_) => "cell",
+            Value::NativeFunction(..) => "builtin_function_or_method",
+            Value::Module(_) => "module",
+            Value::BoundMethod(..) => "method",
+            Value::File(_) => "TextIOWrapper",
+            Value::Regex(_) => "Pattern",
+            Value::Match(_) => "Match",
+            Value::NativeType(_) | Value::ExceptionType(_) | Value::Class(_) => "type",
+            Value::Date(_) => "date",
+            Value::DateTime(_) => "datetime",
+            Value::TimeDelta(_) => "timedelta",
+            Value::TimeZone(_) => "timezone",
+            Value::Hash(_) => "HASH",
+            Value::MemoryView(_) => "memoryview",
+            Value::Slice(_) => "slice",
+            Value::Iterator(iterator) => iterator.type_name(),
+            Value::Generator(_) => "generator",
+            Value::Thread(_) => "Thread",
+            Value::Lock(_) => "lock",
+            Value::Process(_) => "Process",
+            Value::Exception(exception) => exception.kind.short_name(),
+            Value::Instance(instance) => &instance.class.name,
+            Value::EnumMember(member) => member.class_name(),
+            Value::Super(_) => "super",
+            Value::Property(_) => "property",
+            Value::ClassMethod(_) => "classmethod",
+            Value::StaticMethod(_) => "staticmethod",
+            Value::WeakRef(_) => "weakref",
+            Value::WeakProxy(_) => "weakproxy",
+            Value::Null => "NULL",
+        }
+    }
+
+    /// The code of a function.
+    fn as_frame(&self) -> Option<&Frame> {
+        match self {
+            Value::Frame(frame) => Some(frame),
+            _ => None,
+        }
+    }
+
+    /// The code of a function to change, which is copied first if other values share it.
+    fn as_frame_mut(&mut self) -> Option<&mut Frame> {
+        match self {
+            Value::Frame(frame) => Some(Rc::make_mut(frame)),
+            _ => None,
+        }
+    }
+
+    /// Whether the value can be called, what `callable()` gives.
+    fn is_callable(&self) -> bool {
+        matches!(
+            self,
+            Value::Frame(_)
+                | Value::NativeFunction(..)
+                | Value::BoundMethod(..)
+                | Value::NativeType(_)
+                | Value::ExceptionType(_)
+                | Value::Class(_)
+                | Value::WeakRef(_)
+        )
+    }
+
+    /// Whether `if` takes the value as true: zero, empty strings and tuples, and `None` are false.
+    /// Instances can define `__bool__` or `__len__`, so `Vm::python_bool` asks them.
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Int(val) => *val != 0,
+            Value::Bool(val) => *val,
+            Value::Float(val) => *val != 0.0,
+            Value::Str(val) => !val.is_empty(),
+            Value::Bytes(bytes) => !bytes.is_empty(),
+            Value::ByteArray(bytes) => !bytes.borrow().is_empty(),
+            Value::Tuple(values) => !values.is_empty(),
+            Value::List(values) => !values.borrow().is_empty(),
+            Value::Dict(dict) => dict.borrow().len() > 0,
+            Value::FrozenSet(set) => !set.is_empty(),
+            Value::Set(set) => !set.borrow().is_empty(),
+            Value::MemoryView(view) => view.len() > 0,
+            Value::Nonetype => false,
+            Value::TimeDelta(delta) => !delta.is_zero(),
+            _ => true,
+        }
+    }
+
+    fn get_attr(&self, name: &Rc<String>) -> Result<Value, VmError> {
+        match self {
+            Value::Str(_) => str_attr(self, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'str' object has no attribute '{}'", name))
+            }),
+            Value::Bytes(_) => bytes_attr(self, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'bytes' object has no attribute '{}'", name))
+            }),
+            Value::Module(module) => module.attributes.get(name).cloned().ok_or_else(|| {
+                VmError::AttributeError(format!("module '{}' has no attribute '{}'", module.name, name))
+            }),
+            Value::File(file) => File::get_attr(file, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'_io.TextIOWrapper' object has no attribute '{}'", name))
+            }),
+            Value::Regex(pattern) => Pattern::get_attr(pattern, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'re.Pattern' object has no attribute '{}'", name))
+            }),
+            Value::Match(matched) => Match::get_attr(matched, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'re.Match' object has no attribute '{}'", name))
+            }),
+            Value::NativeType(native_type) => native_type.attributes.get(name).cloned().ok_or_else(|| {
+                VmError::AttributeError(format!("type object '{}' has no attribute '{}'", native_type.name, name))
+            }),
+            Value::Date(date) => date.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("'datetime.date' object has no attribute '{}'", name))
+            }),
+            Value::DateTime(date_time) => date_time.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("'datetime.datetime' object has no attribute '{}'", name))
+            }),
+            Value::TimeDelta(delta) => delta.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("'datetime.timedelta' object has no attribute '{}'", name))
+            }),
+            Value::TimeZone(tz) => tz.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("'datetime.timezone' object has no attribute '{}'", name))
+            }),
+            Value::Hash(hash) => Hash::get_attr(hash, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'_hashlib.HASH' object has no attribute '{}'", name))
+            }),
+            Value::Set(set) => set::get_attr(set, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'set' object has no attribute '{}'", name))
+            }),
+            Value::ByteArray(bytes) => bytearray::get_attr(bytes, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'bytearray' object has no attribute '{}'", name))
+            }),
+            Value::MemoryView(view) => MemoryView::get_attr(view, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'memoryview' object has no attribute '{}'", name))
+            }),
+            Value::Slice(slice) => Slice::get_attr(slice, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'slice' object has no attribute '{}'", name))
+            }),
+            Value::Generator(generator) => Generator::get_attr(generator, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'generator' object has no attribute '{}'", name))
+            }),
+            Value::Thread(thread) => Thread::get_attr(thread, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'Thread' object has no attribute '{}'", name))
+            }),
+            Value::Lock(lock) => Lock::get_attr(lock, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'_thread.lock' object has no attribute '{}'", name))
+            }),
+            Value::Process(process) => Process::get_attr(process, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'Process' object has no attribute '{}'", name))
+            }),
+            Value::Exception(exception) => exception.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("'{}' object has no attribute '{}'", exception.kind.short_name(), name))
+            }),
+            Value::Class(class) => class.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("type object '{}' has no attribute '{}'", class.name, name))
+            }),
+            Value::Instance(instance) => instance.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("'{}' object has no attribute '{}'", instance.class.name, name))
+            }),
+            Value::WeakProxy(proxy) => Value::Instance(modules::weakref::referent(proxy)?).get_attr(name),
+            Value::Frame(frame) => match name.as_str() {
+                "__name__" => Ok(Value::Str(frame.co_name.to_string())),
+                "__qualname__" => Ok(Value::Str(frame.qualname().to_string())),
+                _ => Err(VmError::AttributeError(format!("'function' object has no attribute '{}'", name))),
+            },
+            Value::EnumMember(member) => member.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("'{}' object has no attribute '{}'", member.class_name(), name))
+            }),
+            Value::Super(value) => value.get_attr(name).ok_or_else(|| {
+                VmError::AttributeError(format!("'super' object has no attribute '{}'", name))
+            }),
+            Value::Property(property) => Property::get_attr(property, name).ok_or_else(|| {
+                VmError::AttributeError(format!("'property' object has no attribute '{}'", name))
+            }),
+            Value::ClassMethod(function) | Value::StaticMethod(function) => match name.as_str() {
+                "__func__" => Ok(*function.clone()),
+                _ => Err(VmError::AttributeError(format!("'{}' object has no attribute '{}'", self.type_name(), name))),
+            },
+            _ => Err(VmError::AttributeError(format!("'{}' object has no attribute '{}'", self.type_name(), name)))
+        }
+    }
+
+    /// `self[index]` for the built-in sequences, an int picks one item and a slice a new
+    /// sequence of the same type.
+    fn get_item(self, index: Value) -> Result<Value, VmError> {
+        match &self {
+            Value::MemoryView(view) => return view.get_item(&index),
+            Value::Dict(dict) => return dict.borrow().get(&index)?.ok_or_else(|| VmError::KeyError(index.python_repr())),
+            _ => (),
+        }
+        if let Value::Slice(slice) = &index {
+            return match self {
+                Value::Tuple(values) => slice.select(&values).map(Value::Tuple),
+                Value::List(values) => Ok(Value::List(Rc::new(RefCell::new(slice.select(&values.borrow())?)))),
+                Value::Str(string) => Ok(Value::Str(slice.select(&string.chars().collect::<Vec<_>>())?.into_iter().collect())),
+                Value::Bytes(bytes) => slice.select(&bytes).map(Value::Bytes),
+                Value::ByteArray(bytes) => Ok(Value::ByteArray(Rc::new(RefCell::new(slice.select(&bytes.borrow())?)))),
+                other => Err(VmError::TypeError(format!("\'{}\' object is not subscriptable", other.type_name()))),
+            };
+        }
+
+        let index = match index {
+            Value::Int(index) => index as isize,
+            Value::Bool(index) => index as isize,
+            other => return Err(VmError::TypeError(format!("indices must be integers or slices, not {}", other.type_name()))),
+        };
+        let (len, kind) = match &self {
+            Value::Tuple(values) => (values.len(), "tuple"),
+            Value::List(values) => (values.borrow().len(), "list"),
+            Value::Str(string) => (string.chars().count(), "string"),
+            Value::Bytes(bytes) => (bytes.len(), "index"),
+            Value::ByteArray(bytes) => (bytes.borrow().len(), "bytearray"),
+            other => return Err(VmError::TypeError(format!("\'{}\' object is not subscriptable", other.type_name()))),
+        };
+        let position = if index < 0 { index + len as isize } else { index };
+        if position < 0 || position >= len as isize {
+            return Err(VmError::IndexError(format!("{} index out of range", kind)));
+        }
+        let position = position as usize;
+        Ok(match self {
+            Value::Tuple(mut values) => values.swap_remove(position),
+            Value::List(values) => values.borrow()[position].clone(),
+            Value::Str(string) => Value::Str(string.chars().nth(position).unwrap().to_string()),
+            Value::Bytes(bytes) => Value::Int(i64::from(bytes[position])),
+            Value::ByteArray(bytes) => Value::Int(i64::from(bytes.borrow()[position])),
+            _ => unreachable!(),
+        })
+    }
+
+    /// `self[index] = value` for the built-in mutable sequences and dicts.
+    fn set_item(&self, index: &Value, value: &Value) -> Result<(), VmError> {
+        match self {
+            Value::Dict(dict) => dict.borrow_mut().insert(index.clone(), value.clone()),
+            Value::List(list) => list::set_item(list, index, value),
+            Value::ByteArray(bytes) => bytearray::set_item(bytes, index, value),
+            Value::MemoryView(view) => view.set_item(index, value),
+            other => Err(VmError::TypeError(format!("\'{}\' object does not support item assignment", other.type_name()))),
+        }
+    }
+
+    /// The items iterating over the value gives, for the built-in sequences. `None` if the
+    /// value isn't one of them.
+    fn sequence_items(&self) -> Option<Vec<Value>> {
+        match self {
+            Value::Tuple(values) => Some(values.clone()),
+            Value::List(values) => Some(values.borrow().clone()),
+            Value::Str(string) => Some(string.chars().map(|character| Value::Str(character.to_string())).collect()),
+            Value::Bytes(bytes) => Some(bytes.iter().map(|byte| Value::Int(i64::from(*byte))).collect()),
+            Value::ByteArray(bytes) => Some(bytes.borrow().iter().map(|byte| Value::Int(i64::from(*byte))).collect()),
+            Value::MemoryView(view) => Some(view.to_bytes().into_iter().map(|byte| Value::Int(i64::from(byte))).collect()),
+            Value::FrozenSet(set) => Some(set::sorted_items(set)),
+            Value::Set(set) => Some(set::sorted_items(&set.borrow())),
+            Value::Dict(dict) => Some(dict.borrow().keys()),
+            _ => None,
+        }
+    }
+}
+
+/// `'...'`, quoted with double quotes when that avoids escaping a single quote.
+fn str_repr(string: &str) -> String {
+    let quote = if string.contains('\'') && !string.contains('"') { '"' } else { '\'' };
+    let mut repr = quote.to_string();
+    for character in string.chars() {
+        match character {
+            '\t' => repr += "\\t",
+            '\n' => repr += "\\n",
+            '\r' => repr += "\\r",
+            '\\' => repr += "\\\\",
+            character if character == quote => repr += &format!("\\{}", quote),
+            character if character.is_control() => repr += &format!("\\x{:02x}", character as u32),
+            character => repr.push(character),
+        }
+    }
+    repr.push(quote);
+    repr
+}
+
+/// `b'...'`, quoted with double quotes when that avoids escaping a single quote.
+fn bytes_repr(bytes: &[u8]) -> String {
+    let quote = if bytes.contains(&b'\'') && !bytes.contains(&b'"') { '"' } else { '\'' };
+    let mut repr = format!("b{}", quote);
+    for byte in bytes {
+        match byte {
+            b'\t' => repr += "\\t",
+            b'\n' => repr += "\\n",
+            b'\r' => repr += "\\r",
+            b'\\' => repr += "\\\\",
+            byte if *byte == quote as u8 => repr += &format!("\\{}", quote),
+            0x20..=0x7e => repr.push(*byte as char),
+            byte => repr += &format!("\\x{:02x}", byte),
+        }
+    }
+    repr.push(quote);
+    repr
+}
+
+/// How `ascii()` writes a character of a repr: non-ASCII ones become `\x`, `\u` or `\U` escapes.
+fn ascii_escape(character: char) -> String {
+    match character as u32 {
+        0..=0x7f => character.to_string(),
+        code @ 0x80..=0xff => format!("\\x{:02x}", code),
+        code @ 0x100..=0xffff => format!("\\u{:04x}", code),
+        code => format!("\\U{:08x}", code),
+    }
+}
+
+fn check_encoding(function: &str, args: &[Value]) -> Result<(), VmError> {
+    match args.get(1) {
+        None => Ok(()),
+        Some(Value::Str(encoding)) if ["utf-8", "utf8"].contains(&encoding.to_lowercase().as_str()) => Ok(()),
+        Some(Value::Str(encoding)) => Err(VmError::Unimplemented(format!("{}() with the '{}' encoding", function, encoding))),
+        Some(_) => Err(VmError::TypeError(format!("{}() argument 'encoding' must be str", function))),
+    }
+}
+
+/// `bytes.decode()` with the UTF-8 codec.
+fn decode_utf8(bytes: &[u8]) -> Result<Value, VmError> {
+    String::from_utf8(bytes.to_vec()).map(Value::Str).map_err(|error| {
+        let position = error.utf8_error().valid_up_to();
+        VmError::ValueError(format!(
+            "'utf-8' codec can't decode byte 0x{:02x} in position {}: invalid start byte", bytes[position], position
+        ))
+    })
+}
+
+/// Methods of `str`, only `encode` to UTF-8 so far.
+fn str_attr(string: &Value, name: &str) -> Option<Value> {
+    let method: NativeFn = match name {
+        "encode" => |_, args| {
+            check_encoding("encode", &args)?;
+            match &args[0] {
+                Value::Str(string) => Ok(Value::Bytes(string.clone().into_bytes())),
+                _ => unreachable!("str methods are only reachable through a bound str"),
+            }
+        },
+        _ => return None,
+    };
+
+    Some(modules::bound_method(string.clone(), name, method))
+}
+
+/// Methods of `bytes`.
+fn bytes_attr(bytes: &Value, name: &str) -> Option<Value> {
+    let method: NativeFn = match name {
+        "decode" => |_, args| {
+            check_encoding("decode", &args)?;
+            match &args[0] {
+                Value::Bytes(bytes) => decode_utf8(bytes),
+                _ => unreachable!("bytes methods are only reachable through a bound bytes"),
+            }
+        },
+        "hex" => |_, args| match &args[0] {
+            Value::Bytes(bytes) => Ok(Value::Str(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())),
+            _ => unreachable!("bytes methods are only reachable through a bound bytes"),
+        },
+        _ => return None,
+    };
+
+    Some(modules::bound_method(bytes.clone(), name, method))
+}
+
+/// A byte given as an int, like the items of `bytes([1, 2, 3])`.
+fn byte_value(value: &Value) -> Result<u8, VmError> {
+    match value {
+        Value::Int(number) => u8::try_from(*number).map_err(|_| VmError::ValueError(String::from("bytes must be in range(0, 256)"))),
+        Value::Bool(value) => Ok(u8::from(*value)),
+        other => Err(VmError::TypeError(format!("\'{}\' object cannot be interpreted as an integer", other.type_name()))),
+    }
+}
+
+/// The bytes of a bytes-like object or the ints of a sequence, `None` for other values.
+fn byte_items(value: &Value) -> Option<Result<Vec<u8>, VmError>> {
+    match value {
+        Value::Bytes(bytes) => Some(Ok(bytes.clone())),
+        Value::ByteArray(bytes) => Some(Ok(bytes.borrow().clone())),
+        Value::MemoryView(view) => Some(Ok(view.to_bytes())),
+        Value::Str(_) => None,
+        other => other.sequence_items().map(|items| items.iter().map(byte_value).collect()),
+    }
+}
+
+/// What `bytes(source)` and `bytearray(source)` hold: no bytes, `n` zero bytes, a copy of a
+/// bytes-like object, or the ints of a sequence.
+fn bytes_from(source: Option<&Value>) -> Result<Vec<u8>, VmError> {
+    match source {
+        None => Ok(vec![]),
+        Some(Value::Int(count)) if *count < 0 => Err(VmError::ValueError(String::from("negative count"))),
+        Some(Value::Int(count)) => Ok(vec![0; *count as usize]),
+        Some(Value::Str(_)) => Err(VmError::TypeError(String::from("string argument without an encoding"))),
+        Some(other) => byte_items(other).unwrap_or_else(|| {
+            Err(VmError::TypeError(format!("cannot convert {} to bytes", other.python_repr())))
+        }),
+    }
+}
+
+/// The `bytes([source])` built-in.
+fn builtin_bytes(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    modules::expect_args("bytes", &args, 0, 1)?;
+    bytes_from(args.first()).map(Value::Bytes)
+}
+
+/// The `callable(object)` built-in.
+fn builtin_callable(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    modules::expect_args("callable", &args, 1, 1)?;
+    Ok(Value::Bool(args[0].is_callable()))
+}
+
+/// The `int([x])` built-in type, for ints, bools, floats and strings of decimal digits.
+fn builtin_int(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    modules::expect_args("int", &args, 0, 1)?;
+    match args.first() {
+        None => Ok(Value::Int(0)),
+        Some(Value::Int(number)) => Ok(Value::Int(*number)),
+        Some(Value::Bool(value)) => Ok(Value::Int(i64::from(*value))),
+        Some(Value::Float(number)) if number.is_finite() => Ok(Value::Int(number.trunc() as i64)),
+        Some(Value::Float(number)) => Err(VmError::OverflowError(format!("cannot convert float {:?} to integer", number))),
+        Some(Value::Str(string)) => string.trim().replace('_', "").parse().map(Value::Int).map_err(|_| {
+            VmError::ValueError(format!("invalid literal for int() with base 10: {}", str_repr(string)))
+        }),
+        Some(other) => Err(VmError::TypeError(format!(
+            "int() argument must be a string, a bytes-like object or a number, not \'{}\'", other.type_name()
+        ))),
+    }
+}
+
+/// The `str([object])` built-in type, which calls `__str__` on instances.
+fn builtin_str(vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    modules::expect_args("str", &args, 0, 1)?;
+    match args.first() {
+        None => Ok(Value::Str(String::new())),
+        Some(value) => vm.python_str(value).map(Value::Str),
+    }
 }
 
-impl Default for Value {
-    fn default() -> Self { Value::Nonetype }
+/// The text `str()` gives for the value, which is what `print` shows.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Str(val) => write!(f, "{}", val),
+            Value::Date(val) => write!(f, "{}", val),
+            Value::DateTime(val) => write!(f, "{}", val),
+            Value::TimeDelta(val) => write!(f, "{}", val),
+            Value::TimeZone(val) => write!(f, "{}", val),
+            Value::Exception(val) => write!(f, "{}", val.message()),
+            Value::EnumMember(val) => write!(f, "{}", val),
+            _ => write!(f, "{}", self.python_repr()),
+        }
+    }
 }
 
 impl PartialEq for Value {
@@ -97,13 +1025,39 @@ impl PartialEq for Value {
             (Value::Int(first), Value::Int(second)) => first == second,
             (Value::Bool(first), Value::Bool(second)) => first == second,
             (Value::Str(first), Value::Str(second)) => first == second,
+            (Value::Bytes(first), Value::Bytes(second)) => first == second,
             (Value::Float(first), Value::Float(second)) => first == second,
+            (Value::Tuple(first), Value::Tuple(second)) => first == second,
+            (Value::List(first), Value::List(second)) => *first.borrow() == *second.borrow(),
+            (Value::Dict(first), Value::Dict(second)) => *first.borrow() == *second.borrow(),
+            (Value::Set(_) | Value::FrozenSet(_), Value::Set(_) | Value::FrozenSet(_)) => set::equal(self, other),
+            (Value::NativeType(first), Value::NativeType(second)) => Rc::ptr_eq(first, second),
+            (Value::ExceptionType(first), Value::ExceptionType(second)) => Rc::ptr_eq(first, second),
+            (Value::ByteArray(_) | Value::MemoryView(_), Value::Bytes(_) | Value::ByteArray(_) | Value::MemoryView(_))
+                | (Value::Bytes(_), Value::ByteArray(_) | Value::MemoryView(_)) => {
+                byte_items(self).and_then(Result::ok) == byte_items(other).and_then(Result::ok)
+            },
 
-            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => (*second as f32).eq( first),
-            (Value::Float(first), Value::Bool(second)) | (Value::Bool(second), Value::Float(first))  => first == &((*second as i32) as f32),
-            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => (*first as i32).eq(second),
+            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => (*second as f64).eq( first),
+            (Value::Float(first), Value::Bool(second)) | (Value::Bool(second), Value::Float(first))  => first == &((*second as i64) as f64),
+            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => (*first as i64).eq(second),
+            (Value::TimeZone(first), Value::TimeZone(second)) => first == second,
+            (Value::BigInt(first), _) => bigint::compare(first, other) == Some(Ordering::Equal),
+            (_, Value::BigInt(second)) => bigint::compare(second, self) == Some(Ordering::Equal),
+            (Value::Class(_), _) | (_, Value::Class(_)) | (Value::Instance(_), _) | (_, Value::Instance(_))
+                | (Value::WeakRef(_), _) | (_, Value::WeakRef(_)) | (Value::EnumMember(_), _) | (_, Value::EnumMember(_))
+                | (Value::Super(_), _) | (_, Value::Super(_)) | (Value::Property(_), _) | (_, Value::Property(_)) => {
+                classes::is_same_object(self, other)
+            },
+
+            (Value::Nonetype, Value::Nonetype) => true,
 
-            _ => panic!("Unimplemented comparision between {:?} and {:?}", self, other)
+            _ => match datetime::compare(self, other) {
+                Some(ordering) => ordering == Ordering::Equal,
+                // Values of different types that aren't compared above are never equal.
+                None if mem::discriminant(self) != mem::discriminant(other) => false,
+                None => panic!("Unimplemented comparision between {:?} and {:?}", self, other),
+            }
         }
     }
 }
@@ -114,13 +1068,21 @@ impl PartialOrd for Value {
             (Value::Int(first), Value::Int(second)) => first.partial_cmp(second),
             (Value::Bool(first), Value::Bool(second)) => first.partial_cmp(second),
             (Value::Str(first), Value::Str(second)) => first.partial_cmp(second),
+            (Value::Bytes(first), Value::Bytes(second)) => first.partial_cmp(second),
             (Value::Float(first), Value::Float(second)) => first.partial_cmp(second),
+            (Value::Tuple(first), Value::Tuple(second)) => first.partial_cmp(second),
+            (Value::List(first), Value::List(second)) => first.borrow().partial_cmp(&*second.borrow()),
 
-            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => first.partial_cmp(&(*second as f32)),
-            (Value::Float(first), Value::Bool(second)) | (Value::Bool(second), Value::Float(first))  => first.partial_cmp(&((*second as i32) as f32)),
-            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => (*first as i32).partial_cmp(second),
+            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => first.partial_cmp(&(*second as f64)),
+            (Value::Float(first), Value::Bool(second)) | (Value::Bool(second), Value::Float(first))  => first.partial_cmp(&((*second as i64) as f64)),
+            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => (*first as i64).partial_cmp(second),
+            (Value::BigInt(first), _) if bigint::compare(first, other).is_some() => bigint::compare(first, other),
+            (_, Value::BigInt(second)) if bigint::compare(second, self).is_some() => bigint::compare(second, self).map(Ordering::reverse),
 
-            _ => panic!("Unimplemented comparision between {:?} and {:?}", self, other)
+            _ => match datetime::compare(self, other) {
+                Some(ordering) => Some(ordering),
+                None => panic!("Unimplemented comparision between {:?} and {:?}", self, other),
+            }
         }
     }
 }
@@ -132,10 +1094,19 @@ impl Add for Value {
         match (&self, &rhs) {
             (Value::Int(first), Value::Int(second)) => Value::Int(first + second),
             (Value::Float(first), Value::Float(second)) => Value::Float(first + second),
-            (Value::Bool(first), Value::Bool(second)) => Value::Int((*first as i32) + (*second as i32)),
+            (Value::Bool(first), Value::Bool(second)) => Value::Int((*first as i64) + (*second as i64)),
             (Value::Str(first), Value::Str(second)) => Value::Str(first.clone() + second),
-            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => Value::Float(first + (*second as f32)),
-            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => Value::Int((*first as i32) + second),
+            (Value::Bytes(first), Value::Bytes(second)) => Value::Bytes([first.as_slice(), second].concat()),
+            (Value::Bytes(first), Value::ByteArray(second)) => Value::Bytes([first.as_slice(), &second.borrow()].concat()),
+            (Value::ByteArray(first), Value::Bytes(_) | Value::ByteArray(_)) => {
+                let second = byte_items(&rhs).unwrap().unwrap();
+                Value::ByteArray(Rc::new(RefCell::new([first.borrow().as_slice(), &second].concat())))
+            },
+            (Value::List(first), Value::List(second)) => {
+                Value::List(Rc::new(RefCell::new([first.borrow().as_slice(), &second.borrow()].concat())))
+            },
+            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => Value::Float(first + (*second as f64)),
+            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => Value::Int((*first as i64) + second),
 
             _ => panic!("Unimplemented 'add' operation between {:?} and {:?}", self, rhs)
         }
@@ -149,9 +1120,11 @@ impl Sub for Value {
         match (&self, &rhs) {
             (Value::Int(first), Value::Int(second)) => Value::Int(first - second),
             (Value::Float(first), Value::Float(second)) => Value::Float(first - second),
-            (Value::Bool(first), Value::Bool(second)) => Value::Int((*first as i32) - (*second as i32)),
-            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => Value::Float(first - (*second as f32)),
-            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => Value::Int((*first as i32) - second),
+            (Value::Bool(first), Value::Bool(second)) => Value::Int((*first as i64) - (*second as i64)),
+            (Value::Float(first), Value::Int(second)) => Value::Float(first - (*second as f64)),
+            (Value::Int(first), Value::Float(second)) => Value::Float((*first as f64) - second),
+            (Value::Bool(first), Value::Int(second)) => Value::Int((*first as i64) - second),
+            (Value::Int(first), Value::Bool(second)) => Value::Int(first - (*second as i64)),
 
             _ => panic!("Unimplemented 'add' operation between {:?} and {:?}", self, rhs)
         }
@@ -165,7 +1138,7 @@ impl Mul for Value {
         match (&self, &rhs) {
             (Value::Int(first), Value::Int(second)) => Value::Int(first * second),
             (Value::Float(first), Value::Float(second)) => Value::Float(first * second),
-            (Value::Bool(first), Value::Bool(second)) => Value::Int((*first as i32) * (*second as i32)),
+            (Value::Bool(first), Value::Bool(second)) => Value::Int((*first as i64) * (*second as i64)),
             (Value::Str(first), Value::Int(second)) | (Value::Int(second), Value::Str(first)) => {
                 let mut res = first.clone();
                 for _ in 1..*second {
@@ -173,8 +1146,11 @@ impl Mul for Value {
                 };
                 Value::Str(res)
             },
-            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => Value::Float(first * (*second as f32)),
-            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => Value::Int((*first as i32) * second),
+            (Value::List(items), Value::Int(count)) | (Value::Int(count), Value::List(items)) => {
+                Value::List(Rc::new(RefCell::new(list::repeat(&items.borrow(), *count))))
+            },
+            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => Value::Float(first * (*second as f64)),
+            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => Value::Int((*first as i64) * second),
 
             _ => panic!("Unimplemented 'add' operation between {:?} and {:?}", self, rhs)
         }
@@ -186,390 +1162,3330 @@ impl Div for Value {
 
     fn div(self, rhs: Self) -> Self::Output {
         match (&self, &rhs) {
-            (Value::Int(first), Value::Int(second)) => Value::Float((*first as f32) / (*second as f32)),
+            (Value::Int(first), Value::Int(second)) => Value::Float((*first as f64) / (*second as f64)),
             (Value::Float(first), Value::Float(second)) => Value::Float(first / second),
-            (Value::Bool(first), Value::Bool(second)) => Value::Float((*first as i32) as f32 / (*second as i32) as f32),
-            (Value::Float(first), Value::Int(second)) | (Value::Int(second), Value::Float(first))  => Value::Float(first / (*second as f32)),
-            (Value::Bool(first), Value::Int(second)) | (Value::Int(second), Value::Bool(first)) => Value::Float((*first as i32) as f32 / (*second as i32) as f32),
+            (Value::Bool(first), Value::Bool(second)) => Value::Float((*first as i64) as f64 / (*second as i64) as f64),
+            (Value::Float(first), Value::Int(second)) => Value::Float(first / (*second as f64)),
+            (Value::Int(first), Value::Float(second)) => Value::Float((*first as f64) / second),
+            (Value::Bool(first), Value::Int(second)) => Value::Float((*first as i64) as f64 / *second as f64),
+            (Value::Int(first), Value::Bool(second)) => Value::Float(*first as f64 / (*second as i64) as f64),
 
             _ => panic!("Unimplemented 'add' operation between {:?} and {:?}", self, rhs)
         }
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 struct Frame {
+    /// The function's name, `<module>` for module level code. Empty for bytecode files
+    /// written before `recursive_dis.py` recorded it.
+    #[serde(default)]
+    co_name: Rc<String>,
+    /// The name with the classes and functions the function is defined in, like `A.f`. Only
+    /// Python 3.11 records it, `MAKE_FUNCTION` sets it for earlier versions.
+    #[serde(default)]
+    co_qualname: Rc<String>,
     instructions: Vec<Instruction>,
     constants: Vec<Value>,
     co_names: Vec<Rc<String>>,
     co_varnames: Vec<Rc<String>>,
-
+    /// The number of positional parameters, the first names in `co_varnames`. `None` for
+    /// bytecode files written before `recursive_dis.py` recorded it, whose calls bind their
+    /// arguments to `co_varnames` in order without checking them.
     #[serde(default)]
-    stack: Vec<Value>,
+    co_argcount: Option<usize>,
+    /// How many of the positional parameters are positional-only, the ones before `/`. Only
+    /// Python 3.8 and later record it.
     #[serde(default)]
-    index: usize,
+    co_posonlyargcount: usize,
+    /// The number of keyword-only parameters, the ones after `*`, which follow the positional
+    /// parameters in `co_varnames`.
     #[serde(default)]
-    globals: HashMap<Rc<String>, Value>,
+    co_kwonlyargcount: usize,
+    /// CPython's `CO_*` flags. Zero for bytecode files written before `recursive_dis.py`
+    /// recorded them.
     #[serde(default)]
-    locals: HashMap<Rc<String>, Value>,
+    co_flags: u32,
+    /// Locals that nested functions use, which live in cells. Parameters can be among them.
     #[serde(default)]
-    return_value: Box<Value>,
+    co_cellvars: Vec<Rc<String>>,
+    /// Variables of enclosing functions that this one uses.
     #[serde(default)]
+    co_freevars: Vec<Rc<String>>,
+    /// The cells of `co_freevars`, which `MAKE_FUNCTION` gives a function from the frame it
+    /// is made in.
+    #[serde(skip)]
+    closure: Vec<Rc<RefCell<Value>>>,
+    /// The default values of the last positional parameters, which `MAKE_FUNCTION` gives a
+    /// function. Only written for functions that have some, which `process` and `threading`
+    /// send to child VMs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    defaults: Vec<Value>,
+    /// The default values of keyword-only parameters, by name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    kwdefaults: Vec<(Rc<String>, Value)>,
+    /// Python 3.11's handlers, which replace the blocks `SETUP_FINALLY` pushes in earlier
+    /// versions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    co_exceptiontable: Vec<ExceptionTableEntry>,
+}
+
+/// An entry of Python 3.11's exception table: an exception raised by the instructions from
+/// byte offset `start` up to `end` jumps to `target`, after unwinding the stack to `depth`
+/// items and, when `lasti` is set, pushing the offset of the instruction that raised it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ExceptionTableEntry {
+    start: usize,
+    end: usize,
+    target: usize,
     depth: usize,
+    lasti: bool,
 }
 
 impl Frame {
-    fn run(&mut self) {
-        while let Some(instruction) = self.instructions.get(self.index) {
-            match *instruction {
-                Instruction::LoadConst(arg) => self.load_const(arg),
-                Instruction::StoreName(arg) => self.store_name(arg),
-                Instruction::LoadName(arg) => self.load_name(arg),
-                Instruction::DeleteName(arg) => self.delete_name(arg),
-                Instruction::StoreFast(arg) => self.store_fast(arg),
-                Instruction::LoadFast(arg) => self.load_fast(arg),
-                Instruction::DeleteFast(arg) => self.delete_fast(arg),
-                Instruction::StoreGlobal(arg) => self.store_global(arg),
-                Instruction::LoadGlobal(arg) => self.load_global(arg),
-                Instruction::DeleteGlobal(arg) => self.delete_global(arg),
-                Instruction::CompareOp(arg) => self.compare_op(arg),
-                Instruction::JumpForward(arg) => { self.index += arg / 2 + 1; },
-                Instruction::PopJumpIfTrue(arg) => self.pop_jump_if_true(arg),
-                Instruction::PopJumpIfFalse(arg) => self.pop_jump_if_false(arg),
-                Instruction::JumpIfTrueOrPop(arg) => self.jump_if_true_or_pop(arg),
-                Instruction::JumpIfFalseOrPop(arg) => self.jump_if_false_or_pop(arg),
-                Instruction::JumpAbsolute(arg) =>  { self.index = arg / 2; },
-                Instruction::MakeFunction(arg) => self.make_function(arg),
-                Instruction::CallFunction(arg) => self.call_function(arg),
-                Instruction::ReturnValue => self.return_value(),
-                Instruction::InplaceAdd => self.add(),
-                Instruction::InplaceSubtract => self.subtract(),
-                Instruction::InplaceMultiply => self.multiply(),
-                Instruction::InplaceTrueDivide => self.true_divide(),
-                Instruction::InplaceFloorDivide => self.floor_divide(),
-                Instruction::BinaryAdd => self.add(),
-                Instruction::BinarySubtract => self.subtract(),
-                Instruction::BinaryMultiply => self.multiply(),
-                Instruction::BinaryTrueDivide => self.true_divide(),
-                Instruction::BinaryFloorDivide => self.floor_divide(),
-                Instruction::Nop => { self.index += 1; },
-                Instruction::PopTop => self.pop_top(),
-                Instruction::RotTwo => self.rot_two(),
-                Instruction::RotThree => self.rot_three(),
-                Instruction::RotFour => self.rot_four(),
-                Instruction::DupTop => self.dup_top(),
-                Instruction::DupTopTwo => self.dup_top_two(),
-                Instruction::UnaryPositive => { self.index += 1 },
-                Instruction::UnaryNegative => self.unary_negative(),
-
-                Instruction::Print => self.print(),
-            };
-        };
+    fn create_print_frame() -> Frame {
+        FrameBuilder::new("print")
+            .parameter("to_print")
+            .constant(Value::Str(String::from("to_print")))
+            .load_fast("to_print")
+            .instruction(Instruction::Print)
+            .build()
     }
 
-    fn load_const(&mut self, arg: usize) {
-        self.stack.push(self.constants[arg].clone());
+    /// The name of the `*args` parameter, which follows the positional and keyword-only
+    /// parameters in `co_varnames`.
+    fn varargs_name(&self) -> Option<&Rc<String>> {
+        match self.co_argcount {
+            Some(argcount) if self.co_flags & CO_VARARGS != 0 => self.co_varnames.get(argcount + self.co_kwonlyargcount),
+            _ => None,
+        }
+    }
 
-        self.index += 1;
+    /// The name of the `**kwargs` parameter, the last parameter in `co_varnames`.
+    fn varkeywords_name(&self) -> Option<&Rc<String>> {
+        let varargs = usize::from(self.co_flags & CO_VARARGS != 0);
+        match self.co_argcount {
+            Some(argcount) if self.co_flags & CO_VARKEYWORDS != 0 => self.co_varnames.get(argcount + self.co_kwonlyargcount + varargs),
+            _ => None,
+        }
     }
 
-    fn store_name(&mut self, arg: usize) {
-        self.locals.insert(Rc::clone(&self.co_names[arg]), self.stack.pop().unwrap());
+    /// `__qualname__`, which is `co_name` when the qualified name isn't known.
+    fn qualname(&self) -> &Rc<String> {
+        if self.co_qualname.is_empty() { &self.co_name } else { &self.co_qualname }
+    }
 
-        self.index += 1;
+    /// The name of cell or free variable `arg` of `LOAD_DEREF` and the other cell instructions.
+    fn deref_name(&self, arg: usize) -> Option<&Rc<String>> {
+        self.co_cellvars.iter().chain(&self.co_freevars).nth(arg)
     }
 
-    fn load_name(&mut self, arg: usize) {
-        self.stack.push(self.locals[&self.co_names[arg]].clone());
+    /// The name of local `arg` in Python 3.11's layout, where the cell variables that aren't
+    /// parameters and then the free variables come after `co_varnames`.
+    fn fast_local_name(&self, arg: usize) -> Option<&Rc<String>> {
+        let cells = self.co_cellvars.iter().filter(|name| !self.co_varnames.contains(name));
+        self.co_varnames.iter().chain(cells).chain(&self.co_freevars).nth(arg)
+    }
 
-        self.index += 1;
+    /// The exception table entry that handles exceptions raised by instruction `index`.
+    fn exception_handler(&self, index: usize) -> Option<&ExceptionTableEntry> {
+        self.co_exceptiontable.iter().find(|entry| entry.start <= index * 2 && index * 2 < entry.end)
     }
 
-    fn delete_name(&mut self, arg: usize) {
-        self.locals.remove(&self.co_names[arg]);
+    /// Rewrites version specific encodings into the layout `Vm` executes, which is
+    /// the one of Python 3.8.
+    fn decode(&mut self, instruction_set: InstructionSet) {
+        if let InstructionSet::ExceptionTable | InstructionSet::FlaggedArguments = instruction_set {
+            self.instructions = (0..self.instructions.len()).map(|index| self.decode_code_unit(index, instruction_set)).collect();
+        }
+        if let InstructionSet::InstructionOffsets = instruction_set {
+            for instruction in self.instructions.iter_mut() {
+                *instruction = match *instruction {
+                    Instruction::JumpForward(arg) => Instruction::JumpForward(arg * 2),
+                    Instruction::PopJumpIfTrue(arg) => Instruction::PopJumpIfTrue(arg * 2),
+                    Instruction::PopJumpIfFalse(arg) => Instruction::PopJumpIfFalse(arg * 2),
+                    Instruction::JumpIfTrueOrPop(arg) => Instruction::JumpIfTrueOrPop(arg * 2),
+                    Instruction::JumpIfFalseOrPop(arg) => Instruction::JumpIfFalseOrPop(arg * 2),
+                    Instruction::JumpIfNotExcMatch(arg) => Instruction::JumpIfNotExcMatch(arg * 2),
+                    Instruction::JumpAbsolute(arg) => Instruction::JumpAbsolute(arg * 2),
+                    Instruction::ForIter(arg) => Instruction::ForIter(arg * 2),
+                    Instruction::SetupFinally(arg) => Instruction::SetupFinally(arg * 2),
+                    Instruction::SetupWith(arg) => Instruction::SetupWith(arg * 2),
+                    Instruction::SetupAsyncWith(arg) => Instruction::SetupAsyncWith(arg * 2),
+                    other => other,
+                };
+            }
+        }
 
-        self.index += 1;
+        for constant in self.constants.iter_mut() {
+            if let Some(frame) = constant.as_frame_mut() {
+                frame.decode(instruction_set);
+            }
+        }
     }
 
-    fn store_fast(&mut self, arg: usize) {
-        self.locals.insert(Rc::clone(&self.co_varnames[arg]), self.stack.pop().unwrap());
+    /// Instruction `index` of Python 3.11 or 3.12 code. Jumps count code units from the
+    /// instruction after the jump and its inline caches, and cell and free variables are
+    /// numbered among the locals like `MAKE_CELL` numbers them.
+    fn decode_code_unit(&self, index: usize, instruction_set: InstructionSet) -> Instruction {
+        let flagged = matches!(instruction_set, InstructionSet::FlaggedArguments);
+        let caches = self.instructions[index + 1..].iter().take_while(|instruction| matches!(instruction, Instruction::Cache)).count();
+        let next = index + 1 + caches;
+        let forward = |arg: usize| (next + arg) * 2;
+        let backward = |arg: usize| next.saturating_sub(arg) * 2;
+        // Relative jumps are decoded to count from the instruction right after them.
+        let relative = |target: usize| (target - index - 1) * 2;
+        let deref = |arg: usize| {
+            let name = self.fast_local_name(arg);
+            self.co_cellvars.iter().chain(&self.co_freevars).position(|cell| Some(cell) == name).unwrap_or(arg)
+        };
 
-        self.index += 1;
+        match self.instructions[index] {
+            Instruction::JumpForward(arg) => Instruction::JumpForward(relative(next + arg)),
+            Instruction::JumpBackward(arg) | Instruction::JumpBackwardNoInterrupt(arg) => Instruction::JumpAbsolute(backward(arg)),
+            Instruction::PopJumpIfTrue(arg) | Instruction::PopJumpForwardIfTrue(arg) => Instruction::PopJumpIfTrue(forward(arg)),
+            Instruction::PopJumpIfFalse(arg) | Instruction::PopJumpForwardIfFalse(arg) => Instruction::PopJumpIfFalse(forward(arg)),
+            Instruction::PopJumpIfNone(arg) | Instruction::PopJumpForwardIfNone(arg) => Instruction::PopJumpIfNone(forward(arg)),
+            Instruction::PopJumpIfNotNone(arg) | Instruction::PopJumpForwardIfNotNone(arg) => Instruction::PopJumpIfNotNone(forward(arg)),
+            Instruction::PopJumpBackwardIfTrue(arg) => Instruction::PopJumpIfTrue(backward(arg)),
+            Instruction::PopJumpBackwardIfFalse(arg) => Instruction::PopJumpIfFalse(backward(arg)),
+            Instruction::PopJumpBackwardIfNone(arg) => Instruction::PopJumpIfNone(backward(arg)),
+            Instruction::PopJumpBackwardIfNotNone(arg) => Instruction::PopJumpIfNotNone(backward(arg)),
+            Instruction::JumpIfTrueOrPop(arg) => Instruction::JumpIfTrueOrPop(forward(arg)),
+            Instruction::JumpIfFalseOrPop(arg) => Instruction::JumpIfFalseOrPop(forward(arg)),
+            // Python 3.12 jumps to the `END_FOR` the exhausted iterator skips.
+            Instruction::ForIter(arg) => Instruction::ForIter(relative(next + arg + usize::from(flagged))),
+            Instruction::Send(arg) => Instruction::Send(relative(next + arg)),
+            Instruction::LoadGlobal(arg) if arg & 1 == 1 => Instruction::LoadGlobalWithNull(arg >> 1),
+            Instruction::LoadGlobal(arg) => Instruction::LoadGlobal(arg >> 1),
+            Instruction::LoadAttr(arg) if flagged && arg & 1 == 1 => Instruction::LoadMethod(arg >> 1),
+            Instruction::LoadAttr(arg) if flagged => Instruction::LoadAttr(arg >> 1),
+            Instruction::CompareOp(arg) if flagged => Instruction::CompareOp(arg >> 4),
+            Instruction::LoadClosure(arg) => Instruction::LoadClosure(deref(arg)),
+            Instruction::LoadDeref(arg) => Instruction::LoadDeref(deref(arg)),
+            Instruction::LoadClassDeref(arg) => Instruction::LoadClassDeref(deref(arg)),
+            Instruction::LoadLocals if matches!(self.instructions.get(next), Some(Instruction::LoadFromDictOrDeref(_))) => Instruction::Nop,
+            Instruction::LoadFromDictOrDeref(arg) if index > 0 && matches!(self.instructions[index - 1], Instruction::LoadLocals) => {
+                Instruction::LoadClassDeref(deref(arg))
+            },
+            Instruction::StoreDeref(arg) => Instruction::StoreDeref(deref(arg)),
+            Instruction::DeleteDeref(arg) => Instruction::DeleteDeref(deref(arg)),
+            Instruction::BinaryOp(arg) => binary_operator(arg).unwrap_or(Instruction::BinaryOp(arg)),
+            other => other,
+        }
     }
+}
 
-    fn load_fast(&mut self, arg: usize) {
-        self.stack.push(self.locals.get(&self.co_varnames[arg]).unwrap().clone());
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+struct PythonVersion {
+    major: u8,
+    minor: u8,
+}
 
-        self.index += 1;
-    }
+impl PythonVersion {
+    /// Assumed for bytecode files that don't say which version compiled them.
+    const DEFAULT: PythonVersion = PythonVersion { major: 3, minor: 8 };
 
-    fn delete_fast(&mut self, arg: usize) {
-        self.locals.remove(&self.co_varnames[arg]);
+    fn instruction_set(self) -> Result<InstructionSet, String> {
+        match (self.major, self.minor) {
+            (3, 6..=9) => Ok(InstructionSet::ByteOffsets),
+            (3, 10) => Ok(InstructionSet::InstructionOffsets),
+            (3, 11) => Ok(InstructionSet::ExceptionTable),
+            (3, 12) => Ok(InstructionSet::FlaggedArguments),
+            _ => Err(format!("Python {} bytecode is not supported", self)),
+        }
+    }
+}
 
-        self.index += 1;
+impl fmt::Display for PythonVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
     }
+}
 
-    fn store_global(&mut self, arg: usize) {
-        self.globals.insert(Rc::clone(&self.co_names[arg]), self.stack.pop().unwrap());
+impl FromStr for PythonVersion {
+    type Err = String;
 
-        self.index += 1;
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let mut parts = version.splitn(2, '.').map(u8::from_str);
+        match (parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor))) => Ok(PythonVersion { major, minor }),
+            _ => Err(format!("Invalid Python version '{}', expected <major>.<minor>", version)),
+        }
     }
+}
 
-    fn load_global(&mut self, arg: usize) {
-        self.stack.push(self.globals.get(&self.co_names[arg]).unwrap().clone());
+/// Where a relative jump (`JUMP_FORWARD`, `FOR_ITER`, `SETUP_FINALLY`, `SETUP_WITH` and
+/// `CALL_FINALLY`) at `index` lands. `arg` is a byte offset from the start of the next
+/// instruction and every instruction is 2 bytes, so `JUMP_FORWARD 4` at index 3 skips
+/// indices 4 and 5 and lands on 6. Absolute jumps are `arg / 2`.
+fn relative_target(index: usize, arg: usize) -> usize {
+    index + 1 + arg / 2
+}
+
+/// How the jump arguments of a bytecode file are encoded.
+#[derive(Clone, Copy)]
+enum InstructionSet {
+    /// Python 3.6 to 3.9, jump arguments are byte offsets and every instruction is 2 bytes.
+    ByteOffsets,
+    /// Python 3.10, jump arguments count instructions.
+    InstructionOffsets,
+    /// Python 3.11, jumps are relative and count the inline caches after instructions too,
+    /// and an exception table replaces the blocks.
+    ExceptionTable,
+    /// Python 3.12, like Python 3.11 but conditional jumps only go forward, and `LOAD_ATTR`
+    /// and `COMPARE_OP` keep flags in the low bits of their arguments.
+    FlaggedArguments,
+}
 
-        self.index += 1;
+/// The instruction of `BINARY_OP` operator `arg`, `None` for the operators `Vm` has no
+/// instruction for.
+fn binary_operator(arg: usize) -> Option<Instruction> {
+    const INPLACE: usize = 13;
+    let (binary, inplace) = match arg % INPLACE {
+        0 => (Instruction::BinaryAdd, Instruction::InplaceAdd),
+        1 => (Instruction::BinaryAnd, Instruction::InplaceAnd),
+        2 => (Instruction::BinaryFloorDivide, Instruction::InplaceFloorDivide),
+        5 => (Instruction::BinaryMultiply, Instruction::InplaceMultiply),
+        7 => (Instruction::BinaryOr, Instruction::InplaceOr),
+        10 => (Instruction::BinarySubtract, Instruction::InplaceSubtract),
+        11 => (Instruction::BinaryTrueDivide, Instruction::InplaceTrueDivide),
+        12 => (Instruction::BinaryXor, Instruction::InplaceXor),
+        _ => return None,
+    };
+    match arg / INPLACE {
+        0 => Some(binary),
+        1 => Some(inplace),
+        _ => None,
     }
+}
 
-    fn delete_global(&mut self, arg: usize) {
-        self.globals.remove(&self.co_names[arg]);
+/// `co_flags` bit of functions with a `*args` parameter.
+const CO_VARARGS: u32 = 0x04;
+/// `co_flags` bit of functions with a `**kwargs` parameter.
+const CO_VARKEYWORDS: u32 = 0x08;
 
-        self.index += 1;
-    }
+/// The version a bytecode file was compiled with, recorded by `bytecode_gen/recursive_dis.py`
+/// next to the fields of the module `Frame`.
+#[derive(Deserialize)]
+struct CodeFileHeader {
+    python_version: Option<PythonVersion>,
+}
 
-    fn compare_op(&mut self, arg: usize) {
-        let second_var = self.stack.pop().unwrap();
-        let first_var = self.stack.pop().unwrap();
+type Namespace = Rc<RefCell<HashMap<Rc<String>, Value>>>;
 
-        self.stack.push(Value::Bool(
-            match CompareOps::from(arg) {
-                CompareOps::LessThan => first_var < second_var,
-                CompareOps::LessThanOrEqual => first_var <= second_var,
-                CompareOps::Equal => first_var == second_var,
-                CompareOps::NotEqual => first_var != second_var,
-                CompareOps::GreaterThan => first_var > second_var,
-                CompareOps::GreaterThanOrEqual => first_var >= second_var,
-            }
-        ));
+#[derive(Debug)]
+struct Module {
+    name: Rc<String>,
+    attributes: HashMap<Rc<String>, Value>,
+    /// The `__all__` of the module, names that `from module import *` binds. When it is
+    /// `None` every name that doesn't start with an underscore is bound.
+    all: Option<Vec<Rc<String>>>,
+}
 
-        self.index += 1;
-    }
+/// A type implemented in Rust. Calling it constructs an instance, and its attributes are
+/// class-level functions and constants like `datetime.datetime.now`.
+#[derive(Debug)]
+struct NativeType {
+    name: Rc<String>,
+    constructor: NativeFn,
+    keywords: Keywords,
+    attributes: HashMap<Rc<String>, Value>,
+}
 
-    fn pop_jump_if_true(&mut self, arg: usize) {
-        if let Value::Bool(result) = self.stack.pop().unwrap() {
-            if result {
-                self.index = arg / 2;
-            } else {
-                self.index += 1;
-            }
-        } else {
-            panic!("Invalid `Value` passed to compare");
+impl Module {
+    fn new(name: &str, attributes: HashMap<Rc<String>, Value>) -> Module {
+        Module {
+            name: Rc::new(String::from(name)),
+            attributes,
+            all: None,
         }
     }
 
-    fn pop_jump_if_false(&mut self, arg: usize) {
-        if let Value::Bool(result) = self.stack.pop().unwrap() {
-            if !result {
-                self.index = arg / 2;
-            } else {
-                self.index += 1;
-            }
-        } else {
-            panic!("Invalid `Value` passed to compare");
+    fn public_names(&self) -> Vec<Rc<String>> {
+        match &self.all {
+            Some(all) => all.clone(),
+            None => self.attributes.keys().filter(|name| !name.starts_with('_')).cloned().collect(),
         }
     }
+}
 
-    fn jump_if_true_or_pop(&mut self, arg: usize) {
-        if let Value::Bool(result) = self.stack.last().unwrap() {
-            if *result {
-                self.index = arg / 2;
-            } else {
-                self.stack.pop();
+#[derive(Debug)]
+enum VmError {
+    TypeError(String),
+    AttributeError(String),
+    ImportError(String),
+    ModuleNotFoundError(Rc<String>),
+    /// A global or built-in name that isn't bound.
+    NameError(Rc<String>),
+    /// A local variable of a function read or deleted before it is assigned.
+    UnboundLocalError(Rc<String>),
+    ValueError(String),
+    IndexError(String),
+    /// A key missing from a dict, given as its repr.
+    KeyError(String),
+    OverflowError(String),
+    ZeroDivisionError(String),
+    /// Raised when a `weakref.proxy` is used after its object is gone.
+    ReferenceError(String),
+    /// An `OSError` or one of its subclasses, by name.
+    OSError(&'static str, String),
+    RecursionError,
+    /// `re.error`, raised for invalid patterns and replacement templates.
+    ReError(String),
+    /// `struct.error`, raised for invalid formats and values that don't fit them.
+    StructError(String),
+    PicklingError(String),
+    UnpicklingError(String),
+    /// An exception raised by Python code, or an error that went through an exception handler.
+    Exception(Rc<Exception>),
+    Unimplemented(String),
+    /// Bytecode that asks for something the stack can't give, like swapping with an item below
+    /// the bottom of the stack. Python code can't catch it.
+    InternalError(String),
+}
 
-                self.index += 1;
-            }
-        } else {
-            panic!("Invalid `Value` passed to compare");
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VmError::TypeError(message) => write!(f, "TypeError: {}", message),
+            VmError::AttributeError(message) => write!(f, "AttributeError: {}", message),
+            VmError::ImportError(message) => write!(f, "ImportError: {}", message),
+            VmError::ModuleNotFoundError(name) => write!(f, "ModuleNotFoundError: No module named '{}'", name),
+            VmError::NameError(name) => write!(f, "NameError: name '{}' is not defined", name),
+            VmError::UnboundLocalError(name) => write!(f, "UnboundLocalError: local variable '{}' referenced before assignment", name),
+            VmError::ValueError(message) => write!(f, "ValueError: {}", message),
+            VmError::IndexError(message) => write!(f, "IndexError: {}", message),
+            VmError::KeyError(key) => write!(f, "KeyError: {}", key),
+            VmError::OverflowError(message) => write!(f, "OverflowError: {}", message),
+            VmError::ZeroDivisionError(message) => write!(f, "ZeroDivisionError: {}", message),
+            VmError::ReferenceError(message) => write!(f, "ReferenceError: {}", message),
+            VmError::OSError(name, message) => write!(f, "{}: {}", name, message),
+            VmError::RecursionError => write!(f, "RecursionError: maximum recursion depth exceeded"),
+            VmError::ReError(message) => write!(f, "re.error: {}", message),
+            VmError::StructError(message) => write!(f, "struct.error: {}", message),
+            VmError::PicklingError(message) => write!(f, "_pickle.PicklingError: {}", message),
+            VmError::UnpicklingError(message) => write!(f, "_pickle.UnpicklingError: {}", message),
+            VmError::Exception(exception) => write!(f, "{}", exception),
+            VmError::Unimplemented(message) => write!(f, "NotImplementedError: {}", message),
+            VmError::InternalError(message) => write!(f, "InternalError: {}", message),
         }
     }
+}
 
-    fn jump_if_false_or_pop(&mut self, arg: usize) {
-        if let Value::Bool(result) = self.stack.last().unwrap() {
-            if !(*result) {
-                self.index = arg / 2;
-            } else {
-                self.stack.pop();
+#[derive(Clone, Copy, Deserialize, Serialize)]
+struct VmConfig {
+    recursion_limit: usize,
+    /// Whether a function that returns the result of calling itself reuses its frame for the
+    /// call instead of starting a new one, so self tail recursion isn't limited by
+    /// `recursion_limit`. Python doesn't do this, so tracebacks and `__del__` timing differ.
+    tail_call_trampoline: bool,
+    /// `python -O`, which skips failing `assert` statements.
+    optimize: bool,
+    /// Whether `threading.Thread` runs its target on a thread of its own, see `modules::threading`.
+    threads: bool,
+}
 
-                self.index += 1;
-            }
-        } else {
-            panic!("Invalid `Value` passed to compare");
+impl Default for VmConfig {
+    fn default() -> Self {
+        VmConfig {
+            recursion_limit: 1000,
+            tail_call_trampoline: false,
+            optimize: false,
+            threads: false,
         }
     }
+}
+
+/// Execution state of a single call. The code being executed lives in a `Frame`,
+/// which is never mutated while it runs.
+struct FrameState {
+    stack: Vec<Value>,
+    index: usize,
+    locals: Namespace,
+    /// The locals `LOAD_FAST` and `STORE_FAST` use, the same as `locals` in functions. Module
+    /// and class bodies only have them for the comprehensions Python 3.12 inlines, whose
+    /// variables are kept apart from the names of the body.
+    fast_locals: Namespace,
+    return_value: Value,
+    blocks: Vec<Block>,
+    /// Set by `YIELD_VALUE`, which suspends the frame with the yielded value in `return_value`.
+    yielded: bool,
+    /// The keyword argument names `KW_NAMES` keeps for the next `CALL`.
+    kw_names: Option<Vec<Rc<String>>>,
+    frame_type: FrameType,
+}
+
+/// What a frame runs, which decides what its locals are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FrameType {
+    /// Module level code, and code run by `exec` and `eval`, whose locals are given to it.
+    Module,
+    /// A call of a function, whose locals are its arguments and variables.
+    Function,
+    /// A class body, whose locals `__build_class__` makes the class dict of.
+    Class,
+}
 
-    fn make_function(&mut self, arg: usize) {
-        if arg != 0 {
-            panic!("Unimplemented function flag")
+impl FrameState {
+    fn new(locals: Namespace, frame_type: FrameType) -> FrameState {
+        FrameState {
+            stack: vec![],
+            index: 0,
+            fast_locals: FrameState::fast_locals(&locals, frame_type),
+            locals,
+            return_value: Value::Nonetype,
+            blocks: vec![],
+            yielded: false,
+            kw_names: None,
+            frame_type,
         }
+    }
 
-        if let (Value::Str(_), Value::Frame(frame)) = (self.stack.pop().unwrap(), self.stack.pop().unwrap()) {
-            self.stack.push( Value::Frame(frame));
-        } else {
-            panic!("Wrong types for TOS and TOS1")
+    fn fast_locals(locals: &Namespace, frame_type: FrameType) -> Namespace {
+        match frame_type {
+            FrameType::Function => Rc::clone(locals),
+            FrameType::Module | FrameType::Class => Rc::default(),
         }
+    }
 
-        self.index += 1;
+    /// Starts the frame over with `locals`, as if it was just created with them.
+    fn reset(&mut self, locals: Namespace) {
+        self.stack.clear();
+        self.index = 0;
+        self.fast_locals = FrameState::fast_locals(&locals, self.frame_type);
+        self.locals = locals;
+        self.return_value = Value::Nonetype;
+        self.blocks.clear();
+        self.yielded = false;
+        self.kw_names = None;
     }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BlockKind {
+    /// `try` and `with` bodies, whose handler runs when an exception is raised in them.
+    Finally,
+    /// A running exception handler. The three values below `level` are the exception that was
+    /// being handled before, restored when the handler is left.
+    ExceptHandler,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Block {
+    kind: BlockKind,
+    handler: usize,
+    /// The stack height to unwind to.
+    level: usize,
+}
+
+struct Vm {
+    globals: Namespace,
+    builtins: HashMap<Rc<String>, Value>,
+    /// Modules that can be imported, by name.
+    modules: HashMap<Rc<String>, Value>,
+    config: VmConfig,
+    call_stack: Vec<FrameState>,
+    /// The exception being handled, `sys.exc_info()` in CPython.
+    handled_exception: Value,
+    /// Whether a `__del__` is running, see `Vm::run_finalizers`.
+    finalizing: bool,
+    snapshot: Option<Snapshot>,
+    profiler: Option<Profiler>,
+    /// The globals shared with other threads under `--threads`, see `modules::threading`.
+    shared_globals: Option<SharedGlobals>,
+}
 
-    fn call_function(&mut self, arg: usize) {
-        if let Value::Frame(mut frame) = self.stack.remove(self.stack.len() - arg - 1) {
-            for i in 0..arg {
-                frame.locals.insert(Rc::clone(&frame.co_varnames[frame.co_varnames.len() - i - 1]), self.stack.pop().unwrap());
+impl Vm {
+    fn new(config: VmConfig) -> Vm {
+        let mut builtins = HashMap::new();
+        builtins.insert(Rc::new(String::from("print")), Value::Frame(Rc::new(Frame::create_print_frame())));
+        let functions = [
+            ("exec", builtin_exec as NativeFn),
+            ("eval", builtin_eval),
+            ("open", modules::io::open),
+            ("format", format::builtin_format),
+            ("hash", hash::builtin_hash),
+            ("id", id::builtin_id),
+            ("next", iterator::builtin_next),
+            ("bytes", builtin_bytes),
+            ("callable", builtin_callable),
+            ("bytearray", bytearray::builtin_bytearray),
+            ("memoryview", memoryview::builtin_memoryview),
+            ("__build_class__", classes::build_class),
+            ("super", classes::builtin_super),
+        ];
+        for (name, function) in functions.iter() {
+            let name = Rc::new(String::from(*name));
+            builtins.insert(Rc::clone(&name), Value::NativeFunction(name, *function, &[]));
+        }
+        builtins.insert(Rc::new(String::from("object")), Value::Class(classes::object_class()));
+        let types = [
+            ("int", builtin_int as NativeFn, &[] as Keywords),
+            ("str", builtin_str, &[]),
+            ("set", set::builtin_set, &[]),
+            ("frozenset", set::builtin_frozenset, &[]),
+            ("property", classes::builtin_property, &["fget", "fset", "fdel", "doc"]),
+            ("classmethod", classes::builtin_classmethod, &[]),
+            ("staticmethod", classes::builtin_staticmethod, &[]),
+        ];
+        for (name, constructor, keywords) in types.iter() {
+            let name = Rc::new(String::from(*name));
+            let native_type = NativeType {
+                name: Rc::clone(&name),
+                constructor: *constructor,
+                keywords,
+                attributes: HashMap::new(),
             };
-            // These were not supposed to be clones but lifetimes are hard
-            if self.depth == 0 {
-                frame.globals = self.locals.clone();
-            } else {
-                frame.globals = self.globals.clone();
+            builtins.insert(name, Value::NativeType(Rc::new(native_type)));
+        }
+        for kind in exceptions::builtin_types() {
+            builtins.insert(Rc::clone(&kind.name), Value::ExceptionType(kind));
+        }
+
+        let mut modules = HashMap::new();
+        let builtins_module = Module::new("builtins", builtins.clone());
+        modules.insert(Rc::clone(&builtins_module.name), Value::Module(Rc::new(builtins_module)));
+        for module in modules::builtin_modules() {
+            modules.insert(Rc::clone(&module.name), Value::Module(module));
+        }
+
+        Vm {
+            globals: Rc::new(RefCell::new(HashMap::from([(Rc::new(String::from("__name__")), Value::Str(String::from("__main__")))]))),
+            builtins,
+            modules,
+            config,
+            call_stack: vec![],
+            handled_exception: Value::Nonetype,
+            finalizing: false,
+            snapshot: None,
+            profiler: None,
+            shared_globals: None,
+        }
+    }
+
+    /// Drops the globals, like CPython does at exit, so the `__del__` of objects they hold runs.
+    fn shutdown(&mut self) -> Result<(), VmError> {
+        modules::threading::join_threads()?;
+        let globals = mem::take(&mut *self.globals.borrow_mut());
+        drop(globals);
+        self.run_finalizers()
+    }
+
+    /// Runs `frame` as module level code, so its locals are the globals.
+    fn run_frame(&mut self, frame: &Frame) -> Result<Value, VmError> {
+        let state = FrameState::new(Rc::clone(&self.globals), FrameType::Module);
+        self.execute(frame, state)
+    }
+
+    fn execute(&mut self, frame: &Frame, state: FrameState) -> Result<Value, VmError> {
+        self.run_state(frame, state, None).map(|state| state.return_value)
+    }
+
+    /// Runs the frame until it returns or yields and gives its state back, raising `thrown`
+    /// in it first if there is one.
+    fn run_state(&mut self, frame: &Frame, state: FrameState, thrown: Option<VmError>) -> Result<FrameState, VmError> {
+        if self.call_stack.len() >= self.config.recursion_limit {
+            return Err(VmError::RecursionError);
+        }
+
+        self.call_stack.push(state);
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter(&frame.co_name);
+        }
+        let result = match thrown {
+            Some(error) => self.unwind(frame, error).and_then(|_| self.eval(frame)),
+            None => self.eval(frame),
+        };
+        if let Some(profiler) = &mut self.profiler {
+            profiler.leave();
+        }
+        let state = self.call_stack.pop().unwrap();
+        result.map(|_| state)
+    }
+
+    fn state(&mut self) -> &mut FrameState {
+        self.call_stack.last_mut().unwrap()
+    }
+
+    fn eval(&mut self, frame: &Frame) -> Result<(), VmError> {
+        while let Some(instruction) = frame.instructions.get(self.state().index) {
+            if let Err(error) = self.dispatch(frame, *instruction).and_then(|_| self.run_finalizers()) {
+                self.unwind(frame, error)?;
+            }
+            self.snapshot_step();
+            if let Some(profiler) = &mut self.profiler {
+                profiler.step();
+            }
+            if self.state().yielded {
+                break;
             }
-            frame.depth += self.depth + 1;
-            frame.run();
-            self.stack.push(*frame.return_value);
-        } else {
-            panic!("Wrong type for TOS");
         }
 
-        self.index += 1;
+        Ok(())
+    }
+
+    fn dispatch(&mut self, frame: &Frame, instruction: Instruction) -> Result<(), VmError> {
+        match instruction {
+            Instruction::LoadConst(arg) => self.load_const(frame, arg),
+            Instruction::StoreName(arg) => self.store_name(frame, arg),
+            Instruction::LoadName(arg) => self.load_name(frame, arg)?,
+            Instruction::DeleteName(arg) => self.delete_name(frame, arg)?,
+            Instruction::StoreFast(arg) => self.store_fast(frame, arg),
+            Instruction::LoadFast(arg) => self.load_fast(frame, arg)?,
+            Instruction::DeleteFast(arg) => self.delete_fast(frame, arg)?,
+            Instruction::LoadFastAndClear(arg) => self.load_fast_and_clear(frame, arg),
+            Instruction::LoadFastCheck(arg) => self.load_fast(frame, arg)?,
+            Instruction::StoreGlobal(arg) => self.store_global(frame, arg),
+            Instruction::LoadGlobal(arg) if self.is_optimized_assert(frame, frame.co_names[arg].as_str()) => self.skip_assert(frame),
+            Instruction::LoadGlobal(arg) => self.load_global(frame, arg)?,
+            Instruction::LoadGlobalWithNull(arg) => {
+                self.state().stack.push(Value::Null);
+                self.load_global(frame, arg)?;
+            },
+            Instruction::DeleteGlobal(arg) => self.delete_global(frame, arg)?,
+            Instruction::LoadClosure(arg) => self.load_closure(frame, arg)?,
+            Instruction::LoadDeref(arg) => self.load_deref(frame, arg)?,
+            Instruction::LoadClassDeref(arg) => self.load_class_deref(frame, arg)?,
+            Instruction::StoreDeref(arg) => self.store_deref(frame, arg)?,
+            Instruction::DeleteDeref(arg) => self.delete_deref(frame, arg)?,
+            Instruction::MakeCell(arg) => self.make_cell(frame, arg)?,
+            Instruction::CopyFreeVars(arg) => self.copy_free_vars(frame, arg),
+            Instruction::CompareOp(arg) => self.compare_op(arg)?,
+            Instruction::IsOp(arg) => self.compare_op(if arg == 1 { 9 } else { 8 })?,
+            Instruction::ContainsOp(arg) => self.compare_op(if arg == 1 { 7 } else { 6 })?,
+            Instruction::JumpForward(arg) => { let state = self.state(); state.index = relative_target(state.index, arg); },
+            Instruction::PopJumpIfTrue(arg) => self.pop_jump_if_true(arg)?,
+            Instruction::PopJumpIfFalse(arg) => self.pop_jump_if_false(arg)?,
+            Instruction::PopJumpIfNone(arg) => self.pop_jump_if_none(arg, true),
+            Instruction::PopJumpIfNotNone(arg) => self.pop_jump_if_none(arg, false),
+            Instruction::PopJumpForwardIfTrue(_) | Instruction::PopJumpForwardIfFalse(_)
+            | Instruction::PopJumpForwardIfNone(_) | Instruction::PopJumpForwardIfNotNone(_)
+            | Instruction::PopJumpBackwardIfTrue(_) | Instruction::PopJumpBackwardIfFalse(_)
+            | Instruction::PopJumpBackwardIfNone(_) | Instruction::PopJumpBackwardIfNotNone(_)
+            | Instruction::JumpBackward(_) | Instruction::JumpBackwardNoInterrupt(_) => {
+                return Err(VmError::InternalError(format!("{:?} is only decoded in Python 3.11 bytecode", instruction)));
+            },
+            Instruction::JumpIfTrueOrPop(arg) => self.jump_if_true_or_pop(arg)?,
+            Instruction::JumpIfFalseOrPop(arg) => self.jump_if_false_or_pop(arg)?,
+            Instruction::JumpIfNotExcMatch(arg) => self.jump_if_not_exc_match(arg)?,
+            Instruction::JumpAbsolute(arg) =>  { self.state().index = arg / 2; },
+            Instruction::GetIter => self.get_iter()?,
+            // Generators are their own iterators, so `yield from` can iterate anything
+            // `GET_ITER` accepts.
+            Instruction::GetYieldFromIter => self.get_iter()?,
+            Instruction::GetAwaitable(_) => self.get_awaitable()?,
+            Instruction::GetAiter => self.get_aiter()?,
+            Instruction::GetAnext => self.get_anext()?,
+            Instruction::EndAsyncFor => self.end_async_for()?,
+            Instruction::ForIter(arg) => self.for_iter(arg)?,
+            Instruction::ImportName(arg) => self.import_name(frame, arg)?,
+            Instruction::ImportFrom(arg) => self.import_from(frame, arg)?,
+            Instruction::LoadAttr(arg) => self.load_attr(frame, arg)?,
+            Instruction::StoreAttr(arg) => self.store_attr(frame, arg)?,
+            Instruction::DeleteAttr(arg) => self.delete_attr(frame, arg)?,
+            Instruction::LoadMethod(arg) => self.load_method(frame, arg)?,
+            Instruction::CallMethod(arg) => self.call(frame, arg)?,
+            Instruction::LoadSuperAttr(arg) => self.load_super_attr(frame, arg)?,
+            Instruction::MakeFunction(arg) => self.make_function(arg)?,
+            Instruction::CallFunction(arg) => self.call_function(frame, arg)?,
+            Instruction::CallFunctionKw(arg) => self.call_function_kw(frame, arg)?,
+            Instruction::KwNames(arg) => self.kw_names(frame, arg)?,
+            Instruction::Call(arg) => self.call(frame, arg)?,
+            Instruction::FormatValue(arg) => self.format_value(arg)?,
+            Instruction::BuildString(arg) => self.build_string(arg),
+            Instruction::BuildSlice(arg) => self.build_slice(arg),
+            Instruction::BinarySlice => {
+                self.push_slice();
+                self.subscript()?;
+            },
+            Instruction::StoreSlice => {
+                self.push_slice();
+                self.store_subscr()?;
+            },
+            Instruction::BuildTuple(arg) => self.build_tuple(arg),
+            Instruction::UnpackSequence(arg) => self.unpack_sequence(arg)?,
+            Instruction::BuildList(arg) => self.build_list(arg),
+            Instruction::BuildSet(arg) => self.build_set(arg)?,
+            Instruction::SetAdd(arg) => self.set_add(arg)?,
+            Instruction::SetUpdate(arg) => self.set_update(arg)?,
+            Instruction::BuildSetUnpack(arg) => self.build_set_unpack(arg)?,
+            Instruction::BuildMap(arg) => self.build_map(arg)?,
+            Instruction::BuildConstKeyMap(arg) => self.build_const_key_map(arg)?,
+            Instruction::DictUpdate(arg) => self.dict_update(arg, false)?,
+            Instruction::DictMerge(arg) => self.dict_update(arg, true)?,
+            Instruction::BuildMapUnpack(arg) => self.build_map_unpack(arg, false)?,
+            Instruction::BuildMapUnpackWithCall(arg) => self.build_map_unpack(arg, true)?,
+            Instruction::ListExtend(arg) => self.list_extend(arg)?,
+            Instruction::ListAppend(arg) => self.list_append(arg)?,
+            Instruction::ListToTuple => self.call_intrinsic_1(intrinsics::LIST_TO_TUPLE)?,
+            Instruction::BuildTupleUnpackWithCall(arg) => self.build_tuple_unpack_with_call(arg)?,
+            Instruction::CallFunctionEx(arg) => self.call_function_ex(arg)?,
+            Instruction::ReturnValue | Instruction::InterpreterExit => self.return_value(frame),
+            Instruction::ReturnConst(arg) => {
+                self.load_const(frame, arg);
+                self.return_value(frame);
+            },
+            Instruction::ReturnGenerator => self.return_generator(),
+            Instruction::YieldValue(_) => self.yield_value(),
+            Instruction::YieldFrom => self.yield_from()?,
+            Instruction::Send(arg) => self.send(frame, arg)?,
+            Instruction::EndSend => self.end_send(),
+            Instruction::CleanupThrow => self.cleanup_throw()?,
+            Instruction::GenStart(_) => self.pop_top(),
+            Instruction::ImportStar => self.import_star()?,
+            Instruction::CallIntrinsic1(arg) => self.call_intrinsic_1(arg)?,
+            Instruction::LoadBuildClass => self.load_build_class(),
+            Instruction::SetupAnnotations => self.setup_annotations(),
+            Instruction::StoreAnnotation(arg) => self.store_annotation(frame, arg)?,
+            Instruction::InplaceAdd => self.binary_op("add", true, Vm::inplace_add)?,
+            Instruction::InplaceSubtract => self.binary_op("sub", true, |vm| vm.inplace_set_operation('-'))?,
+            Instruction::InplaceMultiply => self.binary_op("mul", true, Vm::inplace_multiply)?,
+            Instruction::InplaceTrueDivide => self.binary_op("truediv", true, Vm::true_divide)?,
+            Instruction::InplaceFloorDivide => self.binary_op("floordiv", true, Vm::floor_divide)?,
+            Instruction::InplaceAnd => self.binary_op("and", true, |vm| vm.inplace_set_operation('&'))?,
+            Instruction::InplaceOr => self.binary_op("or", true, |vm| vm.inplace_set_operation('|'))?,
+            Instruction::InplaceXor => self.binary_op("xor", true, |vm| vm.inplace_set_operation('^'))?,
+            Instruction::BinaryAdd => self.binary_op("add", false, Vm::add)?,
+            Instruction::BinarySubtract => self.binary_op("sub", false, Vm::subtract)?,
+            Instruction::BinaryMultiply => self.binary_op("mul", false, Vm::multiply)?,
+            Instruction::BinaryTrueDivide => self.binary_op("truediv", false, Vm::true_divide)?,
+            Instruction::BinaryFloorDivide => self.binary_op("floordiv", false, Vm::floor_divide)?,
+            Instruction::BinaryAnd => self.binary_op("and", false, |vm| vm.bitwise('&'))?,
+            Instruction::BinaryOr => self.binary_op("or", false, |vm| vm.bitwise('|'))?,
+            Instruction::BinaryXor => self.binary_op("xor", false, |vm| vm.bitwise('^'))?,
+            Instruction::BinaryOp(arg) => return Err(VmError::Unimplemented(format!("BINARY_OP {}", arg))),
+            Instruction::LoadLocals => return Err(VmError::Unimplemented(String::from("LOAD_LOCALS"))),
+            Instruction::LoadFromDictOrDeref(_) => return Err(VmError::Unimplemented(String::from("LOAD_FROM_DICT_OR_DEREF"))),
+            Instruction::BinarySubscr => self.subscript()?,
+            Instruction::StoreSubscr => self.store_subscr()?,
+            Instruction::Nop | Instruction::Cache | Instruction::ExtendedArg(_) | Instruction::Precall(_) | Instruction::Resume(_) => { self.state().index += 1; },
+            Instruction::PushNull => self.push_null(),
+            Instruction::EndFor => self.end_for(),
+            Instruction::PopTop => self.pop_top(),
+            Instruction::RotTwo => self.rot_two(),
+            Instruction::RotThree => self.rot_three(),
+            Instruction::RotFour => self.rot_four(),
+            Instruction::DupTop => self.dup_top(),
+            Instruction::DupTopTwo => self.dup_top_two(),
+            Instruction::Copy(arg) => self.copy(arg)?,
+            Instruction::Swap(arg) => self.swap(arg)?,
+            Instruction::MatchClass(arg) => self.match_class(frame, arg)?,
+            Instruction::UnaryPositive => { self.state().index += 1 },
+            Instruction::UnaryNegative => self.unary_negative()?,
+
+            Instruction::SetupFinally(arg) => self.setup_finally(arg),
+            Instruction::SetupWith(arg) => self.setup_with(arg)?,
+            Instruction::BeforeWith => self.before_with()?,
+            Instruction::BeforeAsyncWith => self.before_async_with()?,
+            Instruction::SetupAsyncWith(arg) => self.setup_async_with(arg),
+            Instruction::PopBlock => self.pop_block(),
+            Instruction::PopExcept => self.pop_except(),
+            Instruction::PushExcInfo => self.push_exc_info(),
+            Instruction::CheckExcMatch => self.check_exc_match()?,
+            Instruction::CheckEgMatch => self.check_eg_match()?,
+            Instruction::BeginFinally => self.begin_finally(),
+            Instruction::EndFinally => self.end_finally()?,
+            Instruction::CallFinally(arg) => self.call_finally(arg),
+            Instruction::PopFinally(arg) => self.pop_finally(arg),
+            Instruction::WithCleanupStart => self.with_cleanup_start()?,
+            Instruction::WithCleanupFinish => self.with_cleanup_finish()?,
+            Instruction::WithExceptStart => self.with_except_start()?,
+            Instruction::RaiseVarargs(arg) => self.raise_varargs(arg)?,
+            Instruction::Reraise(_) => self.reraise()?,
+            Instruction::LoadAssertionError if self.config.optimize => self.skip_assert(frame),
+            Instruction::LoadAssertionError => self.load_assertion_error(),
+
+            Instruction::PrintExpr => self.print_expr()?,
+            Instruction::Print => self.print()?,
+        };
+
+        Ok(())
     }
 
-    fn return_value(&mut self) {
-        self.return_value = Box::new(self.stack.pop().unwrap());
+    /// Jumps to the innermost handler for `error`, or gives the error back when the frame
+    /// has none, after leaving every handler that was running in it. Python 3.11 has no
+    /// blocks, its handler is the exception table entry of the instruction that raised it.
+    fn unwind(&mut self, frame: &Frame, error: VmError) -> Result<(), VmError> {
+        let exception = error.into_exception()?;
+        while let Some(block) = self.state().blocks.pop() {
+            match block.kind {
+                BlockKind::ExceptHandler => self.unwind_except_handler(block),
+                BlockKind::Finally => {
+                    let previous = mem::replace(&mut self.handled_exception, Value::Exception(Rc::clone(&exception)));
+                    let previous_kind = match &previous {
+                        Value::Exception(previous) => Value::ExceptionType(Rc::clone(&previous.kind)),
+                        _ => Value::Nonetype,
+                    };
+
+                    let kind = Value::ExceptionType(Rc::clone(&exception.kind));
+                    let state = self.state();
+                    state.stack.truncate(block.level);
+                    state.blocks.push(Block { kind: BlockKind::ExceptHandler, handler: 0, level: state.stack.len() });
+                    // The traceback, value and type of the previous exception and then of the
+                    // new one. Tracebacks aren't kept, so they are always None.
+                    state.stack.extend(vec![Value::Nonetype, previous, previous_kind]);
+                    state.stack.extend(vec![Value::Nonetype, Value::Exception(exception), kind]);
+                    state.index = block.handler;
+                    return Ok(());
+                },
+            }
+        }
+
+        let index = self.state().index;
+        if let Some(entry) = frame.exception_handler(index) {
+            let state = self.state();
+            state.stack.truncate(entry.depth);
+            if entry.lasti {
+                state.stack.push(Value::Int(index as i64 * 2));
+            }
+            state.stack.push(Value::Exception(exception));
+            state.index = entry.target / 2;
+            return Ok(());
+        }
 
-        self.index = self.instructions.len();
+        Err(VmError::Exception(exception))
     }
 
-    fn add(&mut self) {
-        let mut result = self.stack.pop().unwrap();
-        result = self.stack.pop().unwrap() + result;
-        self.stack.push(result);
+    fn unwind_except_handler(&mut self, block: Block) {
+        self.state().stack.truncate(block.level + 3);
+        self.restore_handled_exception();
+    }
 
-        self.index += 1;
+    /// Pops the type, value and traceback of the exception that was being handled before the
+    /// current handler, and handles it again.
+    fn restore_handled_exception(&mut self) {
+        let state = self.state();
+        state.stack.pop();
+        let previous = state.stack.pop().unwrap();
+        state.stack.pop();
+        self.handled_exception = previous;
     }
 
-    fn subtract(&mut self) {
-        let mut result = self.stack.pop().unwrap();
-        result = self.stack.pop().unwrap() - result;
-        self.stack.push(result);
+    fn load_const(&mut self, frame: &Frame, arg: usize) {
+        let state = self.state();
+        state.stack.push(frame.constants[arg].clone());
 
-        self.index += 1;
+        state.index += 1;
     }
 
-    fn multiply(&mut self) {
-        let mut result = self.stack.pop().unwrap();
-        result = self.stack.pop().unwrap() * result;
-        self.stack.push(result);
+    fn store_name(&mut self, frame: &Frame, arg: usize) {
+        let name = &frame.co_names[arg];
+        let state = self.state();
+        state.locals.borrow_mut().insert(Rc::clone(name), state.stack.pop().unwrap());
+        let locals = Rc::clone(&state.locals);
+        if Rc::ptr_eq(&locals, &self.globals) {
+            self.push_global(name);
+        }
 
-        self.index += 1;
+        self.state().index += 1;
     }
 
-    fn true_divide(&mut self) {
-        let mut result = self.stack.pop().unwrap();
-        result = self.stack.pop().unwrap() / result;
-        self.stack.push(result);
+    fn load_name(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let name = &frame.co_names[arg];
+        self.pull_global(name);
+        let local = self.state().locals.borrow().get(name).cloned();
+        let value = local
+            .or_else(|| self.globals.borrow().get(name).cloned())
+            .or_else(|| self.builtins.get(name).cloned())
+            .ok_or_else(|| VmError::NameError(Rc::clone(name)))?;
+
+        let state = self.state();
+        state.stack.push(value);
 
-        self.index += 1;
+        state.index += 1;
+        Ok(())
     }
 
-    fn floor_divide(&mut self) {
-        let mut result = self.stack.pop().unwrap();
-        result = self.stack.pop().unwrap() / result;
-        if let Value::Float(result) = result {
-            self.stack.push(Value::Int(result as i32));
-        } else {
-            self.stack.push(result);
+    fn delete_name(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let name = &frame.co_names[arg];
+        self.pull_global(name);
+        let state = self.state();
+        state.locals.borrow_mut().remove(name).ok_or_else(|| VmError::NameError(Rc::clone(name)))?;
+        let locals = Rc::clone(&state.locals);
+        if Rc::ptr_eq(&locals, &self.globals) {
+            self.push_global(name);
         }
 
-        self.index += 1;
+        self.state().index += 1;
+        Ok(())
     }
 
-    fn pop_top(&mut self) {
-        self.stack.pop();
+    /// Storing the `Null` of `LOAD_FAST_AND_CLEAR` restores a local that was unbound.
+    fn store_fast(&mut self, frame: &Frame, arg: usize) {
+        let state = self.state();
+        let name = Rc::clone(&frame.co_varnames[arg]);
+        match state.stack.pop().unwrap() {
+            Value::Null => state.fast_locals.borrow_mut().remove(&name),
+            value => state.fast_locals.borrow_mut().insert(name, value),
+        };
 
-        self.index += 1;
+        state.index += 1;
     }
 
-    fn rot_two(&mut self) {
-        let last_pos = self.stack.len() - 1;
-        self.stack.swap(last_pos, last_pos - 1);
+    fn load_fast(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let name = &frame.co_varnames[arg];
+        let value = state.fast_locals.borrow().get(name).cloned().ok_or_else(|| VmError::UnboundLocalError(Rc::clone(name)))?;
+        state.stack.push(value);
 
-        self.index += 1;
+        state.index += 1;
+        Ok(())
     }
 
-    fn rot_three(&mut self) {
-        let last_pos = self.stack.len() - 1;
-        self.stack.swap(last_pos, last_pos - 1);
-        self.stack.swap(last_pos - 1, last_pos - 2);
+    fn delete_fast(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let name = &frame.co_varnames[arg];
+        state.fast_locals.borrow_mut().remove(name).ok_or_else(|| VmError::UnboundLocalError(Rc::clone(name)))?;
 
-        self.index += 1;
+        state.index += 1;
+        Ok(())
     }
 
-    fn rot_four(&mut self) {
-        let last_pos = self.stack.len() - 1;
-        self.stack.swap(last_pos, last_pos - 1);
-        self.stack.swap(last_pos - 1, last_pos - 2);
-        self.stack.swap(last_pos - 2, last_pos - 3);
+    fn load_fast_and_clear(&mut self, frame: &Frame, arg: usize) {
+        let state = self.state();
+        let value = state.fast_locals.borrow_mut().remove(&frame.co_varnames[arg]);
+        state.stack.push(value.unwrap_or(Value::Null));
 
-        self.index += 1;
+        state.index += 1;
     }
 
-    fn dup_top(&mut self) {
-        self.stack.push(self.stack.last().unwrap().clone());
+    fn store_global(&mut self, frame: &Frame, arg: usize) {
+        let value = self.state().stack.pop().unwrap();
+        self.globals.borrow_mut().insert(Rc::clone(&frame.co_names[arg]), value);
+        self.push_global(&frame.co_names[arg]);
 
-        self.index += 1;
+        self.state().index += 1;
     }
 
-    fn dup_top_two(&mut self) {
-        self.stack.push(self.stack[self.stack.len() - 1].clone());
-        self.stack.insert(self.stack.len() - 3, self.stack[self.stack.len() - 3].clone());
+    fn load_global(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let name = &frame.co_names[arg];
+        self.pull_global(name);
+        let global = self.globals.borrow().get(name).cloned();
+        let value = global.or_else(|| self.builtins.get(name).cloned()).ok_or_else(|| VmError::NameError(Rc::clone(name)))?;
 
-        self.index += 1;
+        let state = self.state();
+        state.stack.push(value);
+
+        state.index += 1;
+        Ok(())
     }
 
-    fn unary_negative(&mut self) {
-        let negative = Value::Int(0) - self.stack.pop().unwrap();
-        self.stack.push(negative);
+    fn delete_global(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let name = &frame.co_names[arg];
+        self.pull_global(name);
+        self.globals.borrow_mut().remove(name).ok_or_else(|| VmError::NameError(Rc::clone(name)))?;
+        self.push_global(name);
 
-        self.index += 1;
+        self.state().index += 1;
+        Ok(())
     }
-    
-    fn create_print_frame() -> Frame {
-        Frame {
-            instructions: vec![
-                Instruction::LoadFast(0),
-                Instruction::Print
-            ],
-            constants: vec![Value::Str(String::from("to_print"))],
-            co_names: vec![],
-            co_varnames: vec![Rc::new(String::from("to_print"))],
-            stack: vec![],
-            index: 0,
-            globals: Default::default(),
-            locals: Default::default(),
-            return_value: Box::new(Value::Nonetype),
-            depth: 0
+
+    /// The cell of cell or free variable `arg`, which the call put in the locals.
+    fn cell(&mut self, frame: &Frame, arg: usize) -> Result<Rc<RefCell<Value>>, VmError> {
+        let name = frame.deref_name(arg).ok_or_else(|| VmError::InternalError(format!("cell variable {} out of range", arg)))?;
+        match self.state().locals.borrow().get(name) {
+            Some(Value::Cell(cell)) => Ok(Rc::clone(cell)),
+            _ => Err(VmError::InternalError(format!("'{}' is not a cell", name))),
         }
     }
 
-    fn print(&mut self) {
-        match self.stack.pop().unwrap() {
-            Value::Int(val) => println!("{}", val),
-            Value::Bool(val) => println!("{}", val),
-            Value::Float(val) => println!("{}", val),
-            Value::Str(val) => println!("{}", val),
-            Value::Nonetype => println!("None"),
-            Value::Frame(val) => println!("{:#?}", val)
+    /// The error for reading or deleting cell or free variable `arg` while it is unbound.
+    fn unbound_deref(frame: &Frame, arg: usize) -> VmError {
+        if arg < frame.co_cellvars.len() {
+            VmError::UnboundLocalError(Rc::clone(&frame.co_cellvars[arg]))
+        } else {
+            let message = format!("free variable '{}' referenced before assignment in enclosing scope", frame.co_freevars[arg - frame.co_cellvars.len()]);
+            VmError::Exception(Exception::new("NameError", message))
         }
+    }
+
+    fn load_closure(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let cell = self.cell(frame, arg)?;
+        let state = self.state();
+        state.stack.push(Value::Cell(cell));
 
-        self.index += 1;
+        state.index += 1;
+        Ok(())
     }
-}
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let mut frame: Frame = serde_json::from_str(&fs::read_to_string(&args[1]).unwrap()).unwrap();
-    frame.locals.insert(Rc::new(String::from("print")), Value::Frame(Frame::create_print_frame()));
+    fn load_deref(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let value = match self.cell(frame, arg)?.borrow().clone() {
+            Value::Null => return Err(Vm::unbound_deref(frame, arg)),
+            value => value,
+        };
+        let state = self.state();
+        state.stack.push(value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// The class namespace holds the cells of the free variables under their names too, so a
+    /// value there that isn't a cell was bound by the class body.
+    fn load_class_deref(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let local = frame.deref_name(arg).and_then(|name| self.state().locals.borrow().get(name).cloned());
+        match local {
+            Some(Value::Cell(_)) | None => self.load_deref(frame, arg),
+            Some(value) => {
+                let state = self.state();
+                state.stack.push(value);
+
+                state.index += 1;
+                Ok(())
+            },
+        }
+    }
+
+    fn store_deref(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let cell = self.cell(frame, arg)?;
+        let state = self.state();
+        cell.replace(state.stack.pop().unwrap());
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn delete_deref(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        if let Value::Null = self.cell(frame, arg)?.replace(Value::Null) {
+            return Err(Vm::unbound_deref(frame, arg));
+        }
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    fn make_cell(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let name = frame.fast_local_name(arg).ok_or_else(|| VmError::InternalError(format!("MAKE_CELL {} out of range", arg)))?;
+        let state = self.state();
+        let value = state.fast_locals.borrow_mut().remove(name).unwrap_or(Value::Null);
+        state.locals.borrow_mut().insert(Rc::clone(name), Value::Cell(Rc::new(RefCell::new(value))));
+
+        state.index += 1;
+        Ok(())
+    }
 
-    let now = Instant::now();
-    frame.run();
-    println!("Running Took: {:?}", now.elapsed());
+    fn copy_free_vars(&mut self, frame: &Frame, arg: usize) {
+        let state = self.state();
+        let mut locals = state.locals.borrow_mut();
+        for (name, cell) in frame.co_freevars.iter().zip(&frame.closure).take(arg) {
+            locals.insert(Rc::clone(name), Value::Cell(Rc::clone(cell)));
+        }
+        drop(locals);
+
+        state.index += 1;
+    }
+
+    fn compare_op(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let second_var = state.stack.pop().unwrap();
+        let first_var = state.stack.pop().unwrap();
+
+        let result = match CompareOps::from(arg) {
+            CompareOps::LessThan => first_var < second_var,
+            CompareOps::LessThanOrEqual => first_var <= second_var,
+            CompareOps::Equal => first_var == second_var,
+            CompareOps::NotEqual => first_var != second_var,
+            CompareOps::GreaterThan => first_var > second_var,
+            CompareOps::GreaterThanOrEqual => first_var >= second_var,
+            CompareOps::In => self.contains(&second_var, &first_var)?,
+            CompareOps::NotIn => !self.contains(&second_var, &first_var)?,
+            CompareOps::Is => is_same(&first_var, &second_var),
+            CompareOps::IsNot => !is_same(&first_var, &second_var),
+            CompareOps::ExceptionMatch => exception_matches(&first_var, &second_var)?,
+        };
+
+        let state = self.state();
+        state.stack.push(Value::Bool(result));
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `item in container`: a substring of a string, a byte or a run of bytes of a bytes-like
+    /// object, a key of a dict, or an item equal to `item` for the other containers. Instances
+    /// answer with `__contains__`, or are searched with the iterator `__iter__` returns.
+    fn contains(&mut self, container: &Value, item: &Value) -> Result<bool, VmError> {
+        match container {
+            Value::Instance(instance) => {
+                if let Some(method) = instance.get_attr(&Rc::new(String::from("__contains__"))) {
+                    let result = self.call_value(method, vec![item.clone()])?;
+                    return self.python_bool(&result);
+                }
+                let iter = instance.get_attr(&Rc::new(String::from("__iter__"))).ok_or_else(|| {
+                    VmError::TypeError(format!("argument of type '{}' is not iterable", instance.class.name))
+                })?;
+                let iterator = self.call_value(iter, vec![])?;
+                let iterator = match SequenceIterator::new(&iterator) {
+                    Some(sequence_iterator) => Value::Iterator(Rc::new(sequence_iterator)),
+                    None => iterator,
+                };
+                while let Some(value) = self.next_value(&iterator)? {
+                    if value == *item {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            },
+            Value::Str(string) => match item {
+                Value::Str(substring) => Ok(string.contains(substring.as_str())),
+                other => Err(VmError::TypeError(format!("'in <string>' requires string as left operand, not {}", other.type_name()))),
+            },
+            Value::Bytes(_) | Value::ByteArray(_) | Value::MemoryView(_) => {
+                let bytes = byte_items(container).unwrap()?;
+                match item {
+                    Value::Int(_) | Value::Bool(_) => Ok(bytes.contains(&byte_value(item)?)),
+                    Value::Bytes(_) | Value::ByteArray(_) | Value::MemoryView(_) => {
+                        let run = byte_items(item).unwrap()?;
+                        Ok(run.is_empty() || bytes.windows(run.len()).any(|window| window == run.as_slice()))
+                    },
+                    other => Err(VmError::TypeError(format!("a bytes-like object is required, not \'{}\'", other.type_name()))),
+                }
+            },
+            Value::Dict(dict) => Ok(dict.borrow().get(item)?.is_some()),
+            Value::FrozenSet(set) => {
+                item.python_hash()?;
+                Ok(set.contains(item))
+            },
+            Value::Set(set) => {
+                item.python_hash()?;
+                Ok(set.borrow().contains(item))
+            },
+            container => match container.sequence_items() {
+                Some(items) => Ok(items.contains(item)),
+                None => Err(VmError::TypeError(format!("argument of type \'{}\' is not iterable", container.type_name()))),
+            },
+        }
+    }
+
+    /// Python 3.12's `POP_JUMP_IF_NONE`, or `POP_JUMP_IF_NOT_NONE` when `none` is false.
+    fn pop_jump_if_none(&mut self, arg: usize, none: bool) {
+        let state = self.state();
+        let is_none = matches!(state.stack.pop().unwrap(), Value::Nonetype);
+        if is_none == none {
+            state.index = arg / 2;
+        } else {
+            state.index += 1;
+        }
+    }
+
+    fn pop_jump_if_true(&mut self, arg: usize) -> Result<(), VmError> {
+        let value = self.state().stack.pop().unwrap();
+        let truthy = self.python_bool(&value)?;
+        let state = self.state();
+        if truthy {
+            state.index = arg / 2;
+        } else {
+            state.index += 1;
+        }
+        Ok(())
+    }
+
+    fn pop_jump_if_false(&mut self, arg: usize) -> Result<(), VmError> {
+        let value = self.state().stack.pop().unwrap();
+        let truthy = self.python_bool(&value)?;
+        let state = self.state();
+        if !truthy {
+            state.index = arg / 2;
+        } else {
+            state.index += 1;
+        }
+        Ok(())
+    }
+
+    fn jump_if_true_or_pop(&mut self, arg: usize) -> Result<(), VmError> {
+        let value = self.state().stack.last().unwrap().clone();
+        let truthy = self.python_bool(&value)?;
+        let state = self.state();
+        if truthy {
+            state.index = arg / 2;
+        } else {
+            state.stack.pop();
+
+            state.index += 1;
+        }
+        Ok(())
+    }
+
+    fn jump_if_false_or_pop(&mut self, arg: usize) -> Result<(), VmError> {
+        let value = self.state().stack.last().unwrap().clone();
+        let truthy = self.python_bool(&value)?;
+        let state = self.state();
+        if !truthy {
+            state.index = arg / 2;
+        } else {
+            state.stack.pop();
+
+            state.index += 1;
+        }
+        Ok(())
+    }
+
+    fn jump_if_not_exc_match(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let clause = state.stack.pop().unwrap();
+        let kind = state.stack.pop().unwrap();
+        if exception_matches(&kind, &clause)? {
+            state.index += 1;
+        } else {
+            state.index = arg / 2;
+        }
+        Ok(())
+    }
+
+    /// Makes a function of the code below its qualified name, with the defaults and closure
+    /// below them that the flags in `arg` say are there.
+    fn make_function(&mut self, arg: usize) -> Result<(), VmError> {
+        const DEFAULTS: usize = 0x01;
+        const KWDEFAULTS: usize = 0x02;
+        const CLOSURE: usize = 0x08;
+        if arg & !(DEFAULTS | KWDEFAULTS | CLOSURE) != 0 {
+            return Err(VmError::Unimplemented(String::from("Unimplemented function flag")));
+        }
+
+        let state = self.state();
+        // Python 3.11 doesn't push the qualified name, the code has it.
+        let qualname = match state.stack.last() {
+            Some(Value::Str(_)) => state.stack.pop(),
+            _ => None,
+        };
+        let (qualname, mut function) = match (qualname, state.stack.pop().unwrap()) {
+            (Some(Value::Str(qualname)), function @ Value::Frame(_)) => (qualname, function),
+            (None, function @ Value::Frame(_)) => (function.as_frame().unwrap().qualname().to_string(), function),
+            _ => return Err(VmError::TypeError(String::from("Wrong types for TOS and TOS1"))),
+        };
+        if arg & CLOSURE != 0 {
+            let closure = match state.stack.pop().unwrap() {
+                Value::Tuple(cells) => cells.into_iter().map(|cell| match cell {
+                    Value::Cell(cell) => Ok(cell),
+                    other => Err(VmError::InternalError(format!("closure item {} is not a cell", other.python_repr()))),
+                }).collect::<Result<Vec<_>, _>>()?,
+                other => return Err(VmError::InternalError(format!("closure {} is not a tuple", other.python_repr()))),
+            };
+            // The code is copied so the function can hold its own cells and defaults.
+            function.as_frame_mut().unwrap().closure = closure;
+        }
+        if arg & KWDEFAULTS != 0 {
+            let kwdefaults = match state.stack.pop().unwrap() {
+                Value::Dict(dict) => dict.borrow().items().map(|(name, value)| (Rc::new(name.to_string()), value.clone())).collect(),
+                other => return Err(VmError::InternalError(format!("keyword-only defaults {} are not a dict", other.python_repr()))),
+            };
+            function.as_frame_mut().unwrap().kwdefaults = kwdefaults;
+        }
+        if arg & DEFAULTS != 0 {
+            let defaults = match state.stack.pop().unwrap() {
+                Value::Tuple(defaults) => defaults,
+                other => return Err(VmError::InternalError(format!("defaults {} are not a tuple", other.python_repr()))),
+            };
+            function.as_frame_mut().unwrap().defaults = defaults;
+        }
+        let qualname = match (state.frame_type, state.locals.borrow().get(&String::from("__qualname__"))) {
+            // Methods are named after their class, even when the compiler didn't name them.
+            (FrameType::Class, Some(Value::Str(class))) if !qualname.starts_with(&format!("{}.", class)) => {
+                format!("{}.{}", class, qualname)
+            },
+            _ => qualname,
+        };
+        if function.as_frame().unwrap().qualname().as_str() != qualname {
+            function.as_frame_mut().unwrap().co_qualname = Rc::new(qualname);
+        }
+        state.stack.push(function);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn call_function(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let args = state.stack.split_off(state.stack.len() - arg);
+        let function = state.stack.pop().unwrap();
+        let args = self.implicit_arguments(frame, &function, args)?;
+        if self.is_self_tail_call(frame, &function) {
+            // The result would be returned right away, so the call can run in this frame.
+            let locals = Rc::new(RefCell::new(bind_arguments(frame, args, vec![])?));
+            self.state().reset(locals);
+            return Ok(());
+        }
+        let return_value = self.call_value(function, args)?;
+
+        let state = self.state();
+        state.stack.push(return_value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn call_function_kw(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let names = keyword_names(&self.state().stack.pop().unwrap())?;
+        self.call_with_keywords(frame, arg, names)
+    }
+
+    fn kw_names(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let names = keyword_names(&frame.constants[arg])?;
+        let state = self.state();
+        state.kw_names = Some(names);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// Python 3.11's `CALL`. Below the function is `Null`, or it is the first argument and the
+    /// function is below it, like the `self` of a method.
+    fn call(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let names = self.state().kw_names.take().unwrap_or_default();
+        let stack = &mut self.state().stack;
+        match stack.len().checked_sub(arg + 2) {
+            Some(position) if matches!(stack[position], Value::Null) => {
+                stack.remove(position);
+                self.call_with_keywords(frame, arg, names)
+            },
+            Some(_) => self.call_with_keywords(frame, arg + 1, names),
+            None => self.call_with_keywords(frame, arg, names),
+        }
+    }
+
+    /// Calls the function below the `arg` arguments on top of the stack, the last of which are
+    /// passed by the keywords in `names`.
+    fn call_with_keywords(&mut self, frame: &Frame, arg: usize, names: Vec<Rc<String>>) -> Result<(), VmError> {
+        let state = self.state();
+        let mut args = state.stack.split_off(state.stack.len() - arg);
+        let values = args.split_off(args.len() - names.len());
+        let kwargs = names.into_iter().zip(values).collect();
+        let function = state.stack.pop().unwrap();
+        let args = self.implicit_arguments(frame, &function, args)?;
+        let return_value = self.call_value_kw(function, args, kwargs)?;
+
+        let state = self.state();
+        state.stack.push(return_value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn call_function_ex(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let kwargs = match arg & 1 {
+            0 => vec![],
+            _ => match state.stack.pop().unwrap() {
+                Value::Dict(dict) => dict.borrow().items().map(|(key, value)| match key {
+                    Value::Str(name) => Ok((Rc::new(name.clone()), value.clone())),
+                    _ => Err(VmError::TypeError(String::from("keywords must be strings"))),
+                }).collect::<Result<Vec<_>, _>>()?,
+                other => return Err(VmError::TypeError(format!("argument after ** must be a mapping, not {}", other.type_name()))),
+            },
+        };
+        // `f(*args)` passes `args` itself, which can be any iterable.
+        let args = state.stack.pop().unwrap();
+        let args = args.sequence_items().ok_or_else(|| {
+            VmError::TypeError(format!("argument after * must be an iterable, not {}", args.type_name()))
+        })?;
+        let function = state.stack.pop().unwrap();
+        // Python 3.11 pushes `Null` below functions that aren't methods.
+        if let Some(Value::Null) = state.stack.last() {
+            state.stack.pop();
+        }
+        let return_value = self.call_value_kw(function, args, kwargs)?;
+
+        let state = self.state();
+        state.stack.push(return_value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// The arguments of a call of `function` without any in `frame`, which for `super()` in a
+    /// method are the method's class and first argument.
+    fn implicit_arguments(&mut self, frame: &Frame, function: &Value, args: Vec<Value>) -> Result<Vec<Value>, VmError> {
+        match function {
+            Value::NativeFunction(name, ..) if args.is_empty() && name.as_str() == "super" => self.implicit_super_arguments(frame),
+            _ => Ok(args),
+        }
+    }
+
+    /// Whether calling `function` is `return f(...)` inside `f`, which `tail_call_trampoline`
+    /// runs without a new frame. Calls inside `try` and `with` blocks aren't, their handlers
+    /// still have to run after the call.
+    fn is_self_tail_call(&mut self, frame: &Frame, function: &Value) -> bool {
+        let returns_result = matches!(frame.instructions.get(self.state().index + 1), Some(Instruction::ReturnValue));
+        self.config.tail_call_trampoline
+            && returns_result
+            && self.state().blocks.is_empty()
+            && function.as_frame().is_some_and(|callee| std::ptr::eq(callee, frame))
+            && !frame.is_generator()
+    }
+
+    fn call_value(&mut self, function: Value, args: Vec<Value>) -> Result<Value, VmError> {
+        self.call_value_kw(function, args, vec![])
+    }
+
+    /// Calls `function` with positional arguments `args` and keyword arguments `kwargs`. Only
+    /// functions and classes defined in Python, and the native functions and types registered
+    /// with `Keywords`, take keyword arguments.
+    fn call_value_kw(&mut self, function: Value, mut args: Vec<Value>, kwargs: Vec<(Rc<String>, Value)>) -> Result<Value, VmError> {
+        if !kwargs.is_empty() && !matches!(function, Value::Frame(_) | Value::Class(_) | Value::BoundMethod(..) | Value::NativeFunction(..) | Value::NativeType(_)) {
+            return Err(VmError::TypeError(format!("{}() takes no keyword arguments", function.type_name())));
+        }
+
+        match function {
+            Value::Frame(frame) => {
+                let locals = Rc::new(RefCell::new(bind_arguments(&frame, args, kwargs)?));
+                let state = FrameState::new(locals, FrameType::Function);
+                if frame.is_generator() {
+                    return Ok(Value::Generator(Rc::new(Generator::new(frame, state))));
+                }
+                self.execute(&frame, state)
+            },
+            Value::NativeFunction(name, function, keywords) => {
+                let args = modules::keywords_to_positional(&name, keywords, args, kwargs)?;
+                function(self, args)
+            },
+            Value::NativeType(native_type) => {
+                let args = modules::keywords_to_positional(&native_type.name, native_type.keywords, args, kwargs)?;
+                (native_type.constructor)(self, args)
+            },
+            Value::ExceptionType(kind) => Exception::instantiate(kind, args),
+            Value::Class(class) => classes::instantiate(self, class, args, kwargs),
+            Value::WeakRef(reference) if args.is_empty() => Ok(reference.upgrade().map_or(Value::Nonetype, Value::Instance)),
+            Value::WeakRef(_) => Err(VmError::TypeError(format!("weakref() takes no arguments ({} given)", args.len()))),
+            Value::BoundMethod(receiver, function) => {
+                args.insert(0, *receiver);
+                self.call_value_kw(*function, args, kwargs)
+            },
+            other => Err(VmError::TypeError(format!("'{}' object is not callable", other.type_name())))
+        }
+    }
+
+    fn import_name(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let name = &frame.co_names[arg];
+        // Only built-in modules can be imported, so the level doesn't change which module
+        // is loaded.
+        let state = self.state();
+        let fromlist = state.stack.pop().unwrap();
+        state.stack.pop();
+
+        if !self.modules.contains_key(name) {
+            return Err(VmError::ModuleNotFoundError(Rc::clone(name)));
+        }
+        // `import os.path` binds `os`, only `from os.path import ...` uses the sub-module.
+        let module = match (fromlist, name.find('.')) {
+            (Value::Nonetype, Some(dot)) => self.modules[&String::from(&name[..dot])].clone(),
+            _ => self.modules[name].clone(),
+        };
+        let state = self.state();
+        state.stack.push(module);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn import_from(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let name = &frame.co_names[arg];
+        let state = self.state();
+        let value = if let Value::Module(module) = state.stack.last().unwrap() {
+            module.attributes.get(name).cloned()
+                .ok_or_else(|| VmError::ImportError(format!("cannot import name '{}' from '{}'", name, module.name)))?
+        } else {
+            return Err(VmError::TypeError(String::from("Wrong type for TOS")));
+        };
+        state.stack.push(value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn load_attr(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let object = self.state().stack.pop().unwrap();
+        let value = self.get_attribute(object, &frame.co_names[arg])?;
+        let state = self.state();
+        state.stack.push(value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn load_method(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let object = self.state().stack.pop().unwrap();
+        let value = self.get_attribute(object, &frame.co_names[arg])?;
+        let state = self.state();
+        state.stack.push(Value::Null);
+        state.stack.push(value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn load_super_attr(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let object = state.stack.pop().unwrap();
+        let class = state.stack.pop().unwrap();
+        let function = state.stack.pop().unwrap();
+        let args = if arg & 2 != 0 { vec![class, object] } else { vec![] };
+        let args = self.implicit_arguments(frame, &function, args)?;
+        let proxy = self.call_value(function, args)?;
+        let value = self.get_attribute(proxy, &frame.co_names[arg >> 2])?;
+        let state = self.state();
+        if arg & 1 != 0 {
+            state.stack.push(Value::Null);
+        }
+        state.stack.push(value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn store_attr(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let object = state.stack.pop().unwrap();
+        let value = state.stack.pop().unwrap();
+        let name = Rc::clone(&frame.co_names[arg]);
+        match object {
+            Value::Instance(instance) => self.set_attribute(&instance, name, value)?,
+            Value::WeakProxy(proxy) => self.set_attribute(&modules::weakref::referent(&proxy)?, name, value)?,
+            Value::Class(class) => { class.dict.borrow_mut().insert(name, value); },
+            other => return Err(VmError::AttributeError(format!("{} has no attribute '{}' to set", other.python_repr(), name))),
+        }
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    fn delete_attr(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let object = self.state().stack.pop().unwrap();
+        let name = &frame.co_names[arg];
+        match object {
+            Value::Instance(instance) => self.delete_attribute(&instance, name)?,
+            Value::WeakProxy(proxy) => self.delete_attribute(&modules::weakref::referent(&proxy)?, name)?,
+            Value::Class(class) => {
+                class.dict.borrow_mut().remove(name).ok_or_else(|| {
+                    VmError::AttributeError(format!("type object '{}' has no attribute '{}'", class.name, name))
+                })?;
+            },
+            other => return Err(VmError::AttributeError(format!("{} has no attribute '{}' to delete", other.python_repr(), name))),
+        }
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    fn get_iter(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let top = state.stack.last_mut().unwrap();
+        match top {
+            Value::Generator(generator) if generator.is_coroutine() => {
+                return Err(VmError::TypeError(format!("\'{}\' object is not iterable", top.type_name())));
+            },
+            Value::File(_) | Value::Iterator(_) | Value::Generator(_) => {},
+            _ => match SequenceIterator::new(top) {
+                Some(iterator) => *top = Value::Iterator(Rc::new(iterator)),
+                None => return Err(VmError::TypeError(format!("\'{}\' object is not iterable", top.type_name()))),
+            },
+        }
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `await`: replaces TOS with the iterator `YIELD_FROM` runs.
+    fn get_awaitable(&mut self) -> Result<(), VmError> {
+        let awaitable = self.state().stack.pop().unwrap();
+        let iterator = self.awaited_iterator(awaitable)?;
+
+        let state = self.state();
+        state.stack.push(iterator);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// What awaiting `awaitable` iterates: a coroutine itself, or the iterator an instance's
+    /// `__await__` returns.
+    fn awaited_iterator(&mut self, awaitable: Value) -> Result<Value, VmError> {
+        let await_method = match &awaitable {
+            Value::Instance(instance) => instance.get_attr(&Rc::new(String::from("__await__"))),
+            _ => None,
+        };
+        match (awaitable, await_method) {
+            (Value::Generator(generator), _) if generator.is_coroutine() => Ok(Value::Generator(generator)),
+            (_, Some(method)) => match self.call_value(method, vec![])? {
+                Value::Generator(generator) if generator.is_coroutine() => {
+                    Err(VmError::TypeError(String::from("__await__() returned a coroutine")))
+                },
+                iterator @ (Value::Generator(_) | Value::Iterator(_)) => Ok(iterator),
+                other => Err(VmError::TypeError(format!("__await__() returned non-iterator {}", other.python_repr()))),
+            },
+            (other, None) => Err(VmError::TypeError(format!("object {} can't be used in 'await' expression", other.python_repr()))),
+        }
+    }
+
+    /// `async for`: replaces TOS with the async iterator its `__aiter__` returns.
+    fn get_aiter(&mut self) -> Result<(), VmError> {
+        let iterable = self.state().stack.pop().unwrap();
+        let iterator = match iterable.get_attr(&Rc::new(String::from("__aiter__"))) {
+            Ok(method) => self.call_value(method, vec![])?,
+            Err(_) => return Err(VmError::TypeError(format!(
+                "'async for' requires an object with __aiter__ method, got {}", iterable.python_repr()
+            ))),
+        };
+        if iterator.get_attr(&Rc::new(String::from("__anext__"))).is_err() {
+            return Err(VmError::TypeError(format!(
+                "'async for' received an object from __aiter__ that does not implement __anext__: {}", iterator.python_repr()
+            )));
+        }
+
+        let state = self.state();
+        state.stack.push(iterator);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// Pushes the iterator that awaits what the async iterator on top of the stack's
+    /// `__anext__` returns. The loop ends when that raises `StopAsyncIteration`.
+    fn get_anext(&mut self) -> Result<(), VmError> {
+        let iterator = self.state().stack.last().unwrap().clone();
+        let method = iterator.get_attr(&Rc::new(String::from("__anext__")))?;
+        let awaitable = self.call_value(method, vec![])?;
+        let awaited = self.awaited_iterator(awaitable).map_err(|_| VmError::TypeError(String::from(
+            "'async for' received an invalid object from __anext__"
+        )))?;
+
+        let state = self.state();
+        state.stack.push(awaited);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// The handler of an `async for` loop. `StopAsyncIteration` ends the loop, which leaves the
+    /// handler and pops the async iterator, other exceptions are raised again. Python 3.11 only
+    /// has the exception above the async iterator, and no handler to leave.
+    fn end_async_for(&mut self) -> Result<(), VmError> {
+        let (kind, handled) = match self.state().stack.last().unwrap() {
+            Value::Exception(exception) => (Value::ExceptionType(Rc::clone(&exception.kind)), false),
+            kind => (kind.clone(), true),
+        };
+        let stop = Value::ExceptionType(exceptions::exception_type("StopAsyncIteration"));
+        if !exception_matches(&kind, &stop)? {
+            return self.reraise();
+        }
+        if handled {
+            let block = self.state().blocks.pop().unwrap();
+            self.unwind_except_handler(block);
+        } else {
+            self.state().stack.pop();
+        }
+
+        let state = self.state();
+        state.stack.pop();
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn for_iter(&mut self, arg: usize) -> Result<(), VmError> {
+        let next = match self.state().stack.last().unwrap().clone() {
+            Value::File(file) => file.next_line()?,
+            Value::Iterator(iterator) => iterator.next_item(),
+            Value::Generator(generator) => match self.resume(&generator, Value::Nonetype, None)? {
+                Resumed::Yielded(value) => Some(value),
+                Resumed::Returned(_) => None,
+            },
+            _ => return Err(VmError::TypeError(String::from("Wrong type for TOS"))),
+        };
+
+        let state = self.state();
+
+        match next {
+            Some(value) => {
+                state.stack.push(value);
+                state.index += 1;
+            },
+            None => {
+                state.stack.pop();
+                state.index = relative_target(state.index, arg);
+            }
+        }
+        Ok(())
+    }
+
+    fn import_star(&mut self) -> Result<(), VmError> {
+        let module = self.state().stack.pop().unwrap();
+        self.import_public_names(module)?;
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    /// Binds the public names of `module` in the running frame's locals.
+    fn import_public_names(&mut self, module: Value) -> Result<(), VmError> {
+        let module = match module {
+            Value::Module(module) => module,
+            _ => return Err(VmError::TypeError(String::from("Wrong type for TOS"))),
+        };
+        let mut locals = self.state().locals.borrow_mut();
+        for name in module.public_names() {
+            let value = module.attributes.get(&name).cloned().ok_or_else(|| {
+                VmError::AttributeError(format!("module '{}' has no attribute '{}'", module.name, name))
+            })?;
+            locals.insert(name, value);
+        }
+        Ok(())
+    }
+
+    fn load_build_class(&mut self) {
+        let build_class = self.builtins[&String::from("__build_class__")].clone();
+        let state = self.state();
+        state.stack.push(build_class);
+
+        state.index += 1;
+    }
+
+    fn setup_annotations(&mut self) {
+        let state = self.state();
+        state.locals.borrow_mut().entry(Rc::new(String::from("__annotations__")))
+            .or_insert_with(|| Value::Dict(Rc::new(RefCell::new(Dict::new()))));
+
+        state.index += 1;
+    }
+
+    fn store_annotation(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let annotation = state.stack.pop().unwrap();
+        let name = Rc::new(String::from("__annotations__"));
+        let annotations = state.locals.borrow().get(&name).cloned().ok_or(VmError::NameError(name))?;
+        annotations.set_item(&Value::Str(frame.co_names[arg].to_string()), &annotation)?;
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    fn yield_value(&mut self) {
+        let state = self.state();
+        state.return_value = state.stack.pop().unwrap();
+        state.yielded = true;
+
+        state.index += 1;
+    }
+
+    fn return_value(&mut self, frame: &Frame) {
+        let state = self.state();
+        state.return_value = state.stack.pop().unwrap();
+
+        state.index = frame.instructions.len();
+    }
+
+    /// Runs binary operator `name` with the instance methods that implement it if an operand
+    /// is an instance, and with `native` for the built-in types otherwise.
+    fn binary_op(&mut self, name: &str, inplace: bool, native: fn(&mut Vm) -> Result<(), VmError>) -> Result<(), VmError> {
+        let stack = &self.state().stack;
+        let (first, second) = (stack[stack.len() - 2].clone(), stack[stack.len() - 1].clone());
+        let result = match self.binary_method(name, inplace, &first, &second) {
+            Some(result) => result?,
+            None => match bigint::operation(name, &first, &second) {
+                Some(result) => result?,
+                None => return native(self),
+            },
+        };
+
+        let state = self.state();
+        state.stack.truncate(state.stack.len() - 2);
+        state.stack.push(result);
+        state.index += 1;
+        Ok(())
+    }
+
+    fn add(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let second = state.stack.pop().unwrap();
+        let first = state.stack.pop().unwrap();
+        let result = match datetime::add(&first, &second) {
+            Some(result) => result?,
+            None => match int_operation(&first, &second, i64::checked_add, |first, second| first + second) {
+                Some(result) => result,
+                None => first + second,
+            },
+        };
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `+=`, which extends lists and bytearrays in place instead of making a new one.
+    fn inplace_add(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        if let [.., Value::List(list), iterable] = state.stack.as_slice() {
+            list::extend(list, iterable)?;
+            state.stack.pop();
+            state.index += 1;
+            return Ok(());
+        }
+        if let [.., Value::ByteArray(bytes), other @ (Value::Bytes(_) | Value::ByteArray(_))] = state.stack.as_slice() {
+            let items = byte_items(other).unwrap()?;
+            bytes.borrow_mut().extend(items);
+            state.stack.pop();
+            state.index += 1;
+            return Ok(());
+        }
+        self.add()
+    }
+
+    fn subtract(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let second = state.stack.pop().unwrap();
+        let first = state.stack.pop().unwrap();
+        let result = match datetime::sub(&first, &second) {
+            Some(result) => result?,
+            None => match set::operation('-', &first, &second) {
+                Some(result) => result,
+                None => match int_operation(&first, &second, i64::checked_sub, |first, second| first - second) {
+                    Some(result) => result,
+                    None => first - second,
+                },
+            },
+        };
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `-=`, `&=`, `|=` and `^=`, which change sets in place instead of making a new one.
+    fn inplace_set_operation(&mut self, operator: char) -> Result<(), VmError> {
+        let state = self.state();
+        if let [.., first, second] = state.stack.as_slice() {
+            if let Some(result) = set::operation_in_place(operator, first, second) {
+                state.stack.truncate(state.stack.len() - 2);
+                state.stack.push(result);
+                state.index += 1;
+                return Ok(());
+            }
+        }
+        match operator {
+            '-' => self.subtract(),
+            _ => self.bitwise(operator),
+        }
+    }
+
+    /// `&`, `|` and `^`, between ints and between sets. Between two bools they give a bool.
+    fn bitwise(&mut self, operator: char) -> Result<(), VmError> {
+        let state = self.state();
+        let second = state.stack.pop().unwrap();
+        let first = state.stack.pop().unwrap();
+        let apply = |first: i64, second: i64| match operator {
+            '&' => first & second,
+            '|' => first | second,
+            _ => first ^ second,
+        };
+        let result = match (&first, &second) {
+            (Value::Bool(first), Value::Bool(second)) => Value::Bool(apply(i64::from(*first), i64::from(*second)) != 0),
+            (Value::Int(first), Value::Int(second)) => Value::Int(apply(*first, *second)),
+            (Value::Int(first), Value::Bool(second)) => Value::Int(apply(*first, i64::from(*second))),
+            (Value::Bool(first), Value::Int(second)) => Value::Int(apply(i64::from(*first), *second)),
+            _ => set::operation(operator, &first, &second).ok_or_else(|| VmError::TypeError(format!(
+                "unsupported operand type(s) for {}: {} and {}", operator, first.python_repr(), second.python_repr()
+            )))?,
+        };
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn multiply(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let second = state.stack.pop().unwrap();
+        let first = state.stack.pop().unwrap();
+        let result = match datetime::mul(&first, &second) {
+            Some(result) => result?,
+            None => match int_operation(&first, &second, i64::checked_mul, |first, second| first * second) {
+                Some(result) => result,
+                None => first * second,
+            },
+        };
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `*=`, which repeats lists in place instead of making a new one.
+    fn inplace_multiply(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        if let [.., Value::List(list), Value::Int(count)] = state.stack.as_slice() {
+            list::repeat_in_place(list, *count);
+            state.stack.pop();
+            state.index += 1;
+            return Ok(());
+        }
+        self.multiply()
+    }
+
+    fn true_divide(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let second = state.stack.pop().unwrap();
+        let first = state.stack.pop().unwrap();
+        check_divisor(&first, &second, "division by zero", "float division by zero")?;
+        let result = first / second;
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn floor_divide(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let second = state.stack.pop().unwrap();
+        let first = state.stack.pop().unwrap();
+        check_divisor(&first, &second, "integer division or modulo by zero", "float divmod()")?;
+        let result = match (int_operation(&first, &second, floor_divide, bigint::floor_divide), float_operands(&first, &second)) {
+            (Some(result), _) => result,
+            (None, Some((first, second))) => Value::Float(float_floor_divide(first, second)),
+            (None, None) => match first / second {
+                Value::Float(result) => Value::Int(result.floor() as i64),
+                result => result,
+            },
+        };
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `container[index]`, which calls `__getitem__` on instances.
+    fn subscript(&mut self) -> Result<(), VmError> {
+        let index = self.state().stack.pop().unwrap();
+        let container = self.state().stack.pop().unwrap();
+        let item = match container {
+            Value::Instance(instance) => match instance.get_attr(&Rc::new(String::from("__getitem__"))) {
+                Some(method) => self.call_value(method, vec![index])?,
+                None => return Err(VmError::TypeError(format!("'{}' object is not subscriptable", instance.class.name))),
+            },
+            container => container.get_item(index)?,
+        };
+
+        let state = self.state();
+        state.stack.push(item);
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `container[index] = value`, with `index` on top of the stack and then the container and
+    /// the value.
+    fn store_subscr(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let index = state.stack.pop().unwrap();
+        let container = state.stack.pop().unwrap();
+        let value = state.stack.pop().unwrap();
+        match container {
+            Value::Instance(instance) => match instance.get_attr(&Rc::new(String::from("__setitem__"))) {
+                Some(method) => { self.call_value(method, vec![index, value])?; },
+                None => return Err(VmError::TypeError(format!("'{}' object does not support item assignment", instance.class.name))),
+            },
+            container => container.set_item(&index, &value)?,
+        }
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    fn build_tuple(&mut self, arg: usize) {
+        let state = self.state();
+        let values = state.stack.split_off(state.stack.len() - arg);
+        state.stack.push(Value::Tuple(values));
+
+        state.index += 1;
+    }
+
+    /// `a, b = iterable`, which needs exactly as many items as there are targets.
+    fn unpack_sequence(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let iterable = state.stack.pop().unwrap();
+        let items = iterable.sequence_items().ok_or_else(|| {
+            VmError::TypeError(format!("cannot unpack non-iterable {} object", iterable.type_name()))
+        })?;
+        match items.len() {
+            len if len < arg => return Err(VmError::ValueError(format!("not enough values to unpack (expected {}, got {})", arg, len))),
+            len if len > arg => return Err(VmError::ValueError(format!("too many values to unpack (expected {})", arg))),
+            _ => state.stack.extend(items.into_iter().rev()),
+        }
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn build_list(&mut self, arg: usize) {
+        let state = self.state();
+        let values = state.stack.split_off(state.stack.len() - arg);
+        state.stack.push(Value::List(Rc::new(RefCell::new(values))));
+
+        state.index += 1;
+    }
+
+    fn build_set(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let items = state.stack.split_off(state.stack.len() - arg);
+        state.stack.push(set::new(items)?);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn set_add(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let item = state.stack.pop().unwrap();
+        match &state.stack[state.stack.len() - arg] {
+            Value::Set(set) => set::add(set, item)?,
+            _ => return Err(VmError::TypeError(String::from("Wrong type for the set being extended"))),
+        }
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn set_update(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let iterable = state.stack.pop().unwrap();
+        match &state.stack[state.stack.len() - arg] {
+            Value::Set(set) => set::update(set, &iterable)?,
+            _ => return Err(VmError::TypeError(String::from("Wrong type for the set being extended"))),
+        }
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn build_set_unpack(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let iterables = state.stack.split_off(state.stack.len() - arg);
+        let mut items = vec![];
+        for iterable in iterables {
+            items.extend(iterable.sequence_items().ok_or_else(|| {
+                VmError::TypeError(format!("\'{}\' object is not iterable", iterable.type_name()))
+            })?);
+        }
+        state.stack.push(set::new(items)?);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn build_map(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let items = state.stack.split_off(state.stack.len() - 2 * arg);
+        let mut dict = Dict::new();
+        for pair in items.chunks(2) {
+            dict.insert(pair[0].clone(), pair[1].clone())?;
+        }
+        state.stack.push(Value::Dict(Rc::new(RefCell::new(dict))));
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `DICT_UPDATE` and, with `merge`, `DICT_MERGE`.
+    fn dict_update(&mut self, arg: usize, merge: bool) -> Result<(), VmError> {
+        let mapping = self.state().stack.pop().unwrap();
+        self.update_dict(arg, &mapping, merge)?;
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    /// `BUILD_MAP_UNPACK` and, with `merge`, `BUILD_MAP_UNPACK_WITH_CALL`, which replace the
+    /// `arg` mappings on top of the stack with a dict of their items.
+    fn build_map_unpack(&mut self, arg: usize, merge: bool) -> Result<(), VmError> {
+        let state = self.state();
+        let mappings = state.stack.split_off(state.stack.len() - arg);
+        state.stack.push(Value::Dict(Rc::new(RefCell::new(Dict::new()))));
+        for mapping in mappings {
+            self.update_dict(1, &mapping, merge)?;
+        }
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    /// Adds the items of `mapping` to the dict `arg` positions from the top. With `merge` the
+    /// dict is the keyword arguments of the function below it and the tuple of positional
+    /// arguments, so a key that is already in it is an argument given twice.
+    fn update_dict(&mut self, arg: usize, mapping: &Value, merge: bool) -> Result<(), VmError> {
+        let state = self.state();
+        let target = state.stack.len() - arg;
+        let function_name = || match &state.stack[target - 2] {
+            Value::Frame(frame) => frame.qualname().to_string(),
+            other => other.type_name().to_string(),
+        };
+        let items = match mapping {
+            Value::Dict(dict) => dict.borrow().items().map(|(key, value)| (key.clone(), value.clone())).collect::<Vec<_>>(),
+            other if merge => return Err(VmError::TypeError(format!(
+                "{}() argument after ** must be a mapping, not {}", function_name(), other.type_name()
+            ))),
+            other => return Err(VmError::TypeError(format!("'{}' object is not a mapping", other.type_name()))),
+        };
+        let dict = match &state.stack[target] {
+            Value::Dict(dict) => Rc::clone(dict),
+            _ => return Err(VmError::TypeError(String::from("Wrong type for the dict being updated"))),
+        };
+        for (key, value) in items {
+            if merge && dict.borrow().get(&key)?.is_some() {
+                return Err(VmError::TypeError(format!(
+                    "{}() got multiple values for keyword argument {}", function_name(), key.python_repr()
+                )));
+            }
+            dict.borrow_mut().insert(key, value)?;
+        }
+        Ok(())
+    }
+
+    fn build_const_key_map(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let keys = match state.stack.pop().unwrap() {
+            Value::Tuple(keys) if keys.len() == arg => keys,
+            other => return Err(VmError::InternalError(format!("BUILD_CONST_KEY_MAP {} with keys {}", arg, other.python_repr()))),
+        };
+        let values = state.stack.split_off(state.stack.len() - arg);
+        let mut dict = Dict::new();
+        for (key, value) in keys.into_iter().zip(values) {
+            dict.insert(key, value)?;
+        }
+        state.stack.push(Value::Dict(Rc::new(RefCell::new(dict))));
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `[*a, *b]`, extends the list being built with the items of TOS.
+    fn list_extend(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let iterable = state.stack.pop().unwrap();
+        let items = iterable.sequence_items().ok_or_else(|| {
+            VmError::TypeError(format!("Value after * must be an iterable, not {}", iterable.python_repr()))
+        })?;
+        match &state.stack[state.stack.len() - arg] {
+            Value::List(values) => values.borrow_mut().extend(items),
+            _ => return Err(VmError::TypeError(String::from("Wrong type for the list being extended"))),
+        }
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn build_tuple_unpack_with_call(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let iterables = state.stack.split_off(state.stack.len() - arg);
+        let mut items = vec![];
+        for iterable in iterables {
+            items.extend(iterable.sequence_items().ok_or_else(|| {
+                VmError::TypeError(format!("argument after * must be an iterable, not {}", iterable.type_name()))
+            })?);
+        }
+        state.stack.push(Value::Tuple(items));
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn list_append(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let value = state.stack.pop().unwrap();
+        match &state.stack[state.stack.len() - arg] {
+            Value::List(values) => values.borrow_mut().push(value),
+            _ => return Err(VmError::TypeError(String::from("Wrong type for the list being extended"))),
+        }
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// Replaces the start and stop on top of the stack with a slice of them, for Python 3.12's
+    /// `BINARY_SLICE` and `STORE_SLICE`.
+    fn push_slice(&mut self) {
+        let state = self.state();
+        let stop = state.stack.pop().unwrap();
+        let start = state.stack.pop().unwrap();
+        state.stack.push(Value::Slice(Rc::new(Slice { start, stop, step: Value::Nonetype })));
+    }
+
+    fn build_slice(&mut self, arg: usize) {
+        let state = self.state();
+        let step = if arg == 3 { state.stack.pop().unwrap() } else { Value::Nonetype };
+        let stop = state.stack.pop().unwrap();
+        let start = state.stack.pop().unwrap();
+        state.stack.push(Value::Slice(Rc::new(Slice { start, stop, step })));
+
+        state.index += 1;
+    }
+
+    /// `[..., subject, class, keywords]` becomes `[..., attributes, True]` when the subject
+    /// matches the class pattern and `[..., subject, False]` when it doesn't.
+    /// Python 3.10 replaces the subject with the attributes when it matches and pushes whether it
+    /// did. Python 3.11 replaces the subject with the attributes or `None`, which the `COPY` after
+    /// it keeps for the `POP_JUMP_IF_NONE`.
+    fn match_class(&mut self, frame: &Frame, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let pushes_none = matches!(frame.instructions.get(state.index + 1), Some(Instruction::Copy(_)));
+        let keywords = match state.stack.pop().unwrap() {
+            Value::Tuple(keywords) => keywords,
+            _ => return Err(VmError::TypeError(String::from("Wrong type for TOS"))),
+        };
+        let class = state.stack.pop().unwrap();
+        let subject = state.stack.last_mut().unwrap();
+        let matched = classes::match_class(subject, &class, arg, &keywords)?;
+        match (matched, pushes_none) {
+            (Some(attributes), true) => *subject = Value::Tuple(attributes),
+            (None, true) => *subject = Value::Nonetype,
+            (Some(attributes), false) => {
+                *subject = Value::Tuple(attributes);
+                state.stack.push(Value::Bool(true));
+            },
+            (None, false) => state.stack.push(Value::Bool(false)),
+        }
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn pop_top(&mut self) {
+        let state = self.state();
+        state.stack.pop();
+
+        state.index += 1;
+    }
+
+    fn rot_two(&mut self) {
+        let state = self.state();
+        let last_pos = state.stack.len() - 1;
+        state.stack.swap(last_pos, last_pos - 1);
+
+        state.index += 1;
+    }
+
+    /// `[..., a, b, c]` becomes `[..., c, a, b]`, TOS moves down to the third position.
+    fn rot_three(&mut self) {
+        let state = self.state();
+        let len = state.stack.len();
+        state.stack[len - 3..].rotate_right(1);
+
+        state.index += 1;
+    }
+
+    /// `[..., a, b, c, d]` becomes `[..., d, a, b, c]`.
+    fn rot_four(&mut self) {
+        let state = self.state();
+        let len = state.stack.len();
+        state.stack[len - 4..].rotate_right(1);
+
+        state.index += 1;
+    }
+
+    fn dup_top(&mut self) {
+        let state = self.state();
+        state.stack.push(state.stack.last().unwrap().clone());
+
+        state.index += 1;
+    }
+
+    fn copy(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let position = stack_position(&state.stack, "COPY", arg)?;
+        let value = state.stack[position].clone();
+        state.stack.push(value);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn swap(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let position = stack_position(&state.stack, "SWAP", arg)?;
+        let top = state.stack.len() - 1;
+        state.stack.swap(top, position);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn dup_top_two(&mut self) {
+        let state = self.state();
+        let top_two = state.stack[state.stack.len() - 2..].to_vec();
+        state.stack.extend(top_two);
+
+        state.index += 1;
+    }
+
+    fn unary_negative(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let negative = match state.stack.pop().unwrap() {
+            Value::Int(number) => number.checked_neg().map_or_else(|| bigint::narrow(-BigInt::from(number)), Value::Int),
+            Value::BigInt(number) => bigint::narrow(-&*number),
+            Value::Float(number) => Value::Float(-number),
+            Value::Bool(value) => Value::Int(-(value as i64)),
+            other => return Err(VmError::TypeError(format!("bad operand type for unary -: \'{}\'", other.type_name()))),
+        };
+        state.stack.push(negative);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `f"{value!conversion:spec}"`. The low two bits of `arg` pick the conversion and the
+    /// third says whether a format spec is on the stack above the value.
+    fn format_value(&mut self, arg: usize) -> Result<(), VmError> {
+        let state = self.state();
+        let spec = match arg & 0x04 {
+            0 => String::new(),
+            _ => match state.stack.pop().unwrap() {
+                Value::Str(spec) => spec,
+                _ => return Err(VmError::TypeError(String::from("Wrong type for TOS"))),
+            },
+        };
+        let value = state.stack.pop().unwrap();
+        let value = match arg & 0x03 {
+            0 => value,
+            1 => Value::Str(self.python_str(&value)?),
+            2 => Value::Str(self.python_repr(&value)?),
+            _ => Value::Str(self.python_repr(&value)?.chars().map(ascii_escape).collect()),
+        };
+        let formatted = self.python_format(&value, &spec)?;
+
+        let state = self.state();
+        state.stack.push(Value::Str(formatted));
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn build_string(&mut self, arg: usize) {
+        let state = self.state();
+        let parts = state.stack.split_off(state.stack.len() - arg);
+        state.stack.push(Value::Str(parts.iter().map(Value::to_string).collect()));
+
+        state.index += 1;
+    }
+
+    fn setup_finally(&mut self, arg: usize) {
+        let state = self.state();
+        let level = state.stack.len();
+        state.blocks.push(Block { kind: BlockKind::Finally, handler: relative_target(state.index, arg), level });
+
+        state.index += 1;
+    }
+
+    /// Replaces the context manager with its `__exit__` and pushes what `__enter__` returns,
+    /// with the block that calls `__exit__` in between.
+    fn setup_with(&mut self, arg: usize) -> Result<(), VmError> {
+        let result = self.enter_context("__enter__", "__exit__")?;
+
+        let state = self.state();
+        let level = state.stack.len();
+        state.blocks.push(Block { kind: BlockKind::Finally, handler: relative_target(state.index, arg), level });
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// Pushes the exit method of the context manager on top of the stack and gives what its
+    /// enter method returns.
+    fn enter_context(&mut self, enter: &str, exit: &str) -> Result<Value, VmError> {
+        let manager = self.state().stack.pop().unwrap();
+        let special_method = |name: &str| {
+            manager.get_attr(&Rc::new(String::from(name))).map_err(|_| VmError::AttributeError(String::from(name)))
+        };
+        let enter = special_method(enter)?;
+        let exit = special_method(exit)?;
+        self.state().stack.push(exit);
+        self.call_value(enter, vec![])
+    }
+
+    /// Replaces the context manager with its `__exit__` and what its `__enter__` returns.
+    fn before_with(&mut self) -> Result<(), VmError> {
+        let result = self.enter_context("__enter__", "__exit__")?;
+
+        let state = self.state();
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// `async with`: replaces the context manager with its `__aexit__` and the awaitable its
+    /// `__aenter__` returns, which is awaited before `SETUP_ASYNC_WITH`.
+    fn before_async_with(&mut self) -> Result<(), VmError> {
+        let result = self.enter_context("__aenter__", "__aexit__")?;
+
+        let state = self.state();
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// Sets up the handler of an `async with` body below the awaited result of `__aenter__`.
+    /// The handler awaits what `WITH_CLEANUP_START` gets from `__aexit__`.
+    fn setup_async_with(&mut self, arg: usize) {
+        let state = self.state();
+        let result = state.stack.pop().unwrap();
+        let level = state.stack.len();
+        state.blocks.push(Block { kind: BlockKind::Finally, handler: relative_target(state.index, arg), level });
+        state.stack.push(result);
+
+        state.index += 1;
+    }
+
+    fn push_null(&mut self) {
+        let state = self.state();
+        state.stack.push(Value::Null);
+
+        state.index += 1;
+    }
+
+    fn return_generator(&mut self) {
+        let state = self.state();
+        state.stack.push(Value::Nonetype);
+
+        state.index += 1;
+    }
+
+    fn end_for(&mut self) {
+        let state = self.state();
+        state.stack.truncate(state.stack.len() - 2);
+
+        state.index += 1;
+    }
+
+    fn pop_block(&mut self) {
+        let state = self.state();
+        state.blocks.pop();
+
+        state.index += 1;
+    }
+
+    /// Leaves an exception handler. `return` in a handler puts the value it returns below the
+    /// previous exception with `ROT_FOUR`, so the stack isn't unwound to the block's level.
+    /// Python 3.11 has no blocks and only keeps the previous exception on the stack.
+    fn pop_except(&mut self) {
+        match self.state().blocks.pop() {
+            Some(_) => self.restore_handled_exception(),
+            None => self.handled_exception = self.state().stack.pop().unwrap(),
+        }
+
+        self.state().index += 1;
+    }
+
+    fn push_exc_info(&mut self) {
+        let exception = self.state().stack.pop().unwrap();
+        let previous = mem::replace(&mut self.handled_exception, exception.clone());
+        let state = self.state();
+        state.stack.push(previous);
+        state.stack.push(exception);
+
+        state.index += 1;
+    }
+
+    fn check_exc_match(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let clause = state.stack.pop().unwrap();
+        let kind = match state.stack.last().unwrap() {
+            Value::Exception(exception) => Value::ExceptionType(Rc::clone(&exception.kind)),
+            other => other.clone(),
+        };
+        state.stack.push(Value::Bool(exception_matches(&kind, &clause)?));
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn check_eg_match(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let clause = state.stack.pop().unwrap();
+        let exception = match state.stack.last().unwrap() {
+            Value::Exception(exception) => Rc::clone(exception),
+            other => return Err(VmError::InternalError(format!("CHECK_EG_MATCH expects an exception, found {:?}", other))),
+        };
+        let is_group_type = |clause: &Value| matches!(clause, Value::ExceptionType(kind) if kind.name.ends_with("ExceptionGroup"));
+        if is_group_type(&clause) || matches!(&clause, Value::Tuple(clauses) if clauses.iter().any(is_group_type)) {
+            return Err(VmError::TypeError(String::from("catching ExceptionGroup with except* is not allowed. Use except instead.")));
+        }
+
+        match exceptions::split_exception_group(&exception, &clause)? {
+            (Value::Nonetype, _) => state.stack.push(Value::Nonetype),
+            (matched, rest) => {
+                // A bare exception is handled as a group of one, which `except*` binds.
+                let matched = match matched {
+                    Value::Exception(matched) if !matched.is_group() => {
+                        let name = match matched.kind.is_subclass(&exceptions::exception_type("Exception")) {
+                            true => "ExceptionGroup",
+                            false => "BaseExceptionGroup",
+                        };
+                        let exceptions = Value::Tuple(vec![Value::Exception(matched)]);
+                        Exception::instantiate(exceptions::exception_type(name), vec![Value::Str(String::new()), exceptions])?
+                    },
+                    matched => matched,
+                };
+                *state.stack.last_mut().unwrap() = rest;
+                state.stack.push(matched);
+            },
+        }
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn begin_finally(&mut self) {
+        let state = self.state();
+        state.stack.push(Value::Null);
+
+        state.index += 1;
+    }
+
+    /// Leaves a `finally` block: re-raises the exception it was entered with, returns to where
+    /// `CALL_FINALLY` was or goes on after NULL.
+    fn end_finally(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        match state.stack.pop().unwrap() {
+            Value::Int(index) => state.index = index as usize,
+            Value::ExceptionType(_) => {
+                let exception = state.stack.pop().unwrap();
+                state.stack.pop();
+                match exception {
+                    Value::Exception(exception) => return Err(VmError::Exception(exception)),
+                    _ => unreachable!("an exception type is pushed above its exception"),
+                }
+            },
+            _ => state.index += 1,
+        }
+        Ok(())
+    }
+
+    fn call_finally(&mut self, arg: usize) {
+        let state = self.state();
+        state.stack.push(Value::Int(state.index as i64 + 1));
+
+        state.index = relative_target(state.index, arg);
+    }
+
+    /// Leaves a `finally` block without running the rest of it, for `return` and `break`.
+    /// `preserve_tos` keeps the value on top, like the value being returned.
+    fn pop_finally(&mut self, preserve_tos: usize) {
+        let state = self.state();
+        let top = if preserve_tos != 0 { state.stack.pop() } else { None };
+        if let Value::ExceptionType(_) = state.stack.pop().unwrap() {
+            state.stack.pop();
+            state.stack.pop();
+            let block = state.blocks.pop().unwrap();
+            self.unwind_except_handler(block);
+        }
+
+        let state = self.state();
+        state.stack.extend(top);
+
+        state.index += 1;
+    }
+
+    /// Calls `__exit__` with the exception the `with` block raised, or with Nones when it
+    /// finished normally.
+    fn with_cleanup_start(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let (exit, args) = match state.stack.last().unwrap() {
+            Value::Null => {
+                let exit = state.stack.remove(state.stack.len() - 2);
+                (exit, vec![Value::Nonetype, Value::Nonetype, Value::Nonetype])
+            },
+            _ => {
+                // `__exit__` is below the previous exception, which moves down into its place
+                // and leaves a NULL for the except handler block to pop.
+                let length = state.stack.len();
+                let exit = state.stack.remove(length - 7);
+                state.stack.insert(length - 4, Value::Null);
+                state.blocks.last_mut().unwrap().level -= 1;
+                let args = state.stack[length - 3..].iter().rev().cloned().collect();
+                (exit, args)
+            },
+        };
+        let exception = args[0].clone();
+        let result = self.call_value(exit, args)?;
+
+        let state = self.state();
+        state.stack.push(exception);
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    /// Swallows the exception when `__exit__` returned a true value.
+    fn with_cleanup_finish(&mut self) -> Result<(), VmError> {
+        let result = self.state().stack.pop().unwrap();
+        let exception = self.state().stack.pop().unwrap();
+        if !matches!(exception, Value::Nonetype) && self.python_bool(&result)? {
+            let block = self.state().blocks.pop().unwrap();
+            self.unwind_except_handler(block);
+            self.state().stack.push(Value::Null);
+        }
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    /// Calls `__exit__` with the exception being handled and pushes what it returns. Python 3.9
+    /// and 3.10 have the type, value and traceback of the exception and of the one handled
+    /// before it above `__exit__`, Python 3.11 has the offset of the instruction that raised it,
+    /// the exception handled before it and the exception.
+    fn with_except_start(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let length = state.stack.len();
+        let (exit, args) = match state.stack.last().unwrap() {
+            Value::Exception(exception) => {
+                let kind = Value::ExceptionType(Rc::clone(&exception.kind));
+                (state.stack[length - 4].clone(), vec![kind, Value::Exception(Rc::clone(exception)), Value::Nonetype])
+            },
+            _ => (state.stack[length - 7].clone(), state.stack[length - 3..].iter().rev().cloned().collect()),
+        };
+        let result = self.call_value(exit, args)?;
+
+        let state = self.state();
+        state.stack.push(result);
+
+        state.index += 1;
+        Ok(())
+    }
+
+    fn raise_varargs(&mut self, arg: usize) -> Result<(), VmError> {
+        let reraised = match &self.handled_exception {
+            Value::Exception(exception) => Some(Rc::clone(exception)),
+            _ => None,
+        };
+        let state = self.state();
+        if arg == 2 {
+            // Tracebacks aren't shown, so the cause isn't kept either.
+            state.stack.pop();
+        }
+        let exception = match arg {
+            0 => reraised.unwrap_or_else(|| Exception::new("RuntimeError", String::from("No active exception to reraise"))),
+            _ => match state.stack.pop().unwrap() {
+                Value::ExceptionType(kind) => Rc::new(Exception { kind, args: vec![] }),
+                Value::Exception(exception) => exception,
+                _ => return Err(VmError::TypeError(String::from("exceptions must derive from BaseException"))),
+            },
+        };
+
+        Err(VmError::Exception(exception))
+    }
+
+    /// Python 3.11 only keeps the exception on the stack. The offset of the instruction that
+    /// raised it, which some handlers keep below it, is left for the unwinding to drop.
+    fn reraise(&mut self) -> Result<(), VmError> {
+        let state = self.state();
+        let exception = match state.stack.pop().unwrap() {
+            exception @ Value::Exception(_) => exception,
+            _ => {
+                let exception = state.stack.pop().unwrap();
+                state.stack.pop();
+                exception
+            },
+        };
+        match exception {
+            Value::Exception(exception) => Err(VmError::Exception(exception)),
+            _ => Err(VmError::InternalError(format!("RERAISE of {}", exception.python_repr()))),
+        }
+    }
+
+    fn load_assertion_error(&mut self) {
+        let state = self.state();
+        state.stack.push(Value::ExceptionType(exceptions::exception_type("AssertionError")));
+
+        state.index += 1;
+    }
+
+    /// Whether loading `name` starts raising a failed `assert` that `-O` skips. Before Python 3.9,
+    /// `assert` loads the global `AssertionError` right after the jump that checks the condition.
+    fn is_optimized_assert(&mut self, frame: &Frame, name: &str) -> bool {
+        let index = self.state().index;
+        self.config.optimize
+            && name == "AssertionError"
+            && index > 0
+            && matches!(frame.instructions[index - 1], Instruction::PopJumpIfTrue(_))
+    }
+
+    /// Continues after the `RAISE_VARARGS` of a failed `assert`, like CPython does with `-O`,
+    /// where the statement isn't compiled at all.
+    fn skip_assert(&mut self, frame: &Frame) {
+        let state = self.state();
+        let raise = frame.instructions[state.index..].iter()
+            .position(|instruction| matches!(instruction, Instruction::RaiseVarargs(_)))
+            .unwrap();
+        state.index += raise + 1;
+    }
+
+    /// Shows the value of an expression statement in interactive mode, and binds it to `_`.
+    fn print_expr(&mut self) -> Result<(), VmError> {
+        let value = self.state().stack.pop().unwrap();
+        self.display_value(value)?;
+
+        self.state().index += 1;
+        Ok(())
+    }
+
+    /// `sys.displayhook`: prints the `repr()` of an expression statement's value in the
+    /// interactive interpreter and keeps it in `_`.
+    fn display_value(&mut self, value: Value) -> Result<(), VmError> {
+        if let Value::Nonetype = value {
+            return Ok(());
+        }
+        println!("{}", self.python_repr(&value)?);
+        self.builtins.insert(Rc::new(String::from("_")), value);
+        Ok(())
+    }
+
+    fn print(&mut self) -> Result<(), VmError> {
+        let value = self.state().stack.pop().unwrap();
+        println!("{}", self.python_str(&value)?);
+
+        self.state().index += 1;
+        Ok(())
+    }
+}
+
+/// The locals a call of `frame` starts with, its parameters bound to `args` and then by name
+/// to `kwargs`. Parameters come first in `co_varnames`, followed by the other local variables.
+fn bind_arguments(frame: &Frame, mut args: Vec<Value>, kwargs: Vec<(Rc<String>, Value)>) -> Result<HashMap<Rc<String>, Value>, VmError> {
+    let argcount = frame.co_argcount.unwrap_or(frame.co_varnames.len());
+    let mut locals = HashMap::new();
+    if let Some(name) = frame.varargs_name() {
+        let extra = args.split_off(argcount.min(args.len()));
+        locals.insert(Rc::clone(name), Value::Tuple(extra));
+    } else if frame.co_argcount.is_some() && args.len() > argcount {
+        let takes = match frame.defaults.len().min(argcount) {
+            0 => argcount.to_string(),
+            defaults => format!("from {} to {}", argcount - defaults, argcount),
+        };
+        return Err(VmError::TypeError(format!(
+            "{}() takes {} positional argument{} but {} {} given",
+            frame.qualname(), takes, if argcount == 1 { "" } else { "s" }, args.len(), if args.len() == 1 { "was" } else { "were" }
+        )));
+    }
+    for (name, value) in frame.co_varnames.iter().zip(args) {
+        locals.insert(Rc::clone(name), value);
+    }
+    let parameters = &frame.co_varnames[..(argcount + frame.co_kwonlyargcount).min(frame.co_varnames.len())];
+    let (positional_only, keyword_parameters) = parameters.split_at(frame.co_posonlyargcount.min(parameters.len()));
+    // Keywords no parameter takes go to `**kwargs`, even the names of positional-only ones.
+    let mut varkeywords = frame.varkeywords_name().map(|name| (name, Dict::new()));
+    let passed_by_keyword = kwargs.iter()
+        .filter(|(name, _)| positional_only.contains(name))
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    if !passed_by_keyword.is_empty() && varkeywords.is_none() {
+        return Err(VmError::TypeError(format!(
+            "{}() got some positional-only arguments passed as keyword arguments: '{}'", frame.qualname(), passed_by_keyword.join(", ")
+        )));
+    }
+    for (name, value) in kwargs {
+        if !keyword_parameters.contains(&name) {
+            match &mut varkeywords {
+                Some((_, dict)) => dict.insert(Value::Str(name.to_string()), value)?,
+                None => return Err(VmError::TypeError(format!("{}() got an unexpected keyword argument '{}'", frame.qualname(), name))),
+            }
+            continue;
+        }
+        if locals.insert(Rc::clone(&name), value).is_some() {
+            return Err(VmError::TypeError(format!("{}() got multiple values for argument '{}'", frame.qualname(), name)));
+        }
+    }
+    if let Some((name, dict)) = varkeywords {
+        locals.insert(Rc::clone(name), Value::Dict(Rc::new(RefCell::new(dict))));
+    }
+    if frame.co_argcount.is_some() {
+        let (positional, keyword_only) = parameters.split_at(argcount.min(parameters.len()));
+        let defaulted = positional.iter().skip(positional.len().saturating_sub(frame.defaults.len()));
+        let defaults = frame.defaults.iter().skip(frame.defaults.len().saturating_sub(positional.len()));
+        for (name, value) in defaulted.zip(defaults).chain(frame.kwdefaults.iter().map(|(name, value)| (name, value))) {
+            locals.entry(Rc::clone(name)).or_insert_with(|| value.clone());
+        }
+        check_missing_arguments(frame, positional, "positional", &locals)?;
+        check_missing_arguments(frame, keyword_only, "keyword-only", &locals)?;
+    }
+    bind_cells(frame, &mut locals);
+    Ok(locals)
+}
+
+/// Raises CPython's `TypeError` for the `parameters` of `kind` that a call didn't bind, as in
+/// `f() missing 2 required positional arguments: 'a' and 'b'`.
+fn check_missing_arguments(frame: &Frame, parameters: &[Rc<String>], kind: &str, locals: &HashMap<Rc<String>, Value>) -> Result<(), VmError> {
+    let missing = parameters.iter()
+        .filter(|name| !locals.contains_key(*name))
+        .map(|name| format!("'{}'", name))
+        .collect::<Vec<_>>();
+    let names = match missing.as_slice() {
+        [] => return Ok(()),
+        [name] => name.clone(),
+        [first, second] => format!("{} and {}", first, second),
+        [names @ .., last] => format!("{}, and {}", names.join(", "), last),
+    };
+    Err(VmError::TypeError(format!(
+        "{}() missing {} required {} argument{}: {}", frame.qualname(), missing.len(), kind, if missing.len() == 1 { "" } else { "s" }, names
+    )))
+}
+
+/// The names in the tuple of keyword argument names of a call.
+fn keyword_names(names: &Value) -> Result<Vec<Rc<String>>, VmError> {
+    match names {
+        Value::Tuple(names) => Ok(names.iter().map(|name| Rc::new(name.to_string())).collect()),
+        other => Err(VmError::InternalError(format!("keyword names {} are not a tuple", other.python_repr()))),
+    }
+}
+
+/// Puts the cell and free variables of a call in its locals, as `Value::Cell`s: a new cell
+/// for every cell variable, holding the argument when it is a parameter, and the function's
+/// closure for the free variables. Python 3.11 code binds them itself with `MAKE_CELL` and
+/// `COPY_FREE_VARS`.
+fn bind_cells(frame: &Frame, locals: &mut HashMap<Rc<String>, Value>) {
+    if !frame.instructions.iter().any(|instruction| matches!(instruction, Instruction::MakeCell(_))) {
+        for name in &frame.co_cellvars {
+            let value = locals.remove(name).unwrap_or(Value::Null);
+            locals.insert(Rc::clone(name), Value::Cell(Rc::new(RefCell::new(value))));
+        }
+    }
+    if !frame.instructions.iter().any(|instruction| matches!(instruction, Instruction::CopyFreeVars(_))) {
+        for (name, cell) in frame.co_freevars.iter().zip(&frame.closure) {
+            locals.insert(Rc::clone(name), Value::Cell(Rc::clone(cell)));
+        }
+    }
+}
+
+/// The index of the item `arg` positions from the top of the stack, 1 being TOS.
+fn stack_position(stack: &[Value], instruction: &str, arg: usize) -> Result<usize, VmError> {
+    match stack.len().checked_sub(arg) {
+        Some(position) if arg > 0 => Ok(position),
+        _ => Err(VmError::InternalError(format!("{} {} on a stack of {} items", instruction, arg, stack.len()))),
+    }
+}
+
+/// Raises `ZeroDivisionError` for a zero divisor, with CPython's message for dividing ints
+/// or for dividing floats, which is used as soon as either operand is one.
+fn check_divisor(dividend: &Value, divisor: &Value, int_message: &str, float_message: &str) -> Result<(), VmError> {
+    let is_zero = match divisor {
+        Value::Int(number) => *number == 0,
+        Value::Bool(value) => !value,
+        Value::Float(number) => *number == 0.0,
+        _ => false,
+    };
+    match (dividend, divisor) {
+        _ if !is_zero => Ok(()),
+        (Value::Float(_), _) | (_, Value::Float(_)) => Err(VmError::ZeroDivisionError(String::from(float_message))),
+        _ => Err(VmError::ZeroDivisionError(String::from(int_message))),
+    }
+}
+
+/// `operation` between two ints, `None` if either operand isn't one. Results that don't fit
+/// in an `i64` are worked out again with `big` instead of wrapping around.
+fn int_operation(
+    first: &Value,
+    second: &Value,
+    operation: fn(i64, i64) -> Option<i64>,
+    big: fn(BigInt, BigInt) -> BigInt,
+) -> Option<Value> {
+    match (first, second) {
+        (Value::Int(first), Value::Int(second)) => Some(operation(*first, *second).map_or_else(
+            || bigint::narrow(big(BigInt::from(*first), BigInt::from(*second))),
+            Value::Int,
+        )),
+        _ => None,
+    }
+}
+
+/// `first // second` between ints, which rounds towards negative infinity.
+fn floor_divide(first: i64, second: i64) -> Option<i64> {
+    let quotient = first.checked_div(second)?;
+    Some(if first % second != 0 && (first < 0) != (second < 0) { quotient - 1 } else { quotient })
+}
+
+/// Both operands as floats when either of them is a float and the other is a number, which
+/// makes the result of an arithmetic operation a float.
+fn float_operands(first: &Value, second: &Value) -> Option<(f64, f64)> {
+    let number = |value: &Value| match value {
+        Value::Int(number) => Some(*number as f64),
+        Value::Bool(value) => Some(f64::from(u8::from(*value))),
+        Value::Float(number) => Some(*number),
+        _ => None,
+    };
+    match (first, second) {
+        (Value::Float(_), _) | (_, Value::Float(_)) => Some((number(first)?, number(second)?)),
+        _ => None,
+    }
+}
+
+/// `first // second` between floats, CPython's `float_floor_div`. It is computed from the
+/// remainder rather than by flooring `first / second`, which rounds `1.0 // 0.1` up to 10.0.
+fn float_floor_divide(first: f64, second: f64) -> f64 {
+    let remainder = first % second;
+    let mut quotient = (first - remainder) / second;
+    if remainder != 0.0 && (second < 0.0) != (remainder < 0.0) {
+        quotient -= 1.0;
+    }
+    if quotient == 0.0 {
+        return 0.0_f64.copysign(first / second);
+    }
+    let floor = quotient.floor();
+    if quotient - floor > 0.5 { floor + 1.0 } else { floor }
+}
+
+/// `first is second`, which is whether they have the same `id()`. Values `id()` doesn't
+/// support are never the same.
+fn is_same(first: &Value, second: &Value) -> bool {
+    match (first.python_id(), second.python_id()) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => false,
+    }
+}
+
+/// Whether an exception of type `kind` is caught by `except clause:`, where the clause is
+/// an exception type or a tuple of them.
+fn exception_matches(kind: &Value, clause: &Value) -> Result<bool, VmError> {
+    match (kind, clause) {
+        (_, Value::Tuple(clauses)) => {
+            let mut matches = false;
+            for clause in clauses {
+                matches |= exception_matches(kind, clause)?;
+            }
+            Ok(matches)
+        },
+        (Value::ExceptionType(kind), Value::ExceptionType(clause)) => Ok(kind.is_subclass(clause)),
+        (_, Value::ExceptionType(_)) => Ok(false),
+        _ => Err(VmError::TypeError(String::from("catching classes that do not inherit from BaseException is not allowed"))),
+    }
+}
+
+/// Runs the code object in `args[0]` with the caller's locals, so names it stores
+/// are visible to the caller afterwards. The globals and locals dicts in `args[1]` and
+/// `args[2]` are used instead when they are given, the globals for both when only they are.
+fn run_in_caller_scope(vm: &mut Vm, name: &str, args: Vec<Value>) -> Result<Value, VmError> {
+    if args.is_empty() || args.len() > 3 {
+        return Err(VmError::TypeError(format!("{}() takes from 1 to 3 positional arguments but {} were given", name, args.len())));
+    }
+    let frame = match &args[0] {
+        Value::Frame(frame) => Rc::clone(frame),
+        _ => return Err(VmError::TypeError(format!("{}() arg 1 must be a code object", name))),
+    };
+    let globals = match args.get(1) {
+        None | Some(Value::Nonetype) => None,
+        Some(Value::Dict(globals)) => Some(globals),
+        Some(other) => return Err(VmError::TypeError(format!("{}() globals must be a dict, not {}", name, other.type_name()))),
+    };
+    let locals = match args.get(2) {
+        None | Some(Value::Nonetype) => globals,
+        Some(Value::Dict(locals)) => Some(locals),
+        Some(other) => return Err(VmError::TypeError(format!("locals must be a mapping, not {}", other.type_name()))),
+    };
+    let (globals, locals) = match (globals, locals) {
+        (None, None) => {
+            let locals = Rc::clone(&vm.state().locals);
+            return vm.execute(&frame, FrameState::new(locals, FrameType::Module));
+        },
+        (globals, locals) => (globals.cloned(), locals.cloned()),
+    };
+
+    // The code runs with namespaces holding the items of the dicts, which are written back
+    // to the dicts afterwards, even when it raised.
+    let global_namespace = match &globals {
+        Some(globals) => namespace_from_dict(&globals.borrow()),
+        None => Rc::clone(&vm.globals),
+    };
+    let local_namespace = match (&locals, &globals) {
+        (Some(locals), Some(globals)) if Rc::ptr_eq(locals, globals) => Rc::clone(&global_namespace),
+        (Some(locals), _) => namespace_from_dict(&locals.borrow()),
+        (None, _) => Rc::clone(&vm.state().locals),
+    };
+    let caller_globals = mem::replace(&mut vm.globals, Rc::clone(&global_namespace));
+    let result = vm.execute(&frame, FrameState::new(Rc::clone(&local_namespace), FrameType::Module));
+    vm.globals = caller_globals;
+
+    if let Some(globals) = &globals {
+        update_dict_from_namespace(&mut globals.borrow_mut(), &global_namespace.borrow())?;
+    }
+    if let Some(locals) = &locals {
+        update_dict_from_namespace(&mut locals.borrow_mut(), &local_namespace.borrow())?;
+    }
+    result
+}
+
+/// A namespace with the items of `dict` whose keys are strings, the only ones code can name.
+fn namespace_from_dict(dict: &Dict) -> Namespace {
+    let names = dict.items().filter_map(|(key, value)| match key {
+        Value::Str(name) => Some((Rc::new(name.clone()), value.clone())),
+        _ => None,
+    });
+    Rc::new(RefCell::new(names.collect()))
+}
+
+/// Makes the string keys of `dict` the names bound in `namespace`, binding new ones and
+/// removing the ones that were deleted.
+fn update_dict_from_namespace(dict: &mut Dict, namespace: &HashMap<Rc<String>, Value>) -> Result<(), VmError> {
+    let deleted = dict.keys().into_iter().filter(|key| match key {
+        Value::Str(name) => !namespace.contains_key(name),
+        _ => false,
+    });
+    for key in deleted.collect::<Vec<_>>() {
+        dict.remove(&key)?;
+    }
+    let mut names = namespace.iter().collect::<Vec<_>>();
+    // New names are added in a fixed order, the order they were bound in isn't known.
+    names.sort_by_key(|(name, _)| *name);
+    for (name, value) in names {
+        dict.insert(Value::Str(name.to_string()), value.clone())?;
+    }
+    Ok(())
+}
+
+fn builtin_exec(vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    run_in_caller_scope(vm, "exec", args)?;
+    Ok(Value::Nonetype)
+}
+
+fn builtin_eval(vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    run_in_caller_scope(vm, "eval", args)
+}
+
+const USAGE: &str = "usage: py_vm [--version <major>.<minor>] [--tail-call-trampoline] [-O] [--threads] \
+    [--snapshot <file> | --verify-snapshot <file>] [--check] [--emit-json] [--profile <file>] [--cache-dir <dir>] <bytecode.json>";
+
+/// What `--snapshot` and `--verify-snapshot` ask for.
+enum SnapshotMode {
+    Record(String),
+    Verify(String),
+}
+
+struct Options {
+    path: String,
+    python_version: Option<PythonVersion>,
+    tail_call_trampoline: bool,
+    optimize: bool,
+    threads: bool,
+    snapshot: Option<SnapshotMode>,
+    /// Only verify the bytecode and report every issue, see `check`.
+    check: bool,
+    /// Only print the decoded frame as JSON, see `emit_json`.
+    emit_json: bool,
+    /// Where `--profile` writes the callgrind profile.
+    profile: Option<String>,
+    /// Where `--cache-dir` keeps decoded bytecode files.
+    cache_dir: Option<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut path = None;
+        let mut python_version = None;
+        let mut tail_call_trampoline = false;
+        let mut optimize = false;
+        let mut threads = false;
+        let mut snapshot = None;
+        let mut check = false;
+        let mut emit_json = false;
+        let mut profile = None;
+        let mut cache_dir = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--version" => {
+                    let version = args.next().ok_or("--version requires a value")?;
+                    python_version = Some(version.parse()?);
+                },
+                "--tail-call-trampoline" => tail_call_trampoline = true,
+                "-O" => optimize = true,
+                "--threads" => threads = true,
+                "--check" => check = true,
+                "--emit-json" => emit_json = true,
+                "--profile" => profile = Some(args.next().ok_or("--profile requires a file")?),
+                "--cache-dir" => cache_dir = Some(args.next().ok_or("--cache-dir requires a directory")?),
+                "--snapshot" => snapshot = Some(SnapshotMode::Record(args.next().ok_or("--snapshot requires a file")?)),
+                "--verify-snapshot" => {
+                    snapshot = Some(SnapshotMode::Verify(args.next().ok_or("--verify-snapshot requires a file")?));
+                },
+                _ if path.is_none() => path = Some(arg),
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+
+        Ok(Options {
+            path: path.ok_or("Missing bytecode file")?,
+            python_version,
+            tail_call_trampoline,
+            optimize,
+            threads,
+            snapshot,
+            check,
+            emit_json,
+            profile,
+            cache_dir,
+        })
+    }
+}
+
+/// Reads the bytecode file, uses `--version` over the version recorded in the file. With
+/// `--cache-dir` the decoded frame is cached, and taken from the cache when the file is unchanged.
+fn load_frame(options: &Options) -> Result<Frame, String> {
+    let source = fs::read_to_string(&options.path).map_err(|error| format!("Can't read {}: {}", options.path, error))?;
+    let cache = options.cache_dir.as_ref().map(|dir| Cache::new(dir, &source));
+    if let Some(frame) = cache.as_ref().and_then(|cache| cache.load(options.python_version)) {
+        return Ok(frame);
+    }
+
+    let frame = parse_frame(&source, options.python_version).map_err(|error| format!("{} in {}", error, options.path))?;
+    if let Some(cache) = cache {
+        // The run doesn't need the cache, so failing to write it is only reported.
+        if let Err(error) = cache.store(options.python_version, &frame) {
+            eprintln!("{}", error);
+        }
+    }
+    Ok(frame)
+}
+
+/// Decodes the bytecode file `source`, compiled by `python_version` or else by the version
+/// recorded in it.
+fn parse_frame(source: &str, python_version: Option<PythonVersion>) -> Result<Frame, String> {
+    let invalid = |error: serde_json::Error| format!("Invalid bytecode: {}", error);
+    let header: CodeFileHeader = serde_json::from_str(source).map_err(invalid)?;
+    let python_version = python_version
+        .or(header.python_version)
+        .unwrap_or(PythonVersion::DEFAULT);
+    let instruction_set = python_version.instruction_set()?;
+
+    let mut frame: Frame = serde_json::from_str(source).map_err(invalid)?;
+    frame.decode(instruction_set);
+    Ok(frame)
+}
+
+/// `--emit-json`: prints the frame the VM would run as indented JSON and returns the exit
+/// status. Jumps are byte offsets whatever version compiled the file, so the output loads
+/// as Python 3.8 bytecode and gives the same frame again.
+fn emit_json(frame: &Frame) -> i32 {
+    match serde_json::to_string_pretty(frame) {
+        Ok(json) => {
+            println!("{}", json);
+            0
+        },
+        Err(error) => {
+            eprintln!("Can't write the frame as JSON: {}", error);
+            2
+        },
+    }
+}
+
+/// `--check`: prints every issue the verifier finds and returns the exit status.
+fn check(frame: &Frame) -> i32 {
+    let (report, status) = check_report(frame);
+    print!("{}", report);
+    status
+}
+
+/// The lines `--check` prints for `frame`, and the exit status, 2 if there are errors and 0
+/// if there are only warnings or nothing.
+fn check_report(frame: &Frame) -> (String, i32) {
+    let issues = frame.verify();
+    let errors = issues.iter().filter(|issue| !issue.error.is_warning()).count();
+    let mut report = String::new();
+    for issue in &issues {
+        let severity = if issue.error.is_warning() { "warning" } else { "error" };
+        report += &format!("{}: {}\n", severity, issue);
+    }
+    report += &format!("{} errors, {} warnings\n", errors, issues.len() - errors);
+
+    (report, if errors > 0 { 2 } else { 0 })
+}
+
+/// The size of the stack the VM runs on. Every Python call nests a few Rust calls, and the
+/// stack has to hold `recursion_limit` of them, which takes far more than the 8 MB of the main
+/// thread in debug builds.
+const VM_STACK_SIZE: usize = 256 << 20;
+
+/// Runs `function` on a thread with a stack of `VM_STACK_SIZE`.
+fn on_vm_stack<R: Send + 'static>(function: impl FnOnce() -> R + Send + 'static) -> R {
+    let thread = thread::Builder::new().stack_size(VM_STACK_SIZE).spawn(function).unwrap();
+    thread.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+fn main() {
+    process::exit(on_vm_stack(run_main));
+}
+
+/// Runs the VM as the command line asks and returns the exit status.
+fn run_main() -> i32 {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if let [flag, result_path] = args.as_slice() {
+        if flag == modules::process::CHILD_FLAG {
+            return modules::process::run_child(result_path);
+        }
+    }
+    let options = match Options::parse(args.into_iter()) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            return 2;
+        },
+    };
+    let frame = match load_frame(&options) {
+        Ok(frame) => frame,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        },
+    };
+    if options.emit_json {
+        return emit_json(&frame);
+    }
+    if options.check {
+        return check(&frame);
+    }
+    let errors = frame.verify().into_iter().filter(|issue| !issue.error.is_warning()).collect::<Vec<_>>();
+    if !errors.is_empty() {
+        eprintln!("Invalid bytecode in {}:", options.path);
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        return 2;
+    }
+    let mut vm = Vm::new(VmConfig {
+        tail_call_trampoline: options.tail_call_trampoline,
+        optimize: options.optimize,
+        threads: options.threads,
+        ..VmConfig::default()
+    });
+    let snapshot = match &options.snapshot {
+        Some(SnapshotMode::Record(path)) => Some(Snapshot::record(path)),
+        Some(SnapshotMode::Verify(path)) => Some(Snapshot::verify(path)),
+        None => None,
+    };
+    vm.snapshot = match snapshot.transpose() {
+        Ok(snapshot) => snapshot,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 2;
+        },
+    };
+
+    if options.profile.is_some() {
+        vm.profiler = Some(Profiler::default());
+    }
+
+    let start = modules::time::perf_counter();
+    let result = vm.run_frame(&frame).and_then(|_| vm.shutdown());
+    if let (Some(profiler), Some(path)) = (vm.profiler.take(), &options.profile) {
+        if let Err(error) = profiler.write(path, &options.path) {
+            eprintln!("Can't write the profile to {}: {}", path, error);
+        }
+    }
+    // Finished even if the program raised, the lines before the error are the ones worth looking at.
+    let snapshot = vm.snapshot.take().map_or(Ok(()), Snapshot::finish);
+    match &result {
+        Ok(_) => println!("Running Took: {:?}", modules::time::perf_counter() - start),
+        Err(error) => eprintln!("{}", error),
+    }
+
+    if let Err(error) = snapshot {
+        eprintln!("{}", error);
+        return 1;
+    }
+    if result.is_err() { 1 } else { 0 }
 }
@@ -0,0 +1,172 @@
+//! `memoryview`, a view of the bytes of another object. Slicing a view makes a narrower view of
+//! the same buffer instead of copying it. Views of a `bytearray` can change it.
+//!
+//! CPython doesn't let a bytearray be resized while there are views of it. Here it can be, and
+//! the views only see the bytes that are still in their range.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::modules::{bound_method, expect_args};
+use crate::slice::Slice;
+use crate::{byte_items, byte_value, NativeFn, Value, Vm, VmError};
+
+pub(crate) struct MemoryView {
+    buffer: Rc<RefCell<Vec<u8>>>,
+    /// Where the view starts in `buffer`.
+    offset: usize,
+    len: usize,
+    readonly: bool,
+    /// Views are always of unsigned bytes, format `B`.
+    itemsize: usize,
+    format: char,
+}
+
+impl fmt::Debug for MemoryView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<memory at {:#x}>", self as *const MemoryView as usize)
+    }
+}
+
+/// The `memoryview(object)` built-in. A `bytes` object is copied into the view's buffer once,
+/// which is fine as neither can change it, a `bytearray` shares its buffer.
+pub(crate) fn builtin_memoryview(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("memoryview", &args, 1, 1)?;
+    let view = match &args[0] {
+        Value::Bytes(bytes) => MemoryView::new(Rc::new(RefCell::new(bytes.clone())), true),
+        Value::ByteArray(bytes) => MemoryView::new(Rc::clone(bytes), false),
+        Value::MemoryView(view) => view.narrow(0, view.len()),
+        other => return Err(VmError::TypeError(format!(
+            "memoryview: a bytes-like object is required, not \'{}\'", other.type_name()
+        ))),
+    };
+    Ok(Value::MemoryView(Rc::new(view)))
+}
+
+impl MemoryView {
+    /// A view of all of `buffer`.
+    pub(crate) fn new(buffer: Rc<RefCell<Vec<u8>>>, readonly: bool) -> MemoryView {
+        let len = buffer.borrow().len();
+        MemoryView { buffer, offset: 0, len, readonly, itemsize: 1, format: 'B' }
+    }
+
+    /// The view of `len` bytes from `start` on, counted from the start of this view.
+    fn narrow(&self, start: usize, len: usize) -> MemoryView {
+        MemoryView { buffer: Rc::clone(&self.buffer), offset: self.offset + start, len, ..*self }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.range().len()
+    }
+
+    pub(crate) fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// The range of `buffer` the view sees, cut short if the buffer shrank.
+    fn range(&self) -> Range<usize> {
+        let buffer_len = self.buffer.borrow().len();
+        self.offset.min(buffer_len)..(self.offset + self.len).min(buffer_len)
+    }
+
+    /// A copy of the bytes in the view, `tobytes()`.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        self.buffer.borrow()[self.range()].to_vec()
+    }
+
+    /// `view[index]`, a byte as an int, or a view of the bytes a slice selects.
+    pub(crate) fn get_item(&self, index: &Value) -> Result<Value, VmError> {
+        let position = match index {
+            Value::Slice(slice) => {
+                let (start, len) = self.slice_range(slice)?;
+                return Ok(Value::MemoryView(Rc::new(self.narrow(start, len))));
+            },
+            index => self.position(index)?,
+        };
+        Ok(Value::Int(i64::from(self.buffer.borrow()[position])))
+    }
+
+    /// `view[index] = value`, a byte for an int index and a bytes-like object of the same
+    /// length for a slice.
+    pub(crate) fn set_item(&self, index: &Value, value: &Value) -> Result<(), VmError> {
+        if self.readonly {
+            return Err(VmError::TypeError(String::from("cannot modify read-only memory")));
+        }
+        let slice = match index {
+            Value::Slice(slice) => slice,
+            index => {
+                let position = self.position(index)?;
+                let byte = byte_value(value).map_err(|_| {
+                    VmError::TypeError(String::from("memoryview: invalid type for format 'B'"))
+                })?;
+                self.buffer.borrow_mut()[position] = byte;
+                return Ok(());
+            },
+        };
+        let (start, len) = self.slice_range(slice)?;
+        let items = match value {
+            Value::Bytes(_) | Value::ByteArray(_) | Value::MemoryView(_) => byte_items(value).unwrap()?,
+            other => return Err(VmError::TypeError(format!("a bytes-like object is required, not \'{}\'", other.type_name()))),
+        };
+        if items.len() != len {
+            return Err(VmError::ValueError(String::from("memoryview assignment: lvalue and rvalue have different structures")));
+        }
+        let start = self.offset + start;
+        self.buffer.borrow_mut()[start..start + len].copy_from_slice(&items);
+        Ok(())
+    }
+
+    /// The start and length of what `slice` selects in the view. Only steps of 1 are supported.
+    fn slice_range(&self, slice: &Slice) -> Result<(usize, usize), VmError> {
+        let (start, stop, step) = slice.indices(self.range().len() as i64)?;
+        if step != 1 {
+            return Err(VmError::Unimplemented(String::from("memoryview slices with a step")));
+        }
+        Ok((start as usize, (stop - start).max(0) as usize))
+    }
+
+    /// The position in `buffer` of the byte at `index` in the view.
+    fn position(&self, index: &Value) -> Result<usize, VmError> {
+        let range = self.range();
+        let position = match index {
+            Value::Int(index) => *index,
+            Value::Bool(index) => i64::from(*index),
+            other => return Err(VmError::TypeError(format!("memoryview: invalid slice key {}", other.python_repr()))),
+        };
+        let position = if position < 0 { position + range.len() as i64 } else { position };
+        if position < 0 || position >= range.len() as i64 {
+            return Err(VmError::IndexError(String::from("index out of bounds on dimension 1")));
+        }
+        Ok(range.start + position as usize)
+    }
+
+    pub(crate) fn get_attr(view: &Rc<MemoryView>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "readonly" => return Some(Value::Bool(view.readonly)),
+            "nbytes" => return Some(Value::Int((view.len() * view.itemsize) as i64)),
+            "itemsize" => return Some(Value::Int(view.itemsize as i64)),
+            "format" => return Some(Value::Str(view.format.to_string())),
+            "tobytes" => |_, args| {
+                expect_args("tobytes", &args[1..], 0, 0)?;
+                Ok(Value::Bytes(receiver(&args).to_bytes()))
+            },
+            "tolist" => |_, args| {
+                expect_args("tolist", &args[1..], 0, 0)?;
+                let items = receiver(&args).to_bytes().into_iter().map(|byte| Value::Int(i64::from(byte))).collect();
+                Ok(Value::List(Rc::new(RefCell::new(items))))
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::MemoryView(Rc::clone(view)), name, method))
+    }
+}
+
+fn receiver(args: &[Value]) -> &MemoryView {
+    match &args[0] {
+        Value::MemoryView(view) => view,
+        _ => unreachable!("memoryview methods are only reachable through a bound memoryview"),
+    }
+}
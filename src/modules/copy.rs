@@ -0,0 +1,124 @@
+//! Shallow and deep copies. Lists, dicts, sets, bytearrays and instances are copied, other
+//! values are immutable or, like functions and classes, copied as themselves, which is what
+//! CPython does for them too.
+
+// Sets only hold hashable values, which lists and the other mutable values aren't.
+#![allow(clippy::mutable_key_type)]
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::dict::Dict;
+use crate::set::{self, Set};
+use crate::{Module, NativeFn, Value, VmError};
+use super::{expect_args, make_module};
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("copy", |_, args| {
+            expect_args("copy", &args, 1, 1)?;
+            copy(&args[0])
+        }),
+        ("deepcopy", |_, args| {
+            expect_args("deepcopy", &args, 1, 2)?;
+            deep_copy(&args[0], &mut HashMap::new())
+        }),
+    ];
+
+    make_module("copy", functions, vec![])
+}
+
+fn uncopyable(value: &Value) -> Option<VmError> {
+    match value {
+        Value::File(_) => Some(VmError::TypeError(String::from("cannot pickle '_io.TextIOWrapper' object"))),
+        Value::Hash(_) => Some(VmError::TypeError(String::from("cannot pickle '_hashlib.HASH' object"))),
+        _ => None,
+    }
+}
+
+/// `copy.copy`, a new container holding the same items.
+fn copy(value: &Value) -> Result<Value, VmError> {
+    if let Some(error) = uncopyable(value) {
+        return Err(error);
+    }
+    Ok(match value {
+        Value::List(items) => Value::List(Rc::new(RefCell::new(items.borrow().clone()))),
+        Value::Dict(dict) => Value::Dict(Rc::new(RefCell::new(dict.borrow().clone()))),
+        Value::Set(items) => Value::Set(Rc::new(RefCell::new(items.borrow().clone()))),
+        Value::ByteArray(bytes) => Value::ByteArray(Rc::new(RefCell::new(bytes.borrow().clone()))),
+        Value::Instance(instance) => Value::Instance(Rc::new(instance.shallow_copy())),
+        _ => value.clone(),
+    })
+}
+
+/// `copy.deepcopy`, a copy of the value and of everything in it. `memo` has the copy of each
+/// container copied so far by its address, so a container reached twice is copied once and
+/// one that contains itself is copied into a copy that contains itself.
+fn deep_copy(value: &Value, memo: &mut HashMap<*const (), Value>) -> Result<Value, VmError> {
+    if let Some(error) = uncopyable(value) {
+        return Err(error);
+    }
+    let address = match value {
+        Value::List(items) => Rc::as_ptr(items) as *const (),
+        Value::Dict(dict) => Rc::as_ptr(dict) as *const (),
+        Value::Set(items) => Rc::as_ptr(items) as *const (),
+        Value::FrozenSet(items) => Rc::as_ptr(items) as *const (),
+        Value::ByteArray(bytes) => Rc::as_ptr(bytes) as *const (),
+        Value::Instance(instance) => Rc::as_ptr(instance) as *const (),
+        Value::Tuple(items) => {
+            let items = items.iter().map(|item| deep_copy(item, memo)).collect::<Result<_, _>>()?;
+            return Ok(Value::Tuple(items));
+        },
+        _ => return Ok(value.clone()),
+    };
+    if let Some(copy) = memo.get(&address) {
+        return Ok(copy.clone());
+    }
+
+    // The copy is in the memo before its items are copied, so the items can refer to it.
+    match value {
+        Value::List(items) => {
+            let copy = Rc::new(RefCell::new(vec![]));
+            memo.insert(address, Value::List(Rc::clone(&copy)));
+            let items = items.borrow().clone();
+            for item in items {
+                let item = deep_copy(&item, memo)?;
+                copy.borrow_mut().push(item);
+            }
+        },
+        Value::Dict(dict) => {
+            let copy = Rc::new(RefCell::new(Dict::new()));
+            memo.insert(address, Value::Dict(Rc::clone(&copy)));
+            let items = dict.borrow().items().map(|(key, value)| (key.clone(), value.clone())).collect::<Vec<_>>();
+            for (key, value) in items {
+                let key = deep_copy(&key, memo)?;
+                let value = deep_copy(&value, memo)?;
+                copy.borrow_mut().insert(key, value)?;
+            }
+        },
+        Value::Set(items) => {
+            let copy = Rc::new(RefCell::new(Set::new()));
+            memo.insert(address, Value::Set(Rc::clone(&copy)));
+            let items = items.borrow().iter().cloned().collect::<Vec<_>>();
+            for item in items {
+                set::add(&copy, deep_copy(&item, memo)?)?;
+            }
+        },
+        // A frozenset can't contain itself, so its items are copied before it is made.
+        Value::FrozenSet(items) => {
+            let items = items.iter().map(|item| deep_copy(item, memo)).collect::<Result<Set, _>>()?;
+            memo.insert(address, Value::FrozenSet(Rc::new(items)));
+        },
+        Value::ByteArray(bytes) => {
+            memo.insert(address, Value::ByteArray(Rc::new(RefCell::new(bytes.borrow().clone()))));
+        },
+        Value::Instance(instance) => {
+            let copy = Rc::new(instance.shallow_copy());
+            memo.insert(address, Value::Instance(Rc::clone(&copy)));
+            copy.replace_values(|value| deep_copy(value, memo))?;
+        },
+        _ => unreachable!("only containers have an address"),
+    }
+    Ok(memo[&address].clone())
+}
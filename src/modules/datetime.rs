@@ -0,0 +1,682 @@
+//! Dates, times and durations. There is no time zone database, so local time is UTC and the only
+//! time zones are fixed offsets.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{Keywords, Module, NativeFn, NativeType, Value, Vm, VmError};
+use super::{bound_method, expect_args, float_arg, int_arg, make_module, str_arg};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+const MAX_DELTA_DAYS: i64 = 999_999_999;
+
+const DAY_NAMES: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+pub(super) fn module() -> Module {
+    let date = native_type("date", &["year", "month", "day"], |_, args| {
+        expect_args("date", &args, 3, 3)?;
+        let (year, month, day) = (int_arg("date", &args[0])?, int_arg("date", &args[1])?, int_arg("date", &args[2])?);
+        Ok(Value::Date(Date { days: days_from_civil(year, month, day)? }))
+    }, vec![
+        ("today", |_, args| {
+            expect_args("today", &args, 0, 0)?;
+            Ok(Value::Date(Date { days: to_micros(SystemTime::now()).div_euclid(MICROS_PER_DAY) }))
+        }),
+    ], vec![]);
+
+    let date_time = native_type("datetime", DATE_TIME_PARAMETERS, new_date_time, vec![
+        ("now", |_, args| {
+            expect_args("now", &args, 0, 1)?;
+            let tz = tz_arg("now", args.first())?;
+            Ok(Value::DateTime(DateTime { time: SystemTime::now(), tz }))
+        }),
+        ("utcnow", |_, args| {
+            expect_args("utcnow", &args, 0, 0)?;
+            Ok(Value::DateTime(DateTime { time: SystemTime::now(), tz: None }))
+        }),
+        ("fromtimestamp", |_, args| {
+            expect_args("fromtimestamp", &args, 1, 2)?;
+            let seconds = float_arg("fromtimestamp", &args[0])?;
+            let tz = tz_arg("fromtimestamp", args.get(1))?;
+            let micros = (seconds * MICROS_PER_SECOND as f64).round() as i64;
+            Ok(Value::DateTime(DateTime { time: from_micros(micros), tz }))
+        }),
+    ], vec![]);
+
+    let time_delta = native_type("timedelta", TIME_DELTA_PARAMETERS, new_time_delta, vec![], vec![]);
+
+    let utc = Value::TimeZone(TimeZone { offset: TimeDelta { micros: 0 } });
+    let time_zone = native_type("timezone", &[], |_, args| {
+        expect_args("timezone", &args, 1, 1)?;
+        match &args[0] {
+            Value::TimeDelta(offset) if offset.micros.abs() < MICROS_PER_DAY => Ok(Value::TimeZone(TimeZone { offset: *offset })),
+            Value::TimeDelta(_) => Err(VmError::ValueError(String::from(
+                "offset must be a timedelta strictly between -timedelta(hours=24) and timedelta(hours=24)."
+            ))),
+            _ => Err(VmError::TypeError(String::from("timezone() argument 1 must be datetime.timedelta"))),
+        }
+    }, vec![], vec![("utc", utc)]);
+
+    make_module("datetime", vec![], vec![
+        ("date", date),
+        ("datetime", date_time),
+        ("timedelta", time_delta),
+        ("timezone", time_zone),
+        ("MINYEAR", Value::Int(1)),
+        ("MAXYEAR", Value::Int(9999)),
+    ])
+}
+
+fn native_type(
+    name: &str,
+    keywords: Keywords,
+    constructor: NativeFn,
+    functions: Vec<(&str, NativeFn)>,
+    constants: Vec<(&str, Value)>,
+) -> Value {
+    let mut attributes = HashMap::new();
+    for (function_name, function) in functions {
+        let function_name = Rc::new(String::from(function_name));
+        attributes.insert(Rc::clone(&function_name), Value::NativeFunction(function_name, function, &[]));
+    }
+    for (constant_name, value) in constants {
+        attributes.insert(Rc::new(String::from(constant_name)), value);
+    }
+
+    Value::NativeType(Rc::new(NativeType {
+        name: Rc::new(format!("datetime.{}", name)),
+        constructor,
+        keywords,
+        attributes,
+    }))
+}
+
+fn tz_arg(function: &str, value: Option<&Value>) -> Result<Option<TimeZone>, VmError> {
+    match value {
+        None | Some(Value::Nonetype) => Ok(None),
+        Some(Value::TimeZone(tz)) => Ok(Some(*tz)),
+        Some(_) => Err(VmError::TypeError(format!("{}() argument tzinfo must be a datetime.timezone", function))),
+    }
+}
+
+fn to_micros(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_micros() as i64,
+        Err(before) => -(before.duration().as_micros() as i64),
+    }
+}
+
+fn from_micros(micros: i64) -> SystemTime {
+    if micros >= 0 {
+        UNIX_EPOCH + Duration::from_micros(micros as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_micros(micros.unsigned_abs())
+    }
+}
+
+fn is_leap(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01, rejecting dates CPython would reject.
+fn days_from_civil(year: i64, month: i64, day: i64) -> Result<i64, VmError> {
+    if !(1..=9999).contains(&year) {
+        return Err(VmError::ValueError(format!("year {} is out of range", year)));
+    }
+    if !(1..=12).contains(&month) {
+        return Err(VmError::ValueError(String::from("month must be in 1..12")));
+    }
+    if day < 1 || day > days_in_month(year, month) {
+        return Err(VmError::ValueError(String::from("day is out of range for month")));
+    }
+
+    // Counts from March, so the leap day is the last day of the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Ok(era * 146_097 + day_of_era - 719_468)
+}
+
+/// The inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn check_range(days: i64) -> Result<i64, VmError> {
+    let (first, last) = (days_from_civil(1, 1, 1)?, days_from_civil(9999, 12, 31)?);
+    if (first..=last).contains(&days) {
+        Ok(days)
+    } else {
+        Err(VmError::OverflowError(String::from("date value out of range")))
+    }
+}
+
+/// A broken down point in time, what `strftime` and the attributes work from.
+struct Fields {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    microsecond: i64,
+    /// Monday is 0.
+    weekday: i64,
+    day_of_year: i64,
+}
+
+impl Fields {
+    fn new(local_micros: i64) -> Fields {
+        let days = local_micros.div_euclid(MICROS_PER_DAY);
+        let time = local_micros.rem_euclid(MICROS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        let day_of_year = days - days_from_civil(year, 1, 1).unwrap_or(days) + 1;
+        let seconds = time / MICROS_PER_SECOND;
+
+        Fields {
+            year,
+            month,
+            day,
+            hour: seconds / 3600,
+            minute: seconds / 60 % 60,
+            second: seconds % 60,
+            microsecond: time % MICROS_PER_SECOND,
+            // 1970-01-01 was a Thursday.
+            weekday: (days + 3).rem_euclid(7),
+            day_of_year,
+        }
+    }
+
+    fn date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    fn time(&self) -> String {
+        let mut time = format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second);
+        if self.microsecond != 0 {
+            time += &format!(".{:06}", self.microsecond);
+        }
+        time
+    }
+
+    /// Supports the directives that don't depend on the locale, in the C locale.
+    fn strftime(&self, format: &str, tz: Option<TimeZone>) -> String {
+        let mut formatted = String::new();
+        let mut characters = format.chars();
+        while let Some(character) = characters.next() {
+            if character != '%' {
+                formatted.push(character);
+                continue;
+            }
+            let hour12 = if self.hour % 12 == 0 { 12 } else { self.hour % 12 };
+            let day_name = DAY_NAMES[self.weekday as usize];
+            let month_name = MONTH_NAMES[self.month as usize - 1];
+            match characters.next() {
+                Some('a') => formatted += &day_name[..3],
+                Some('A') => formatted += day_name,
+                Some('w') => formatted += &((self.weekday + 1) % 7).to_string(),
+                Some('d') => formatted += &format!("{:02}", self.day),
+                Some('b') => formatted += &month_name[..3],
+                Some('B') => formatted += month_name,
+                Some('m') => formatted += &format!("{:02}", self.month),
+                Some('y') => formatted += &format!("{:02}", self.year % 100),
+                Some('Y') => formatted += &format!("{:04}", self.year),
+                Some('H') => formatted += &format!("{:02}", self.hour),
+                Some('I') => formatted += &format!("{:02}", hour12),
+                Some('p') => formatted += if self.hour < 12 { "AM" } else { "PM" },
+                Some('M') => formatted += &format!("{:02}", self.minute),
+                Some('S') => formatted += &format!("{:02}", self.second),
+                Some('f') => formatted += &format!("{:06}", self.microsecond),
+                Some('j') => formatted += &format!("{:03}", self.day_of_year),
+                Some('z') => formatted += &tz.map_or_else(String::new, |tz| tz.offset.as_utc_offset("")),
+                Some('Z') => formatted += &tz.map_or_else(String::new, |tz| tz.to_string()),
+                Some('c') => formatted += &format!(
+                    "{} {} {:2} {:02}:{:02}:{:02} {}",
+                    &day_name[..3], &month_name[..3], self.day, self.hour, self.minute, self.second, self.year,
+                ),
+                Some('x') => formatted += &format!("{:02}/{:02}/{:02}", self.month, self.day, self.year % 100),
+                Some('X') => formatted += &format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second),
+                Some('%') => formatted.push('%'),
+                Some(other) => {
+                    formatted.push('%');
+                    formatted.push(other);
+                },
+                None => formatted.push('%'),
+            }
+        }
+        formatted
+    }
+}
+
+/// `datetime.date`
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) struct Date {
+    /// Days since 1970-01-01.
+    days: i64,
+}
+
+impl fmt::Debug for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = self.fields();
+        write!(f, "datetime.date({}, {}, {})", fields.year, fields.month, fields.day)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.fields().date())
+    }
+}
+
+impl Date {
+    fn fields(&self) -> Fields {
+        Fields::new(self.days * MICROS_PER_DAY)
+    }
+
+    pub(crate) fn get_attr(&self, name: &str) -> Option<Value> {
+        let fields = self.fields();
+        let method: NativeFn = match name {
+            "year" => return Some(Value::Int(fields.year)),
+            "month" => return Some(Value::Int(fields.month)),
+            "day" => return Some(Value::Int(fields.day)),
+            "weekday" => |_, args| date_method("weekday", args, 0, |date, _| Ok(Value::Int(date.fields().weekday))),
+            "isoweekday" => |_, args| {
+                date_method("isoweekday", args, 0, |date, _| Ok(Value::Int(date.fields().weekday + 1)))
+            },
+            "isoformat" => |_, args| date_method("isoformat", args, 0, |date, _| Ok(Value::Str(date.to_string()))),
+            "strftime" => |_, args| {
+                date_method("strftime", args, 1, |date, args| {
+                    Ok(Value::Str(date.fields().strftime(str_arg("strftime", &args[0])?, None)))
+                })
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Date(*self), name, method))
+    }
+}
+
+fn date_method(function: &str, args: Vec<Value>, count: usize, method: fn(Date, &[Value]) -> Result<Value, VmError>) -> Result<Value, VmError> {
+    expect_args(function, &args[1..], count, count)?;
+    match &args[0] {
+        Value::Date(date) => method(*date, &args[1..]),
+        _ => unreachable!("date methods are only reachable through a bound date"),
+    }
+}
+
+/// `datetime.datetime`. Naive datetimes keep the wall clock time as if it was UTC, aware ones
+/// keep the actual point in time.
+#[derive(Clone, Copy)]
+pub(crate) struct DateTime {
+    time: SystemTime,
+    tz: Option<TimeZone>,
+}
+
+impl fmt::Debug for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = self.fields();
+        write!(f, "datetime.datetime({}, {}, {}, {}, {}", fields.year, fields.month, fields.day, fields.hour, fields.minute)?;
+        if fields.second != 0 || fields.microsecond != 0 {
+            write!(f, ", {}", fields.second)?;
+        }
+        if fields.microsecond != 0 {
+            write!(f, ", {}", fields.microsecond)?;
+        }
+        if let Some(tz) = self.tz {
+            write!(f, ", tzinfo={:?}", tz)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.isoformat(' '))
+    }
+}
+
+impl DateTime {
+    fn local_micros(&self) -> i64 {
+        to_micros(self.time) + self.tz.map_or(0, |tz| tz.offset.micros)
+    }
+
+    fn fields(&self) -> Fields {
+        Fields::new(self.local_micros())
+    }
+
+    fn isoformat(&self, separator: char) -> String {
+        let fields = self.fields();
+        let offset = self.tz.map_or_else(String::new, |tz| tz.offset.as_utc_offset(":"));
+        format!("{}{}{}{}", fields.date(), separator, fields.time(), offset)
+    }
+
+    pub(crate) fn get_attr(&self, name: &str) -> Option<Value> {
+        let fields = self.fields();
+        let method: NativeFn = match name {
+            "year" => return Some(Value::Int(fields.year)),
+            "month" => return Some(Value::Int(fields.month)),
+            "day" => return Some(Value::Int(fields.day)),
+            "hour" => return Some(Value::Int(fields.hour)),
+            "minute" => return Some(Value::Int(fields.minute)),
+            "second" => return Some(Value::Int(fields.second)),
+            "microsecond" => return Some(Value::Int(fields.microsecond)),
+            "tzinfo" => return Some(self.tz.map_or(Value::Nonetype, Value::TimeZone)),
+            "date" => |_, args| {
+                date_time_method("date", args, 0, |date_time, _| {
+                    Ok(Value::Date(Date { days: date_time.local_micros().div_euclid(MICROS_PER_DAY) }))
+                })
+            },
+            "weekday" => |_, args| {
+                date_time_method("weekday", args, 0, |date_time, _| Ok(Value::Int(date_time.fields().weekday)))
+            },
+            "isoweekday" => |_, args| {
+                date_time_method("isoweekday", args, 0, |date_time, _| Ok(Value::Int(date_time.fields().weekday + 1)))
+            },
+            "isoformat" => |_, args| {
+                expect_args("isoformat", &args[1..], 0, 1)?;
+                let separator = match args.get(1) {
+                    Some(separator) => {
+                        let separator = str_arg("isoformat", separator)?;
+                        let mut characters = separator.chars();
+                        match (characters.next(), characters.next()) {
+                            (Some(character), None) => character,
+                            _ => return Err(VmError::TypeError(String::from("isoformat() argument 1 must be a unicode character"))),
+                        }
+                    },
+                    None => 'T',
+                };
+                match &args[0] {
+                    Value::DateTime(date_time) => Ok(Value::Str(date_time.isoformat(separator))),
+                    _ => unreachable!("datetime methods are only reachable through a bound datetime"),
+                }
+            },
+            "strftime" => |_, args| {
+                date_time_method("strftime", args, 1, |date_time, args| {
+                    Ok(Value::Str(date_time.fields().strftime(str_arg("strftime", &args[0])?, date_time.tz)))
+                })
+            },
+            "timestamp" => |_, args| {
+                date_time_method("timestamp", args, 0, |date_time, _| {
+                    Ok(Value::Float(to_micros(date_time.time) as f64 / MICROS_PER_SECOND as f64))
+                })
+            },
+            "utcoffset" => |_, args| {
+                date_time_method("utcoffset", args, 0, |date_time, _| {
+                    Ok(date_time.tz.map_or(Value::Nonetype, |tz| Value::TimeDelta(tz.offset)))
+                })
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::DateTime(*self), name, method))
+    }
+}
+
+fn date_time_method(function: &str, args: Vec<Value>, count: usize, method: fn(DateTime, &[Value]) -> Result<Value, VmError>) -> Result<Value, VmError> {
+    expect_args(function, &args[1..], count, count)?;
+    match &args[0] {
+        Value::DateTime(date_time) => method(*date_time, &args[1..]),
+        _ => unreachable!("datetime methods are only reachable through a bound datetime"),
+    }
+}
+
+/// `datetime(year, month, day, hour=0, minute=0, second=0, microsecond=0, tzinfo=None)`
+const DATE_TIME_PARAMETERS: Keywords = &["year", "month", "day", "hour", "minute", "second", "microsecond", "tzinfo"];
+
+fn new_date_time(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("datetime", &args, 3, 8)?;
+    let mut parts = [0; 7];
+    for (position, (part, arg)) in parts.iter_mut().zip(args.iter().take(7)).enumerate() {
+        // The time parts before one given by keyword are `None`, and default to 0.
+        if position < 3 || !matches!(arg, Value::Nonetype) {
+            *part = int_arg("datetime", arg)?;
+        }
+    }
+    let [year, month, day, hour, minute, second, microsecond] = parts;
+    let limits = [("hour", hour, 23), ("minute", minute, 59), ("second", second, 59), ("microsecond", microsecond, 999_999)];
+    for (name, value, max) in limits.iter() {
+        if *value < 0 || value > max {
+            return Err(VmError::ValueError(format!("{} must be in 0..{}", name, max)));
+        }
+    }
+    let tz = tz_arg("datetime", args.get(7))?;
+
+    let seconds = hour * 3600 + minute * 60 + second;
+    let local = days_from_civil(year, month, day)? * MICROS_PER_DAY + seconds * MICROS_PER_SECOND + microsecond;
+    let micros = local - tz.map_or(0, |tz| tz.offset.micros);
+    Ok(Value::DateTime(DateTime { time: from_micros(micros), tz }))
+}
+
+/// `datetime.timedelta`, normalized to microseconds.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) struct TimeDelta {
+    micros: i64,
+}
+
+impl fmt::Debug for TimeDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = [("days", self.days()), ("seconds", self.seconds()), ("microseconds", self.microseconds())]
+            .iter()
+            .filter(|(_, value)| *value != 0)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        if parts.is_empty() {
+            write!(f, "datetime.timedelta(0)")
+        } else {
+            write!(f, "datetime.timedelta({})", parts.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for TimeDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let days = self.days();
+        if days != 0 {
+            write!(f, "{} day{}, ", days, if days.abs() == 1 { "" } else { "s" })?;
+        }
+        let seconds = self.seconds();
+        write!(f, "{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)?;
+        if self.microseconds() != 0 {
+            write!(f, ".{:06}", self.microseconds())?;
+        }
+        Ok(())
+    }
+}
+
+impl TimeDelta {
+    fn new(micros: i64) -> Result<TimeDelta, VmError> {
+        if micros.div_euclid(MICROS_PER_DAY).abs() > MAX_DELTA_DAYS {
+            return Err(VmError::OverflowError(format!("days={}; must have magnitude <= {}", micros.div_euclid(MICROS_PER_DAY), MAX_DELTA_DAYS)));
+        }
+        Ok(TimeDelta { micros })
+    }
+
+    fn days(&self) -> i64 {
+        self.micros.div_euclid(MICROS_PER_DAY)
+    }
+
+    fn seconds(&self) -> i64 {
+        self.micros.rem_euclid(MICROS_PER_DAY) / MICROS_PER_SECOND
+    }
+
+    fn microseconds(&self) -> i64 {
+        self.micros.rem_euclid(MICROS_PER_SECOND)
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.micros == 0
+    }
+
+    /// `+HH:MM`, with seconds and microseconds only when they aren't zero.
+    fn as_utc_offset(&self, separator: &str) -> String {
+        let sign = if self.micros < 0 { '-' } else { '+' };
+        let micros = self.micros.abs();
+        let seconds = micros / MICROS_PER_SECOND;
+        let mut offset = format!("{}{:02}{}{:02}", sign, seconds / 3600, separator, seconds / 60 % 60);
+        if seconds % 60 != 0 || micros % MICROS_PER_SECOND != 0 {
+            offset += &format!("{}{:02}", separator, seconds % 60);
+        }
+        if micros % MICROS_PER_SECOND != 0 {
+            offset += &format!(".{:06}", micros % MICROS_PER_SECOND);
+        }
+        offset
+    }
+
+    pub(crate) fn get_attr(&self, name: &str) -> Option<Value> {
+        match name {
+            "days" => Some(Value::Int(self.days())),
+            "seconds" => Some(Value::Int(self.seconds())),
+            "microseconds" => Some(Value::Int(self.microseconds())),
+            "total_seconds" => Some(bound_method(Value::TimeDelta(*self), name, |_, args| {
+                expect_args("total_seconds", &args[1..], 0, 0)?;
+                match &args[0] {
+                    Value::TimeDelta(delta) => Ok(Value::Float(delta.micros as f64 / MICROS_PER_SECOND as f64)),
+                    _ => unreachable!("timedelta methods are only reachable through a bound timedelta"),
+                }
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// `timedelta(days=0, seconds=0, microseconds=0, milliseconds=0, minutes=0, hours=0, weeks=0)`
+const TIME_DELTA_PARAMETERS: Keywords = &["days", "seconds", "microseconds", "milliseconds", "minutes", "hours", "weeks"];
+
+fn new_time_delta(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("timedelta", &args, 0, 7)?;
+    let units = [MICROS_PER_DAY, MICROS_PER_SECOND, 1, 1000, 60 * MICROS_PER_SECOND, 3600 * MICROS_PER_SECOND, 7 * MICROS_PER_DAY];
+    let mut micros = 0.0;
+    // The units before one given by keyword are `None`, which adds nothing.
+    for (arg, unit) in args.iter().zip(units.iter()).filter(|(arg, _)| !matches!(arg, Value::Nonetype)) {
+        micros += float_arg("timedelta", arg)? * *unit as f64;
+    }
+
+    Ok(Value::TimeDelta(TimeDelta::new(micros.round() as i64)?))
+}
+
+/// `datetime.timezone`, a fixed offset from UTC.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct TimeZone {
+    offset: TimeDelta,
+}
+
+impl fmt::Debug for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.offset.is_zero() {
+            write!(f, "datetime.timezone.utc")
+        } else {
+            write!(f, "datetime.timezone({:?})", self.offset)
+        }
+    }
+}
+
+impl fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.offset.is_zero() {
+            write!(f, "UTC")
+        } else {
+            write!(f, "UTC{}", self.offset.as_utc_offset(":"))
+        }
+    }
+}
+
+impl TimeZone {
+    pub(crate) fn get_attr(&self, name: &str) -> Option<Value> {
+        match name {
+            "utcoffset" => Some(bound_method(Value::TimeZone(*self), name, |_, args| {
+                expect_args("utcoffset", &args[1..], 1, 1)?;
+                match &args[0] {
+                    Value::TimeZone(tz) => Ok(Value::TimeDelta(tz.offset)),
+                    _ => unreachable!("timezone methods are only reachable through a bound timezone"),
+                }
+            })),
+            _ => None,
+        }
+    }
+}
+
+fn shift(date_time: &DateTime, micros: i64) -> Result<Value, VmError> {
+    let shifted = to_micros(date_time.time) + micros;
+    check_range((shifted + date_time.tz.map_or(0, |tz| tz.offset.micros)).div_euclid(MICROS_PER_DAY))?;
+    Ok(Value::DateTime(DateTime { time: from_micros(shifted), tz: date_time.tz }))
+}
+
+/// `+` between date and time values, `None` when the operands aren't supported.
+pub(crate) fn add(first: &Value, second: &Value) -> Option<Result<Value, VmError>> {
+    Some(match (first, second) {
+        (Value::DateTime(date_time), Value::TimeDelta(delta)) | (Value::TimeDelta(delta), Value::DateTime(date_time)) => {
+            shift(date_time, delta.micros)
+        },
+        (Value::Date(date), Value::TimeDelta(delta)) | (Value::TimeDelta(delta), Value::Date(date)) => {
+            check_range(date.days + delta.days()).map(|days| Value::Date(Date { days }))
+        },
+        (Value::TimeDelta(first), Value::TimeDelta(second)) => TimeDelta::new(first.micros + second.micros).map(Value::TimeDelta),
+        _ => return None,
+    })
+}
+
+/// `-` between date and time values, `None` when the operands aren't supported.
+pub(crate) fn sub(first: &Value, second: &Value) -> Option<Result<Value, VmError>> {
+    Some(match (first, second) {
+        (Value::DateTime(first), Value::DateTime(second)) => {
+            if first.tz.is_some() != second.tz.is_some() {
+                return Some(Err(VmError::TypeError(String::from("can't subtract offset-naive and offset-aware datetimes"))));
+            }
+            TimeDelta::new(to_micros(first.time) - to_micros(second.time)).map(Value::TimeDelta)
+        },
+        (Value::DateTime(date_time), Value::TimeDelta(delta)) => shift(date_time, -delta.micros),
+        (Value::Date(first), Value::Date(second)) => TimeDelta::new((first.days - second.days) * MICROS_PER_DAY).map(Value::TimeDelta),
+        (Value::Date(date), Value::TimeDelta(delta)) => check_range(date.days - delta.days()).map(|days| Value::Date(Date { days })),
+        (Value::TimeDelta(first), Value::TimeDelta(second)) => TimeDelta::new(first.micros - second.micros).map(Value::TimeDelta),
+        _ => return None,
+    })
+}
+
+/// `*` between a timedelta and an int, `None` when the operands aren't supported.
+pub(crate) fn mul(first: &Value, second: &Value) -> Option<Result<Value, VmError>> {
+    match (first, second) {
+        (Value::TimeDelta(delta), Value::Int(factor)) | (Value::Int(factor), Value::TimeDelta(delta)) => {
+            Some(TimeDelta::new(delta.micros * *factor).map(Value::TimeDelta))
+        },
+        _ => None,
+    }
+}
+
+/// Orders two date and time values of the same kind, `None` when they can't be compared.
+pub(crate) fn compare(first: &Value, second: &Value) -> Option<std::cmp::Ordering> {
+    match (first, second) {
+        (Value::Date(first), Value::Date(second)) => first.partial_cmp(second),
+        (Value::TimeDelta(first), Value::TimeDelta(second)) => first.partial_cmp(second),
+        (Value::DateTime(first), Value::DateTime(second)) if first.tz.is_some() == second.tz.is_some() => {
+            first.time.partial_cmp(&second.time)
+        },
+        _ => None,
+    }
+}
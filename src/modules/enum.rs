@@ -0,0 +1,98 @@
+//! `enum.Enum`. CPython builds enums with a metaclass, which the VM doesn't have, so
+//! `__build_class__` asks `make_enum` to turn the class attributes of `Enum` subclasses into
+//! members instead.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use crate::classes::{self, Class};
+use crate::{Module, Value, VmError};
+use super::make_module;
+
+thread_local! {
+    static ENUM_CLASS: Rc<Class> = classes::native_class("Enum", "enum");
+}
+
+pub(super) fn module() -> Module {
+    make_module("enum", vec![], vec![("Enum", Value::Class(ENUM_CLASS.with(Rc::clone)))])
+}
+
+/// A member of an enum, which is only ever equal to itself.
+pub(crate) struct EnumMember {
+    /// The enum, which holds its members.
+    class: Weak<Class>,
+    class_name: Rc<String>,
+    name: Rc<String>,
+    value: Value,
+}
+
+impl fmt::Debug for EnumMember {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}.{}: {}>", self.class_name, self.name, self.value.python_repr())
+    }
+}
+
+impl fmt::Display for EnumMember {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.class_name, self.name)
+    }
+}
+
+impl EnumMember {
+    pub(crate) fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// `name` and `value`, then the methods of the enum bound to the member.
+    pub(crate) fn get_attr(self: &Rc<Self>, name: &str) -> Option<Value> {
+        match name {
+            "name" => Some(Value::Str(self.name.to_string())),
+            "value" => Some(self.value.clone()),
+            _ => self.class.upgrade()?.lookup(name).map(|value| match value {
+                Value::Frame(_) | Value::NativeFunction(..) => {
+                    Value::BoundMethod(Box::new(Value::EnumMember(Rc::clone(self))), Box::new(value))
+                },
+                value => value,
+            }),
+        }
+    }
+}
+
+/// Whether a class with `bases` is an enum.
+pub(crate) fn is_enum(bases: &[Rc<Class>]) -> bool {
+    ENUM_CLASS.with(|enum_class| bases.iter().any(|base| base.is_subclass(enum_class)))
+}
+
+/// Turns the attributes of the new enum `class` that aren't functions or dunder names into
+/// its members.
+pub(crate) fn make_enum(class: &Rc<Class>) {
+    let mut dict = class.dict.borrow_mut();
+    let members: HashMap<_, _> = dict.iter().filter_map(|(name, value)| match value {
+        Value::Frame(_) | Value::NativeFunction(..) => None,
+        _ if name.starts_with("__") && name.ends_with("__") => None,
+        value => {
+            let member = EnumMember {
+                class: Rc::downgrade(class),
+                class_name: Rc::clone(&class.name),
+                name: Rc::clone(name),
+                value: value.clone(),
+            };
+            Some((Rc::clone(name), Value::EnumMember(Rc::new(member))))
+        },
+    }).collect();
+    dict.extend(members);
+}
+
+/// Calling an enum class, `Color(1)`, gives the member with that value.
+pub(crate) fn lookup_member(class: &Rc<Class>, args: &[Value]) -> Result<Value, VmError> {
+    let value = match args {
+        [value] => value,
+        _ => return Err(VmError::TypeError(format!("{}() takes exactly one argument ({} given)", class.name, args.len()))),
+    };
+    let member = class.dict.borrow().values().find(|member| match member {
+        Value::EnumMember(member) => member.value == *value,
+        _ => false,
+    }).cloned();
+    member.ok_or_else(|| VmError::ValueError(format!("{} is not a valid {}", value.python_repr(), class.name)))
+}
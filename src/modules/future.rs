@@ -0,0 +1,24 @@
+//! `__future__`, which `from __future__ import ...` needs at run time. The features themselves
+//! are handled by the compiler, `annotations` (PEP 563) for one compiles annotations to string
+//! constants, so the VM stores them without evaluating them. The features are given by their
+//! compiler flag, not as `_Feature` objects.
+
+use crate::{Module, Value};
+use super::make_module;
+
+pub(super) fn module() -> Module {
+    let features = vec![
+        ("nested_scopes", Value::Int(0x0010)),
+        ("generators", Value::Int(0)),
+        ("division", Value::Int(0x20000)),
+        ("absolute_import", Value::Int(0x40000)),
+        ("with_statement", Value::Int(0x80000)),
+        ("print_function", Value::Int(0x100000)),
+        ("unicode_literals", Value::Int(0x200000)),
+        ("barry_as_FLUFL", Value::Int(0x400000)),
+        ("generator_stop", Value::Int(0x800000)),
+        ("annotations", Value::Int(0x1000000)),
+    ];
+
+    make_module("__future__", vec![], features)
+}
@@ -0,0 +1,131 @@
+//! Message digests from the RustCrypto crates.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::digest::DynDigest;
+use sha2::{Sha224, Sha256, Sha384, Sha512};
+
+use crate::{Module, NativeFn, Value, Vm, VmError};
+use super::{bound_method, expect_args, make_module, str_arg};
+
+const ALGORITHMS: [&str; 6] = ["md5", "sha1", "sha224", "sha256", "sha384", "sha512"];
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("new", |_, args| {
+            expect_args("new", &args, 1, 2)?;
+            let name = str_arg("new", &args[0])?.to_lowercase();
+            match ALGORITHMS.iter().find(|algorithm| **algorithm == name) {
+                Some(algorithm) => new_hash(algorithm, args.get(1)),
+                None => Err(VmError::ValueError(format!("unsupported hash type {}", name))),
+            }
+        }),
+        ("md5", |_, args| constructor("md5", args)),
+        ("sha1", |_, args| constructor("sha1", args)),
+        ("sha224", |_, args| constructor("sha224", args)),
+        ("sha256", |_, args| constructor("sha256", args)),
+        ("sha384", |_, args| constructor("sha384", args)),
+        ("sha512", |_, args| constructor("sha512", args)),
+    ];
+    let algorithms = ALGORITHMS.iter().map(|algorithm| Value::Str(String::from(*algorithm))).collect::<Vec<_>>();
+
+    make_module("hashlib", functions, vec![
+        ("algorithms_guaranteed", Value::Tuple(algorithms.clone())),
+        ("algorithms_available", Value::Tuple(algorithms)),
+    ])
+}
+
+fn constructor(name: &'static str, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args(name, &args, 0, 1)?;
+    new_hash(name, args.first())
+}
+
+fn new_hash(name: &'static str, data: Option<&Value>) -> Result<Value, VmError> {
+    let state: Box<dyn DynDigest> = match name {
+        "md5" => Box::new(Md5::default()),
+        "sha1" => Box::new(Sha1::default()),
+        "sha224" => Box::new(Sha224::default()),
+        "sha256" => Box::new(Sha256::default()),
+        "sha384" => Box::new(Sha384::default()),
+        "sha512" => Box::new(Sha512::default()),
+        _ => unreachable!("{} is listed in ALGORITHMS", name),
+    };
+    let hash = Hash { name, state: RefCell::new(state) };
+    if let Some(data) = data {
+        hash.update(data)?;
+    }
+
+    Ok(Value::Hash(Rc::new(hash)))
+}
+
+/// A hash object, which keeps the data it was fed so far.
+pub(crate) struct Hash {
+    name: &'static str,
+    state: RefCell<Box<dyn DynDigest>>,
+}
+
+impl fmt::Debug for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} _hashlib.HASH object>", self.name)
+    }
+}
+
+impl Hash {
+    fn update(&self, data: &Value) -> Result<(), VmError> {
+        match data {
+            Value::Bytes(bytes) => {
+                self.state.borrow_mut().update(bytes);
+                Ok(())
+            },
+            Value::Str(_) => Err(VmError::TypeError(String::from("Strings must be encoded before hashing"))),
+            _ => Err(VmError::TypeError(String::from("object supporting the buffer API required"))),
+        }
+    }
+
+    /// The digest of the data so far, which leaves the object usable for further updates.
+    fn digest(&self) -> Vec<u8> {
+        self.state.borrow().box_clone().finalize().into_vec()
+    }
+
+    pub(crate) fn get_attr(hash: &Rc<Hash>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "name" => return Some(Value::Str(String::from(hash.name))),
+            "digest_size" => return Some(Value::Int(hash.state.borrow().output_size() as i64)),
+            "update" => |_, args| {
+                expect_args("update", &args[1..], 1, 1)?;
+                receiver(&args).update(&args[1])?;
+                Ok(Value::Nonetype)
+            },
+            "digest" => |_, args| {
+                expect_args("digest", &args[1..], 0, 0)?;
+                Ok(Value::Bytes(receiver(&args).digest()))
+            },
+            "hexdigest" => |_, args| {
+                expect_args("hexdigest", &args[1..], 0, 0)?;
+                Ok(Value::Str(receiver(&args).digest().iter().map(|byte| format!("{:02x}", byte)).collect()))
+            },
+            "copy" => copy,
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Hash(Rc::clone(hash)), name, method))
+    }
+}
+
+fn receiver(args: &[Value]) -> &Rc<Hash> {
+    match &args[0] {
+        Value::Hash(hash) => hash,
+        _ => unreachable!("hash methods are only reachable through a bound hash object"),
+    }
+}
+
+fn copy(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("copy", &args[1..], 0, 0)?;
+    let hash = receiver(&args);
+    let state = hash.state.borrow().box_clone();
+    Ok(Value::Hash(Rc::new(Hash { name: hash.name, state: RefCell::new(state) })))
+}
@@ -0,0 +1,270 @@
+//! Text files returned by `open()`, which is also importable as `io.open`.
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::rc::Rc;
+
+use crate::exceptions::exception_type;
+use crate::{Module, NativeFn, Value, Vm, VmError};
+use super::{bound_method, expect_args, int_arg, make_module, os_error, str_arg};
+
+pub(super) fn module() -> Module {
+    make_module("io", vec![("open", open)], vec![
+        ("UnsupportedOperation", Value::ExceptionType(exception_type("io.UnsupportedOperation"))),
+    ])
+}
+
+pub(crate) trait ReadWrite: Read + Write + Seek {}
+
+impl<T: Read + Write + Seek> ReadWrite for T {}
+
+pub(crate) struct File {
+    path: String,
+    mode: String,
+    readable: bool,
+    writable: bool,
+    /// `None` once the file is closed.
+    handle: RefCell<Option<Box<dyn ReadWrite>>>,
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<_io.TextIOWrapper name='{}' mode='{}'>", self.path, self.mode)
+    }
+}
+
+impl File {
+    fn with_handle<T>(&self, operation: impl FnOnce(&mut dyn ReadWrite) -> io::Result<T>) -> Result<T, VmError> {
+        match self.handle.borrow_mut().as_mut() {
+            Some(handle) => operation(handle.as_mut()).map_err(|error| os_error(error, None)),
+            None => Err(VmError::ValueError(String::from("I/O operation on closed file."))),
+        }
+    }
+
+    fn expect_readable(&self) -> Result<(), VmError> {
+        if self.readable {
+            Ok(())
+        } else {
+            Err(VmError::OSError("io.UnsupportedOperation", String::from("not readable")))
+        }
+    }
+
+    /// Reads up to `limit` characters, stopping after a newline when `line` is set.
+    fn read_chars(&self, limit: Option<usize>, line: bool) -> Result<String, VmError> {
+        self.expect_readable()?;
+        let bytes = self.with_handle(|handle| {
+            let mut bytes = vec![];
+            let mut chars = 0;
+            let mut byte = [0];
+            while limit.is_none_or(|limit| chars < limit) && handle.read(&mut byte)? == 1 {
+                bytes.push(byte[0]);
+                // Continuation bytes of UTF-8 don't start a new character.
+                if byte[0] & 0b1100_0000 != 0b1000_0000 {
+                    chars += 1;
+                }
+                if line && byte[0] == b'\n' {
+                    break;
+                }
+            }
+            Ok(bytes)
+        })?;
+
+        decode(bytes)
+    }
+
+    /// The next line for `for line in file`, `None` at the end of the file.
+    pub(crate) fn next_line(&self) -> Result<Option<Value>, VmError> {
+        let line = self.read_chars(None, true)?;
+        Ok(if line.is_empty() { None } else { Some(Value::Str(line)) })
+    }
+
+    fn close(&self) -> Result<(), VmError> {
+        if self.handle.borrow().is_some() {
+            self.with_handle(|handle| handle.flush())?;
+        }
+        *self.handle.borrow_mut() = None;
+        Ok(())
+    }
+
+    pub(crate) fn get_attr(file: &Rc<File>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "name" => return Some(Value::Str(file.path.clone())),
+            "mode" => return Some(Value::Str(file.mode.clone())),
+            "closed" => return Some(Value::Bool(file.handle.borrow().is_none())),
+            "read" => read,
+            "readline" => readline,
+            "readlines" => readlines,
+            "write" => write,
+            "flush" => flush,
+            "close" => close,
+            "seek" => seek,
+            "tell" => tell,
+            "__enter__" => enter,
+            "__exit__" => exit,
+            _ => return None,
+        };
+
+        Some(bound_method(Value::File(Rc::clone(file)), name, method))
+    }
+}
+
+fn decode(bytes: Vec<u8>) -> Result<String, VmError> {
+    String::from_utf8(bytes).map_err(|error| {
+        VmError::ValueError(format!("'utf-8' codec can't decode byte at position {}", error.utf8_error().valid_up_to()))
+    })
+}
+
+/// `open(file, mode='r')`, only text mode is supported.
+pub(crate) fn open(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("open", &args, 1, 2)?;
+    let path = str_arg("open", &args[0])?;
+    let mode = match args.get(1) {
+        Some(mode) => str_arg("open", mode)?,
+        None => "r",
+    };
+
+    let mut options = OpenOptions::new();
+    let (mut readable, mut writable, mut kinds) = (false, false, 0);
+    for (position, character) in mode.char_indices() {
+        match character {
+            'r' => readable = true,
+            'w' => { writable = true; options.create(true).truncate(true); },
+            'a' => { writable = true; options.append(true).create(true); },
+            'x' => { writable = true; options.create_new(true); },
+            '+' if !mode[..position].contains('+') => { readable = true; writable = true; },
+            't' => {},
+            'b' => return Err(VmError::Unimplemented(String::from("binary mode files"))),
+            _ => return Err(VmError::ValueError(format!("invalid mode: '{}'", mode))),
+        }
+        if "rwax".contains(character) {
+            kinds += 1;
+        }
+    }
+    if kinds != 1 {
+        return Err(VmError::ValueError(String::from("must have exactly one of create/read/write/append mode")));
+    }
+
+    let handle = options.read(readable).write(writable).open(path).map_err(|error| os_error(error, Some(path)))?;
+    Ok(Value::File(Rc::new(File {
+        path: String::from(path),
+        mode: String::from(mode),
+        readable,
+        writable,
+        handle: RefCell::new(Some(Box::new(handle))),
+    })))
+}
+
+/// Bound methods are called with the file they were looked up on as the first argument.
+fn receiver(args: &[Value]) -> &File {
+    match &args[0] {
+        Value::File(file) => file,
+        _ => unreachable!("file methods are only bound to files"),
+    }
+}
+
+/// `read(size=-1)`
+fn read(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("read", &args, 1, 2)?;
+    let file = receiver(&args);
+    let size = match args.get(1) {
+        Some(size) => int_arg("read", size)?,
+        None => -1,
+    };
+
+    if size < 0 {
+        file.expect_readable()?;
+        let bytes = file.with_handle(|handle| {
+            let mut bytes = vec![];
+            handle.read_to_end(&mut bytes)?;
+            Ok(bytes)
+        })?;
+        Ok(Value::Str(decode(bytes)?))
+    } else {
+        Ok(Value::Str(file.read_chars(Some(size as usize), false)?))
+    }
+}
+
+fn readline(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("readline", &args, 1, 1)?;
+    Ok(Value::Str(receiver(&args).read_chars(None, true)?))
+}
+
+fn readlines(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("readlines", &args, 1, 1)?;
+    let file = receiver(&args);
+    let mut lines = vec![];
+    while let Some(line) = file.next_line()? {
+        lines.push(line);
+    }
+    Ok(Value::List(Rc::new(RefCell::new(lines))))
+}
+
+fn write(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("write", &args, 2, 2)?;
+    let file = receiver(&args);
+    let text = str_arg("write", &args[1])?;
+    if !file.writable {
+        return Err(VmError::OSError("io.UnsupportedOperation", String::from("not writable")));
+    }
+
+    file.with_handle(|handle| handle.write_all(text.as_bytes()))?;
+    Ok(Value::Int(text.chars().count() as i64))
+}
+
+fn flush(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("flush", &args, 1, 1)?;
+    receiver(&args).with_handle(|handle| handle.flush())?;
+    Ok(Value::Nonetype)
+}
+
+fn close(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("close", &args, 1, 1)?;
+    receiver(&args).close()?;
+    Ok(Value::Nonetype)
+}
+
+fn position(position: u64) -> Result<Value, VmError> {
+    i64::try_from(position)
+        .map(Value::Int)
+        .map_err(|_| VmError::OverflowError(String::from("file position does not fit in an int")))
+}
+
+/// `seek(offset, whence=0)`
+fn seek(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("seek", &args, 2, 3)?;
+    let offset = int_arg("seek", &args[1])?;
+    let whence = match args.get(2) {
+        Some(whence) => int_arg("seek", whence)?,
+        None => 0,
+    };
+
+    let from = match whence {
+        0 if offset >= 0 => SeekFrom::Start(offset as u64),
+        0 => return Err(VmError::ValueError(format!("negative seek position {}", offset))),
+        1 => SeekFrom::Current(offset),
+        2 => SeekFrom::End(offset),
+        _ => return Err(VmError::ValueError(format!("invalid whence ({}, should be 0, 1 or 2)", whence))),
+    };
+    position(receiver(&args).with_handle(|handle| handle.seek(from))?)
+}
+
+fn tell(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("tell", &args, 1, 1)?;
+    position(receiver(&args).with_handle(|handle| handle.stream_position())?)
+}
+
+fn enter(_vm: &mut Vm, mut args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("__enter__", &args, 1, 1)?;
+    receiver(&args).with_handle(|_| Ok(()))?;
+    Ok(args.remove(0))
+}
+
+/// `__exit__(exc_type, exc_value, traceback)`, closes the file and lets exceptions propagate.
+fn exit(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("__exit__", &args, 4, 4)?;
+    receiver(&args).close()?;
+    Ok(Value::Nonetype)
+}
@@ -0,0 +1,321 @@
+//! The `json` module, parsing is done by `serde_json`. Arrays are loaded as lists and objects
+//! as dicts, which keep the order of their keys.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::dict::Dict;
+use crate::{Module, Value, Vm, VmError};
+use super::{expect_args, int_arg, keyword_function, make_module, str_arg};
+
+pub(super) fn module() -> Module {
+    make_module("json", vec![("loads", loads)], vec![
+        ("dumps", keyword_function("dumps", dumps, &["obj", "indent", "sort_keys"])),
+    ])
+}
+
+fn loads(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("loads", &args, 1, 1)?;
+    let source = str_arg("loads", &args[0])?;
+    // Python writes and accepts these even though they aren't standard JSON.
+    match source.trim() {
+        "NaN" => return Ok(Value::Float(f64::NAN)),
+        "Infinity" => return Ok(Value::Float(f64::INFINITY)),
+        "-Infinity" => return Ok(Value::Float(f64::NEG_INFINITY)),
+        _ => {},
+    }
+
+    let document = serde_json::from_str(source).map_err(|error| VmError::ValueError(format!("Invalid JSON: {}", error)))?;
+    from_json(document)
+}
+
+/// A parsed JSON document. `serde_json::Value` sorts the keys of objects, so arrays and objects
+/// are parsed into this to keep the order the keys are written in, which dicts keep.
+enum Document {
+    Scalar(serde_json::Value),
+    Array(Vec<Document>),
+    Object(Vec<(String, Document)>),
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DocumentVisitor)
+    }
+}
+
+struct DocumentVisitor;
+
+impl<'de> Visitor<'de> for DocumentVisitor {
+    type Value = Document;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Document, E> {
+        Ok(Document::Scalar(serde_json::Value::from(value)))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Document, E> {
+        Ok(Document::Scalar(serde_json::Value::from(value)))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Document, E> {
+        Ok(Document::Scalar(serde_json::Value::from(value)))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Document, E> {
+        Ok(Document::Scalar(serde_json::Value::from(value)))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Document, E> {
+        Ok(Document::Scalar(serde_json::Value::from(value)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Document, E> {
+        Ok(Document::Scalar(serde_json::Value::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Document, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Document::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Document, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = map.next_entry()? {
+            items.push(item);
+        }
+        Ok(Document::Object(items))
+    }
+}
+
+fn from_json(document: Document) -> Result<Value, VmError> {
+    let scalar = match document {
+        Document::Scalar(scalar) => scalar,
+        Document::Array(items) => {
+            let items = items.into_iter().map(from_json).collect::<Result<Vec<_>, _>>()?;
+            return Ok(Value::List(Rc::new(RefCell::new(items))));
+        },
+        Document::Object(items) => {
+            // A key that is written twice keeps its first position and its last value, like
+            // assigning it twice does.
+            let mut dict = Dict::new();
+            for (key, value) in items {
+                dict.insert(Value::Str(key), from_json(value)?)?;
+            }
+            return Ok(Value::Dict(Rc::new(RefCell::new(dict))));
+        },
+    };
+    match scalar {
+        serde_json::Value::Null => Ok(Value::Nonetype),
+        serde_json::Value::Bool(val) => Ok(Value::Bool(val)),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(val) => Ok(Value::Int(val)),
+            None if number.is_u64() => Err(VmError::OverflowError(format!("{} does not fit in an int", number))),
+            None => Ok(Value::Float(number.as_f64().unwrap())),
+        },
+        serde_json::Value::String(val) => Ok(Value::Str(val)),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => unreachable!("arrays and objects are parsed as documents"),
+    }
+}
+
+/// How `dumps` lays out what it writes.
+#[derive(Default)]
+struct Layout {
+    /// What each level of nesting is indented by, `None` writes everything on one line.
+    indent: Option<String>,
+    sort_keys: bool,
+}
+
+impl Layout {
+    /// Starts the items of a list or dict nested `depth` levels deep.
+    fn open(&self, document: &mut String, depth: usize) {
+        self.new_line(document, depth);
+    }
+
+    /// Goes on to the next item of a list or dict nested `depth` levels deep.
+    fn separate(&self, document: &mut String, depth: usize) {
+        document.push(',');
+        match self.indent {
+            Some(_) => self.new_line(document, depth),
+            None => document.push(' '),
+        }
+    }
+
+    /// Ends the items of a list or dict nested `depth` levels deep.
+    fn close(&self, document: &mut String, depth: usize) {
+        self.new_line(document, depth - 1);
+    }
+
+    fn new_line(&self, document: &mut String, depth: usize) {
+        if let Some(indent) = &self.indent {
+            document.push('\n');
+            document.push_str(&indent.repeat(depth));
+        }
+    }
+}
+
+/// `dumps(obj, indent=None, sort_keys=False)`
+fn dumps(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("dumps", &args, 1, 3)?;
+    let indent = match args.get(1) {
+        None | Some(Value::Nonetype) => None,
+        Some(Value::Str(indent)) => Some(indent.clone()),
+        Some(indent) => Some(" ".repeat(int_arg("dumps", indent)?.max(0) as usize)),
+    };
+    let sort_keys = match args.get(2) {
+        Some(sort_keys) => sort_keys.is_truthy(),
+        None => false,
+    };
+
+    let mut document = String::new();
+    to_json(&args[0], &mut document, &Layout { indent, sort_keys }, &mut vec![])?;
+    Ok(Value::Str(document))
+}
+
+/// Writes `value` the way `json.dumps` does with `layout`. `containers` are the lists and
+/// dicts being written, a list or dict inside itself can't be written, and tuples as 0 so
+/// their length is how deep `value` is nested.
+fn to_json(value: &Value, document: &mut String, layout: &Layout, containers: &mut Vec<usize>) -> Result<(), VmError> {
+    match value {
+        Value::Nonetype => document.push_str("null"),
+        Value::Bool(val) => document.push_str(if *val { "true" } else { "false" }),
+        Value::Int(val) => document.push_str(&val.to_string()),
+        Value::BigInt(val) => document.push_str(&val.to_string()),
+        Value::Float(val) if val.is_nan() => document.push_str("NaN"),
+        Value::Float(val) if val.is_infinite() => document.push_str(if *val > 0.0 { "Infinity" } else { "-Infinity" }),
+        Value::Float(val) => document.push_str(&format!("{:?}", val)),
+        Value::Str(val) => write_string(val, document),
+        Value::Tuple(values) => {
+            containers.push(0);
+            array_to_json(values, document, layout, containers)?;
+            containers.pop();
+        },
+        Value::List(list) => {
+            enter(Rc::as_ptr(list) as usize, containers)?;
+            array_to_json(&list.borrow(), document, layout, containers)?;
+            containers.pop();
+        },
+        Value::Dict(dict) => {
+            enter(Rc::as_ptr(dict) as usize, containers)?;
+            let dict = dict.borrow();
+            let mut items: Vec<_> = dict.items().collect();
+            if layout.sort_keys {
+                sort_items(&mut items)?;
+            }
+            let is_empty = items.is_empty();
+            document.push('{');
+            for (position, (key, value)) in items.into_iter().enumerate() {
+                if position == 0 {
+                    layout.open(document, containers.len());
+                } else {
+                    layout.separate(document, containers.len());
+                }
+                key_to_json(key, document)?;
+                document.push_str(": ");
+                to_json(value, document, layout, containers)?;
+            }
+            if !is_empty {
+                layout.close(document, containers.len());
+            }
+            document.push('}');
+            containers.pop();
+        },
+        _ => return Err(VmError::TypeError(format!("Object of type {} is not JSON serializable", value.type_name()))),
+    }
+
+    Ok(())
+}
+
+fn enter(container: usize, containers: &mut Vec<usize>) -> Result<(), VmError> {
+    if containers.contains(&container) {
+        return Err(VmError::ValueError(String::from("Circular reference detected")));
+    }
+    containers.push(container);
+    Ok(())
+}
+
+fn array_to_json(values: &[Value], document: &mut String, layout: &Layout, containers: &mut Vec<usize>) -> Result<(), VmError> {
+    let depth = containers.len();
+    document.push('[');
+    for (position, value) in values.iter().enumerate() {
+        if position == 0 {
+            layout.open(document, depth);
+        } else {
+            layout.separate(document, depth);
+        }
+        to_json(value, document, layout, containers)?;
+    }
+    if !values.is_empty() {
+        layout.close(document, depth);
+    }
+    document.push(']');
+    Ok(())
+}
+
+/// Sorts the items of a dict by their keys for `sort_keys=True`, which like `sorted` can only
+/// order strings among themselves and numbers among themselves.
+fn sort_items(items: &mut [(&Value, &Value)]) -> Result<(), VmError> {
+    let is_number = |key: &Value| matches!(key, Value::Int(_) | Value::Float(_) | Value::Bool(_));
+    let mut error = None;
+    items.sort_by(|(first, _), (second, _)| match (first, second) {
+        (Value::Str(first), Value::Str(second)) => first.cmp(second),
+        _ if is_number(first) && is_number(second) => first.partial_cmp(second).unwrap_or(Ordering::Equal),
+        _ => {
+            error.get_or_insert_with(|| VmError::TypeError(format!(
+                "'<' not supported between instances of '{}' and '{}'",
+                first.type_name(),
+                second.type_name(),
+            )));
+            Ordering::Equal
+        },
+    });
+    error.map_or(Ok(()), Err)
+}
+
+/// Object keys are strings, so keys that are numbers, bools or `None` are written as the
+/// string of their JSON.
+fn key_to_json(key: &Value, document: &mut String) -> Result<(), VmError> {
+    match key {
+        Value::Str(key) => write_string(key, document),
+        Value::Nonetype | Value::Bool(_) | Value::Int(_) | Value::Float(_) => {
+            let mut scalar = String::new();
+            to_json(key, &mut scalar, &Layout::default(), &mut vec![])?;
+            write_string(&scalar, document);
+        },
+        _ => return Err(VmError::TypeError(format!("keys must be str, int, float, bool or None, not {}", key.type_name()))),
+    }
+    Ok(())
+}
+
+/// Quotes `val` escaping every non ASCII character, like `ensure_ascii=True`.
+fn write_string(val: &str, document: &mut String) {
+    document.push('"');
+    for character in val.chars() {
+        match character {
+            '"' => document.push_str("\\\""),
+            '\\' => document.push_str("\\\\"),
+            '\n' => document.push_str("\\n"),
+            '\r' => document.push_str("\\r"),
+            '\t' => document.push_str("\\t"),
+            '\u{8}' => document.push_str("\\b"),
+            '\u{c}' => document.push_str("\\f"),
+            ' '..='~' => document.push(character),
+            _ => {
+                let mut units = [0; 2];
+                for unit in character.encode_utf16(&mut units) {
+                    document.push_str(&format!("\\u{:04x}", unit));
+                }
+            },
+        }
+    }
+    document.push('"');
+}
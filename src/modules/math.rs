@@ -0,0 +1,173 @@
+//! The `math` module. Like CPython, a result that is NaN for a non NaN argument raises
+//! `ValueError("math domain error")` and an infinite result for finite arguments raises
+//! `OverflowError("math range error")` where overflowing is possible.
+
+use std::f64::consts;
+
+use num_bigint::BigInt;
+
+use crate::{bigint, Module, NativeFn, Value, VmError};
+use super::{expect_args, float_arg, int_arg, make_module};
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("sin", |_, args| unary("sin", &args, f64::sin, false)),
+        ("cos", |_, args| unary("cos", &args, f64::cos, false)),
+        ("tan", |_, args| unary("tan", &args, f64::tan, false)),
+        ("asin", |_, args| unary("asin", &args, f64::asin, false)),
+        ("acos", |_, args| unary("acos", &args, f64::acos, false)),
+        ("atan", |_, args| unary("atan", &args, f64::atan, false)),
+        ("sinh", |_, args| unary("sinh", &args, f64::sinh, true)),
+        ("cosh", |_, args| unary("cosh", &args, f64::cosh, true)),
+        ("tanh", |_, args| unary("tanh", &args, f64::tanh, false)),
+        ("exp", |_, args| unary("exp", &args, f64::exp, true)),
+        ("log2", |_, args| unary("log2", &args, f64::log2, false)),
+        ("log10", |_, args| unary("log10", &args, f64::log10, false)),
+        ("sqrt", |_, args| unary("sqrt", &args, f64::sqrt, false)),
+        ("fabs", |_, args| unary("fabs", &args, f64::abs, false)),
+        ("atan2", |_, args| binary("atan2", &args, f64::atan2)),
+        ("copysign", |_, args| binary("copysign", &args, f64::copysign)),
+        ("floor", |_, args| to_int("floor", &args, f64::floor)),
+        ("ceil", |_, args| to_int("ceil", &args, f64::ceil)),
+        ("trunc", |_, args| to_int("trunc", &args, f64::trunc)),
+        ("isnan", |_, args| predicate("isnan", &args, f64::is_nan)),
+        ("isinf", |_, args| predicate("isinf", &args, f64::is_infinite)),
+        ("isfinite", |_, args| predicate("isfinite", &args, f64::is_finite)),
+        ("log", log),
+        ("pow", pow),
+        ("hypot", hypot),
+        ("factorial", factorial),
+        ("gcd", gcd),
+        ("lcm", lcm),
+    ];
+    let constants = vec![
+        ("pi", Value::Float(consts::PI)),
+        ("e", Value::Float(consts::E)),
+        ("tau", Value::Float(consts::TAU)),
+        ("inf", Value::Float(f64::INFINITY)),
+        ("nan", Value::Float(f64::NAN)),
+    ];
+
+    make_module("math", functions, constants)
+}
+
+fn checked(arguments: &[f64], result: f64, can_overflow: bool) -> Result<Value, VmError> {
+    if result.is_nan() && !arguments.iter().any(|x| x.is_nan()) {
+        Err(VmError::ValueError(String::from("math domain error")))
+    } else if result.is_infinite() && arguments.iter().all(|x| x.is_finite()) {
+        if can_overflow {
+            Err(VmError::OverflowError(String::from("math range error")))
+        } else {
+            Err(VmError::ValueError(String::from("math domain error")))
+        }
+    } else {
+        Ok(Value::Float(result))
+    }
+}
+
+fn unary(name: &str, args: &[Value], function: fn(f64) -> f64, can_overflow: bool) -> Result<Value, VmError> {
+    expect_args(name, args, 1, 1)?;
+    let x = float_arg(name, &args[0])?;
+    checked(&[x], function(x), can_overflow)
+}
+
+fn binary(name: &str, args: &[Value], function: fn(f64, f64) -> f64) -> Result<Value, VmError> {
+    expect_args(name, args, 2, 2)?;
+    let (x, y) = (float_arg(name, &args[0])?, float_arg(name, &args[1])?);
+    checked(&[x, y], function(x, y), false)
+}
+
+fn predicate(name: &str, args: &[Value], function: fn(f64) -> bool) -> Result<Value, VmError> {
+    expect_args(name, args, 1, 1)?;
+    Ok(Value::Bool(function(float_arg(name, &args[0])?)))
+}
+
+fn to_int(name: &str, args: &[Value], function: fn(f64) -> f64) -> Result<Value, VmError> {
+    expect_args(name, args, 1, 1)?;
+    if let Value::Int(_) | Value::Bool(_) = args[0] {
+        return Ok(Value::Int(int_arg(name, &args[0])?));
+    }
+
+    let result = function(float_arg(name, &args[0])?);
+    if result.is_nan() {
+        Err(VmError::ValueError(String::from("cannot convert float NaN to integer")))
+    } else if result.is_infinite() {
+        Err(VmError::OverflowError(String::from("cannot convert float infinity to integer")))
+    } else if result < i64::MIN as f64 || result >= i64::MAX as f64 {
+        Err(VmError::OverflowError(String::from("int too large to convert")))
+    } else {
+        Ok(Value::Int(result as i64))
+    }
+}
+
+fn log(_vm: &mut crate::Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("log", &args, 1, 2)?;
+    let x = float_arg("log", &args[0])?;
+    match args.get(1) {
+        Some(base) => {
+            let base = float_arg("log", base)?;
+            checked(&[x, base], x.ln() / base.ln(), false)
+        },
+        None => checked(&[x], x.ln(), false),
+    }
+}
+
+fn pow(_vm: &mut crate::Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("pow", &args, 2, 2)?;
+    let (x, y) = (float_arg("pow", &args[0])?, float_arg("pow", &args[1])?);
+    if x == 0.0 && y < 0.0 {
+        return Err(VmError::ValueError(String::from("math domain error")));
+    }
+
+    checked(&[x, y], x.powf(y), true)
+}
+
+fn hypot(_vm: &mut crate::Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    let coordinates = args.iter().map(|arg| float_arg("hypot", arg)).collect::<Result<Vec<f64>, VmError>>()?;
+    let result = coordinates.iter().fold(0.0, |total: f64, x| total.hypot(*x));
+    checked(&coordinates, result, true)
+}
+
+fn factorial(_vm: &mut crate::Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("factorial", &args, 1, 1)?;
+    let n = int_arg("factorial", &args[0])?;
+    if n < 0 {
+        return Err(VmError::ValueError(String::from("factorial() not defined for negative values")));
+    }
+
+    Ok(bigint::narrow((1..=n).map(BigInt::from).product()))
+}
+
+fn gcd_of(first: i64, second: i64) -> i64 {
+    let (mut a, mut b) = (first.abs(), second.abs());
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+    a
+}
+
+fn gcd(_vm: &mut crate::Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    let mut result = 0;
+    for arg in &args {
+        result = gcd_of(result, int_arg("gcd", arg)?);
+    }
+
+    Ok(Value::Int(result))
+}
+
+fn lcm(_vm: &mut crate::Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    let mut result: i64 = 1;
+    for arg in &args {
+        let n = int_arg("lcm", arg)?;
+        if n == 0 {
+            return Ok(Value::Int(0));
+        }
+        result = (result / gcd_of(result, n))
+            .checked_mul(n.abs())
+            .ok_or_else(|| VmError::OverflowError(String::from("lcm() result does not fit in an int")))?;
+    }
+
+    Ok(Value::Int(result))
+}
@@ -0,0 +1,167 @@
+//! Built-in modules that can be imported by Python code.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::{bigint, Keywords, Module, NativeFn, Value, VmError};
+
+mod copy;
+pub(crate) mod datetime;
+pub(crate) mod r#enum;
+mod future;
+pub(crate) mod hashlib;
+pub(crate) mod io;
+mod json;
+mod math;
+mod os;
+mod pickle;
+pub(crate) mod process;
+mod random;
+pub(crate) mod re;
+mod r#struct;
+pub(crate) mod threading;
+pub(crate) mod time;
+pub(crate) mod weakref;
+
+/// Every importable module other than `builtins`.
+pub(crate) fn builtin_modules() -> Vec<Rc<Module>> {
+    let mut modules = vec![
+        Rc::new(copy::module()),
+        Rc::new(datetime::module()),
+        Rc::new(r#enum::module()),
+        Rc::new(future::module()),
+        Rc::new(hashlib::module()),
+        Rc::new(io::module()),
+        Rc::new(json::module()),
+        Rc::new(math::module()),
+        Rc::new(pickle::module()),
+        Rc::new(process::module()),
+        Rc::new(random::module()),
+        Rc::new(re::module()),
+        Rc::new(r#struct::module()),
+        Rc::new(threading::module()),
+        Rc::new(time::module()),
+        Rc::new(weakref::module()),
+    ];
+    modules.extend(os::modules());
+    modules
+}
+
+/// A module with `functions`, which take no keyword arguments, and `constants`, which also
+/// hold the functions made with `keyword_function`.
+fn make_module(name: &str, functions: Vec<(&str, NativeFn)>, constants: Vec<(&str, Value)>) -> Module {
+    let mut attributes = HashMap::new();
+    for (function_name, function) in functions {
+        let function_name = Rc::new(String::from(function_name));
+        attributes.insert(Rc::clone(&function_name), Value::NativeFunction(function_name, function, &[]));
+    }
+    for (constant_name, value) in constants {
+        attributes.insert(Rc::new(String::from(constant_name)), value);
+    }
+
+    Module::new(name, attributes)
+}
+
+/// `function` looked up on `receiver`, which `call_function` passes as the first argument.
+pub(crate) fn bound_method(receiver: Value, name: &str, function: NativeFn) -> Value {
+    let function = Value::NativeFunction(Rc::new(String::from(name)), function, &[]);
+    Value::BoundMethod(Box::new(receiver), Box::new(function))
+}
+
+/// The native function `function`, which takes the parameters in `keywords` by keyword too.
+fn keyword_function(name: &str, function: NativeFn, keywords: Keywords) -> Value {
+    Value::NativeFunction(Rc::new(String::from(name)), function, keywords)
+}
+
+/// The arguments of a call to the native function `function` with `kwargs` moved into the
+/// positions of their parameters in `keywords`. Parameters before them that weren't given are
+/// `None`, which the functions that take keywords treat as not given.
+pub(crate) fn keywords_to_positional(
+    function: &str,
+    keywords: Keywords,
+    mut args: Vec<Value>,
+    kwargs: Vec<(Rc<String>, Value)>,
+) -> Result<Vec<Value>, VmError> {
+    if kwargs.is_empty() {
+        return Ok(args);
+    }
+    if keywords.is_empty() {
+        return Err(VmError::TypeError(format!("{}() takes no keyword arguments", function)));
+    }
+
+    let mut given = vec![true; args.len()];
+    for (name, value) in kwargs {
+        let position = keywords.iter().position(|parameter| *parameter == name.as_str()).ok_or_else(|| {
+            VmError::TypeError(format!("{}() got an unexpected keyword argument '{}'", function, name))
+        })?;
+        if given.get(position) == Some(&true) {
+            return Err(VmError::TypeError(format!("{}() got multiple values for argument '{}'", function, name)));
+        }
+        if position >= args.len() {
+            args.resize(position + 1, Value::Nonetype);
+            given.resize(position + 1, false);
+        }
+        args[position] = value;
+        given[position] = true;
+    }
+    Ok(args)
+}
+
+pub(crate) fn expect_args(function: &str, args: &[Value], min: usize, max: usize) -> Result<(), VmError> {
+    if args.len() < min || args.len() > max {
+        let expected = if min == max { format!("exactly {}", min) } else { format!("from {} to {}", min, max) };
+        let plural = if max == 1 { "" } else { "s" };
+        return Err(VmError::TypeError(format!("{}() takes {} argument{} ({} given)", function, expected, plural, args.len())));
+    }
+
+    Ok(())
+}
+
+fn float_arg(function: &str, value: &Value) -> Result<f64, VmError> {
+    match value {
+        Value::Int(val) => Ok(*val as f64),
+        Value::BigInt(val) => bigint::to_f64(val),
+        Value::Bool(val) => Ok(f64::from(*val as i32)),
+        Value::Float(val) => Ok(*val),
+        _ => Err(VmError::TypeError(format!("{}() argument must be a real number", function))),
+    }
+}
+
+fn int_arg(function: &str, value: &Value) -> Result<i64, VmError> {
+    match value {
+        Value::Int(val) => Ok(*val),
+        Value::Bool(val) => Ok(*val as i64),
+        Value::BigInt(_) => Err(VmError::OverflowError(String::from("Python int too large to convert to C long"))),
+        _ => Err(VmError::TypeError(format!("{}() argument must be an integer", function))),
+    }
+}
+
+fn str_arg<'a>(function: &str, value: &'a Value) -> Result<&'a str, VmError> {
+    match value {
+        Value::Str(val) => Ok(val),
+        _ => Err(VmError::TypeError(format!("{}() argument must be a str", function))),
+    }
+}
+
+/// Converts an I/O error into the `OSError` subclass CPython would raise for it.
+fn os_error(error: std::io::Error, path: Option<&str>) -> VmError {
+    let name = match error.kind() {
+        std::io::ErrorKind::NotFound => "FileNotFoundError",
+        std::io::ErrorKind::AlreadyExists => "FileExistsError",
+        std::io::ErrorKind::PermissionDenied => "PermissionError",
+        _ => "OSError",
+    };
+    let mut message = match error.raw_os_error() {
+        Some(code) => {
+            let description = std::io::Error::from_raw_os_error(code).to_string();
+            let suffix = format!(" (os error {})", code);
+            format!("[Errno {}] {}", code, description.trim_end_matches(&suffix))
+        },
+        None => error.to_string(),
+    };
+    if let Some(path) = path {
+        message += &format!(": '{}'", path);
+    }
+
+    VmError::OSError(name, message)
+}
@@ -0,0 +1,121 @@
+//! The `os` module and its `os.path` sub-module.
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::dict::Dict;
+use crate::{Module, NativeFn, Value, Vm, VmError};
+use super::{expect_args, int_arg, keyword_function, make_module, os_error, str_arg};
+
+/// `os` and `os.path`, which is also reachable as the `path` attribute of `os`.
+pub(super) fn modules() -> Vec<Rc<Module>> {
+    let path_functions: Vec<(&str, NativeFn)> = vec![
+        ("join", join),
+        ("exists", |_, args| path_predicate("exists", &args, Path::exists)),
+        ("isfile", |_, args| path_predicate("isfile", &args, Path::is_file)),
+        ("isdir", |_, args| path_predicate("isdir", &args, Path::is_dir)),
+    ];
+    let path = Rc::new(make_module("os.path", path_functions, vec![]));
+
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("getcwd", getcwd),
+        ("chdir", chdir),
+        ("listdir", listdir),
+        ("getenv", getenv),
+    ];
+    let os = make_module("os", functions, vec![
+        ("path", Value::Module(Rc::clone(&path))),
+        ("environ", environ()),
+        ("makedirs", keyword_function("makedirs", makedirs, &["name", "mode", "exist_ok"])),
+    ]);
+
+    vec![Rc::new(os), path]
+}
+
+fn getcwd(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("getcwd", &args, 0, 0)?;
+    let cwd = env::current_dir().map_err(|error| os_error(error, None))?;
+    Ok(Value::Str(cwd.to_string_lossy().into_owned()))
+}
+
+fn chdir(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("chdir", &args, 1, 1)?;
+    let path = str_arg("chdir", &args[0])?;
+    env::set_current_dir(path).map_err(|error| os_error(error, Some(path)))?;
+    Ok(Value::Nonetype)
+}
+
+/// `listdir(path='.')`, the names in the directory in the order the file system gives them.
+fn listdir(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("listdir", &args, 0, 1)?;
+    let path = match args.first() {
+        Some(path) => str_arg("listdir", path)?,
+        None => ".",
+    };
+    let mut names = vec![];
+    for entry in fs::read_dir(path).map_err(|error| os_error(error, Some(path)))? {
+        let entry = entry.map_err(|error| os_error(error, Some(path)))?;
+        names.push(Value::Str(entry.file_name().to_string_lossy().into_owned()));
+    }
+    Ok(Value::List(Rc::new(RefCell::new(names))))
+}
+
+/// `environ`, the environment when the VM started. Unlike CPython's, changing it doesn't
+/// change the environment `getenv` reads.
+fn environ() -> Value {
+    let mut environ = Dict::new();
+    for (key, value) in env::vars() {
+        environ.insert(Value::Str(key), Value::Str(value)).unwrap();
+    }
+    Value::Dict(Rc::new(RefCell::new(environ)))
+}
+
+/// `getenv(key, default=None)`
+fn getenv(_vm: &mut Vm, mut args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("getenv", &args, 1, 2)?;
+    let default = if args.len() == 2 { args.pop().unwrap() } else { Value::Nonetype };
+    match env::var(str_arg("getenv", &args[0])?) {
+        Ok(value) => Ok(Value::Str(value)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// `makedirs(name, mode=0o777, exist_ok=False)`, the mode is ignored.
+fn makedirs(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("makedirs", &args, 1, 3)?;
+    let path = str_arg("makedirs", &args[0])?;
+    if let Some(mode) = args.get(1).filter(|mode| !matches!(mode, Value::Nonetype)) {
+        int_arg("makedirs", mode)?;
+    }
+    let exist_ok = match args.get(2) {
+        Some(Value::Bool(exist_ok)) => *exist_ok,
+        Some(_) => return Err(VmError::TypeError(String::from("makedirs() exist_ok must be a bool"))),
+        None => false,
+    };
+
+    if Path::new(path).is_dir() && !exist_ok {
+        return Err(VmError::OSError("FileExistsError", format!("[Errno 17] File exists: '{}'", path)));
+    }
+    fs::create_dir_all(path).map_err(|error| os_error(error, Some(path)))?;
+    Ok(Value::Nonetype)
+}
+
+fn join(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    if args.is_empty() {
+        return Err(VmError::TypeError(String::from("join() missing 1 required positional argument: 'a'")));
+    }
+
+    let mut path = PathBuf::new();
+    for part in &args {
+        path.push(str_arg("join", part)?);
+    }
+    Ok(Value::Str(path.to_string_lossy().into_owned()))
+}
+
+fn path_predicate(name: &str, args: &[Value], predicate: fn(&Path) -> bool) -> Result<Value, VmError> {
+    expect_args(name, args, 1, 1)?;
+    Ok(Value::Bool(predicate(Path::new(str_arg(name, &args[0])?))))
+}
@@ -0,0 +1,413 @@
+//! Pickling in CPython's binary format. `dumps` writes protocol 3, `loads` reads what CPython
+//! writes for the supported types with protocols 2 to 5. Lists and dicts are memoized, so one
+//! that is pickled twice, or inside itself, is loaded as a single shared object.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use crate::dict::Dict;
+use crate::exceptions::exception_type;
+use crate::{Module, NativeFn, Value, VmError};
+use super::{expect_args, int_arg, make_module};
+
+const PROTOCOL: u8 = 3;
+
+const PROTO: u8 = 0x80;
+const FRAME: u8 = 0x95;
+const STOP: u8 = b'.';
+const MARK: u8 = b'(';
+const NONE: u8 = b'N';
+const NEWTRUE: u8 = 0x88;
+const NEWFALSE: u8 = 0x89;
+const BININT: u8 = b'J';
+const BININT1: u8 = b'K';
+const BININT2: u8 = b'M';
+const LONG1: u8 = 0x8a;
+const BINFLOAT: u8 = b'G';
+const BINUNICODE: u8 = b'X';
+const SHORT_BINUNICODE: u8 = 0x8c;
+const BINUNICODE8: u8 = 0x8d;
+const BINBYTES: u8 = b'B';
+const SHORT_BINBYTES: u8 = b'C';
+const BINBYTES8: u8 = 0x8e;
+const EMPTY_TUPLE: u8 = b')';
+const TUPLE: u8 = b't';
+const TUPLE1: u8 = 0x85;
+const TUPLE2: u8 = 0x86;
+const TUPLE3: u8 = 0x87;
+const EMPTY_LIST: u8 = b']';
+const APPEND: u8 = b'a';
+const APPENDS: u8 = b'e';
+const LIST: u8 = b'l';
+const EMPTY_DICT: u8 = b'}';
+const SETITEM: u8 = b's';
+const SETITEMS: u8 = b'u';
+const DICT: u8 = b'd';
+const BINPUT: u8 = b'q';
+const LONG_BINPUT: u8 = b'r';
+const MEMOIZE: u8 = 0x94;
+const BINGET: u8 = b'h';
+const LONG_BINGET: u8 = b'j';
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("dumps", |_, args| {
+            expect_args("dumps", &args, 1, 2)?;
+            match args.get(1) {
+                None | Some(Value::Nonetype) => {},
+                Some(protocol) if int_arg("dumps", protocol)? >= i64::from(PROTOCOL) => {},
+                Some(_) => return Err(VmError::Unimplemented(format!("pickle protocols older than {}", PROTOCOL))),
+            }
+
+            let mut pickler = Pickler { data: vec![PROTO, PROTOCOL], memo: HashMap::new() };
+            pickler.dump(&args[0])?;
+            pickler.data.push(STOP);
+            Ok(Value::Bytes(pickler.data))
+        }),
+        ("loads", |_, args| {
+            expect_args("loads", &args, 1, 1)?;
+            match &args[0] {
+                Value::Bytes(data) => Unpickler { data, position: 0 }.load(),
+                _ => Err(VmError::TypeError(String::from("a bytes-like object is required"))),
+            }
+        }),
+    ];
+
+    make_module("pickle", functions, vec![
+        ("HIGHEST_PROTOCOL", Value::Int(5)),
+        ("DEFAULT_PROTOCOL", Value::Int(i64::from(PROTOCOL))),
+        ("PickleError", Value::ExceptionType(exception_type("_pickle.PickleError"))),
+        ("PicklingError", Value::ExceptionType(exception_type("_pickle.PicklingError"))),
+        ("UnpicklingError", Value::ExceptionType(exception_type("_pickle.UnpicklingError"))),
+    ])
+}
+
+struct Pickler {
+    data: Vec<u8>,
+    /// The memo index of each list and dict pickled so far, by its address.
+    memo: HashMap<usize, usize>,
+}
+
+impl Pickler {
+    fn dump(&mut self, value: &Value) -> Result<(), VmError> {
+        let data = &mut self.data;
+        match value {
+            Value::Nonetype => data.push(NONE),
+            Value::Bool(true) => data.push(NEWTRUE),
+            Value::Bool(false) => data.push(NEWFALSE),
+            Value::Int(number) => match u16::try_from(*number) {
+                Ok(number) if number <= 0xff => data.extend(&[BININT1, number as u8]),
+                Ok(number) => {
+                    data.push(BININT2);
+                    data.extend(&number.to_le_bytes());
+                },
+                Err(_) => match i32::try_from(*number) {
+                    Ok(number) => {
+                        data.push(BININT);
+                        data.extend(&number.to_le_bytes());
+                    },
+                    Err(_) => {
+                        // The shortest little-endian two's complement that keeps the sign.
+                        let bytes = number.to_le_bytes();
+                        let mut length = bytes.len();
+                        while length > 1 && i64::from(bytes[length - 1] as i8) == i64::from(bytes[length - 2] as i8 >> 7) {
+                            length -= 1;
+                        }
+                        data.extend(&[LONG1, length as u8]);
+                        data.extend(&bytes[..length]);
+                    },
+                },
+            },
+            Value::Float(number) => {
+                data.push(BINFLOAT);
+                data.extend(&number.to_be_bytes());
+            },
+            Value::Str(string) => {
+                data.push(BINUNICODE);
+                data.extend(&(string.len() as u32).to_le_bytes());
+                data.extend(string.as_bytes());
+            },
+            Value::Bytes(bytes) => {
+                if bytes.len() <= 0xff {
+                    data.extend(&[SHORT_BINBYTES, bytes.len() as u8]);
+                } else {
+                    data.push(BINBYTES);
+                    data.extend(&(bytes.len() as u32).to_le_bytes());
+                }
+                data.extend(bytes);
+            },
+            Value::Tuple(values) => {
+                if values.is_empty() {
+                    data.push(EMPTY_TUPLE);
+                    return Ok(());
+                }
+                if values.len() > 3 {
+                    data.push(MARK);
+                }
+                for value in values {
+                    self.dump(value)?;
+                }
+                self.data.push(match values.len() {
+                    1 => TUPLE1,
+                    2 => TUPLE2,
+                    3 => TUPLE3,
+                    _ => TUPLE,
+                });
+            },
+            Value::List(list) => {
+                if self.memoize(EMPTY_LIST, Rc::as_ptr(list) as usize) {
+                    let items = list.borrow().clone();
+                    self.batch(items.len(), APPEND, APPENDS, |pickler| items.iter().try_for_each(|item| pickler.dump(item)))?;
+                }
+            },
+            Value::Dict(dict) => {
+                if self.memoize(EMPTY_DICT, Rc::as_ptr(dict) as usize) {
+                    let items = dict.borrow().items().map(|(key, value)| (key.clone(), value.clone())).collect::<Vec<_>>();
+                    self.batch(items.len(), SETITEM, SETITEMS, |pickler| {
+                        items.iter().try_for_each(|(key, value)| pickler.dump(key).and_then(|_| pickler.dump(value)))
+                    })?;
+                }
+            },
+            _ => return Err(VmError::PicklingError(format!("Can't pickle {}: it isn't supported yet", value.python_repr()))),
+        }
+        Ok(())
+    }
+
+    /// Writes `empty`, which pushes a new empty list or dict, and memoizes it. When the object at
+    /// `address` was pickled before, it is taken from the memo instead and `false` is returned,
+    /// as its items are already written.
+    fn memoize(&mut self, empty: u8, address: usize) -> bool {
+        if let Some(index) = self.memo.get(&address) {
+            match u8::try_from(*index) {
+                Ok(index) => self.data.extend(&[BINGET, index]),
+                Err(_) => {
+                    self.data.push(LONG_BINGET);
+                    self.data.extend(&(*index as u32).to_le_bytes());
+                },
+            }
+            return false;
+        }
+
+        let index = self.memo.len();
+        self.memo.insert(address, index);
+        self.data.push(empty);
+        match u8::try_from(index) {
+            Ok(index) => self.data.extend(&[BINPUT, index]),
+            Err(_) => {
+                self.data.push(LONG_BINPUT);
+                self.data.extend(&(index as u32).to_le_bytes());
+            },
+        }
+        true
+    }
+
+    /// Writes the `count` items `dump_items` writes, after the list or dict they are added to, with
+    /// `single` for one item and between a MARK and `many` for more, like CPython.
+    fn batch(&mut self, count: usize, single: u8, many: u8, dump_items: impl FnOnce(&mut Pickler) -> Result<(), VmError>) -> Result<(), VmError> {
+        match count {
+            0 => dump_items(self),
+            1 => {
+                dump_items(self)?;
+                self.data.push(single);
+                Ok(())
+            },
+            _ => {
+                self.data.push(MARK);
+                dump_items(self)?;
+                self.data.push(many);
+                Ok(())
+            },
+        }
+    }
+}
+
+struct Unpickler<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Unpickler<'_> {
+    fn read(&mut self, count: usize) -> Result<&[u8], VmError> {
+        let end = self.position.checked_add(count).filter(|end| *end <= self.data.len());
+        match end {
+            Some(end) => {
+                let bytes = &self.data[self.position..end];
+                self.position = end;
+                Ok(bytes)
+            },
+            None => Err(VmError::UnpicklingError(String::from("pickle data was truncated"))),
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8, VmError> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<usize, VmError> {
+        Ok(u32::from_le_bytes(<[u8; 4]>::try_from(self.read(4)?).unwrap()) as usize)
+    }
+
+    fn read_u64(&mut self) -> Result<usize, VmError> {
+        let length = u64::from_le_bytes(<[u8; 8]>::try_from(self.read(8)?).unwrap());
+        usize::try_from(length).map_err(|_| VmError::UnpicklingError(String::from("pickle data was truncated")))
+    }
+
+    fn read_string(&mut self, length: usize) -> Result<Value, VmError> {
+        String::from_utf8(self.read(length)?.to_vec())
+            .map(Value::Str)
+            .map_err(|_| VmError::UnpicklingError(String::from("invalid UTF-8 in a pickled string")))
+    }
+
+    fn load(&mut self) -> Result<Value, VmError> {
+        let mut stack = vec![];
+        // Positions in `stack` where a MARK was pushed.
+        let mut marks = vec![];
+        let mut memo = vec![];
+        let pop = |stack: &mut Vec<Value>| stack.pop().ok_or_else(|| VmError::UnpicklingError(String::from("unpickling stack underflow")));
+
+        loop {
+            let opcode = self.read_byte()?;
+            match opcode {
+                PROTO => {
+                    let protocol = self.read_byte()?;
+                    if protocol > 5 {
+                        return Err(VmError::ValueError(format!("unsupported pickle protocol: {}", protocol)));
+                    }
+                },
+                FRAME => {
+                    self.read(8)?;
+                },
+                STOP => return pop(&mut stack),
+                MARK => marks.push(stack.len()),
+                NONE => stack.push(Value::Nonetype),
+                NEWTRUE => stack.push(Value::Bool(true)),
+                NEWFALSE => stack.push(Value::Bool(false)),
+                BININT => stack.push(Value::Int(i64::from(i32::from_le_bytes(<[u8; 4]>::try_from(self.read(4)?).unwrap())))),
+                BININT1 => stack.push(Value::Int(i64::from(self.read_byte()?))),
+                BININT2 => stack.push(Value::Int(i64::from(u16::from_le_bytes(<[u8; 2]>::try_from(self.read(2)?).unwrap())))),
+                LONG1 => {
+                    let length = usize::from(self.read_byte()?);
+                    let bytes = self.read(length)?;
+                    if length > 8 {
+                        return Err(VmError::OverflowError(String::from("Python int too large to convert to C int")));
+                    }
+                    // Little-endian two's complement, sign extended to 64 bits.
+                    let fill = if bytes.last().is_some_and(|byte| byte & 0x80 != 0) { 0xff } else { 0 };
+                    let mut extended = [fill; 8];
+                    extended[..length].copy_from_slice(bytes);
+                    stack.push(Value::Int(i64::from_le_bytes(extended)));
+                },
+                BINFLOAT => {
+                    let number = f64::from_be_bytes(<[u8; 8]>::try_from(self.read(8)?).unwrap());
+                    stack.push(Value::Float(number));
+                },
+                BINUNICODE => {
+                    let length = self.read_u32()?;
+                    stack.push(self.read_string(length)?);
+                },
+                SHORT_BINUNICODE => {
+                    let length = usize::from(self.read_byte()?);
+                    stack.push(self.read_string(length)?);
+                },
+                BINUNICODE8 => {
+                    let length = self.read_u64()?;
+                    stack.push(self.read_string(length)?);
+                },
+                BINBYTES => {
+                    let length = self.read_u32()?;
+                    stack.push(Value::Bytes(self.read(length)?.to_vec()));
+                },
+                SHORT_BINBYTES => {
+                    let length = usize::from(self.read_byte()?);
+                    stack.push(Value::Bytes(self.read(length)?.to_vec()));
+                },
+                BINBYTES8 => {
+                    let length = self.read_u64()?;
+                    stack.push(Value::Bytes(self.read(length)?.to_vec()));
+                },
+                EMPTY_TUPLE => stack.push(Value::Tuple(vec![])),
+                TUPLE1 | TUPLE2 | TUPLE3 => {
+                    let count = usize::from(opcode - TUPLE1 + 1);
+                    if stack.len() < count {
+                        return Err(VmError::UnpicklingError(String::from("unpickling stack underflow")));
+                    }
+                    let values = stack.split_off(stack.len() - count);
+                    stack.push(Value::Tuple(values));
+                },
+                TUPLE => {
+                    let values = stack.split_off(pop_mark(&mut marks)?);
+                    stack.push(Value::Tuple(values));
+                },
+                EMPTY_LIST => stack.push(Value::List(Rc::new(RefCell::new(vec![])))),
+                LIST => {
+                    let values = stack.split_off(pop_mark(&mut marks)?);
+                    stack.push(Value::List(Rc::new(RefCell::new(values))));
+                },
+                APPEND | APPENDS => {
+                    let values = match opcode {
+                        APPEND => vec![pop(&mut stack)?],
+                        _ => stack.split_off(pop_mark(&mut marks)?),
+                    };
+                    match stack.last() {
+                        Some(Value::List(list)) => list.borrow_mut().extend(values),
+                        _ => return Err(VmError::UnpicklingError(String::from("APPEND needs a list below its items"))),
+                    }
+                },
+                EMPTY_DICT => stack.push(Value::Dict(Rc::new(RefCell::new(Dict::new())))),
+                DICT => {
+                    let items = stack.split_off(pop_mark(&mut marks)?);
+                    let mut dict = Dict::new();
+                    insert_items(&mut dict, items)?;
+                    stack.push(Value::Dict(Rc::new(RefCell::new(dict))));
+                },
+                SETITEM | SETITEMS => {
+                    let items = match opcode {
+                        SETITEM => {
+                            let value = pop(&mut stack)?;
+                            vec![pop(&mut stack)?, value]
+                        },
+                        _ => stack.split_off(pop_mark(&mut marks)?),
+                    };
+                    match stack.last() {
+                        Some(Value::Dict(dict)) => insert_items(&mut dict.borrow_mut(), items)?,
+                        _ => return Err(VmError::UnpicklingError(String::from("SETITEM needs a dict below its items"))),
+                    }
+                },
+                MEMOIZE => memo.push(stack.last().cloned().unwrap_or(Value::Nonetype)),
+                BINPUT | LONG_BINPUT => {
+                    let index = if opcode == BINPUT { usize::from(self.read_byte()?) } else { self.read_u32()? };
+                    if memo.len() <= index {
+                        memo.resize(index + 1, Value::Nonetype);
+                    }
+                    memo[index] = stack.last().cloned().unwrap_or(Value::Nonetype);
+                },
+                BINGET | LONG_BINGET => {
+                    let index = if opcode == BINGET { usize::from(self.read_byte()?) } else { self.read_u32()? };
+                    let value = memo.get(index).cloned()
+                        .ok_or_else(|| VmError::UnpicklingError(format!("Memo value not found at index {}", index)))?;
+                    stack.push(value);
+                },
+                _ => return Err(VmError::UnpicklingError(format!("unsupported pickle opcode {:?}", char::from(opcode)))),
+            }
+        }
+    }
+}
+
+/// The stack position of the innermost MARK, where the items of the opcode that pops it start.
+fn pop_mark(marks: &mut Vec<usize>) -> Result<usize, VmError> {
+    marks.pop().ok_or_else(|| VmError::UnpicklingError(String::from("could not find MARK")))
+}
+
+/// Inserts the keys and values that alternate in `items`.
+fn insert_items(dict: &mut Dict, items: Vec<Value>) -> Result<(), VmError> {
+    if !items.len().is_multiple_of(2) {
+        return Err(VmError::UnpicklingError(String::from("odd number of items for SETITEMS")));
+    }
+    let mut items = items.into_iter();
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        dict.insert(key, value)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,247 @@
+//! `process`, which runs a function in a child VM for CPU-bound work. The function, its
+//! arguments and the globals it can use are sent to the child as JSON on stdin, and the value
+//! it returns comes back as JSON through a temporary file. Nothing else is shared: changes the
+//! function makes to globals stay in the child, and only values `Serialize` supports cross,
+//! so modules are re-imported by name and globals holding instances or files are left out.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{self, Child, Command, Stdio};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::exceptions::Exception;
+use crate::{Module, NativeFn, Value, Vm, VmConfig, VmError};
+use super::threading::{SharedGlobals, SharedTable};
+use super::{bound_method, expect_args, keyword_function, make_module, os_error};
+
+/// The argument `main` recognizes as a child started by `Process.start()`, followed by the
+/// path the result is written to.
+pub(crate) const CHILD_FLAG: &str = "--process-child";
+
+thread_local! {
+    static NEXT_PROCESS: Cell<usize> = const { Cell::new(1) };
+}
+
+pub(super) fn module() -> Module {
+    make_module("process", vec![], vec![
+        ("Process", keyword_function("Process", new_process, &["target", "args"])),
+    ])
+}
+
+/// What a child VM runs. `Process` writes it to the child's stdin, and `Thread` sends it to
+/// the thread it starts under `--threads`.
+#[derive(Serialize, Deserialize)]
+pub(super) struct Job {
+    config: VmConfig,
+    target: Value,
+    args: Vec<Value>,
+    kwargs: Vec<(Rc<String>, Value)>,
+    globals: HashMap<Rc<String>, Value>,
+    /// Globals bound to modules, by the name of the module.
+    modules: HashMap<Rc<String>, Rc<String>>,
+}
+
+pub(crate) struct Process {
+    name: String,
+    target: Value,
+    args: Vec<Value>,
+    result_path: PathBuf,
+    child: RefCell<Option<Child>>,
+    exitcode: Cell<Option<i32>>,
+    result: RefCell<Value>,
+}
+
+impl fmt::Debug for Process {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match (self.exitcode.get(), self.child.borrow().is_some()) {
+            (Some(code), _) => format!("stopped exitcode={}", code),
+            (None, true) => String::from("started"),
+            (None, false) => String::from("initial"),
+        };
+        write!(f, "<Process name='{}' {}>", self.name, status)
+    }
+}
+
+/// `Process(target, args=())`.
+fn new_process(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("Process", &args, 1, 2)?;
+    let mut args = args.into_iter();
+    let target = match args.next().unwrap() {
+        target @ Value::Frame(_) => target,
+        other => return Err(VmError::TypeError(format!("Process() target must be a function, not {}", other.python_repr()))),
+    };
+    let args = match args.next() {
+        None | Some(Value::Nonetype) => vec![],
+        Some(Value::Tuple(args)) => args,
+        Some(other) => return Err(VmError::TypeError(format!("Process() args must be a tuple, not {}", other.python_repr()))),
+    };
+    let number = NEXT_PROCESS.with(|next| next.replace(next.get() + 1));
+    let result_path = env::temp_dir().join(format!("py_vm-{}-{}.json", process::id(), number));
+
+    Ok(Value::Process(Rc::new(Process {
+        name: format!("Process-{}", number),
+        target,
+        args,
+        result_path,
+        child: RefCell::new(None),
+        exitcode: Cell::new(None),
+        result: RefCell::new(Value::Nonetype),
+    })))
+}
+
+impl Process {
+    pub(crate) fn get_attr(process: &Rc<Process>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "name" => return Some(Value::Str(process.name.clone())),
+            "pid" => return Some(process.child.borrow().as_ref().map_or(Value::Nonetype, |child| Value::Int(child.id() as i64))),
+            "exitcode" => return Some(process.exitcode.get().map_or(Value::Nonetype, |code| Value::Int(i64::from(code)))),
+            "result" => return Some(process.result.borrow().clone()),
+            "start" => |vm, args| {
+                expect_args("start", &args[1..], 0, 0)?;
+                receiver(&args).start(vm)?;
+                Ok(Value::Nonetype)
+            },
+            "join" => |_, args| {
+                expect_args("join", &args[1..], 0, 0)?;
+                receiver(&args).join()?;
+                Ok(Value::Nonetype)
+            },
+            "is_alive" => |_, args| {
+                expect_args("is_alive", &args[1..], 0, 0)?;
+                let process = receiver(&args);
+                Ok(Value::Bool(process.child.borrow().is_some() && process.exitcode.get().is_none()))
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Process(Rc::clone(process)), name, method))
+    }
+
+    fn start(&self, vm: &mut Vm) -> Result<(), VmError> {
+        if self.child.borrow().is_some() {
+            return Err(assertion_error("cannot start a process twice"));
+        }
+
+        let job = Job::new(vm, self.target.clone(), self.args.clone(), vec![]);
+        let job = serde_json::to_string(&job).map_err(|error| {
+            VmError::PicklingError(format!("Can't send the arguments of {} to it: {}", self.name, error))
+        })?;
+
+        let executable = env::current_exe().map_err(|error| os_error(error, None))?;
+        let mut child = Command::new(executable)
+            .arg(CHILD_FLAG)
+            .arg(&self.result_path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|error| os_error(error, None))?;
+        child.stdin.take().unwrap().write_all(job.as_bytes()).map_err(|error| os_error(error, None))?;
+        self.child.replace(Some(child));
+        Ok(())
+    }
+
+    /// Waits for the child, then reads the value the target returned if it didn't raise.
+    fn join(&self) -> Result<(), VmError> {
+        if self.exitcode.get().is_some() {
+            return Ok(());
+        }
+        let status = match self.child.borrow_mut().as_mut() {
+            Some(child) => child.wait().map_err(|error| os_error(error, None))?,
+            None => return Err(assertion_error("can only join a started process")),
+        };
+        self.exitcode.set(Some(status.code().unwrap_or(1)));
+
+        if let Ok(result) = fs::read_to_string(&self.result_path) {
+            fs::remove_file(&self.result_path).map_err(|error| os_error(error, None))?;
+            let result = serde_json::from_str(&result)
+                .map_err(|error| VmError::UnpicklingError(format!("Invalid result from {}: {}", self.name, error)))?;
+            self.result.replace(result);
+        }
+        Ok(())
+    }
+}
+
+impl Job {
+    /// A job calling `target` with the globals of `vm` that can be serialized.
+    pub(super) fn new(vm: &Vm, target: Value, args: Vec<Value>, kwargs: Vec<(Rc<String>, Value)>) -> Job {
+        let mut globals = HashMap::new();
+        let mut modules = HashMap::new();
+        for (name, value) in vm.globals.borrow().iter() {
+            match value {
+                Value::Module(module) => { modules.insert(Rc::clone(name), Rc::clone(&module.name)); },
+                value if serde_json::to_value(value).is_ok() => { globals.insert(Rc::clone(name), value.clone()); },
+                _ => {},
+            }
+        }
+        Job { config: vm.config, target, args, kwargs, globals, modules }
+    }
+
+    /// Calls the target in a new VM and gives what it returns, once the VM is shut down. The VM
+    /// of a thread shares the globals in `shared` with the other threads.
+    pub(super) fn run(self, shared: Option<Arc<Mutex<SharedTable>>>) -> Result<Value, VmError> {
+        let mut vm = Vm::new(self.config);
+        vm.shared_globals = shared.map(SharedGlobals::new);
+        {
+            let mut globals = vm.globals.borrow_mut();
+            globals.extend(self.globals);
+            for (name, module) in self.modules {
+                if let Some(module) = vm.modules.get(&module) {
+                    globals.insert(name, module.clone());
+                }
+            }
+        }
+        let result = vm.call_value_kw(self.target, self.args, self.kwargs)?;
+        vm.shutdown()?;
+        Ok(result)
+    }
+}
+
+/// `multiprocessing` reports misuse of a process with failed assertions.
+fn assertion_error(message: &str) -> VmError {
+    VmError::Exception(Exception::new("AssertionError", String::from(message)))
+}
+
+fn receiver(args: &[Value]) -> &Rc<Process> {
+    match &args[0] {
+        Value::Process(process) => process,
+        _ => unreachable!("process methods are only reachable through a bound process"),
+    }
+}
+
+/// The child's side: runs the job on stdin and writes what the target returns to
+/// `result_path`. Returns the exit status, 1 if the target raised.
+pub(crate) fn run_child(result_path: &str) -> i32 {
+    let mut job = String::new();
+    if let Err(error) = std::io::stdin().read_to_string(&mut job) {
+        eprintln!("Can't read the process job: {}", error);
+        return 1;
+    }
+    let job: Job = match serde_json::from_str(&job) {
+        Ok(job) => job,
+        Err(error) => {
+            eprintln!("Invalid process job: {}", error);
+            return 1;
+        },
+    };
+
+    let result = job.run(None).and_then(|result| {
+        let result = serde_json::to_string(&result)
+            .map_err(|error| VmError::PicklingError(format!("Can't send the result back: {}", error)))?;
+        fs::write(result_path, result).map_err(|error| os_error(error, Some(result_path)))
+    });
+
+    match result {
+        Ok(_) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        },
+    }
+}
@@ -0,0 +1,118 @@
+//! Pseudo-random numbers from a generator shared by the whole program, like CPython's
+//! module-level functions. The sequence for a seed differs from CPython's.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::{Module, NativeFn, Value, VmError};
+use super::{expect_args, float_arg, int_arg, make_module};
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+fn with_rng<T>(operation: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| operation(&mut rng.borrow_mut()))
+}
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("seed", |_, args| {
+            expect_args("seed", &args, 0, 1)?;
+            let rng = match args.first() {
+                None | Some(Value::Nonetype) => StdRng::from_entropy(),
+                Some(Value::Int(seed)) => StdRng::seed_from_u64(*seed as u64),
+                Some(Value::Bool(seed)) => StdRng::seed_from_u64(*seed as u64),
+                Some(Value::Float(seed)) => StdRng::seed_from_u64(seed.to_bits()),
+                Some(Value::Str(seed)) => {
+                    StdRng::seed_from_u64(seed.bytes().fold(0, |hash: u64, byte| hash.wrapping_mul(31).wrapping_add(u64::from(byte))))
+                },
+                Some(_) => return Err(VmError::TypeError(String::from(
+                    "The only supported seed types are: None, int, float, str"
+                ))),
+            };
+            with_rng(|current| *current = rng);
+            Ok(Value::Nonetype)
+        }),
+        ("random", |_, args| {
+            expect_args("random", &args, 0, 0)?;
+            Ok(Value::Float(with_rng(|rng| rng.gen::<f64>())))
+        }),
+        ("uniform", |_, args| {
+            expect_args("uniform", &args, 2, 2)?;
+            let (low, high) = (float_arg("uniform", &args[0])?, float_arg("uniform", &args[1])?);
+            Ok(Value::Float(low + (high - low) * with_rng(|rng| rng.gen::<f64>())))
+        }),
+        ("randint", |_, args| {
+            expect_args("randint", &args, 2, 2)?;
+            let (low, high) = (int_arg("randint", &args[0])?, int_arg("randint", &args[1])?);
+            if low > high {
+                return Err(VmError::ValueError(format!("empty range for randrange() ({}, {}, {})", low, high + 1, high + 1 - low)));
+            }
+            Ok(Value::Int(with_rng(|rng| rng.gen_range(low..=high))))
+        }),
+        ("randrange", |_, args| {
+            expect_args("randrange", &args, 1, 3)?;
+            let numbers = args.iter().map(|arg| int_arg("randrange", arg)).collect::<Result<Vec<_>, _>>()?;
+            let (start, stop, step) = match numbers.as_slice() {
+                [stop] => (0, *stop, 1),
+                [start, stop] => (*start, *stop, 1),
+                [start, stop, step] => (*start, *stop, *step),
+                _ => unreachable!(),
+            };
+            if step == 0 {
+                return Err(VmError::ValueError(String::from("zero step for randrange()")));
+            }
+            // The number of values in `range(start, stop, step)`.
+            let count = (stop - start + step - step.signum()) / step;
+            if count <= 0 {
+                return Err(VmError::ValueError(format!("empty range for randrange() ({}, {}, {})", start, stop, stop - start)));
+            }
+            let index = with_rng(|rng| rng.gen_range(0..count));
+            Ok(Value::Int(start + index * step))
+        }),
+        ("choice", |_, args| {
+            expect_args("choice", &args, 1, 1)?;
+            let choices = sequence_items(&args[0])
+                .ok_or_else(|| VmError::TypeError(String::from("choice() argument must be a sequence")))?;
+            if choices.is_empty() {
+                return Err(VmError::IndexError(String::from("Cannot choose from an empty sequence")));
+            }
+            let index = with_rng(|rng| rng.gen_range(0..choices.len()));
+            Ok(choices[index].clone())
+        }),
+        ("shuffle", |_, args| {
+            expect_args("shuffle", &args, 1, 1)?;
+            match &args[0] {
+                Value::List(items) => with_rng(|rng| items.borrow_mut().shuffle(rng)),
+                other => return Err(VmError::TypeError(format!("'{}' object does not support item assignment", other.type_name()))),
+            }
+            Ok(Value::Nonetype)
+        }),
+        ("sample", |_, args| {
+            expect_args("sample", &args, 2, 2)?;
+            let population = sequence_items(&args[0])
+                .ok_or_else(|| VmError::TypeError(String::from("Population must be a sequence.  For dicts or sets, use sorted(d).")))?;
+            let count = int_arg("sample", &args[1])?;
+            if count < 0 || count as usize > population.len() {
+                return Err(VmError::ValueError(String::from("Sample larger than population or is negative")));
+            }
+            let sample = with_rng(|rng| population.choose_multiple(rng, count as usize).cloned().collect());
+            Ok(Value::List(Rc::new(RefCell::new(sample))))
+        }),
+    ];
+
+    make_module("random", functions, vec![])
+}
+
+/// The items of a sequence `choice` and `sample` pick from, sets and dicts aren't sequences.
+fn sequence_items(sequence: &Value) -> Option<Vec<Value>> {
+    match sequence {
+        Value::Tuple(_) | Value::List(_) | Value::Str(_) | Value::Bytes(_) | Value::ByteArray(_) => sequence.sequence_items(),
+        _ => None,
+    }
+}
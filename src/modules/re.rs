@@ -0,0 +1,444 @@
+//! Regular expressions on top of the `regex` crate, so patterns follow its syntax rather than CPython's
+//! (no backreferences or lookaround).
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use regex::{Captures, Regex, RegexBuilder};
+
+use crate::exceptions::exception_type;
+use crate::{Module, NativeFn, Value, Vm, VmError};
+use super::{bound_method, expect_args, int_arg, make_module, str_arg};
+
+const IGNORECASE: i64 = 2;
+const MULTILINE: i64 = 8;
+const DOTALL: i64 = 16;
+const VERBOSE: i64 = 64;
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("compile", |_, args| {
+            expect_args("compile", &args, 1, 2)?;
+            Ok(Value::Regex(Rc::new(compile("compile", &args)?)))
+        }),
+        ("match", |_, args| module_search("match", args, Anchor::Start)),
+        ("search", |_, args| module_search("search", args, Anchor::None)),
+        ("fullmatch", |_, args| module_search("fullmatch", args, Anchor::Both)),
+        ("sub", |_, args| {
+            expect_args("sub", &args, 3, 5)?;
+            let pattern = compile("sub", &[args[0].clone(), args.get(4).cloned().unwrap_or(Value::Int(0))])?;
+            pattern.sub("sub", &args[1..args.len().min(4)])
+        }),
+        ("escape", |_, args| {
+            expect_args("escape", &args, 1, 1)?;
+            Ok(Value::Str(escape(str_arg("escape", &args[0])?)))
+        }),
+        ("findall", |_, args| {
+            expect_args("findall", &args, 2, 3)?;
+            let pattern = compile("findall", &[args[0].clone(), args.get(2).cloned().unwrap_or(Value::Int(0))])?;
+            pattern.findall("findall", &args[1])
+        }),
+        ("split", |_, args| {
+            expect_args("split", &args, 2, 4)?;
+            let pattern = compile("split", &[args[0].clone(), args.get(3).cloned().unwrap_or(Value::Int(0))])?;
+            pattern.split("split", &args[1..args.len().min(3)])
+        }),
+    ];
+    let constants = vec![
+        ("I", Value::Int(IGNORECASE)),
+        ("IGNORECASE", Value::Int(IGNORECASE)),
+        ("M", Value::Int(MULTILINE)),
+        ("MULTILINE", Value::Int(MULTILINE)),
+        ("S", Value::Int(DOTALL)),
+        ("DOTALL", Value::Int(DOTALL)),
+        ("X", Value::Int(VERBOSE)),
+        ("VERBOSE", Value::Int(VERBOSE)),
+        ("error", Value::ExceptionType(exception_type("re.error"))),
+    ];
+
+    make_module("re", functions, constants)
+}
+
+/// `re.escape` escapes every character that isn't alphanumeric or `_`.
+fn escape(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for character in pattern.chars() {
+        if !character.is_alphanumeric() && character != '_' {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+#[derive(Clone, Copy)]
+enum Anchor {
+    None,
+    Start,
+    Both,
+}
+
+/// A compiled pattern, as returned by `re.compile`.
+pub(crate) struct Pattern {
+    pattern: String,
+    flags: i64,
+    regex: Regex,
+    /// The same pattern anchored at the start, and at both ends, for `match` and `fullmatch`.
+    start_anchored: Regex,
+    fully_anchored: Regex,
+}
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "re.compile({})", Value::Str(self.pattern.clone()).python_repr())
+    }
+}
+
+/// Compiles `args[0]` with the flags in `args[1]`, when given.
+fn compile(function: &str, args: &[Value]) -> Result<Pattern, VmError> {
+    let pattern = str_arg(function, &args[0])?;
+    let flags = match args.get(1) {
+        Some(flags) => int_arg(function, flags)?,
+        None => 0,
+    };
+    if flags & !(IGNORECASE | MULTILINE | DOTALL | VERBOSE) != 0 {
+        return Err(VmError::Unimplemented(format!("regular expression flags {}", flags)));
+    }
+
+    let build = |source: String| {
+        RegexBuilder::new(&source)
+            .case_insensitive(flags & IGNORECASE != 0)
+            .multi_line(flags & MULTILINE != 0)
+            .dot_matches_new_line(flags & DOTALL != 0)
+            .ignore_whitespace(flags & VERBOSE != 0)
+            .build()
+            .map_err(|error| VmError::ReError(error.to_string()))
+    };
+    // The anchored forms wrap the pattern in a group, in verbose mode a trailing `#` comment
+    // would swallow the closing parenthesis without a newline before it.
+    let group_end = if flags & VERBOSE != 0 { "\n)" } else { ")" };
+    Ok(Pattern {
+        pattern: String::from(pattern),
+        flags,
+        regex: build(String::from(pattern))?,
+        start_anchored: build(format!("\\A(?:{}{}", pattern, group_end))?,
+        fully_anchored: build(format!("\\A(?:{}{}\\z", pattern, group_end))?,
+    })
+}
+
+fn module_search(function: &str, args: Vec<Value>, anchor: Anchor) -> Result<Value, VmError> {
+    expect_args(function, &args, 2, 3)?;
+    let pattern = compile(function, &[args[0].clone(), args.get(2).cloned().unwrap_or(Value::Int(0))])?;
+    pattern.search(function, &args[1], anchor)
+}
+
+impl Pattern {
+    pub(crate) fn get_attr(pattern: &Rc<Pattern>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "pattern" => return Some(Value::Str(pattern.pattern.clone())),
+            "flags" => return Some(Value::Int(pattern.flags)),
+            "groups" => return Some(Value::Int(pattern.regex.captures_len() as i64 - 1)),
+            "match" => |_, args| pattern_search("match", args, Anchor::Start),
+            "search" => |_, args| pattern_search("search", args, Anchor::None),
+            "fullmatch" => |_, args| pattern_search("fullmatch", args, Anchor::Both),
+            "sub" => |_, args| {
+                let pattern = receiver(&args);
+                expect_args("sub", &args[1..], 2, 3)?;
+                pattern.sub("sub", &args[1..])
+            },
+            "findall" => |_, args| {
+                expect_args("findall", &args[1..], 1, 1)?;
+                receiver(&args).findall("findall", &args[1])
+            },
+            "split" => |_, args| {
+                expect_args("split", &args[1..], 1, 2)?;
+                receiver(&args).split("split", &args[1..])
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Regex(Rc::clone(pattern)), name, method))
+    }
+
+    fn search(&self, function: &str, string: &Value, anchor: Anchor) -> Result<Value, VmError> {
+        let string = str_arg(function, string)?;
+        let regex = match anchor {
+            Anchor::None => &self.regex,
+            Anchor::Start => &self.start_anchored,
+            Anchor::Both => &self.fully_anchored,
+        };
+
+        Ok(match regex.captures(string) {
+            Some(captures) => Value::Match(Rc::new(Match::new(&self.regex, string, &captures))),
+            None => Value::Nonetype,
+        })
+    }
+
+    /// `sub(repl, string, count=0)`, `repl` has to be a string.
+    fn sub(&self, function: &str, args: &[Value]) -> Result<Value, VmError> {
+        let replacement = match &args[0] {
+            Value::Str(replacement) => expand_template(replacement)?,
+            _ => return Err(VmError::Unimplemented(String::from("re.sub() with a callable replacement"))),
+        };
+        let string = str_arg(function, &args[1])?;
+        let count = match args.get(2) {
+            Some(count) => int_arg(function, count)?.max(0) as usize,
+            None => 0,
+        };
+
+        Ok(Value::Str(self.regex.replacen(string, count, replacement.as_str()).into_owned()))
+    }
+
+    /// The matches `findall` and `split` go through. Unlike `captures_iter`, an empty match
+    /// right after a non-empty one counts, like it does in CPython since 3.7.
+    fn matches<'a>(&self, string: &'a str) -> Vec<Captures<'a>> {
+        let mut matches = vec![];
+        let mut position = 0;
+        let mut after_empty = false;
+        while position <= string.len() {
+            let captures = match self.regex.captures_at(string, position) {
+                Some(captures) => captures,
+                None => break,
+            };
+            let found = captures.get(0).unwrap();
+            if after_empty && found.is_empty() && found.start() == position {
+                // Two empty matches in a row would never move on, the next starts a character later.
+                match string[position..].chars().next() {
+                    Some(character) => position += character.len_utf8(),
+                    None => break,
+                }
+                after_empty = false;
+                continue;
+            }
+            after_empty = found.is_empty();
+            position = found.end();
+            matches.push(captures);
+        }
+        matches
+    }
+
+    /// Every match in `string`: the matched strings when the pattern has no groups, the group
+    /// when it has one, and a tuple of the groups when it has more. Groups that didn't take
+    /// part in a match are empty strings.
+    fn findall(&self, function: &str, string: &Value) -> Result<Value, VmError> {
+        let string = str_arg(function, string)?;
+        let group = |captures: &Captures, group| Value::Str(String::from(captures.get(group).map_or("", |group| group.as_str())));
+        let groups = self.regex.captures_len() - 1;
+        let found = self.matches(string).into_iter().map(|captures| match groups {
+            0 => group(&captures, 0),
+            1 => group(&captures, 1),
+            _ => Value::Tuple((1..=groups).map(|index| group(&captures, index)).collect()),
+        });
+
+        Ok(Value::List(Rc::new(RefCell::new(found.collect()))))
+    }
+
+    /// `split(string, maxsplit=0)`, the groups of each separator are put between the parts
+    /// it separates, `None` for the ones that didn't take part in the match.
+    fn split(&self, function: &str, args: &[Value]) -> Result<Value, VmError> {
+        let string = str_arg(function, &args[0])?;
+        let max_split = match args.get(1) {
+            Some(max_split) => int_arg(function, max_split)?,
+            None => 0,
+        };
+        if max_split < 0 {
+            return Ok(Value::List(Rc::new(RefCell::new(vec![Value::Str(String::from(string))]))));
+        }
+
+        let mut parts = vec![];
+        let mut start = 0;
+        for (count, captures) in self.matches(string).into_iter().enumerate() {
+            if max_split > 0 && count as i64 == max_split {
+                break;
+            }
+            let separator = captures.get(0).unwrap();
+            parts.push(Value::Str(String::from(&string[start..separator.start()])));
+            for group in captures.iter().skip(1) {
+                parts.push(group.map_or(Value::Nonetype, |group| Value::Str(String::from(group.as_str()))));
+            }
+            start = separator.end();
+        }
+        parts.push(Value::Str(String::from(&string[start..])));
+
+        Ok(Value::List(Rc::new(RefCell::new(parts))))
+    }
+}
+
+fn receiver(args: &[Value]) -> &Rc<Pattern> {
+    match &args[0] {
+        Value::Regex(pattern) => pattern,
+        _ => unreachable!("Pattern methods are only reachable through a bound Pattern"),
+    }
+}
+
+fn pattern_search(function: &str, args: Vec<Value>, anchor: Anchor) -> Result<Value, VmError> {
+    expect_args(function, &args[1..], 1, 1)?;
+    receiver(&args).search(function, &args[1], anchor)
+}
+
+/// Turns a CPython replacement template (`\1`, `\g<name>`) into the `regex` crate's (`${1}`, `${name}`).
+fn expand_template(template: &str) -> Result<String, VmError> {
+    let mut expanded = String::with_capacity(template.len());
+    let mut characters = template.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '$' => expanded.push_str("$$"),
+            '\\' => match characters.next() {
+                Some('n') => expanded.push('\n'),
+                Some('t') => expanded.push('\t'),
+                Some('r') => expanded.push('\r'),
+                Some('f') => expanded.push('\x0c'),
+                Some('v') => expanded.push('\x0b'),
+                Some('a') => expanded.push('\x07'),
+                Some('\\') => expanded.push('\\'),
+                Some('0') => expanded.push('\0'),
+                Some(digit) if digit.is_ascii_digit() => {
+                    let mut group = digit.to_string();
+                    if let Some(next) = characters.peek().filter(|next| next.is_ascii_digit()) {
+                        group.push(*next);
+                        characters.next();
+                    }
+                    expanded.push_str(&format!("${{{}}}", group));
+                },
+                Some('g') => {
+                    if characters.next() != Some('<') {
+                        return Err(VmError::ReError(String::from("missing <")));
+                    }
+                    let group: String = characters.by_ref().take_while(|character| *character != '>').collect();
+                    if group.is_empty() {
+                        return Err(VmError::ReError(String::from("missing group name")));
+                    }
+                    expanded.push_str(&format!("${{{}}}", group));
+                },
+                Some(letter) if letter.is_ascii_alphabetic() => {
+                    return Err(VmError::ReError(format!("bad escape \\{}", letter)));
+                },
+                Some(other) => {
+                    expanded.push('\\');
+                    expanded.push(other);
+                },
+                None => return Err(VmError::ReError(String::from("bad escape (end of pattern)"))),
+            },
+            _ => expanded.push(character),
+        }
+    }
+    Ok(expanded)
+}
+
+/// The result of a successful `match` or `search`.
+pub(crate) struct Match {
+    string: String,
+    /// Byte ranges of every group, `None` for the ones that didn't participate in the match.
+    spans: Vec<Option<(usize, usize)>>,
+    names: HashMap<String, usize>,
+}
+
+impl fmt::Debug for Match {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (start, end) = self.char_span(0).unwrap();
+        let matched = Value::Str(self.group(0).unwrap());
+        write!(f, "<re.Match object; span=({}, {}), match={}>", start, end, matched.python_repr())
+    }
+}
+
+impl Match {
+    fn new(regex: &Regex, string: &str, captures: &Captures) -> Match {
+        let spans = captures.iter().map(|group| group.map(|group| (group.start(), group.end()))).collect();
+        let names = regex.capture_names().enumerate()
+            .filter_map(|(index, name)| name.map(|name| (String::from(name), index)))
+            .collect();
+
+        Match { string: String::from(string), spans, names }
+    }
+
+    pub(crate) fn get_attr(matched: &Rc<Match>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "string" => return Some(Value::Str(matched.string.clone())),
+            "group" => match_group,
+            "groups" => match_groups,
+            "start" => |_, args| match_position("start", args, |start, _| Value::Int(start)),
+            "end" => |_, args| match_position("end", args, |_, end| Value::Int(end)),
+            "span" => |_, args| match_position("span", args, |start, end| Value::Tuple(vec![Value::Int(start), Value::Int(end)])),
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Match(Rc::clone(matched)), name, method))
+    }
+
+    /// Finds a group by number or name.
+    fn group_index(&self, group: &Value) -> Result<usize, VmError> {
+        let index = match group {
+            Value::Int(index) if *index >= 0 => Some(*index as usize),
+            Value::Str(name) => self.names.get(name).copied(),
+            Value::Int(_) => None,
+            _ => return Err(VmError::IndexError(String::from("no such group"))),
+        };
+
+        index.filter(|index| *index < self.spans.len()).ok_or_else(|| VmError::IndexError(String::from("no such group")))
+    }
+
+    fn group(&self, index: usize) -> Option<String> {
+        self.spans[index].map(|(start, end)| String::from(&self.string[start..end]))
+    }
+
+    /// CPython reports positions in characters, not bytes.
+    fn char_span(&self, index: usize) -> Option<(usize, usize)> {
+        self.spans[index].map(|(start, end)| {
+            let start_chars = self.string[..start].chars().count();
+            (start_chars, start_chars + self.string[start..end].chars().count())
+        })
+    }
+}
+
+fn match_receiver(args: &[Value]) -> &Rc<Match> {
+    match &args[0] {
+        Value::Match(matched) => matched,
+        _ => unreachable!("Match methods are only reachable through a bound Match"),
+    }
+}
+
+fn group_value(matched: &Match, index: usize) -> Value {
+    matched.group(index).map_or(Value::Nonetype, Value::Str)
+}
+
+/// `group(*groups)`, a single group gives a string and several give a tuple.
+fn match_group(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    let matched = match_receiver(&args);
+    let groups = &args[1..];
+    match groups.len() {
+        0 => Ok(group_value(matched, 0)),
+        1 => Ok(group_value(matched, matched.group_index(&groups[0])?)),
+        _ => {
+            let values = groups.iter()
+                .map(|group| Ok(group_value(matched, matched.group_index(group)?)))
+                .collect::<Result<_, VmError>>()?;
+            Ok(Value::Tuple(values))
+        },
+    }
+}
+
+/// `groups(default=None)`
+fn match_groups(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("groups", &args[1..], 0, 1)?;
+    let matched = match_receiver(&args);
+    let default = args.get(1).cloned().unwrap_or(Value::Nonetype);
+    let values = (1..matched.spans.len())
+        .map(|index| matched.group(index).map_or_else(|| default.clone(), Value::Str))
+        .collect();
+    Ok(Value::Tuple(values))
+}
+
+/// `start`, `end` and `span`, which give -1 for groups that didn't participate.
+fn match_position(function: &str, args: Vec<Value>, convert: fn(i64, i64) -> Value) -> Result<Value, VmError> {
+    expect_args(function, &args[1..], 0, 1)?;
+    let matched = match_receiver(&args);
+    let index = match args.get(1) {
+        Some(group) => matched.group_index(group)?,
+        None => 0,
+    };
+
+    let (start, end) = matched.char_span(index).map_or((-1, -1), |(start, end)| (start as i64, end as i64));
+    Ok(convert(start, end))
+}
@@ -0,0 +1,265 @@
+//! Converting between values and packed binary data described by a format string.
+
+use std::convert::TryFrom;
+use std::mem;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian, NativeEndian};
+
+use crate::exceptions::exception_type;
+use crate::{Module, NativeFn, Value, VmError};
+use super::{expect_args, make_module, str_arg};
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("calcsize", |_, args| {
+            expect_args("calcsize", &args, 1, 1)?;
+            let format = Format::parse(str_arg("calcsize", &args[0])?)?;
+            Ok(Value::Int(format.size() as i64))
+        }),
+        ("pack", |_, args| {
+            if args.is_empty() {
+                return Err(VmError::TypeError(String::from("pack expected at least 1 argument, got 0")));
+            }
+            let format = Format::parse(str_arg("pack", &args[0])?)?;
+            format.pack(&args[1..]).map(Value::Bytes)
+        }),
+        ("unpack", |_, args| {
+            expect_args("unpack", &args, 2, 2)?;
+            let format = Format::parse(str_arg("unpack", &args[0])?)?;
+            match &args[1] {
+                Value::Bytes(buffer) => format.unpack(buffer).map(Value::Tuple),
+                _ => Err(VmError::TypeError(String::from("a bytes-like object is required"))),
+            }
+        }),
+    ];
+
+    make_module("struct", functions, vec![("error", Value::ExceptionType(exception_type("struct.error")))])
+}
+
+#[derive(Clone, Copy)]
+enum Endian {
+    Little,
+    Big,
+    Native,
+}
+
+/// A format string like `<hhl`: the byte order and the items it packs.
+struct Format {
+    endian: Endian,
+    /// `@` lays items out like a C struct, with native sizes and alignment.
+    native_layout: bool,
+    items: Vec<(char, usize)>,
+}
+
+impl Format {
+    fn parse(format: &str) -> Result<Format, VmError> {
+        let mut characters = format.chars().peekable();
+        let (endian, native_layout) = match characters.peek() {
+            Some('<') => (Endian::Little, false),
+            Some('>') | Some('!') => (Endian::Big, false),
+            Some('=') => (Endian::Native, false),
+            Some('@') => (Endian::Native, true),
+            _ => (Endian::Native, true),
+        };
+        if !native_layout || characters.peek() == Some(&'@') {
+            characters.next();
+        }
+
+        let mut items = vec![];
+        while let Some(character) = characters.next() {
+            if character.is_whitespace() {
+                continue;
+            }
+            let mut count = None;
+            let mut code = character;
+            while let Some(digit) = code.to_digit(10) {
+                count = Some(count.unwrap_or(0) * 10 + digit as usize);
+                code = characters.next().ok_or_else(|| VmError::StructError(String::from("repeat count given without format specifier")))?;
+            }
+            if !"xcbB?hHiIlLqQnNfdsp".contains(code) || (!native_layout && "nN".contains(code)) {
+                return Err(VmError::StructError(String::from("bad char in struct format")));
+            }
+            items.push((code, count.unwrap_or(1)));
+        }
+
+        Ok(Format { endian, native_layout, items })
+    }
+
+    fn item_size(&self, code: char) -> usize {
+        match code {
+            'x' | 'c' | 'b' | 'B' | '?' | 's' | 'p' => 1,
+            'h' | 'H' => 2,
+            'i' | 'I' | 'f' => 4,
+            'l' | 'L' if self.native_layout => mem::size_of::<std::os::raw::c_long>(),
+            'l' | 'L' => 4,
+            'n' | 'N' => mem::size_of::<usize>(),
+            _ => 8,
+        }
+    }
+
+    /// Padding needed before an item at `offset`, only the native layout aligns items.
+    fn padding(&self, code: char, offset: usize) -> usize {
+        if self.native_layout && !"xcbB?sp".contains(code) {
+            let size = self.item_size(code);
+            (size - offset % size) % size
+        } else {
+            0
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.items.iter().fold(0, |offset, (code, count)| {
+            offset + self.padding(*code, offset) + self.item_size(*code) * count
+        })
+    }
+
+    /// How many values the items consume, strings and padding count differently.
+    fn value_count(&self) -> usize {
+        self.items.iter().map(|(code, count)| match code {
+            'x' => 0,
+            's' | 'p' => 1,
+            _ => *count,
+        }).sum()
+    }
+
+    fn pack(&self, values: &[Value]) -> Result<Vec<u8>, VmError> {
+        if values.len() != self.value_count() {
+            return Err(VmError::StructError(format!(
+                "pack expected {} items for packing (got {})", self.value_count(), values.len()
+            )));
+        }
+
+        let mut buffer = vec![];
+        let mut values = values.iter();
+        for (code, count) in &self.items {
+            buffer.resize(buffer.len() + self.padding(*code, buffer.len()), 0);
+            match code {
+                'x' => buffer.resize(buffer.len() + count, 0),
+                's' | 'p' => {
+                    let bytes = match values.next().unwrap() {
+                        Value::Bytes(bytes) => bytes,
+                        _ => return Err(VmError::StructError(format!("argument for '{}' must be a bytes object", code))),
+                    };
+                    let start = buffer.len();
+                    buffer.resize(start + count, 0);
+                    // A Pascal string starts with its length, which takes a byte of the count.
+                    let (offset, limit) = if *code == 'p' { (1, count.saturating_sub(1).min(255)) } else { (0, *count) };
+                    let length = bytes.len().min(limit);
+                    if *code == 'p' && *count > 0 {
+                        buffer[start] = length as u8;
+                    }
+                    buffer[start + offset..start + offset + length].copy_from_slice(&bytes[..length]);
+                },
+                _ => for _ in 0..*count {
+                    let mut item = vec![0; self.item_size(*code)];
+                    match self.endian {
+                        Endian::Little => write::<LittleEndian>(&mut item, *code, values.next().unwrap())?,
+                        Endian::Big => write::<BigEndian>(&mut item, *code, values.next().unwrap())?,
+                        Endian::Native => write::<NativeEndian>(&mut item, *code, values.next().unwrap())?,
+                    }
+                    buffer.extend(item);
+                },
+            }
+        }
+        Ok(buffer)
+    }
+
+    fn unpack(&self, buffer: &[u8]) -> Result<Vec<Value>, VmError> {
+        if buffer.len() != self.size() {
+            return Err(VmError::StructError(format!("unpack requires a buffer of {} bytes", self.size())));
+        }
+
+        let mut values = vec![];
+        let mut offset = 0;
+        for (code, count) in &self.items {
+            offset += self.padding(*code, offset);
+            match code {
+                'x' => offset += count,
+                's' => {
+                    values.push(Value::Bytes(buffer[offset..offset + count].to_vec()));
+                    offset += count;
+                },
+                'p' => {
+                    let string = match count {
+                        0 => vec![],
+                        _ => {
+                            let length = usize::from(buffer[offset]).min(count - 1);
+                            buffer[offset + 1..offset + 1 + length].to_vec()
+                        },
+                    };
+                    values.push(Value::Bytes(string));
+                    offset += count;
+                },
+                _ => for _ in 0..*count {
+                    let item = &buffer[offset..offset + self.item_size(*code)];
+                    values.push(match self.endian {
+                        Endian::Little => read::<LittleEndian>(item, *code)?,
+                        Endian::Big => read::<BigEndian>(item, *code)?,
+                        Endian::Native => read::<NativeEndian>(item, *code)?,
+                    });
+                    offset += item.len();
+                },
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn integer(code: char, value: &Value, min: i64, max: i64) -> Result<i64, VmError> {
+    let value = match value {
+        Value::Int(value) => *value,
+        Value::Bool(value) => i64::from(*value),
+        _ => return Err(VmError::StructError(String::from("required argument is not an integer"))),
+    };
+    if value < min || value > max {
+        return Err(VmError::StructError(format!("'{}' format requires {} <= number <= {}", code, min, max)));
+    }
+    Ok(value)
+}
+
+fn float(value: &Value) -> Result<f64, VmError> {
+    match value {
+        Value::Int(value) => Ok(*value as f64),
+        Value::Bool(value) => Ok(f64::from(u8::from(*value))),
+        Value::Float(value) => Ok(*value),
+        _ => Err(VmError::StructError(String::from("required argument is not a float"))),
+    }
+}
+
+/// The largest value a signed or unsigned integer of `size` bytes holds, as far as `i64` reaches.
+fn max_value(size: usize, signed: bool) -> i64 {
+    let bits = size as u32 * 8 - u32::from(signed);
+    if bits >= 63 { i64::MAX } else { (1 << bits) - 1 }
+}
+
+/// Writes one value into `item`, which is exactly the size of the item.
+fn write<B: ByteOrder>(item: &mut [u8], code: char, value: &Value) -> Result<(), VmError> {
+    let size = item.len();
+    match code {
+        'c' => match value {
+            Value::Bytes(bytes) if bytes.len() == 1 => item[0] = bytes[0],
+            _ => return Err(VmError::StructError(String::from("char format requires a bytes object of length 1"))),
+        },
+        '?' => item[0] = u8::from(value.is_truthy()),
+        'f' => B::write_f32(item, float(value)? as f32),
+        'd' => B::write_f64(item, float(value)?),
+        'b' | 'h' | 'i' | 'l' | 'q' | 'n' => {
+            let max = max_value(size, true);
+            B::write_int(item, integer(code, value, -max - 1, max)?, size);
+        },
+        _ => B::write_uint(item, integer(code, value, 0, max_value(size, false))? as u64, size),
+    }
+    Ok(())
+}
+
+fn read<B: ByteOrder>(item: &[u8], code: char) -> Result<Value, VmError> {
+    let too_large = || VmError::OverflowError(String::from("Python int too large to convert to C int"));
+    match code {
+        'c' => Ok(Value::Bytes(item.to_vec())),
+        '?' => Ok(Value::Bool(item[0] != 0)),
+        'f' => Ok(Value::Float(f64::from(B::read_f32(item)))),
+        'd' => Ok(Value::Float(B::read_f64(item))),
+        'b' | 'h' | 'i' | 'l' | 'q' | 'n' => Ok(Value::Int(B::read_int(item, item.len()))),
+        _ => i64::try_from(B::read_uint(item, item.len())).map(Value::Int).map_err(|_| too_large()),
+    }
+}
@@ -0,0 +1,473 @@
+//! `threading`. Values are shared through `Rc`, which can't cross threads, so by default
+//! `start()` runs the target to completion on the calling thread, like the old
+//! `dummy_threading` module did. Code that only synchronizes through `join()` and locks gives
+//! the results it would with real threads.
+//!
+//! With `--threads`, `start()` runs the target in a child VM on a thread of its own, like
+//! `process` does in a child process: the target and its arguments are sent to it as JSON.
+//! The module globals are shared: every VM reads and writes them through one table behind a
+//! mutex, so a global one thread binds, like a counter it increments, is seen by the others.
+//! The table holds values as JSON, so changes to a list or an instance are only seen by the
+//! thread that made them until it binds the global again. Locks aren't copied, every thread
+//! gets the same lock.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::{de, ser, Deserialize, Deserializer, Serializer};
+
+use crate::exceptions::Exception;
+use crate::{Module, NativeFn, Value, Vm, VmError, VM_STACK_SIZE};
+use super::process::Job;
+use super::{bound_method, expect_args, float_arg, keyword_function, make_module, os_error};
+
+/// The `get_ident()` of the next thread that asks for its own.
+static NEXT_IDENT: AtomicI64 = AtomicI64::new(1);
+
+thread_local! {
+    /// `get_ident()`, which numbers threads in the order they first call it.
+    static IDENT: i64 = NEXT_IDENT.fetch_add(1, Ordering::Relaxed);
+    /// The number in the next default thread name, `Thread-1` is the first.
+    static NEXT_NAME: Cell<usize> = const { Cell::new(1) };
+    static NEXT_ID: Cell<usize> = const { Cell::new(0) };
+    /// The threads started under `--threads` that haven't been joined yet, by `Thread::id`.
+    static RUNNING: RefCell<HashMap<usize, JoinHandle<()>>> = RefCell::new(HashMap::new());
+    /// The locks serialized into the job of a thread being started, `None` when no thread is.
+    static SENT_LOCKS: RefCell<Option<Vec<Arc<LockState>>>> = const { RefCell::new(None) };
+    /// The locks sent with the job this thread runs, by the address they were written as.
+    static RECEIVED_LOCKS: RefCell<HashMap<usize, Arc<LockState>>> = RefCell::new(HashMap::new());
+}
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("Lock", |_, args| {
+            expect_args("Lock", &args, 0, 0)?;
+            let state = Arc::new(LockState { locked: Mutex::new(false), released: Condvar::new() });
+            Ok(Value::Lock(Rc::new(Lock { state })))
+        }),
+        ("get_ident", |_, args| {
+            expect_args("get_ident", &args, 0, 0)?;
+            Ok(Value::Int(IDENT.with(|ident| *ident)))
+        }),
+        ("active_count", |_, args| {
+            expect_args("active_count", &args, 0, 0)?;
+            let running = RUNNING.with(|running| running.borrow().values().filter(|handle| !handle.is_finished()).count());
+            Ok(Value::Int(running as i64 + 1))
+        }),
+    ];
+
+    make_module("threading", functions, vec![
+        ("Thread", keyword_function("Thread", new_thread, &["group", "target", "name", "args", "kwargs", "daemon"])),
+    ])
+}
+
+fn runtime_error(message: &str) -> VmError {
+    VmError::Exception(Exception::new("RuntimeError", String::from(message)))
+}
+
+pub(crate) struct Thread {
+    name: String,
+    id: usize,
+    target: Value,
+    args: Vec<Value>,
+    kwargs: Vec<(Rc<String>, Value)>,
+    daemon: bool,
+    started: Cell<bool>,
+}
+
+impl fmt::Debug for Thread {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match (self.started.get(), self.is_alive()) {
+            (false, _) => "initial",
+            (true, true) => "started",
+            (true, false) => "stopped",
+        };
+        let daemon = if self.daemon { " daemon" } else { "" };
+        write!(f, "<Thread({}, {}{})>", self.name, status, daemon)
+    }
+}
+
+/// `Thread(group=None, target=None, name=None, args=(), kwargs=None, *, daemon=None)`.
+fn new_thread(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("Thread", &args, 0, 6)?;
+    let mut args = args.into_iter();
+    if !matches!(args.next(), None | Some(Value::Nonetype)) {
+        return Err(VmError::ValueError(String::from("group argument must be None for now")));
+    }
+    let target = args.next().unwrap_or(Value::Nonetype);
+    let name = match args.next() {
+        None | Some(Value::Nonetype) => format!("Thread-{}", NEXT_NAME.with(|next| next.replace(next.get() + 1))),
+        Some(Value::Str(name)) => name,
+        Some(other) => other.python_repr(),
+    };
+    let positional = match args.next() {
+        None | Some(Value::Nonetype) => vec![],
+        Some(Value::Tuple(args)) => args,
+        Some(Value::List(args)) => args.borrow().clone(),
+        Some(other) => return Err(VmError::TypeError(format!("Thread() args must be a tuple, not {}", other.python_repr()))),
+    };
+    let kwargs = match args.next() {
+        None | Some(Value::Nonetype) => vec![],
+        Some(Value::Dict(dict)) => dict.borrow().items().map(|(key, value)| match key {
+            Value::Str(key) => Ok((Rc::new(key.clone()), value.clone())),
+            _ => Err(VmError::TypeError(String::from("keywords must be strings"))),
+        }).collect::<Result<_, _>>()?,
+        Some(other) => return Err(VmError::TypeError(format!("Thread() kwargs must be a dict, not {}", other.python_repr()))),
+    };
+    let daemon = args.next().is_some_and(|daemon| daemon.is_truthy());
+
+    let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+    Ok(Value::Thread(Rc::new(Thread { name, id, target, args: positional, kwargs, daemon, started: Cell::new(false) })))
+}
+
+impl Thread {
+    pub(crate) fn get_attr(thread: &Rc<Thread>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "name" => return Some(Value::Str(thread.name.clone())),
+            "daemon" => return Some(Value::Bool(thread.daemon)),
+            "start" => |vm, args| {
+                expect_args("start", &args[1..], 0, 0)?;
+                thread_receiver(&args).start(vm)?;
+                Ok(Value::Nonetype)
+            },
+            "join" => |vm, args| {
+                expect_args("join", &args[1..], 0, 1)?;
+                let timeout = match args.get(1) {
+                    None | Some(Value::Nonetype) => None,
+                    Some(timeout) => Some(float_arg("join", timeout)?),
+                };
+                thread_receiver(&args).join(timeout)?;
+                vm.pull_globals();
+                Ok(Value::Nonetype)
+            },
+            "is_alive" => |_, args| {
+                expect_args("is_alive", &args[1..], 0, 0)?;
+                Ok(Value::Bool(thread_receiver(&args).is_alive()))
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Thread(Rc::clone(thread)), name, method))
+    }
+
+    /// Runs the target, on a thread of its own under `--threads`. An exception it raises ends
+    /// the thread, and is printed like the default `threading.excepthook` does instead of
+    /// propagating to the caller.
+    fn start(&self, vm: &mut Vm) -> Result<(), VmError> {
+        if self.started.replace(true) {
+            return Err(runtime_error("threads can only be started once"));
+        }
+        if let Value::Nonetype = self.target {
+            return Ok(());
+        }
+        if vm.config.threads {
+            return self.spawn(vm);
+        }
+
+        if let Err(error) = vm.call_value_kw(self.target.clone(), self.args.clone(), self.kwargs.clone()) {
+            let exception = error.into_exception()?;
+            eprintln!("Exception in thread {}:\n{}", self.name, exception);
+        }
+        Ok(())
+    }
+
+    fn spawn(&self, vm: &mut Vm) -> Result<(), VmError> {
+        let table = vm.share_globals();
+        let job = Job::new(vm, self.target.clone(), self.args.clone(), self.kwargs.clone());
+        let (job, locks) = to_json(&job).map_err(|error| {
+            VmError::PicklingError(format!("Can't send the arguments of {} to it: {}", self.name, error))
+        })?;
+
+        let name = self.name.clone();
+        let handle = thread::Builder::new()
+            .name(name.clone())
+            .stack_size(VM_STACK_SIZE)
+            .spawn(move || {
+                let result = from_json::<Job>(&job, &locks)
+                    .map_err(|error| VmError::UnpicklingError(format!("Invalid job for {}: {}", name, error)))
+                    .and_then(|job| job.run(Some(table)))
+                    .and_then(|_| join_threads());
+                if let Err(error) = result {
+                    eprintln!("Exception in thread {}:\n{}", name, error);
+                }
+            })
+            .map_err(|error| os_error(error, None))?;
+        RUNNING.with(|running| running.borrow_mut().insert(self.id, handle));
+        Ok(())
+    }
+
+    /// Waits for the thread to finish, at most `timeout` seconds if there is one.
+    fn join(&self, timeout: Option<f64>) -> Result<(), VmError> {
+        if !self.started.get() {
+            return Err(runtime_error("cannot join thread before it is started"));
+        }
+        let handle = match RUNNING.with(|running| running.borrow_mut().remove(&self.id)) {
+            Some(handle) => handle,
+            None => return Ok(()),
+        };
+        if let Some(timeout) = timeout {
+            let deadline = Instant::now() + Duration::from_secs_f64(timeout.max(0.0));
+            while !handle.is_finished() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(1));
+            }
+            if !handle.is_finished() {
+                RUNNING.with(|running| running.borrow_mut().insert(self.id, handle));
+                return Ok(());
+            }
+        }
+        handle.join().map_err(|_| VmError::InternalError(format!("{} panicked", self.name)))
+    }
+
+    fn is_alive(&self) -> bool {
+        RUNNING.with(|running| running.borrow().get(&self.id).is_some_and(|handle| !handle.is_finished()))
+    }
+}
+
+/// `value` as JSON, with the locks it refers to.
+fn to_json<T: serde::Serialize>(value: &T) -> serde_json::Result<(String, Vec<Arc<LockState>>)> {
+    SENT_LOCKS.with(|sent| sent.replace(Some(vec![])));
+    let json = serde_json::to_string(value);
+    let locks = SENT_LOCKS.with(|sent| sent.replace(None)).unwrap_or_default();
+    json.map(|json| (json, locks))
+}
+
+/// The value `to_json` wrote as `json`, which refers to the same `locks`.
+fn from_json<T: de::DeserializeOwned>(json: &str, locks: &[Arc<LockState>]) -> serde_json::Result<T> {
+    RECEIVED_LOCKS.with(|received| {
+        received.replace(locks.iter().map(|lock| (Arc::as_ptr(lock) as usize, Arc::clone(lock))).collect())
+    });
+    let value = serde_json::from_str(json);
+    RECEIVED_LOCKS.with(|received| received.borrow_mut().clear());
+    value
+}
+
+/// The module globals of every thread under `--threads`, by name.
+#[derive(Default)]
+pub(crate) struct SharedTable {
+    globals: HashMap<String, SharedValue>,
+    /// The number of writes to the table, which numbers the next one.
+    writes: u64,
+}
+
+struct SharedValue {
+    /// The write that set the value, for threads to tell whether theirs is outdated.
+    write: u64,
+    json: String,
+    locks: Vec<Arc<LockState>>,
+}
+
+/// A VM's side of the shared table: the write each of its globals was last read from or
+/// written as.
+pub(crate) struct SharedGlobals {
+    table: Arc<Mutex<SharedTable>>,
+    seen: HashMap<Rc<String>, u64>,
+}
+
+impl SharedGlobals {
+    pub(super) fn new(table: Arc<Mutex<SharedTable>>) -> SharedGlobals {
+        SharedGlobals { table, seen: HashMap::new() }
+    }
+}
+
+impl Vm {
+    /// The table this VM shares its globals through, made from its globals when it starts its
+    /// first thread.
+    fn share_globals(&mut self) -> Arc<Mutex<SharedTable>> {
+        if let Some(shared) = &self.shared_globals {
+            return Arc::clone(&shared.table);
+        }
+        self.shared_globals = Some(SharedGlobals::new(Arc::default()));
+        let names = self.globals.borrow().keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            self.push_global(&name);
+        }
+        Arc::clone(&self.shared_globals.as_ref().unwrap().table)
+    }
+
+    /// Reads global `name` from the shared table when another thread wrote it since this VM
+    /// last saw it, and unbinds it when another thread deleted it.
+    pub(crate) fn pull_global(&mut self, name: &Rc<String>) {
+        let shared = match &mut self.shared_globals {
+            Some(shared) => shared,
+            None => return,
+        };
+        let table = shared.table.lock().unwrap();
+        let seen = shared.seen.get(name).copied();
+        match table.globals.get(name.as_str()) {
+            Some(shared_value) if Some(shared_value.write) != seen => {
+                if let Ok(value) = from_json(&shared_value.json, &shared_value.locks) {
+                    self.globals.borrow_mut().insert(Rc::clone(name), value);
+                    shared.seen.insert(Rc::clone(name), shared_value.write);
+                }
+            },
+            None if seen.is_some() => {
+                self.globals.borrow_mut().remove(name);
+                shared.seen.remove(name);
+            },
+            _ => {},
+        }
+    }
+
+    /// Reads every global other threads wrote since this VM last saw them, so the globals a
+    /// joined thread bound are there for code that doesn't load them by name, like `globals()`.
+    fn pull_globals(&mut self) {
+        let names = match &self.shared_globals {
+            Some(shared) => {
+                let table = shared.table.lock().unwrap();
+                table.globals.keys().map(|name| Rc::new(name.clone())).chain(shared.seen.keys().cloned()).collect::<Vec<_>>()
+            },
+            None => return,
+        };
+        for name in names {
+            self.pull_global(&name);
+        }
+    }
+
+    /// Writes global `name` to the shared table, or takes it out when it is unbound or can't be
+    /// serialized, like modules and threads, which then stay with this thread.
+    pub(crate) fn push_global(&mut self, name: &Rc<String>) {
+        let shared = match &mut self.shared_globals {
+            Some(shared) => shared,
+            None => return,
+        };
+        let value = match self.globals.borrow().get(name) {
+            Some(Value::Module(_)) | None => None,
+            Some(value) => to_json(value).ok(),
+        };
+        let mut table = shared.table.lock().unwrap();
+        match value {
+            Some((json, locks)) => {
+                table.writes += 1;
+                let write = table.writes;
+                table.globals.insert(name.to_string(), SharedValue { write, json, locks });
+                shared.seen.insert(Rc::clone(name), write);
+            },
+            None => {
+                table.globals.remove(name.as_str());
+                shared.seen.remove(name);
+            },
+        }
+    }
+}
+
+/// Waits for the threads started by this thread that haven't been joined, which CPython does
+/// before it exits. Daemon threads are waited for too, as nothing stops them early.
+pub(crate) fn join_threads() -> Result<(), VmError> {
+    let handles = RUNNING.with(|running| running.borrow_mut().drain().collect::<Vec<_>>());
+    for (_, handle) in handles {
+        let name = handle.thread().name().unwrap_or_default().to_string();
+        handle.join().map_err(|_| VmError::InternalError(format!("{} panicked", name)))?;
+    }
+    Ok(())
+}
+
+/// A lock, shared with the threads it is sent to under `--threads`.
+pub(crate) struct Lock {
+    state: Arc<LockState>,
+}
+
+struct LockState {
+    locked: Mutex<bool>,
+    /// Notified when the lock is released, for the threads waiting to acquire it.
+    released: Condvar,
+}
+
+impl fmt::Debug for Lock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = if *self.state.locked.lock().unwrap() { "locked" } else { "unlocked" };
+        write!(f, "<{} _thread.lock object at {:#x}>", status, self as *const Lock as usize)
+    }
+}
+
+impl Lock {
+    pub(crate) fn get_attr(lock: &Rc<Lock>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "acquire" | "__enter__" => |vm, args| {
+                expect_args("acquire", &args[1..], 0, 2)?;
+                let blocking = !matches!(args.get(1), Some(Value::Bool(false)) | Some(Value::Int(0)));
+                lock_receiver(&args).acquire(blocking, vm.config.threads)
+            },
+            "release" => |_, args| {
+                expect_args("release", &args[1..], 0, 0)?;
+                lock_receiver(&args).release()
+            },
+            "__exit__" => |_, args| {
+                lock_receiver(&args).release()?;
+                Ok(Value::Nonetype)
+            },
+            "locked" => |_, args| {
+                expect_args("locked", &args[1..], 0, 0)?;
+                Ok(Value::Bool(*lock_receiver(&args).state.locked.lock().unwrap()))
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Lock(Rc::clone(lock)), name, method))
+    }
+
+    /// Waits for a held lock to be released if another thread can release it, which needs
+    /// `--threads`. Without it, waiting would wait forever.
+    fn acquire(&self, blocking: bool, threads: bool) -> Result<Value, VmError> {
+        let mut locked = self.state.locked.lock().unwrap();
+        if *locked && blocking && !threads {
+            return Err(runtime_error("deadlock: acquiring a lock that is already held"));
+        }
+        if *locked && !blocking {
+            return Ok(Value::Bool(false));
+        }
+        while *locked {
+            locked = self.state.released.wait(locked).unwrap();
+        }
+        *locked = true;
+        Ok(Value::Bool(true))
+    }
+
+    fn release(&self) -> Result<Value, VmError> {
+        let mut locked = self.state.locked.lock().unwrap();
+        if !*locked {
+            return Err(runtime_error("release unlocked lock"));
+        }
+        *locked = false;
+        self.state.released.notify_one();
+        Ok(Value::Nonetype)
+    }
+}
+
+/// Writes the address of the lock and keeps the lock to send it with the job of the thread
+/// being started. Other jobs, like the one of `process`, can't share a lock.
+pub(crate) fn serialize_lock<S: Serializer>(lock: &Rc<Lock>, serializer: S) -> Result<S::Ok, S::Error> {
+    let sent = SENT_LOCKS.with(|sent| {
+        sent.borrow_mut().as_mut().map(|sent| sent.push(Arc::clone(&lock.state))).is_some()
+    });
+    if !sent {
+        return Err(ser::Error::custom("a lock can only be sent to a thread"));
+    }
+    serializer.serialize_u64(Arc::as_ptr(&lock.state) as usize as u64)
+}
+
+/// The lock sent with the job this thread runs that was written as the address.
+pub(crate) fn deserialize_lock<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rc<Lock>, D::Error> {
+    let address = u64::deserialize(deserializer)? as usize;
+    let state = RECEIVED_LOCKS.with(|received| received.borrow().get(&address).cloned());
+    state.map(|state| Rc::new(Lock { state })).ok_or_else(|| de::Error::custom("a lock can only be received from a thread"))
+}
+
+fn thread_receiver(args: &[Value]) -> &Rc<Thread> {
+    match &args[0] {
+        Value::Thread(thread) => thread,
+        _ => unreachable!("thread methods are only reachable through a bound thread"),
+    }
+}
+
+fn lock_receiver(args: &[Value]) -> &Rc<Lock> {
+    match &args[0] {
+        Value::Lock(lock) => lock,
+        _ => unreachable!("lock methods are only reachable through a bound lock"),
+    }
+}
@@ -0,0 +1,44 @@
+//! Clocks and `sleep`.
+
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{Module, NativeFn, Value, VmError};
+use super::{expect_args, float_arg, make_module};
+
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Time since the clock was first read, which is what `monotonic` and `perf_counter` count from.
+pub(crate) fn perf_counter() -> Duration {
+    START.get_or_init(Instant::now).elapsed()
+}
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("time", |_, args| {
+            expect_args("time", &args, 0, 0)?;
+            let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            Ok(Value::Float(since_epoch.as_secs_f64()))
+        }),
+        ("sleep", |_, args| {
+            expect_args("sleep", &args, 1, 1)?;
+            let seconds = float_arg("sleep", &args[0])?;
+            if seconds < 0.0 {
+                return Err(VmError::ValueError(String::from("sleep length must be non-negative")));
+            }
+            thread::sleep(Duration::from_secs_f64(seconds));
+            Ok(Value::Nonetype)
+        }),
+        ("monotonic", |_, args| {
+            expect_args("monotonic", &args, 0, 0)?;
+            Ok(Value::Float(perf_counter().as_secs_f64()))
+        }),
+        ("perf_counter", |_, args| {
+            expect_args("perf_counter", &args, 0, 0)?;
+            Ok(Value::Float(perf_counter().as_secs_f64()))
+        }),
+    ];
+
+    make_module("time", functions, vec![])
+}
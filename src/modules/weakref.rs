@@ -0,0 +1,57 @@
+//! Weak references to instances, which don't keep them alive.
+
+use std::rc::{Rc, Weak};
+
+use crate::classes::Instance;
+use crate::exceptions::exception_type;
+use crate::{Module, NativeFn, Value, VmError};
+use super::{expect_args, make_module};
+
+pub(super) fn module() -> Module {
+    let functions: Vec<(&str, NativeFn)> = vec![
+        ("ref", |_, args| {
+            expect_args("ref", &args, 1, 2)?;
+            Ok(Value::WeakRef(downgrade("ref", &args)?))
+        }),
+        ("proxy", |_, args| {
+            expect_args("proxy", &args, 1, 2)?;
+            Ok(Value::WeakProxy(downgrade("proxy", &args)?))
+        }),
+        ("getweakrefcount", |_, args| {
+            expect_args("getweakrefcount", &args, 1, 1)?;
+            match &args[0] {
+                Value::Instance(instance) => Ok(Value::Int(Rc::weak_count(instance) as i64)),
+                _ => Ok(Value::Int(0)),
+            }
+        }),
+    ];
+    let constants = vec![("ReferenceError", Value::ExceptionType(exception_type("ReferenceError")))];
+
+    make_module("weakref", functions, constants)
+}
+
+/// The weak reference `ref()` and `proxy()` make, only instances can be weakly referenced.
+fn downgrade(function: &str, args: &[Value]) -> Result<Weak<Instance>, VmError> {
+    if args.len() == 2 {
+        return Err(VmError::Unimplemented(format!("weakref.{}() with a callback", function)));
+    }
+    match &args[0] {
+        Value::Instance(instance) => Ok(Rc::downgrade(instance)),
+        other => Err(VmError::TypeError(format!("cannot create weak reference to {}", other.python_repr()))),
+    }
+}
+
+/// The object a proxy forwards to, proxies to dead objects raise `ReferenceError`.
+pub(crate) fn referent(proxy: &Weak<Instance>) -> Result<Rc<Instance>, VmError> {
+    proxy.upgrade().ok_or_else(|| VmError::ReferenceError(String::from("weakly-referenced object no longer exists")))
+}
+
+pub(crate) fn repr(reference: &Weak<Instance>, proxy: bool) -> String {
+    let address = reference.as_ptr() as usize;
+    match (reference.upgrade(), proxy) {
+        (Some(instance), false) => format!("<weakref at {:#x}; to '{}' at {:#x}>", address, instance.class.name, address),
+        (Some(instance), true) => format!("<weakproxy at {:#x} to {} at {:#x}>", address, instance.class.name, address),
+        (None, false) => format!("<weakref at {:#x}; dead>", address),
+        (None, true) => format!("<weakproxy at {:#x}; dead>", address),
+    }
+}
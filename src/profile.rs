@@ -0,0 +1,83 @@
+//! `--profile`: counts the instructions each function runs and the calls between functions,
+//! and writes them in the callgrind format KCachegrind and `callgrind_annotate` read.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::rc::Rc;
+
+#[derive(Default)]
+struct Edge {
+    calls: u64,
+    /// Instructions run by the callee and everything it called.
+    inclusive: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct Profiler {
+    /// Instructions run so far.
+    total: u64,
+    /// Instructions each function ran itself, by `co_name`.
+    own: HashMap<Rc<String>, u64>,
+    edges: HashMap<(Rc<String>, Rc<String>), Edge>,
+    /// The running functions and the `total` when each was entered.
+    stack: Vec<(Rc<String>, u64)>,
+}
+
+impl Profiler {
+    pub(crate) fn enter(&mut self, name: &Rc<String>) {
+        self.stack.push((Rc::clone(name), self.total));
+    }
+
+    pub(crate) fn leave(&mut self) {
+        let (callee, start) = self.stack.pop().unwrap();
+        if let Some((caller, _)) = self.stack.last() {
+            let edge = self.edges.entry((Rc::clone(caller), callee)).or_default();
+            edge.calls += 1;
+            edge.inclusive += self.total - start;
+        }
+    }
+
+    /// Counts an instruction of the running function.
+    pub(crate) fn step(&mut self) {
+        self.total += 1;
+        if let Some((name, _)) = self.stack.last() {
+            *self.own.entry(Rc::clone(name)).or_default() += 1;
+        }
+    }
+
+    /// Writes the profile of running `command`. There are no line numbers in the bytecode,
+    /// so every cost is on line 0.
+    pub(crate) fn write(&self, path: &str, command: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "# callgrind format")?;
+        writeln!(file, "version: 1")?;
+        writeln!(file, "creator: py_vm")?;
+        writeln!(file, "cmd: {}", command)?;
+        writeln!(file, "positions: line")?;
+        writeln!(file, "events: Instructions")?;
+        writeln!(file, "summary: {}", self.total)?;
+
+        // Sorted, so profiles of the same program can be diffed.
+        let mut functions: BTreeMap<&str, Vec<(&str, &Edge)>> = BTreeMap::new();
+        for name in self.own.keys() {
+            functions.entry(name.as_str()).or_default();
+        }
+        for ((caller, callee), edge) in &self.edges {
+            functions.entry(caller.as_str()).or_default().push((callee.as_str(), edge));
+            functions.entry(callee.as_str()).or_default();
+        }
+        for (name, calls) in functions.iter_mut() {
+            calls.sort_by_key(|(callee, _)| *callee);
+            writeln!(file)?;
+            writeln!(file, "fn={}", name)?;
+            writeln!(file, "0 {}", self.own.get(&String::from(*name)).copied().unwrap_or(0))?;
+            for (callee, edge) in calls.iter() {
+                writeln!(file, "cfn={}", callee)?;
+                writeln!(file, "calls={} 0", edge.calls)?;
+                writeln!(file, "0 {}", edge.inclusive)?;
+            }
+        }
+        file.flush()
+    }
+}
@@ -0,0 +1,220 @@
+//! `set` and `frozenset`. Items are hashed with `hash()`, so they have to be hashable and `1`,
+//! `1.0` and `True` are the same item. Sets are mutable and shared like lists, frozensets are
+//! immutable and hashable, and the compiler stores `x in {1, 2}` as a frozenset constant.
+
+// Values with interior mutability, like lists, are unhashable, and `from_items` rejects them.
+#![allow(clippy::mutable_key_type)]
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+use crate::modules::{bound_method, expect_args};
+use crate::{NativeFn, Value, Vm, VmError};
+
+pub(crate) type Set = HashSet<Value>;
+
+/// The `set([iterable])` built-in type.
+pub(crate) fn builtin_set(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("set", &args, 0, 1)?;
+    let items = match args.first() {
+        None => vec![],
+        Some(iterable) => iterable_items(iterable)?,
+    };
+    new(items)
+}
+
+/// The `frozenset([iterable])` built-in type.
+pub(crate) fn builtin_frozenset(_vm: &mut Vm, args: Vec<Value>) -> Result<Value, VmError> {
+    expect_args("frozenset", &args, 0, 1)?;
+    let items = match args.first() {
+        None => vec![],
+        Some(Value::FrozenSet(set)) => return Ok(Value::FrozenSet(Rc::clone(set))),
+        Some(iterable) => iterable_items(iterable)?,
+    };
+    from_items(items).map(|set| Value::FrozenSet(Rc::new(set)))
+}
+
+/// A new set of `items`, for `set()` and `BUILD_SET`.
+pub(crate) fn new(items: Vec<Value>) -> Result<Value, VmError> {
+    from_items(items).map(|set| Value::Set(Rc::new(RefCell::new(set))))
+}
+
+fn iterable_items(iterable: &Value) -> Result<Vec<Value>, VmError> {
+    iterable.sequence_items().ok_or_else(|| {
+        VmError::TypeError(format!("\'{}\' object is not iterable", iterable.type_name()))
+    })
+}
+
+fn from_items(items: Vec<Value>) -> Result<Set, VmError> {
+    let mut set = Set::new();
+    for item in items {
+        item.python_hash()?;
+        set.insert(item);
+    }
+    Ok(set)
+}
+
+/// `set.add(item)`, also used by `SET_ADD` in set comprehensions.
+pub(crate) fn add(set: &RefCell<Set>, item: Value) -> Result<(), VmError> {
+    item.python_hash()?;
+    set.borrow_mut().insert(item);
+    Ok(())
+}
+
+/// `set.update(iterable)`, also used by `SET_UPDATE` for `{*a, *b}`.
+pub(crate) fn update(set: &RefCell<Set>, iterable: &Value) -> Result<(), VmError> {
+    let items = from_items(iterable_items(iterable)?)?;
+    set.borrow_mut().extend(items);
+    Ok(())
+}
+
+/// The items in the order of their hashes, which for small ints is the order CPython's
+/// hash table gives them in.
+pub(crate) fn sorted_items(set: &Set) -> Vec<Value> {
+    let mut items = set.iter().cloned().collect::<Vec<_>>();
+    items.sort_by_key(|item| item.python_hash().map_or(0, |hash| hash as u32));
+    items
+}
+
+/// `{1, 2}` for a set and `frozenset({1, 2})` for a frozenset. Empty ones have no braces, as
+/// `{}` is a dict.
+pub(crate) fn repr(set: &Set, frozen: bool) -> String {
+    let type_name = if frozen { "frozenset" } else { "set" };
+    if set.is_empty() {
+        return format!("{}()", type_name);
+    }
+    let items = sorted_items(set).iter().map(Value::python_repr).collect::<Vec<_>>();
+    if frozen {
+        format!("frozenset({{{}}})", items.join(", "))
+    } else {
+        format!("{{{}}}", items.join(", "))
+    }
+}
+
+/// `_Py_HashSet`: the items' hashes, shuffled so that sets of similar items don't collide, are
+/// combined with xor so the order of the items doesn't matter.
+pub(crate) fn hash(set: &Set) -> Result<i32, VmError> {
+    let shuffle_bits = |hash: u32| ((hash ^ 89_869_747) ^ (hash << 16)).wrapping_mul(3_644_798_167);
+
+    let mut hash: u32 = 0;
+    for item in set {
+        hash ^= shuffle_bits(item.python_hash()? as u32);
+    }
+    hash ^= (set.len() as u32 + 1).wrapping_mul(1_927_868_237);
+    hash ^= (hash >> 11) ^ (hash >> 25);
+    hash = hash.wrapping_mul(69_069).wrapping_add(907_133_923);
+
+    Ok(if hash == u32::MAX { 590_923_713 } else { hash as i32 })
+}
+
+/// Calls `function` with the items of a set or frozenset, `None` if `value` is neither.
+fn with_items<R>(value: &Value, function: impl FnOnce(&Set) -> R) -> Option<R> {
+    match value {
+        Value::Set(set) => Some(function(&set.borrow())),
+        Value::FrozenSet(set) => Some(function(set)),
+        _ => None,
+    }
+}
+
+/// Sets and frozensets are equal when they have the same items, whichever type they are.
+pub(crate) fn equal(first: &Value, second: &Value) -> bool {
+    with_items(first, |first| with_items(second, |second| first == second)).flatten().unwrap_or(false)
+}
+
+fn combine(operator: char, first: &Set, second: &Set) -> Set {
+    match operator {
+        '|' => first.union(second).cloned().collect(),
+        '&' => first.intersection(second).cloned().collect(),
+        '-' => first.difference(second).cloned().collect(),
+        '^' => first.symmetric_difference(second).cloned().collect(),
+        _ => unreachable!("sets only have the set operators"),
+    }
+}
+
+/// `|`, `&`, `-` and `^` between sets and frozensets, `None` if either operand isn't one. The
+/// result has the type of the first operand, like in CPython.
+pub(crate) fn operation(operator: char, first: &Value, second: &Value) -> Option<Value> {
+    let result = with_items(first, |first| with_items(second, |second| combine(operator, first, second)))??;
+    Some(match first {
+        Value::Set(_) => Value::Set(Rc::new(RefCell::new(result))),
+        _ => Value::FrozenSet(Rc::new(result)),
+    })
+}
+
+/// `|=`, `&=`, `-=` and `^=`, which change a set in place and give it back. `None` if the first
+/// operand isn't a set or the second isn't a set or frozenset.
+pub(crate) fn operation_in_place(operator: char, first: &Value, second: &Value) -> Option<Value> {
+    let set = match first {
+        Value::Set(set) => set,
+        _ => return None,
+    };
+    let result = with_items(first, |first| with_items(second, |second| combine(operator, first, second)))??;
+    *set.borrow_mut() = result;
+    Some(first.clone())
+}
+
+pub(crate) fn get_attr(set: &Rc<RefCell<Set>>, name: &str) -> Option<Value> {
+    let method: NativeFn = match name {
+        "add" => |_, args| {
+            expect_args("add", &args[1..], 1, 1)?;
+            add(receiver(&args), args[1].clone())?;
+            Ok(Value::Nonetype)
+        },
+        "remove" => |_, args| {
+            expect_args("remove", &args[1..], 1, 1)?;
+            args[1].python_hash()?;
+            if !receiver(&args).borrow_mut().remove(&args[1]) {
+                return Err(VmError::KeyError(args[1].python_repr()));
+            }
+            Ok(Value::Nonetype)
+        },
+        "discard" => |_, args| {
+            expect_args("discard", &args[1..], 1, 1)?;
+            args[1].python_hash()?;
+            receiver(&args).borrow_mut().remove(&args[1]);
+            Ok(Value::Nonetype)
+        },
+        "update" => |_, args| {
+            for iterable in &args[1..] {
+                update(receiver(&args), iterable)?;
+            }
+            Ok(Value::Nonetype)
+        },
+        _ => return None,
+    };
+
+    Some(bound_method(Value::Set(Rc::clone(set)), name, method))
+}
+
+/// Writes the items in the order `repr` shows them, so the same set is always written the same.
+pub(crate) fn serialize<S: Serializer>(set: &Rc<RefCell<Set>>, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_frozen(&set.borrow(), serializer)
+}
+
+pub(crate) fn serialize_frozen<S: Serializer>(set: &Set, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(sorted_items(set))
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rc<RefCell<Set>>, D::Error> {
+    deserialize_items(deserializer).map(|set| Rc::new(RefCell::new(set)))
+}
+
+pub(crate) fn deserialize_frozen<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rc<Set>, D::Error> {
+    deserialize_items(deserializer).map(Rc::new)
+}
+
+/// The set of a list of items, an error when one of them is unhashable.
+fn deserialize_items<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Set, D::Error> {
+    let items = Vec::<Value>::deserialize(deserializer)?;
+    from_items(items).map_err(de::Error::custom)
+}
+
+fn receiver(args: &[Value]) -> &RefCell<Set> {
+    match &args[0] {
+        Value::Set(set) => set,
+        _ => unreachable!("set methods are only reachable through a bound set"),
+    }
+}
@@ -0,0 +1,103 @@
+//! `slice` objects, which `BUILD_SLICE` makes for `a[start:stop:step]`, and slicing tuples,
+//! strings and bytes with them.
+
+use std::fmt;
+use std::rc::Rc;
+
+use crate::modules::{bound_method, expect_args};
+use crate::{NativeFn, Value, VmError};
+
+pub(crate) struct Slice {
+    pub(crate) start: Value,
+    pub(crate) stop: Value,
+    pub(crate) step: Value,
+}
+
+impl fmt::Debug for Slice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "slice({}, {}, {})", self.start.python_repr(), self.stop.python_repr(), self.step.python_repr())
+    }
+}
+
+/// A slice bound as a number, `None` if it was left out.
+fn bound(value: &Value) -> Result<Option<i64>, VmError> {
+    match value {
+        Value::Nonetype => Ok(None),
+        Value::Int(number) => Ok(Some(*number)),
+        Value::Bool(value) => Ok(Some(i64::from(*value))),
+        _ => Err(VmError::TypeError(String::from("slice indices must be integers or None or have an __index__ method"))),
+    }
+}
+
+/// Moves a bound counted from the end into the sequence, then clamps it to the range a walk
+/// in the direction of `step` can start or stop at.
+fn adjust(index: i64, length: i64, step: i64) -> i64 {
+    if index < 0 {
+        let index = index + length;
+        if index < 0 { if step < 0 { -1 } else { 0 } } else { index }
+    } else if index >= length {
+        if step < 0 { length - 1 } else { length }
+    } else {
+        index
+    }
+}
+
+impl Slice {
+    pub(crate) fn get_attr(slice: &Rc<Slice>, name: &str) -> Option<Value> {
+        let method: NativeFn = match name {
+            "start" => return Some(slice.start.clone()),
+            "stop" => return Some(slice.stop.clone()),
+            "step" => return Some(slice.step.clone()),
+            "indices" => |_, args| {
+                expect_args("indices", &args[1..], 1, 1)?;
+                let slice = match &args[0] {
+                    Value::Slice(slice) => slice,
+                    _ => unreachable!("slice methods are only reachable through a bound slice"),
+                };
+                let length = match bound(&args[1])? {
+                    Some(length) if length >= 0 => length,
+                    Some(_) => return Err(VmError::ValueError(String::from("length should not be negative"))),
+                    None => return Err(VmError::TypeError(String::from("'NoneType' object cannot be interpreted as an integer"))),
+                };
+                let (start, stop, step) = slice.indices(length)?;
+                Ok(Value::Tuple(vec![Value::Int(start), Value::Int(stop), Value::Int(step)]))
+            },
+            _ => return None,
+        };
+
+        Some(bound_method(Value::Slice(Rc::clone(slice)), name, method))
+    }
+
+    /// `PySlice_GetIndicesEx`: the start, stop and step the slice selects in a sequence of
+    /// `length` items, with `None` replaced by the defaults and negative bounds counted from
+    /// the end.
+    pub(crate) fn indices(&self, length: i64) -> Result<(i64, i64, i64), VmError> {
+        let step = bound(&self.step)?.unwrap_or(1);
+        if step == 0 {
+            return Err(VmError::ValueError(String::from("slice step cannot be zero")));
+        }
+        let start = match bound(&self.start)? {
+            Some(start) => adjust(start, length, step),
+            None if step < 0 => length - 1,
+            None => 0,
+        };
+        let stop = match bound(&self.stop)? {
+            Some(stop) => adjust(stop, length, step),
+            None if step < 0 => -1,
+            None => length,
+        };
+        Ok((start, stop, step))
+    }
+
+    /// The items of `items` the slice selects, in order.
+    pub(crate) fn select<T: Clone>(&self, items: &[T]) -> Result<Vec<T>, VmError> {
+        let (start, stop, step) = self.indices(items.len() as i64)?;
+        let mut selected = vec![];
+        let mut index = start;
+        while (step > 0 && index < stop) || (step < 0 && index > stop) {
+            selected.push(items[index as usize].clone());
+            index += step;
+        }
+        Ok(selected)
+    }
+}
@@ -0,0 +1,95 @@
+//! `--snapshot` and `--verify-snapshot`: the state of the running frame after every
+//! instruction, one JSON object per line, to find the instruction a regression starts at.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::rc::Rc;
+
+use regex::Regex;
+use serde_json::json;
+
+use crate::{Namespace, Vm};
+
+thread_local! {
+    static ADDRESS: Regex = Regex::new("0x[0-9a-f]+").unwrap();
+}
+
+pub(crate) enum Snapshot {
+    Record(BufWriter<File>),
+    Verify {
+        lines: Lines<BufReader<File>>,
+        line_number: usize,
+        /// The first line that didn't match, with the line the run produced instead.
+        mismatch: Option<(usize, String, String)>,
+    },
+}
+
+impl Snapshot {
+    pub(crate) fn record(path: &str) -> Result<Snapshot, String> {
+        let file = File::create(path).map_err(|error| format!("Can't create {}: {}", path, error))?;
+        Ok(Snapshot::Record(BufWriter::new(file)))
+    }
+
+    pub(crate) fn verify(path: &str) -> Result<Snapshot, String> {
+        let file = File::open(path).map_err(|error| format!("Can't open {}: {}", path, error))?;
+        Ok(Snapshot::Verify { lines: BufReader::new(file).lines(), line_number: 0, mismatch: None })
+    }
+
+    fn step(&mut self, line: String) {
+        match self {
+            Snapshot::Record(file) => writeln!(file, "{}", line).expect("writing the snapshot failed"),
+            Snapshot::Verify { lines, line_number, mismatch } => {
+                *line_number += 1;
+                let expected = lines.next().map(|expected| expected.expect("reading the snapshot failed"));
+                if mismatch.is_none() && expected.as_ref() != Some(&line) {
+                    let expected = expected.unwrap_or_else(|| String::from("<end of snapshot>"));
+                    *mismatch = Some((*line_number, expected, line));
+                }
+            },
+        }
+    }
+
+    /// Flushes a recorded snapshot, or reports the first difference from a verified one.
+    pub(crate) fn finish(self) -> Result<(), String> {
+        match self {
+            Snapshot::Record(mut file) => file.flush().map_err(|error| error.to_string()),
+            Snapshot::Verify { mut lines, line_number, mismatch } => match mismatch {
+                Some((line_number, expected, actual)) => Err(format!(
+                    "Snapshot differs at line {}\nexpected: {}\n  actual: {}", line_number, expected, actual
+                )),
+                None if lines.next().is_some() => Err(format!("The run ended after {} lines of the snapshot", line_number)),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// Reprs of a namespace, sorted so the line doesn't depend on the order of the `HashMap`.
+fn reprs(namespace: &Namespace) -> BTreeMap<String, String> {
+    namespace.borrow().iter().map(|(name, value)| (name.to_string(), value.python_repr())).collect()
+}
+
+impl Vm {
+    /// Adds the state of the running frame to the snapshot, if there is one.
+    pub(crate) fn snapshot_step(&mut self) {
+        if self.snapshot.is_none() {
+            return;
+        }
+
+        let state = self.call_stack.last().unwrap();
+        // Module level code has the globals as its locals, they are only written once.
+        let locals = if Rc::ptr_eq(&state.locals, &self.globals) { None } else { Some(reprs(&state.locals)) };
+        let line = json!({
+            "depth": self.call_stack.len(),
+            "index": state.index,
+            "stack": state.stack.iter().map(|value| value.python_repr()).collect::<Vec<_>>(),
+            "locals": locals,
+            "globals": reprs(&self.globals),
+        }).to_string();
+        // Addresses in reprs change from run to run.
+        let line = ADDRESS.with(|address| address.replace_all(&line, "0x?").into_owned());
+
+        self.snapshot.as_mut().unwrap().step(line);
+    }
+}
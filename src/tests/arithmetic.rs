@@ -0,0 +1,129 @@
+use super::{assert_globals, run, run_program, stack_reprs};
+use crate::exceptions::exception_type;
+use crate::{FrameBuilder, Instruction, Value, VmConfig};
+
+/// `first <instruction> second` with both operands as constants.
+fn binary(first: Value, second: Value, instruction: Instruction) -> String {
+    let frame = FrameBuilder::new("<module>")
+        .constant(first)
+        .constant(second)
+        .instruction(Instruction::LoadConst(0))
+        .instruction(Instruction::LoadConst(1))
+        .instruction(instruction)
+        .build();
+    stack_reprs(&frame).join(", ")
+}
+
+#[test]
+fn float_addition_rounds_like_cpython() {
+    let frame = FrameBuilder::new("<module>")
+        .constant(Value::Float(0.1))
+        .constant(Value::Float(0.2))
+        .constant(Value::Float(0.30000000000000004))
+        .instruction(Instruction::LoadConst(0))
+        .instruction(Instruction::LoadConst(1))
+        .instruction(Instruction::BinaryAdd)
+        .instruction(Instruction::LoadConst(2))
+        .instruction(Instruction::CompareOp(2))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["True"]);
+    assert_eq!(binary(Value::Float(0.1), Value::Float(0.2), Instruction::BinaryAdd), "0.30000000000000004");
+}
+
+#[test]
+fn floor_division_of_floats_is_a_float() {
+    assert_eq!(binary(Value::Float(7.5), Value::Int(2), Instruction::BinaryFloorDivide), "3.0");
+    assert_eq!(binary(Value::Float(-7.0), Value::Int(2), Instruction::BinaryFloorDivide), "-4.0");
+    assert_eq!(binary(Value::Int(7), Value::Float(-2.0), Instruction::BinaryFloorDivide), "-4.0");
+    assert_eq!(binary(Value::Float(1.0), Value::Float(0.1), Instruction::BinaryFloorDivide), "9.0");
+    assert_eq!(binary(Value::Float(-0.5), Value::Float(2.0), Instruction::BinaryFloorDivide), "-1.0");
+}
+
+#[test]
+fn floor_division_of_ints_is_an_int() {
+    assert_eq!(binary(Value::Int(7), Value::Int(2), Instruction::BinaryFloorDivide), "3");
+    assert_eq!(binary(Value::Int(-7), Value::Int(2), Instruction::BinaryFloorDivide), "-4");
+    assert_eq!(binary(Value::Bool(true), Value::Int(2), Instruction::BinaryFloorDivide), "0");
+}
+
+/// `-operand` with the operand as a constant.
+fn negative(operand: Value) -> String {
+    let frame = FrameBuilder::new("<module>").push_const(operand).instruction(Instruction::UnaryNegative).build();
+    stack_reprs(&frame).join(", ")
+}
+
+#[test]
+fn unary_negative_keeps_the_type_of_numbers() {
+    assert_eq!(negative(Value::Int(5)), "-5");
+    assert_eq!(negative(Value::Int(-5)), "5");
+    assert_eq!(negative(Value::Float(2.5)), "-2.5");
+    assert_eq!(negative(Value::Float(0.0)), "-0.0");
+    assert_eq!(negative(Value::Bool(true)), "-1");
+    assert_eq!(negative(Value::Bool(false)), "0");
+
+    let frame = FrameBuilder::new("<module>").push_const(Value::Str(String::from("a"))).instruction(Instruction::UnaryNegative).build();
+    assert_eq!(run(&frame).unwrap_err().to_string(), "TypeError: bad operand type for unary -: 'str'");
+}
+
+/// The error `first <instruction> second` raises.
+fn binary_error(first: Value, second: Value, instruction: Instruction) -> String {
+    let frame = FrameBuilder::new("<module>").push_const(first).push_const(second).instruction(instruction).build();
+    run(&frame).unwrap_err().to_string()
+}
+
+#[test]
+fn division_by_zero_raises_zero_division_error() {
+    let cases = [
+        (Value::Int(1), Value::Int(0), Instruction::BinaryTrueDivide, "division by zero"),
+        (Value::Int(1), Value::Int(0), Instruction::BinaryFloorDivide, "integer division or modulo by zero"),
+        (Value::Float(1.0), Value::Float(0.0), Instruction::BinaryTrueDivide, "float division by zero"),
+        (Value::Float(1.0), Value::Float(0.0), Instruction::BinaryFloorDivide, "float divmod()"),
+        (Value::Int(0), Value::Int(0), Instruction::BinaryTrueDivide, "division by zero"),
+        (Value::Int(1), Value::Bool(false), Instruction::InplaceTrueDivide, "division by zero"),
+    ];
+    for (first, second, instruction, message) in cases {
+        assert_eq!(binary_error(first, second, instruction), format!("ZeroDivisionError: {}", message));
+    }
+}
+
+#[test]
+fn ints_grow_past_64_bits() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/big_ints.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("above", "9223372036854775808"),
+        ("squared", "85070591730234615847396907784232501249"),
+        ("back", "9223372036854775807"),
+        ("negated", "9223372036854775808"),
+        ("below", "-9223372036854775809"),
+        ("f25", "15511210043330985984000000"),
+        ("ratio", "600"),
+        ("divided", "25.0"),
+        ("bigger", "True"),
+        ("narrowed", "True"),
+        ("looked_up", "'f25'"),
+        ("as_text", "'15511210043330985984000000'"),
+    ]);
+}
+
+#[test]
+fn compare_op_tests_membership_identity_and_exception_matches() {
+    let pair = || Value::Tuple(vec![Value::Int(1), Value::Int(2)]);
+    assert_eq!(binary(Value::Int(2), pair(), Instruction::CompareOp(6)), "True");
+    assert_eq!(binary(Value::Int(2), pair(), Instruction::CompareOp(7)), "False");
+    assert_eq!(binary(Value::Int(1), Value::Int(2), Instruction::CompareOp(8)), "False");
+    assert_eq!(binary(Value::Nonetype, Value::Nonetype, Instruction::CompareOp(8)), "True");
+    assert_eq!(binary(Value::Int(1), Value::Nonetype, Instruction::CompareOp(9)), "True");
+
+    // Python 3.8 handlers compare the type of the exception they caught with the clause.
+    let kind = |name| Value::ExceptionType(exception_type(name));
+    assert_eq!(binary(kind("KeyError"), kind("LookupError"), Instruction::CompareOp(10)), "True");
+    assert_eq!(binary(kind("KeyError"), kind("ValueError"), Instruction::CompareOp(10)), "False");
+}
+
+#[test]
+fn ints_past_32_bits_are_exact() {
+    assert_eq!(binary(Value::Int(1 << 20), Value::Int(1 << 20), Instruction::BinaryMultiply), "1099511627776");
+    assert_eq!(binary(Value::Int(65536), Value::Int(65536), Instruction::BinaryMultiply), "4294967296");
+    assert_eq!(binary(Value::Int(i64::from(i32::MAX)), Value::Int(1), Instruction::BinaryAdd), "2147483648");
+    assert_eq!(binary(Value::Int(i64::from(i32::MIN)), Value::Int(1), Instruction::BinarySubtract), "-2147483649");
+}
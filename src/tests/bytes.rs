@@ -0,0 +1,24 @@
+use super::run_program;
+use crate::VmConfig;
+
+#[test]
+fn memoryviews_of_bytearrays_change_them() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/memoryviews.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["changed"], "bytearray(b'hello World')");
+    assert_eq!(globals["first"], "104");
+    assert_eq!(globals["sliced"], "b'or'");
+    assert_eq!(globals["as_list"], "[104, 101, 108, 108, 111]");
+    assert_eq!(globals["sizes"], "(11, 5, 1, 'B')");
+    assert_eq!(globals["readonly"], "True");
+    assert_eq!(globals["write_error"], "'cannot modify read-only memory'");
+}
+
+#[test]
+fn bytearrays_change_in_place() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/bytearrays.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["data"], "bytearray(b'AXYZdef!')");
+    assert_eq!(globals["item"], "89");
+    assert_eq!(globals["copied"], "bytearray(b'AX')");
+    assert_eq!(globals["as_bytes"], "b'AXYZdef!'");
+    assert_eq!(globals["empty"], "bytearray(b'')");
+}
@@ -0,0 +1,270 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::rc::Rc;
+
+use super::{assert_every_version, run, run_program, stack_reprs, temporary_file};
+use crate::profile::Profiler;
+use crate::{Block, BlockKind, FrameBuilder, FrameState, FrameType, Instruction, Value, Vm, VmConfig};
+
+/// `f(first, second, third)` for `def f(a, b, c): return a - b - c`.
+fn subtract(first: i64, second: i64, third: i64) -> String {
+    let function = FrameBuilder::new("f")
+        .parameter("a")
+        .parameter("b")
+        .parameter("c")
+        .load_fast("a")
+        .load_fast("b")
+        .instruction(Instruction::BinarySubtract)
+        .load_fast("c")
+        .instruction(Instruction::BinarySubtract)
+        .return_value()
+        .build();
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Frame(Rc::new(function)))
+        .push_const(Value::Int(first))
+        .push_const(Value::Int(second))
+        .push_const(Value::Int(third))
+        .call_function(3)
+        .build();
+    stack_reprs(&frame).join(", ")
+}
+
+#[test]
+fn positional_arguments_bind_to_parameters_in_order() {
+    assert_eq!(subtract(10, 3, 1), "6");
+    assert_eq!(subtract(1, 2, 3), "-4");
+    assert_eq!(subtract(3, 2, 1), "0");
+}
+
+#[test]
+fn parameters_take_their_defaults() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/defaults.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["default"], "'Hello, a!'");
+    assert_eq!(globals["positional"], "'Hi, b!'");
+    assert_eq!(globals["keyword_only"], "'Hello, c?'");
+    assert_eq!(globals["keywords"], "'Yo, d!'");
+    assert_eq!(globals["too_many"], "'greet() takes from 1 to 2 positional arguments but 3 were given'");
+}
+
+#[test]
+fn deep_self_tail_recursion_needs_the_trampoline() {
+    let source = include_str!("../../bytecode_gen/tests/tail_call.json");
+    let error = run_program(source, VmConfig::default()).unwrap_err();
+    assert_eq!(error, "RecursionError: maximum recursion depth exceeded");
+
+    let config = VmConfig { tail_call_trampoline: true, ..VmConfig::default() };
+    let globals = run_program(source, config).unwrap();
+    assert_eq!(globals["total"], "5000050000");
+}
+
+#[test]
+fn reset_frames_start_over_with_the_new_locals() {
+    let mut state = FrameState::new(Rc::new(RefCell::new(HashMap::new())), FrameType::Function);
+    state.stack.push(Value::Int(1));
+    state.index = 7;
+    state.return_value = Value::Int(2);
+    state.blocks.push(Block { kind: BlockKind::Finally, handler: 9, level: 0 });
+    state.yielded = true;
+    state.kw_names = Some(vec![Rc::new(String::from("key"))]);
+
+    let locals = Rc::new(RefCell::new(HashMap::from([(Rc::new(String::from("n")), Value::Int(3))])));
+    state.reset(Rc::clone(&locals));
+    assert!(state.stack.is_empty() && state.blocks.is_empty());
+    assert_eq!(state.index, 0);
+    assert!(matches!(state.return_value, Value::Nonetype));
+    assert!(!state.yielded && state.kw_names.is_none());
+    assert!(Rc::ptr_eq(&state.locals, &locals));
+    assert_eq!(state.frame_type, FrameType::Function);
+}
+
+#[test]
+fn interpreter_exit_returns_the_top_item_from_a_call() {
+    let function = FrameBuilder::new("f")
+        .push_const(Value::Int(5))
+        .instruction(Instruction::InterpreterExit)
+        .push_const(Value::Int(6))
+        .return_value()
+        .build();
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Frame(Rc::new(function)))
+        .call_function(0)
+        .build();
+    assert_eq!(stack_reprs(&frame), ["5"]);
+}
+
+#[test]
+fn interpreter_exit_ends_module_code() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .push_const(Value::Int(2))
+        .instruction(Instruction::InterpreterExit)
+        .push_const(Value::Int(3))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["1"]);
+}
+
+/// `def f(a, b): return a - b`, then `PUSH_NULL` and `f` for a Python 3.11 call of it.
+fn push_subtract_for_call() -> FrameBuilder {
+    let function = FrameBuilder::new("f")
+        .parameter("a")
+        .parameter("b")
+        .load_fast("a")
+        .load_fast("b")
+        .instruction(Instruction::BinarySubtract)
+        .return_value()
+        .build();
+    FrameBuilder::new("<module>")
+        .instruction(Instruction::PushNull)
+        .push_const(Value::Frame(Rc::new(function)))
+}
+
+fn names(names: &[&str]) -> Value {
+    Value::Tuple(names.iter().map(|name| Value::Str(String::from(*name))).collect())
+}
+
+#[test]
+fn kw_names_passes_the_last_arguments_by_name() {
+    let frame = push_subtract_for_call()
+        .push_const(Value::Int(3))
+        .push_const(Value::Int(10))
+        .constant(names(&["b", "a"]))
+        .instruction(Instruction::KwNames(3))
+        .instruction(Instruction::Call(2))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["7"]);
+}
+
+#[test]
+fn kw_names_can_follow_positional_arguments() {
+    let frame = push_subtract_for_call()
+        .push_const(Value::Int(10))
+        .push_const(Value::Int(4))
+        .constant(names(&["b"]))
+        .instruction(Instruction::KwNames(3))
+        .instruction(Instruction::Call(2))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["6"]);
+}
+
+#[test]
+fn kw_names_only_names_the_arguments_of_the_next_call() {
+    let frame = push_subtract_for_call()
+        .push_const(Value::Int(1))
+        .push_const(Value::Int(2))
+        .constant(names(&["b", "a"]))
+        .instruction(Instruction::KwNames(3))
+        .instruction(Instruction::Call(2))
+        .instruction(Instruction::PushNull)
+        .instruction(Instruction::LoadConst(0))
+        .push_const(Value::Int(1))
+        .push_const(Value::Int(2))
+        .instruction(Instruction::Call(2))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["1", "-1"]);
+}
+
+#[test]
+fn kw_names_of_a_constant_that_is_not_a_tuple_is_an_error() {
+    let frame = push_subtract_for_call().push_const(Value::Int(1)).instruction(Instruction::KwNames(1)).build();
+    assert_eq!(run(&frame).unwrap_err().to_string(), "InternalError: keyword names 1 are not a tuple");
+}
+
+#[test]
+fn profiles_count_instructions_and_calls_of_each_function() {
+    let function = FrameBuilder::new("f").push_const(Value::Int(1)).return_value().build();
+    let function = Value::Frame(Rc::new(function));
+    let frame = FrameBuilder::new("<module>")
+        .push_const(function.clone())
+        .call_function(0)
+        .pop_top()
+        .push_const(function)
+        .call_function(0)
+        .build();
+    let mut vm = Vm::new(VmConfig::default());
+    vm.profiler = Some(Profiler::default());
+    vm.run_frame(&frame).unwrap();
+
+    let path = temporary_file("profile.out", "");
+    vm.profiler.unwrap().write(path.to_str().unwrap(), "test").unwrap();
+    let profile = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(profile.starts_with("# callgrind format\n"), "{}", profile);
+    assert!(profile.contains("summary: 9\n"), "{}", profile);
+    assert!(profile.contains("\nfn=<module>\n0 5\ncfn=f\ncalls=2 0\n0 4\n"), "{}", profile);
+    assert!(profile.contains("\nfn=f\n0 4\n"), "{}", profile);
+}
+
+#[test]
+fn build_tuple_unpack_with_call_joins_the_iterables_into_one_tuple() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .build_list(1)
+        .push_const(Value::Tuple(vec![Value::Int(2), Value::Int(3)]))
+        .instruction(Instruction::BuildTupleUnpackWithCall(2))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["(1, 2, 3)"]);
+
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .instruction(Instruction::BuildTupleUnpackWithCall(1))
+        .build();
+    assert_eq!(run(&frame).unwrap_err().to_string(), "TypeError: argument after * must be an iterable, not int");
+}
+
+#[test]
+fn positional_only_parameters_refuse_keyword_arguments() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/positional_only.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/positional_only.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("positional", "'Hi, Ann!'"),
+        ("by_keyword", "'Hi, Ann?'"),
+        ("shared_name", "'first/second'"),
+        ("passed_by_keyword", "\"greet() got some positional-only arguments passed as keyword arguments: 'name'\""),
+    ]);
+}
+
+#[test]
+fn keyword_only_parameters_refuse_positional_arguments() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/keyword_only.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/keyword_only.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("named", "'main:80x10'"),
+        ("overridden", "'main:80x5'"),
+        ("passed_positionally", "'window() takes 1 positional argument but 2 were given'"),
+        ("missing", "\"window() missing 1 required keyword-only argument: 'width'\""),
+    ]);
+}
+
+#[test]
+fn star_args_collects_the_extra_positional_arguments() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/varargs.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/varargs.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("extra", "(2, 3)"),
+        ("none_extra", "()"),
+        ("summed", "10"),
+        ("spread", "18"),
+    ]);
+}
+
+#[test]
+fn star_star_kwargs_collects_the_extra_keyword_arguments() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/varkwargs.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/varkwargs.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("extra", "{'width': 3, 'depth': 4}"),
+        ("none_extra", "{}"),
+        ("described", "'red large'"),
+        ("spread", "'blue small'"),
+        ("twice", "\"options() got multiple values for argument 'name'\""),
+    ]);
+}
@@ -0,0 +1,106 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{run, stack_reprs};
+use crate::{Frame, FrameBuilder, Instruction, Value, VmError};
+
+fn names(names: &[&str]) -> Vec<Rc<String>> {
+    names.iter().map(|name| Rc::new(String::from(*name))).collect()
+}
+
+/// `builder`'s frame with cell variables `cellvars`.
+fn with_cells(builder: FrameBuilder, cellvars: &[&str]) -> Frame {
+    let mut frame = builder.build();
+    frame.co_cellvars = names(cellvars);
+    frame
+}
+
+#[test]
+fn make_cell_puts_a_parameter_in_a_cell() {
+    let builder = FrameBuilder::new("<module>")
+        .push_const(Value::Int(7))
+        .store_fast("x")
+        .instruction(Instruction::MakeCell(0))
+        .instruction(Instruction::LoadDeref(0))
+        .instruction(Instruction::LoadClosure(0));
+    let stack = run(&with_cells(builder, &["x"])).unwrap();
+    assert_eq!(stack[0].python_repr(), "7");
+    assert!(matches!(&stack[1], Value::Cell(cell) if matches!(*cell.borrow(), Value::Int(7))));
+}
+
+#[test]
+fn make_cell_counts_cells_that_are_not_parameters_after_the_locals() {
+    let builder = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .store_fast("local")
+        .instruction(Instruction::MakeCell(1))
+        .push_const(Value::Int(2))
+        .instruction(Instruction::StoreDeref(0))
+        .instruction(Instruction::LoadDeref(0))
+        .load_fast("local");
+    assert_eq!(stack_reprs(&with_cells(builder, &["cell"])), ["2", "1"]);
+}
+
+#[test]
+fn make_cell_of_an_unbound_local_makes_an_empty_cell() {
+    let builder = FrameBuilder::new("<module>")
+        .instruction(Instruction::MakeCell(0))
+        .instruction(Instruction::LoadDeref(0));
+    let error = run(&with_cells(builder, &["cell"])).unwrap_err();
+    assert_eq!(error.to_string(), "UnboundLocalError: local variable 'cell' referenced before assignment");
+}
+
+#[test]
+fn make_cell_out_of_range_is_an_error() {
+    let builder = FrameBuilder::new("<module>").instruction(Instruction::MakeCell(1));
+    assert!(matches!(run(&with_cells(builder, &["cell"])), Err(VmError::InternalError(_))));
+}
+
+/// `builder`'s frame with free variables `freevars`, closing over cells holding `values`.
+fn with_closure(builder: FrameBuilder, freevars: &[&str], values: Vec<Value>) -> Frame {
+    let mut frame = builder.build();
+    frame.co_freevars = names(freevars);
+    frame.closure = values.into_iter().map(|value| Rc::new(RefCell::new(value))).collect();
+    frame
+}
+
+#[test]
+fn copy_free_vars_binds_free_variables_to_the_closure() {
+    let builder = FrameBuilder::new("<module>")
+        .instruction(Instruction::CopyFreeVars(2))
+        .instruction(Instruction::LoadDeref(1))
+        .instruction(Instruction::LoadDeref(0));
+    let frame = with_closure(builder, &["x", "y"], vec![Value::Int(1), Value::Int(2)]);
+    assert_eq!(stack_reprs(&frame), ["2", "1"]);
+}
+
+#[test]
+fn copy_free_vars_shares_the_cells_of_the_closure() {
+    let builder = FrameBuilder::new("<module>")
+        .instruction(Instruction::CopyFreeVars(1))
+        .push_const(Value::Int(9))
+        .instruction(Instruction::StoreDeref(0));
+    let frame = with_closure(builder, &["x"], vec![Value::Int(1)]);
+    run(&frame).unwrap();
+    assert!(matches!(*frame.closure[0].borrow(), Value::Int(9)));
+}
+
+#[test]
+fn copy_free_vars_only_binds_the_first_arg_free_variables() {
+    let builder = FrameBuilder::new("<module>")
+        .instruction(Instruction::CopyFreeVars(1))
+        .instruction(Instruction::LoadDeref(1));
+    let frame = with_closure(builder, &["x", "y"], vec![Value::Int(1), Value::Int(2)]);
+    assert_eq!(run(&frame).unwrap_err().to_string(), "InternalError: 'y' is not a cell");
+}
+
+#[test]
+fn free_variables_of_a_cell_variable_come_after_it() {
+    let builder = FrameBuilder::new("<module>")
+        .instruction(Instruction::CopyFreeVars(1))
+        .instruction(Instruction::MakeCell(0))
+        .instruction(Instruction::LoadDeref(1));
+    let mut frame = with_closure(builder, &["free"], vec![Value::Int(3)]);
+    frame.co_cellvars = names(&["cell"]);
+    assert_eq!(stack_reprs(&frame), ["3"]);
+}
@@ -0,0 +1,255 @@
+use std::rc::Rc;
+
+use super::{assert_every_version, assert_globals, run, run_program, stack_reprs};
+use crate::{Frame, FrameBuilder, Instruction, Value, VmConfig};
+
+/// A module defining `class Shape:` with `body`, followed by the code `then` adds.
+fn define_shape(body: FrameBuilder, then: impl FnOnce(FrameBuilder) -> FrameBuilder) -> Frame {
+    let body = body.push_const(Value::Nonetype).return_value().build();
+    let module = FrameBuilder::new("<module>")
+        .instruction(Instruction::LoadBuildClass)
+        .push_const(Value::Frame(Rc::new(body)))
+        .push_const(Value::Str(String::from("Shape")))
+        .instruction(Instruction::MakeFunction(0))
+        .push_const(Value::Str(String::from("Shape")))
+        .call_function(2)
+        .store_name("Shape");
+    then(module).build()
+}
+
+#[test]
+fn class_bodies_store_and_load_in_the_class_namespace() {
+    let body = FrameBuilder::new("Shape")
+        .push_const(Value::Int(4))
+        .store_name("sides")
+        .load_name("sides")
+        .load_name("sides")
+        .instruction(Instruction::BinaryAdd)
+        .store_name("corners");
+    let frame = define_shape(body, |module| {
+        module.load_name("Shape").load_attr("sides").load_name("Shape").load_attr("corners")
+    });
+    assert_eq!(stack_reprs(&frame), ["4", "8"]);
+}
+
+#[test]
+fn methods_are_named_after_their_class() {
+    let method = FrameBuilder::new("area").parameter("self").push_const(Value::Int(0)).return_value().build();
+    let body = FrameBuilder::new("Shape")
+        .push_const(Value::Str(String::from("Shape")))
+        .store_name("__qualname__")
+        .push_const(Value::Frame(Rc::new(method)))
+        .push_const(Value::Str(String::from("area")))
+        .instruction(Instruction::MakeFunction(0))
+        .store_name("area");
+    let frame = define_shape(body, |module| module.load_name("Shape").load_attr("area").load_attr("__qualname__"));
+    assert_eq!(stack_reprs(&frame), ["'Shape.area'"]);
+}
+
+#[test]
+fn super_without_arguments_needs_a_method() {
+    let body = FrameBuilder::new("Shape").load_name("super").call_function(0).store_name("parent");
+    let error = run(&define_shape(body, |module| module)).unwrap_err();
+    assert_eq!(error.to_string(), "RuntimeError: super(): no arguments");
+}
+
+#[test]
+fn super_finds_the_methods_of_the_next_class() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/super.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("name", "'square'"),
+        ("sides", "4"),
+        ("description", "'square, shape square with sides'"),
+        ("polygon_description", "'shape square with sides'"),
+        ("shape_description", "'shape square'"),
+        ("wrong_object", "'super(type, obj): obj must be an instance or subtype of type'"),
+        ("outside_method", "'super(): no arguments'"),
+    ]);
+}
+
+#[test]
+fn properties_call_their_getter_setter_and_deleter() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/properties.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("boiling", "212"),
+        ("freezing", "0"),
+        ("deleted", "True"),
+        ("kelvin", "273"),
+        ("kelvin_doc", "'The temperature in kelvin.'"),
+        ("fahrenheit_doc", "'The temperature in fahrenheit.'"),
+        ("has_getter", "True"),
+        ("read_only", "\"can't set attribute\""),
+        ("deleted_extra", "\"'Temperature' object has no attribute 'extra'\""),
+    ]);
+}
+
+#[test]
+fn class_methods_get_the_class_and_static_methods_nothing() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/class_methods.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("from_class", "10"),
+        ("from_instance", "10"),
+        ("kind_from_class", "'Counter'"),
+        ("kind_from_instance", "'Counter'"),
+        ("subclass_kind", "'loud LoudCounter'"),
+        ("subclass_instance_kind", "'loud LoudCounter'"),
+        ("static_from_class", "5"),
+        ("static_from_instance", "9"),
+        ("unwrapped", "13"),
+    ]);
+}
+
+#[test]
+fn classes_derive_from_object() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/object_root.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("bases", "(<class 'object'>,)"),
+        ("mro", "(<class '__main__.Child'>, <class '__main__.Plain'>, <class 'object'>)"),
+        ("equal_to_itself", "True"),
+        ("equal_to_another", "False"),
+        ("not_equal", "True"),
+        ("same_hash", "True"),
+        ("described", "True"),
+        ("ready", "True"),
+    ]);
+}
+
+#[test]
+fn slotted_instances_only_take_their_slots() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/slots.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("coordinates", "(10, 2)"),
+        ("not_a_slot", "\"'Point' object has no attribute 'z'\""),
+        ("unset_slot", "\"'Point' object has no attribute 'y'\""),
+        ("inherited", "(4, 5, 6)"),
+    ]);
+}
+
+#[test]
+fn finalizers_run_when_the_last_reference_goes() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/finalizers.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("after_del", "'first;'"),
+        ("after_return", "'first;local;'"),
+        ("after_rebind", "'first;local;second;'"),
+        ("after_list", "'first;local;second;in list;'"),
+    ]);
+}
+
+#[test]
+fn missing_attributes_name_the_type_of_the_object() {
+    for (value, message) in [
+        (Value::Int(1), "AttributeError: 'int' object has no attribute 'missing'"),
+        (Value::Nonetype, "AttributeError: 'NoneType' object has no attribute 'missing'"),
+    ] {
+        let frame = FrameBuilder::new("<module>").push_const(value).load_attr("missing").build();
+        assert_eq!(run(&frame).unwrap_err().to_string(), message);
+    }
+}
+
+#[test]
+fn class_patterns_match_instances_and_bind_their_attributes() {
+    assert_every_version(&[
+        ("3.10", include_str!("../../bytecode_gen/tests/match_class.3.10.json")),
+        ("3.11", include_str!("../../bytecode_gen/tests/match_class.3.11.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/match_class.3.12.json")),
+    ], &[
+        ("origin", "'origin'"),
+        ("on_x", "'on the x axis at 3'"),
+        ("on_y", "'on the y axis at 4'"),
+        ("point", "'a point'"),
+        ("other", "'something else'"),
+    ]);
+}
+
+#[test]
+fn getitem_is_given_slices() {
+    assert_every_version(&[
+        ("3.8", include_str!("../../bytecode_gen/tests/getitem.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/getitem.3.12.json")),
+    ], &[
+        ("index", "3"),
+        ("full", "slice(1, 5, 2)"),
+        ("parts", "(1, 5, 2)"),
+        ("open_parts", "(None, 2, None)"),
+        ("every_other", "[1, 3, 5]"),
+        ("backwards", "[6, 5, 4, 3, 2, 1]"),
+        ("tail", "[5, 6]"),
+        ("word", "'yth'"),
+    ]);
+}
+
+#[test]
+fn instances_are_true_unless_bool_or_len_say_otherwise() {
+    assert_every_version(&[
+        ("3.8", include_str!("../../bytecode_gen/tests/truthiness.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/truthiness.3.12.json")),
+    ], &[
+        ("when_false", "'skipped'"),
+        ("when_true", "'taken'"),
+        ("chosen", "'fallback'"),
+        ("both", "'second'"),
+        ("empty", "'skipped'"),
+        ("plain", "'taken'"),
+        ("count", "3"),
+    ]);
+}
+
+#[test]
+fn class_bodies_read_the_variables_of_the_enclosing_function() {
+    assert_every_version(&[
+        ("3.8", include_str!("../../bytecode_gen/tests/class_deref.json")),
+        ("3.10", include_str!("../../bytecode_gen/tests/class_deref.3.10.json")),
+        ("3.11", include_str!("../../bytecode_gen/tests/class_deref.3.11.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/class_deref.3.12.json")),
+    ], &[
+        ("capacity", "3"),
+        ("doubled", "6"),
+        ("from_method", "3"),
+        ("seen", "'class attribute'"),
+    ]);
+}
+
+#[test]
+fn in_asks_contains_then_searches_the_iterator() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/contains.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/contains.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("in_bag", "True"),
+        ("not_in_bag", "True"),
+        ("in_countdown", "True"),
+        ("past_countdown", "False"),
+        ("not_iterable", "\"argument of type 'Opaque' is not iterable\""),
+    ]);
+}
+
+#[test]
+fn augmented_assignments_try_the_in_place_method_then_the_binary_ones() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/inplace.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/inplace.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("added", "(11, 22)"),
+        ("same_object", "True"),
+        ("subtracted", "(10, 21)"),
+        ("new_object", "True"),
+        ("multiplied", "(30, 63)"),
+    ]);
+}
+
+#[test]
+fn functions_are_known_by_their_qualified_names() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/qualnames.json")),
+        ("3.11", include_str!("../../bytecode_gen/tests/qualnames.3.11.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/qualnames.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("method", "'Shape.area'"),
+        ("nested", "'outer.<locals>.inner'"),
+        ("too_many", "'Shape.area() takes 1 positional argument but 2 were given'"),
+    ]);
+}
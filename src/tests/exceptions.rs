@@ -0,0 +1,170 @@
+use super::{assert_every_version, assert_globals, run, run_program, stack_reprs, temporary_file};
+use crate::exceptions::{exception_type, Exception};
+use crate::{FrameBuilder, Instruction, Value, VmConfig};
+
+fn exception(name: &str, message: &str) -> Value {
+    Value::Exception(Exception::new(name, String::from(message)))
+}
+
+#[test]
+fn push_exc_info_pushes_the_handled_exception_below_the_new_one() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(exception("ValueError", "outer"))
+        .instruction(Instruction::PushExcInfo)
+        .push_const(exception("KeyError", "inner"))
+        .instruction(Instruction::PushExcInfo)
+        .build();
+    assert_eq!(stack_reprs(&frame), ["None", "ValueError('outer')", "ValueError('outer')", "KeyError('inner')"]);
+}
+
+#[test]
+fn check_exc_match_keeps_the_exception_and_pushes_whether_it_matches() {
+    for (clause, matches) in [("ValueError", "True"), ("Exception", "True"), ("KeyError", "False")] {
+        let frame = FrameBuilder::new("<module>")
+            .push_const(exception("ValueError", "bad"))
+            .load_name(clause)
+            .instruction(Instruction::CheckExcMatch)
+            .build();
+        assert_eq!(stack_reprs(&frame), ["ValueError('bad')", matches], "except {}", clause);
+    }
+}
+
+#[test]
+fn check_exc_match_matches_any_type_of_a_tuple() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(exception("KeyError", "key"))
+        .load_name("IndexError")
+        .load_name("LookupError")
+        .build_tuple(2)
+        .instruction(Instruction::CheckExcMatch)
+        .build();
+    assert_eq!(stack_reprs(&frame), ["KeyError('key')", "True"]);
+}
+
+#[test]
+fn python_3_11_handlers_restore_the_exception_they_interrupted() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/handlers.3.11.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["restored"], "'outer'");
+    assert_eq!(globals["caught"], "\"'missing'\"");
+}
+
+#[test]
+fn with_closes_files_when_its_body_raises() {
+    for (version, source) in [
+        ("3.8", include_str!("../../bytecode_gen/tests/with_files.json")),
+        ("3.10", include_str!("../../bytecode_gen/tests/with_files.3.10.json")),
+        ("3.11", include_str!("../../bytecode_gen/tests/with_files.3.11.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/with_files.3.12.json")),
+    ] {
+        let path = temporary_file(&format!("with_{}.txt", version), "");
+        let source = source.replace("<path>", &path.to_string_lossy());
+        let globals = run_program(source, VmConfig::default()).unwrap_or_else(|error| panic!("Python {}: {}", version, error));
+        assert_globals(&globals, &[
+            ("caught", "'inside'"),
+            ("closed", "True"),
+            ("write_error", "'I/O operation on closed file.'"),
+            ("contents", "'before'"),
+            ("entered", "'entered'"),
+            ("exited", "'suppressed'"),
+        ]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+/// `ExceptionGroup('group', [ValueError('value'), KeyError('key')])` on the stack.
+fn push_group() -> FrameBuilder {
+    FrameBuilder::new("<module>")
+        .load_name("ExceptionGroup")
+        .push_const(Value::Str(String::from("group")))
+        .push_const(exception("ValueError", "value"))
+        .push_const(exception("KeyError", "key"))
+        .build_list(2)
+        .call_function(2)
+}
+
+#[test]
+fn check_eg_match_splits_off_the_matching_exceptions() {
+    let frame = push_group().load_name("ValueError").instruction(Instruction::CheckEgMatch).build();
+    assert_eq!(stack_reprs(&frame), [
+        "ExceptionGroup('group', [KeyError('key')])",
+        "ExceptionGroup('group', [ValueError('value')])",
+    ]);
+}
+
+#[test]
+fn check_eg_match_without_a_match_pushes_none() {
+    let frame = push_group().load_name("TypeError").instruction(Instruction::CheckEgMatch).build();
+    assert_eq!(stack_reprs(&frame), ["ExceptionGroup('group', [ValueError('value'), KeyError('key')])", "None"]);
+}
+
+#[test]
+fn check_eg_match_wraps_a_bare_exception_in_a_group() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(exception("ValueError", "value"))
+        .load_name("ValueError")
+        .instruction(Instruction::CheckEgMatch)
+        .build();
+    assert_eq!(stack_reprs(&frame), ["None", "ExceptionGroup('', (ValueError('value'),))"]);
+}
+
+#[test]
+fn check_eg_match_rejects_exception_group_clauses() {
+    let frame = push_group().load_name("ExceptionGroup").instruction(Instruction::CheckEgMatch).build();
+    assert_eq!(
+        run(&frame).unwrap_err().to_string(),
+        "TypeError: catching ExceptionGroup with except* is not allowed. Use except instead."
+    );
+}
+
+#[test]
+fn failing_asserts_raise_assertion_error_unless_optimized() {
+    for (version, source) in [
+        ("3.8", include_str!("../../bytecode_gen/tests/asserts.json")),
+        ("3.10", include_str!("../../bytecode_gen/tests/asserts.3.10.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/asserts.3.12.json")),
+    ] {
+        let globals = run_program(source, VmConfig::default()).unwrap();
+        assert_eq!(globals["checked"], "1", "Python {}", version);
+        assert_eq!(globals["message"], "'checked is 1'", "Python {}", version);
+
+        let globals = run_program(source, VmConfig { optimize: true, ..VmConfig::default() }).unwrap();
+        assert_eq!(globals["message"], "None", "Python {} with -O", version);
+    }
+}
+
+#[test]
+fn finally_blocks_and_bare_raise_reraise_the_exception() {
+    assert_every_version(&[
+        ("3.8", include_str!("../../bytecode_gen/tests/reraise.json")),
+        ("3.10", include_str!("../../bytecode_gen/tests/reraise.3.10.json")),
+        ("3.11", include_str!("../../bytecode_gen/tests/reraise.3.11.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/reraise.3.12.json")),
+    ], &[
+        ("log", "'finally;handled;named;'"),
+        ("after_finally", "'from the body'"),
+        ("unmatched", "'unmatched'"),
+        ("bare", "'bare raise'"),
+        ("named", "'in the handler'"),
+    ]);
+}
+
+#[test]
+fn jump_if_not_exc_match_pops_both_and_jumps_past_unmatched_handlers() {
+    let kind = |name| Value::ExceptionType(exception_type(name));
+    for (clause, expected) in [
+        (kind("ValueError"), vec!["'handled'", "'after'"]),
+        (kind("Exception"), vec!["'handled'", "'after'"]),
+        (kind("KeyError"), vec!["'after'"]),
+        (Value::Tuple(vec![kind("KeyError"), kind("ValueError")]), vec!["'handled'", "'after'"]),
+    ] {
+        let description = clause.python_repr();
+        let frame = FrameBuilder::new("<module>")
+            .push_const(kind("ValueError"))
+            .push_const(clause)
+            .instruction(Instruction::JumpIfNotExcMatch(8))
+            .push_const(Value::Str(String::from("handled")))
+            .push_const(Value::Str(String::from("after")))
+            .build();
+        assert_eq!(stack_reprs(&frame), expected, "except {}", description);
+    }
+}
@@ -0,0 +1,76 @@
+use std::rc::Rc;
+
+use crate::{Frame, FrameBuilder, FrameState, FrameType, Instruction, Value, Vm, VmConfig};
+
+/// `x = y + 1`, a code object built from JSON like the ones `recursive_dis.py` writes.
+fn code() -> Value {
+    serde_json::from_str(r#"{"Frame": {
+        "instructions": [{"LoadName": 0}, {"LoadConst": 0}, {"BinaryAdd": null}, {"StoreName": 1}, {"LoadName": 1}, {"ReturnValue": null}],
+        "constants": [{"Int": 1}],
+        "co_names": ["y", "x"],
+        "co_varnames": []
+    }}"#).unwrap()
+}
+
+/// A module that calls `function` with the code object and the dicts `{"y": 10}` and
+/// `{"y": 20}`, as many of them as `dicts` says, and leaves the result, the dicts and the
+/// global `x` on the stack. The global `y` is 1.
+fn calling(function: &str, dicts: usize) -> Frame {
+    let mut builder = FrameBuilder::new("<module>")
+        .constant(code())
+        .constant(Value::Int(1))
+        .constant(Value::Str(String::from("y")))
+        .constant(Value::Int(10))
+        .constant(Value::Int(20))
+        .name("y")
+        .name(function)
+        .name("x")
+        .instruction(Instruction::LoadConst(1))
+        .instruction(Instruction::StoreName(0))
+        .instruction(Instruction::LoadConst(2))
+        .instruction(Instruction::LoadConst(3))
+        .instruction(Instruction::BuildMap(1))
+        .instruction(Instruction::LoadConst(2))
+        .instruction(Instruction::LoadConst(4))
+        .instruction(Instruction::BuildMap(1))
+        .instruction(Instruction::LoadName(1))
+        .instruction(Instruction::LoadConst(0));
+    for _ in 0..dicts {
+        builder = builder.instruction(Instruction::Copy(4));
+    }
+    builder
+        .instruction(Instruction::CallFunction(1 + dicts))
+        .instruction(Instruction::LoadName(2))
+        .build()
+}
+
+/// The stack the module leaves, `x` being `None` when it isn't bound.
+fn run(frame: &Frame) -> Vec<String> {
+    let mut vm = Vm::new(VmConfig::default());
+    vm.builtins.insert(Rc::new(String::from("x")), Value::Nonetype);
+    let state = FrameState::new(Rc::clone(&vm.globals), FrameType::Module);
+    let state = vm.run_state(frame, state, None).unwrap();
+    state.stack.iter().map(Value::python_repr).collect()
+}
+
+#[test]
+fn exec_runs_code_in_the_callers_namespace() {
+    assert_eq!(run(&calling("exec", 0)), ["{'y': 10}", "{'y': 20}", "None", "2"]);
+}
+
+#[test]
+fn eval_gives_the_value_of_the_code() {
+    assert_eq!(run(&calling("eval", 0)), ["{'y': 10}", "{'y': 20}", "2", "2"]);
+}
+
+#[test]
+fn exec_with_globals_runs_code_in_them() {
+    assert_eq!(run(&calling("exec", 1)), ["{'y': 10, 'x': 11}", "{'y': 20}", "None", "None"]);
+}
+
+#[test]
+fn exec_with_globals_and_locals_stores_in_the_locals() {
+    // The code reads `y` from the locals, which shadow the globals.
+    assert_eq!(run(&calling("exec", 2)), ["{'y': 10}", "{'y': 20, 'x': 21}", "None", "None"]);
+    assert_eq!(run(&calling("eval", 2)), ["{'y': 10}", "{'y': 20, 'x': 21}", "21", "None"]);
+}
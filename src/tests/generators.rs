@@ -0,0 +1,62 @@
+use super::assert_every_version;
+
+/// `generators.py` compiled by each Python version.
+const GENERATORS: [(&str, &str); 4] = [
+    ("3.8", include_str!("../../bytecode_gen/tests/generators.json")),
+    ("3.10", include_str!("../../bytecode_gen/tests/generators.3.10.json")),
+    ("3.11", include_str!("../../bytecode_gen/tests/generators.3.11.json")),
+    ("3.12", include_str!("../../bytecode_gen/tests/generators.3.12.json")),
+];
+
+/// `coroutines.py` compiled by each Python version.
+const COROUTINES: [(&str, &str); 4] = [
+    ("3.8", include_str!("../../bytecode_gen/tests/coroutines.json")),
+    ("3.10", include_str!("../../bytecode_gen/tests/coroutines.3.10.json")),
+    ("3.11", include_str!("../../bytecode_gen/tests/coroutines.3.11.json")),
+    ("3.12", include_str!("../../bytecode_gen/tests/coroutines.3.12.json")),
+];
+
+#[test]
+fn generators_are_driven_by_send_throw_and_close() {
+    assert_every_version(&GENERATORS, &[
+        ("started", "'ready'"),
+        ("sent", "'ready'"),
+        ("thrown", "'ready'"),
+        ("echo_log", "[42, 'caught bad', 'closed']"),
+        ("returned", "('done',)"),
+    ]);
+}
+
+#[test]
+fn yield_from_delegates_to_generators() {
+    assert_every_version(&GENERATORS, &[
+        ("delegated", "['inner', 42, 0, 1, 2, 4, 5]"),
+        ("thrown_through", "'handled'"),
+        ("closing_log", "['inner closed', 'outer closed']"),
+    ]);
+}
+
+#[test]
+fn yield_from_iterates_sequences() {
+    assert_every_version(&GENERATORS, &[("plain_items", "[1, 2, 3, 'a', 'b']")]);
+}
+
+#[test]
+fn coroutines_are_driven_by_send() {
+    assert_every_version(&COROUTINES, &[
+        ("waiting", "'waiting'"),
+        ("result", "('finished',)"),
+    ]);
+}
+
+#[test]
+fn async_with_awaits_enter_and_exit() {
+    assert_every_version(&COROUTINES, &[
+        ("log", "[42, 'sent', 'enter', 'resource', 'exit None', 'enter', 'exit inside', 'caught inside']"),
+    ]);
+}
+
+#[test]
+fn async_for_ends_at_stop_async_iteration() {
+    assert_every_version(&COROUTINES, &[("numbers", "[1, 2, 3]"), ("result", "('finished',)")]);
+}
@@ -0,0 +1,74 @@
+use super::{run, run_program, stack_reprs};
+use crate::{FrameBuilder, Instruction, Value, VmConfig};
+
+#[test]
+fn intrinsic_print_pushes_none_and_binds_underscore() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(5))
+        .instruction(Instruction::CallIntrinsic1(1))
+        .load_name("_")
+        .build();
+    assert_eq!(stack_reprs(&frame), ["None", "5"]);
+}
+
+#[test]
+fn print_expr_pops_the_value_and_binds_underscore_unless_it_is_none() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .push_const(Value::Int(5))
+        .instruction(Instruction::PrintExpr)
+        .push_const(Value::Nonetype)
+        .instruction(Instruction::PrintExpr)
+        .load_name("_")
+        .build();
+    assert_eq!(stack_reprs(&frame), ["1", "5"]);
+}
+
+#[test]
+fn intrinsic_import_star_binds_the_public_names_of_the_module() {
+    let frame = FrameBuilder::new("<module>")
+        .name("math")
+        .push_const(Value::Int(0))
+        .push_const(Value::Tuple(vec![Value::Str(String::from("*"))]))
+        .instruction(Instruction::ImportName(0))
+        .instruction(Instruction::CallIntrinsic1(2))
+        .pop_top()
+        .load_name("pi")
+        .build();
+    assert_eq!(stack_reprs(&frame), ["3.141592653589793"]);
+}
+
+#[test]
+fn unknown_and_unimplemented_intrinsics_are_errors() {
+    for (arg, message) in [
+        (0, "NotImplementedError: CALL_INTRINSIC_1 INTRINSIC_1_INVALID"),
+        (12, "InternalError: CALL_INTRINSIC_1 with unknown intrinsic 12"),
+    ] {
+        let frame = FrameBuilder::new("<module>")
+            .push_const(Value::Int(1))
+            .instruction(Instruction::CallIntrinsic1(arg))
+            .build();
+        assert_eq!(run(&frame).unwrap_err().to_string(), message);
+    }
+}
+
+#[test]
+fn python_3_12_calls_intrinsics() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/intrinsics.3.12.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["positive"], "1");
+    assert_eq!(globals["unpacked"], "(1, 2, 3)");
+    assert_eq!(globals["circle"], "31");
+}
+
+#[test]
+fn list_to_tuple_replaces_the_list_with_a_tuple_of_its_items() {
+    for instruction in [Instruction::CallIntrinsic1(6), Instruction::ListToTuple] {
+        let frame = FrameBuilder::new("<module>")
+            .push_const(Value::Int(1))
+            .push_const(Value::Int(2))
+            .build_list(2)
+            .instruction(instruction)
+            .build();
+        assert_eq!(stack_reprs(&frame), ["(1, 2)"], "{:?}", instruction);
+    }
+}
@@ -0,0 +1,42 @@
+use super::stack_reprs;
+use crate::{relative_target, FrameBuilder, Instruction, Value};
+
+fn string(value: &str) -> Value {
+    Value::Str(String::from(value))
+}
+
+#[test]
+fn jump_forward_skips_arg_bytes_after_the_next_instruction() {
+    assert_eq!(relative_target(3, 4), 6);
+    assert_eq!(relative_target(3, 0), 4);
+
+    let frame = FrameBuilder::new("<module>")
+        .push_const(string("0"))
+        .push_const(string("1"))
+        .push_const(string("2"))
+        .instruction(Instruction::JumpForward(4))
+        .push_const(string("skipped 4"))
+        .push_const(string("skipped 5"))
+        .push_const(string("landed on 6"))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["'0'", "'1'", "'2'", "'landed on 6'"]);
+}
+
+#[test]
+fn jump_forward_by_nothing_runs_the_next_instruction() {
+    let frame = FrameBuilder::new("<module>")
+        .instruction(Instruction::JumpForward(0))
+        .push_const(string("next"))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["'next'"]);
+}
+
+#[test]
+fn jump_absolute_lands_on_half_its_byte_offset() {
+    let frame = FrameBuilder::new("<module>")
+        .instruction(Instruction::JumpAbsolute(4))
+        .push_const(string("skipped 1"))
+        .push_const(string("landed on 2"))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["'landed on 2'"]);
+}
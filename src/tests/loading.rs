@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use super::{run_program, temporary_file};
+use crate::snapshot::Snapshot;
+use crate::{check_report, load_frame, on_vm_stack, Cache, Frame, Options, Vm, VmConfig};
+
+fn load(path: &str) -> Result<Frame, String> {
+    let options = Options::parse(vec![String::from(path)].into_iter()).unwrap();
+    load_frame(&options)
+}
+
+/// The repr of each global `frame` leaves when it is run as a module.
+fn globals_after(frame: &Frame) -> BTreeMap<String, String> {
+    let mut vm = Vm::new(VmConfig::default());
+    vm.run_frame(frame).unwrap();
+    let globals = vm.globals.borrow();
+    globals.iter().map(|(name, value)| (name.to_string(), value.python_repr())).collect()
+}
+
+#[test]
+fn missing_file_is_an_error() {
+    let error = load("/nonexistent/bytecode.json").unwrap_err();
+    assert!(error.starts_with("Can't read /nonexistent/bytecode.json"), "{}", error);
+}
+
+#[test]
+fn malformed_json_is_an_error() {
+    let path = temporary_file("malformed.json", "{\"instructions\": [");
+    let error = load(path.to_str().unwrap()).unwrap_err();
+    assert!(error.starts_with("Invalid bytecode: EOF while parsing"), "{}", error);
+}
+
+#[test]
+fn json_that_is_not_a_frame_is_an_error() {
+    let path = temporary_file("not_a_frame.json", "{\"instructions\": [{\"LoadConst\": 0}]}");
+    let error = load(path.to_str().unwrap()).unwrap_err();
+    assert!(error.contains("missing field `constants`"), "{}", error);
+}
+
+#[test]
+fn check_reports_every_issue_of_a_bad_file() {
+    let options = Options::parse(vec![String::from("--check"), String::from("bytecode_gen/tests/invalid.json")].into_iter()).unwrap();
+    assert!(options.check);
+    let (report, status) = check_report(&load_frame(&options).unwrap());
+    assert_eq!(report, "\
+error: <module>: instruction 0: constant 5 out of range (3 constants)
+error: <module>: instruction 1: name 3 out of range (1 co_names)
+error: <module>: instruction 3: jump target 20 out of range (7 instructions)
+warning: <module>: instruction 6 is unreachable
+error: <module>.constants[2]: instruction 0: local variable 1 out of range (1 co_varnames)
+4 errors, 1 warnings
+");
+    assert_eq!(status, 2);
+}
+
+#[test]
+fn check_passes_a_file_with_only_warnings() {
+    let path = temporary_file("only_warnings.json", r#"{"instructions": [{"LoadConst": 0}, {"ReturnValue": null}, {"Nop": null}],
+        "constants": [{"Nonetype": null}], "co_name": "<module>", "co_names": [], "co_varnames": [], "co_argcount": 0,
+        "co_posonlyargcount": 0, "co_kwonlyargcount": 0, "co_flags": 64, "co_cellvars": [], "co_freevars": []}"#);
+    let (report, status) = check_report(&load(path.to_str().unwrap()).unwrap());
+    assert_eq!(report, "warning: <module>: instruction 2 is unreachable\n0 errors, 1 warnings\n");
+    assert_eq!(status, 0);
+}
+
+#[test]
+fn every_python_version_runs_a_program_the_same() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/versions.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["keyword_greeting"], "'Hi, there!'");
+    assert_eq!(globals["count"], "16");
+    assert_eq!(globals["square_list"], "[0, 1, 4, 9, 16]");
+    assert_eq!(globals["speech"], "'Rex makes a sound, woof'");
+    assert_eq!(globals["classes"], "['negative', 'zero', 'big even', 'positive']");
+    assert_eq!(globals["division_error"], "'integer division or modulo by zero'");
+    assert_eq!(globals["formatted"], "'   6|16'");
+    assert_eq!(globals["sliced"], "[1, 4]");
+
+    for (version, source) in [
+        ("3.10", include_str!("../../bytecode_gen/tests/versions.3.10.json")),
+        ("3.11", include_str!("../../bytecode_gen/tests/versions.3.11.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/versions.3.12.json")),
+    ] {
+        assert_eq!(run_program(source, VmConfig::default()).unwrap(), globals, "Python {}", version);
+    }
+}
+
+/// Runs every program in `bytecode_gen/snapshots` against the snapshot stored next to it, the
+/// same as `--verify-snapshot` does.
+#[test]
+fn programs_match_their_snapshots() {
+    let mut snapshots = fs::read_dir("bytecode_gen/snapshots").unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "jsonl"))
+        .collect::<Vec<_>>();
+    snapshots.sort();
+    assert!(!snapshots.is_empty());
+
+    for snapshot in snapshots {
+        let snapshot = snapshot.to_str().unwrap().to_string();
+        on_vm_stack(move || {
+            let frame = load(&snapshot.replace(".jsonl", ".json")).unwrap();
+            let mut vm = Vm::new(VmConfig::default());
+            vm.snapshot = Some(Snapshot::verify(&snapshot).unwrap());
+            vm.run_frame(&frame).unwrap_or_else(|error| panic!("{}: {}", snapshot, error));
+            vm.snapshot.take().unwrap().finish().unwrap_or_else(|error| panic!("{}: {}", snapshot, error));
+        });
+    }
+}
+
+#[test]
+fn cached_frames_run_the_same_as_decoded_ones() {
+    on_vm_stack(|| {
+        let source = include_str!("../../bytecode_gen/tests/versions.json");
+        let path = temporary_file("cached.json", source);
+        let dir = std::env::temp_dir().join(format!("py_vm_{}_cache", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let args = ["--cache-dir", dir.to_str().unwrap(), path.to_str().unwrap()];
+        let options = Options::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+
+        let decoded = load_frame(&options).unwrap();
+        assert!(Cache::new(dir.to_str().unwrap(), source).load(None).is_some(), "the first run stores the frame");
+        let cached = load_frame(&options).unwrap();
+        assert_eq!(globals_after(&cached), globals_after(&decoded));
+
+        fs::write(&path, source.replacen("16", "17", 1)).unwrap();
+        load_frame(&options).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2, "a changed file gets its own entry");
+        fs::remove_dir_all(&dir).unwrap();
+    });
+}
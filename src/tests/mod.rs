@@ -0,0 +1,93 @@
+//! Tests of the VM, which run frames built with `FrameBuilder` or loaded from bytecode files.
+
+mod arithmetic;
+mod bytes;
+mod calls;
+mod cells;
+mod classes;
+mod exceptions;
+mod exec;
+mod generators;
+mod intrinsics;
+mod jumps;
+mod loading;
+mod modules;
+mod names;
+mod serialization;
+mod stack;
+mod threads;
+mod verify;
+
+use std::env;
+use std::fs;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::{on_vm_stack, parse_frame, Frame, FrameState, FrameType, Value, Vm, VmConfig, VmError};
+
+/// Runs `frame` as module level code until it runs out of instructions or returns, and gives
+/// the stack it leaves.
+pub(crate) fn run(frame: &Frame) -> Result<Vec<Value>, VmError> {
+    let mut vm = Vm::new(VmConfig::default());
+    let state = FrameState::new(Rc::clone(&vm.globals), FrameType::Module);
+    vm.run_state(frame, state, None).map(|state| state.stack)
+}
+
+/// Runs a bytecode file from `bytecode_gen/tests` on a stack as big as the one `main` runs the
+/// VM on, and gives the repr of each global it leaves. An exception it raises is given as its
+/// message.
+fn run_program(source: impl Into<String>, config: VmConfig) -> Result<BTreeMap<String, String>, String> {
+    let source = source.into();
+    on_vm_stack(move || {
+        let frame = parse_frame(&source, None)?;
+        let mut vm = Vm::new(config);
+        vm.run_frame(&frame).map_err(|error| error.to_string())?;
+        let globals = vm.globals.borrow();
+        Ok(globals.iter().map(|(name, value)| (name.to_string(), value.python_repr())).collect())
+    })
+}
+
+/// Checks that each global named in `expected` has the repr given with it.
+fn assert_globals(globals: &BTreeMap<String, String>, expected: &[(&str, &str)]) {
+    for (name, repr) in expected {
+        assert_eq!(globals[*name], *repr, "{}", name);
+    }
+}
+
+/// Runs each version of a program, given with the Python version that compiled it, and checks
+/// the globals in `expected`.
+fn assert_every_version(programs: &[(&str, &str)], expected: &[(&str, &str)]) {
+    for (version, source) in programs {
+        let globals = run_program(*source, VmConfig::default()).unwrap_or_else(|error| panic!("Python {}: {}", version, error));
+        for (name, repr) in expected {
+            assert_eq!(globals[*name], *repr, "{} on Python {}", name, version);
+        }
+    }
+}
+
+/// The repr of every item `frame` leaves on the stack, which tells ints and floats apart.
+pub(crate) fn stack_reprs(frame: &Frame) -> Vec<String> {
+    run(frame).unwrap().iter().map(Value::python_repr).collect()
+}
+
+/// Calls `function` of the built-in module `module`.
+fn call(vm: &mut Vm, module: &str, function: &str, args: Vec<Value>) -> Result<Value, VmError> {
+    let module = vm.modules[&Rc::new(String::from(module))].clone();
+    let function = module.get_attr(&Rc::new(String::from(function)))?;
+    vm.call_value(function, args)
+}
+
+/// A new list of `items`.
+fn list(items: Vec<Value>) -> Value {
+    Value::List(Rc::new(RefCell::new(items)))
+}
+
+/// A file in the temporary directory with `contents`, named after the test that writes it so
+/// tests running at the same time don't share files.
+fn temporary_file(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("py_vm_{}_{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
@@ -0,0 +1,388 @@
+use std::rc::Rc;
+
+use super::{assert_every_version, assert_globals, call, list, run_program, temporary_file};
+use crate::{Value, Vm, VmConfig};
+
+fn string(value: &str) -> Value {
+    Value::Str(String::from(value))
+}
+
+#[test]
+fn json_loads_arrays_as_lists_and_objects_as_dicts() {
+    let mut vm = Vm::new(VmConfig::default());
+    let document = string(r#"{"b": [1, 2.5, "x", null, true], "a": {"nested": []}, "b": [3]}"#);
+    let value = call(&mut vm, "json", "loads", vec![document]).unwrap();
+    assert!(matches!(&value, Value::Dict(_)));
+    // Keys keep the order they are written in, a repeated key its first position.
+    assert_eq!(value.python_repr(), "{'b': [3], 'a': {'nested': []}}");
+}
+
+#[test]
+fn json_dumps_lists_and_dicts() {
+    let mut vm = Vm::new(VmConfig::default());
+    let document = string(r#"{"b": [1, 2.5, "x", null, true], "a": {}}"#);
+    let value = call(&mut vm, "json", "loads", vec![document]).unwrap();
+    let dumped = call(&mut vm, "json", "dumps", vec![value]).unwrap();
+    assert_eq!(dumped.python_repr(), r#"'{"b": [1, 2.5, "x", null, true], "a": {}}'"#);
+}
+
+#[test]
+fn json_dumps_rejects_circular_lists() {
+    let mut vm = Vm::new(VmConfig::default());
+    let circular = list(vec![]);
+    if let Value::List(items) = &circular {
+        items.borrow_mut().push(circular.clone());
+    }
+    let error = call(&mut vm, "json", "dumps", vec![circular]).unwrap_err();
+    assert_eq!(error.to_string(), "ValueError: Circular reference detected");
+}
+
+#[test]
+fn pickle_round_trips_lists_and_dicts() {
+    let mut vm = Vm::new(VmConfig::default());
+    let document = string(r#"{"list": [1, "two", [3.5]], "empty": [], "one": [null], "dict": {}}"#);
+    let value = call(&mut vm, "json", "loads", vec![document]).unwrap();
+    let pickled = call(&mut vm, "pickle", "dumps", vec![value.clone()]).unwrap();
+    let loaded = call(&mut vm, "pickle", "loads", vec![pickled]).unwrap();
+    assert_eq!(loaded.python_repr(), value.python_repr());
+}
+
+#[test]
+fn pickle_keeps_shared_and_circular_lists_shared() {
+    let mut vm = Vm::new(VmConfig::default());
+    let shared = list(vec![Value::Int(1)]);
+    let circular = list(vec![shared.clone(), shared]);
+    if let Value::List(items) = &circular {
+        items.borrow_mut().push(circular.clone());
+    }
+    let pickled = call(&mut vm, "pickle", "dumps", vec![circular]).unwrap();
+    let items = match call(&mut vm, "pickle", "loads", vec![pickled]).unwrap() {
+        Value::List(items) => items,
+        other => panic!("loaded {}", other.python_repr()),
+    };
+    let items = items.borrow();
+    match (&items[0], &items[1], &items[2]) {
+        (Value::List(first), Value::List(second), Value::List(last)) => {
+            assert!(Rc::ptr_eq(first, second));
+            assert_eq!(last.borrow().len(), 3);
+        },
+        _ => panic!("loaded {:?}", items),
+    }
+}
+
+#[test]
+fn pickle_loads_what_cpython_writes() {
+    let mut vm = Vm::new(VmConfig::default());
+    // `pickle.dumps([1, {"a": 2, "b": []}], protocol=4)`
+    let data = b"\x80\x04\x95\x17\x00\x00\x00\x00\x00\x00\x00]\x94(K\x01}\x94(\x8c\x01a\x94K\x02\x8c\x01b\x94]\x94ue.";
+    let loaded = call(&mut vm, "pickle", "loads", vec![Value::Bytes(data.to_vec())]).unwrap();
+    assert_eq!(loaded.python_repr(), "[1, {'a': 2, 'b': []}]");
+}
+
+#[test]
+fn copies_can_be_changed_without_changing_the_original() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/copies.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("inner", "[1, 2, 3]"),
+        ("outer", "[[1, 2, 3], {'key': [1, 2, 3]}]"),
+        ("shallow", "[[1, 2, 3], {'key': [1, 2, 3]}, 3]"),
+        ("deep", "[[1, 2, 3, 4], {'key': [1, 2, 3, 4]}]"),
+        ("deep_shares_inner", "True"),
+        ("counts", "{'a': 1}"),
+        ("counts_copy", "{'a': 1, 'b': 2}"),
+        ("seen", "{1, 2}"),
+        ("seen_copy", "{1, 2, 3}"),
+        ("buffer", "bytearray(b'ab')"),
+        ("buffer_copy", "bytearray(b'abc')"),
+        ("point_x", "1"),
+        ("point_tags", "['a', 'b']"),
+        ("point_deep_tags", "['a', 'b', 'c']"),
+        ("cycle_is_new", "True"),
+        ("cycle_copy_contains_itself", "True"),
+    ]);
+}
+
+#[test]
+fn os_getcwd_gives_the_current_directory() {
+    let mut vm = Vm::new(VmConfig::default());
+    let cwd = call(&mut vm, "os", "getcwd", vec![]).unwrap();
+    assert_eq!(cwd, string(&std::env::current_dir().unwrap().to_string_lossy()));
+    assert!(matches!(&cwd, Value::Str(cwd) if !cwd.is_empty()));
+}
+
+#[test]
+fn os_listdir_lists_and_makedirs_creates_directories() {
+    let mut vm = Vm::new(VmConfig::default());
+    let directory = std::env::temp_dir().join(format!("py_vm_{}_listdir", std::process::id()));
+    let nested = directory.join("nested");
+    let nested = string(&nested.to_string_lossy());
+    call(&mut vm, "os", "makedirs", vec![nested.clone()]).unwrap();
+    let error = call(&mut vm, "os", "makedirs", vec![nested.clone()]).unwrap_err();
+    assert!(error.to_string().starts_with("FileExistsError: [Errno 17] File exists"));
+
+    let makedirs = vm.modules[&Rc::new(String::from("os"))].get_attr(&Rc::new(String::from("makedirs"))).unwrap();
+    let exist_ok = vec![(Rc::new(String::from("exist_ok")), Value::Bool(true))];
+    assert_eq!(vm.call_value_kw(makedirs, vec![nested], exist_ok).unwrap(), Value::Nonetype);
+
+    let names = call(&mut vm, "os", "listdir", vec![string(&directory.to_string_lossy())]).unwrap();
+    assert_eq!(names.python_repr(), "['nested']");
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn os_environ_holds_the_environment() {
+    let vm = Vm::new(VmConfig::default());
+    let os = vm.modules[&Rc::new(String::from("os"))].clone();
+    let environ = match os.get_attr(&Rc::new(String::from("environ"))).unwrap() {
+        Value::Dict(environ) => environ,
+        other => panic!("environ is {}", other.python_repr()),
+    };
+    let (key, value) = std::env::vars().next().unwrap();
+    assert_eq!(environ.borrow().get(&string(&key)).unwrap(), Some(string(&value)));
+}
+
+#[test]
+fn files_read_back_what_was_written() {
+    let mut vm = Vm::new(VmConfig::default());
+    let path = temporary_file("round_trip.txt", "");
+    let name = string(&path.to_string_lossy());
+    let method = |file: &Value, name: &str| file.get_attr(&Rc::new(String::from(name))).unwrap();
+
+    let file = call(&mut vm, "io", "open", vec![name.clone(), string("w")]).unwrap();
+    vm.call_value(method(&file, "write"), vec![string("one\ntwo\n")]).unwrap();
+    vm.call_value(method(&file, "close"), vec![]).unwrap();
+
+    let file = call(&mut vm, "io", "open", vec![name]).unwrap();
+    let lines = vm.call_value(method(&file, "readlines"), vec![]).unwrap();
+    assert_eq!(lines, list(vec![string("one\n"), string("two\n")]));
+    assert_eq!(vm.call_value(method(&file, "readlines"), vec![]).unwrap(), list(vec![]));
+    vm.call_value(method(&file, "close"), vec![]).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn json_dumps_indents_nested_values() {
+    let mut vm = Vm::new(VmConfig::default());
+    let document = string(r#"{"b": [1, {"c": []}], "a": {}}"#);
+    let value = call(&mut vm, "json", "loads", vec![document]).unwrap();
+    let dumps = vm.modules[&Rc::new(String::from("json"))].get_attr(&Rc::new(String::from("dumps"))).unwrap();
+    let indent = vec![(Rc::new(String::from("indent")), Value::Int(2))];
+    let dumped = vm.call_value_kw(dumps, vec![value], indent).unwrap();
+    assert_eq!(dumped, string("{\n  \"b\": [\n    1,\n    {\n      \"c\": []\n    }\n  ],\n  \"a\": {}\n}"));
+}
+
+#[test]
+fn json_dumps_sorts_keys() {
+    let mut vm = Vm::new(VmConfig::default());
+    let document = string(r#"{"b": 1, "c": {"z": 2, "y": 3}, "a": [4]}"#);
+    let value = call(&mut vm, "json", "loads", vec![document]).unwrap();
+    let dumps = vm.modules[&Rc::new(String::from("json"))].get_attr(&Rc::new(String::from("dumps"))).unwrap();
+    let sort_keys = vec![(Rc::new(String::from("sort_keys")), Value::Bool(true))];
+    let dumped = vm.call_value_kw(dumps.clone(), vec![value], sort_keys.clone()).unwrap();
+    assert_eq!(dumped, string(r#"{"a": [4], "b": 1, "c": {"y": 3, "z": 2}}"#));
+
+    let mixed = call(&mut vm, "json", "loads", vec![string(r#"{"a": 1}"#)]).unwrap();
+    if let Value::Dict(dict) = &mixed {
+        dict.borrow_mut().insert(Value::Int(1), Value::Int(2)).unwrap();
+    }
+    let error = vm.call_value_kw(dumps, vec![mixed], sort_keys).unwrap_err();
+    assert_eq!(error.to_string(), "TypeError: '<' not supported between instances of 'int' and 'str'");
+}
+
+#[test]
+fn datetime_does_date_arithmetic_with_keyword_arguments() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/dates.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("step", "datetime.timedelta(days=1, seconds=5)"),
+        ("week", "datetime.timedelta(days=6, seconds=82800)"),
+        ("later", "datetime.datetime(2024, 2, 29, 23, 0, 35)"),
+        ("elapsed", "datetime.timedelta(days=59, seconds=82835)"),
+        ("elapsed_days", "59"),
+        ("elapsed_seconds", "82835"),
+        ("leap_year", "datetime.timedelta(days=366)"),
+        ("day_before", "datetime.date(2024, 2, 29)"),
+        ("fortnight", "datetime.timedelta(days=14)"),
+        ("total_seconds", "86405.0"),
+        ("noon_utc", "datetime.datetime(2024, 1, 1, 12, 0, tzinfo=datetime.timezone.utc)"),
+    ]);
+}
+
+#[test]
+fn re_findall_finds_phone_numbers() {
+    let mut vm = Vm::new(VmConfig::default());
+    let text = string("call (555) 123-4567 or 555.987.6543");
+    let numbers = call(&mut vm, "re", "findall", vec![string(r"\(?\d{3}\)?[-. ]?\d{3}[-.]\d{4}"), text.clone()]).unwrap();
+    assert_eq!(numbers.python_repr(), "['(555) 123-4567', '555.987.6543']");
+    let parts = call(&mut vm, "re", "findall", vec![string(r"\(?(\d{3})\)?[-. ]?(\d{3})[-.](\d{4})"), text]).unwrap();
+    assert_eq!(parts.python_repr(), "[('555', '123', '4567'), ('555', '987', '6543')]");
+
+    let pattern = call(&mut vm, "re", "compile", vec![string(r"(\d{3})-\d{4}")]).unwrap();
+    let findall = pattern.get_attr(&Rc::new(String::from("findall"))).unwrap();
+    let exchanges = vm.call_value(findall, vec![string("a 123-4567 b 765-4321")]).unwrap();
+    assert_eq!(exchanges.python_repr(), "['123', '765']");
+}
+
+#[test]
+fn re_sub_replaces_matches() {
+    let mut vm = Vm::new(VmConfig::default());
+    let swapped = call(&mut vm, "re", "sub", vec![string(r"(\d{3})-(\d{4})"), string(r"\2-\1"), string("call 123-4567")]).unwrap();
+    assert_eq!(swapped, string("call 4567-123"));
+    let first = call(&mut vm, "re", "sub", vec![string("a"), string("o"), string("banana"), Value::Int(1)]).unwrap();
+    assert_eq!(first, string("bonana"));
+}
+
+#[test]
+fn re_split_keeps_the_groups_of_separators() {
+    let mut vm = Vm::new(VmConfig::default());
+    let parts = call(&mut vm, "re", "split", vec![string("(,)|;"), string("a,b;c")]).unwrap();
+    assert_eq!(parts.python_repr(), "['a', ',', 'b', None, 'c']");
+    let parts = call(&mut vm, "re", "split", vec![string(","), string("a,b,c"), Value::Int(1)]).unwrap();
+    assert_eq!(parts.python_repr(), "['a', 'b,c']");
+    let parts = call(&mut vm, "re", "split", vec![string(r"\s*"), string("a b")]).unwrap();
+    assert_eq!(parts.python_repr(), "['', 'a', '', 'b', '']");
+}
+
+#[test]
+fn random_repeats_its_sequence_for_a_seed() {
+    let mut vm = Vm::new(VmConfig::default());
+    let draw = |vm: &mut Vm| {
+        call(vm, "random", "seed", vec![Value::Int(42)]).unwrap();
+        let numbers = list((0..10).map(Value::Int).collect());
+        call(vm, "random", "shuffle", vec![numbers.clone()]).unwrap();
+        [
+            call(vm, "random", "random", vec![]).unwrap(),
+            call(vm, "random", "randint", vec![Value::Int(1), Value::Int(6)]).unwrap(),
+            call(vm, "random", "choice", vec![list(vec![string("a"), string("b"), string("c")])]).unwrap(),
+            numbers,
+            call(vm, "random", "sample", vec![string("abcdef"), Value::Int(3)]).unwrap(),
+        ].iter().map(Value::python_repr).collect::<Vec<_>>()
+    };
+    assert_eq!(draw(&mut vm), draw(&mut vm));
+}
+
+#[test]
+fn random_shuffle_and_sample_keep_every_item() {
+    let mut vm = Vm::new(VmConfig::default());
+    let numbers = list((0..20).map(Value::Int).collect());
+    call(&mut vm, "random", "shuffle", vec![numbers.clone()]).unwrap();
+    let mut shuffled = match &numbers {
+        Value::List(items) => items.borrow().clone(),
+        _ => unreachable!(),
+    };
+    shuffled.sort_by(|first, second| first.partial_cmp(second).unwrap());
+    assert_eq!(shuffled, (0..20).map(Value::Int).collect::<Vec<_>>());
+
+    let sample = call(&mut vm, "random", "sample", vec![numbers, Value::Int(5)]).unwrap();
+    let sample = match sample {
+        Value::List(items) => items.borrow().clone(),
+        other => panic!("sampled {}", other.python_repr()),
+    };
+    assert_eq!(sample.len(), 5);
+    assert!(sample.iter().all(|item| matches!(item, Value::Int(0..=19))));
+    assert!((1..5).all(|position| !sample[..position].contains(&sample[position])));
+
+    let error = call(&mut vm, "random", "sample", vec![list(vec![]), Value::Int(1)]).unwrap_err();
+    assert_eq!(error.to_string(), "ValueError: Sample larger than population or is negative");
+}
+
+#[test]
+fn random_stays_in_its_ranges() {
+    let mut vm = Vm::new(VmConfig::default());
+    let mut total = 0.0;
+    for _ in 0..1000 {
+        match call(&mut vm, "random", "random", vec![]).unwrap() {
+            Value::Float(number) => {
+                assert!((0.0..1.0).contains(&number));
+                total += number;
+            },
+            other => panic!("random() gave {}", other.python_repr()),
+        }
+        let roll = call(&mut vm, "random", "randint", vec![Value::Int(1), Value::Int(6)]).unwrap();
+        assert!(matches!(roll, Value::Int(1..=6)));
+    }
+    // The mean of 1000 uniform numbers is within 0.1 of 0.5 with overwhelming probability.
+    assert!((total / 1000.0 - 0.5).abs() < 0.1);
+}
+
+#[test]
+fn math_works_out_functions_and_factorials() {
+    let mut vm = Vm::new(VmConfig::default());
+    let pi = vm.modules[&Rc::new(String::from("math"))].get_attr(&Rc::new(String::from("pi"))).unwrap();
+    match call(&mut vm, "math", "sin", vec![pi]).unwrap() {
+        Value::Float(sine) => assert!(sine.abs() < 1e-15),
+        other => panic!("sin(pi) gave {}", other.python_repr()),
+    }
+    assert_eq!(call(&mut vm, "math", "factorial", vec![Value::Int(20)]).unwrap(), Value::Int(2_432_902_008_176_640_000));
+    let factorial = call(&mut vm, "math", "factorial", vec![Value::Int(25)]).unwrap();
+    assert!(matches!(factorial, Value::BigInt(_)));
+    assert_eq!(factorial.python_repr(), "15511210043330985984000000");
+}
+
+#[test]
+fn time_gives_the_epoch_time_and_sleeps() {
+    let mut vm = Vm::new(VmConfig::default());
+    match call(&mut vm, "time", "time", vec![]).unwrap() {
+        // Later than 2020-01-01.
+        Value::Float(seconds) => assert!(seconds > 1_577_836_800.0, "{}", seconds),
+        other => panic!("time() gave {}", other.python_repr()),
+    }
+    let start = std::time::Instant::now();
+    assert_eq!(call(&mut vm, "time", "sleep", vec![Value::Float(0.01)]).unwrap(), Value::Nonetype);
+    assert!(start.elapsed() >= std::time::Duration::from_millis(10));
+}
+
+#[test]
+fn hashlib_hashes_bytes() {
+    let mut vm = Vm::new(VmConfig::default());
+    let hash = call(&mut vm, "hashlib", "sha256", vec![Value::Bytes(b"hello".to_vec())]).unwrap();
+    let hexdigest = hash.get_attr(&Rc::new(String::from("hexdigest"))).unwrap();
+    assert_eq!(
+        vm.call_value(hexdigest, vec![]).unwrap(),
+        string("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"),
+    );
+}
+
+#[test]
+fn struct_round_trips_packed_values() {
+    let mut vm = Vm::new(VmConfig::default());
+    let format = string("<hId");
+    let values = vec![Value::Int(-2), Value::Int(4_000_000_000), Value::Float(1.5)];
+    let mut args = vec![format.clone()];
+    args.extend(values.clone());
+    let packed = call(&mut vm, "struct", "pack", args).unwrap();
+    assert!(matches!(&packed, Value::Bytes(bytes) if bytes.len() == 14));
+    assert_eq!(call(&mut vm, "struct", "unpack", vec![format.clone(), packed]).unwrap(), Value::Tuple(values));
+    assert_eq!(call(&mut vm, "struct", "calcsize", vec![format]).unwrap(), Value::Int(14));
+    // Native alignment pads the int after the short.
+    assert_eq!(call(&mut vm, "struct", "calcsize", vec![string("hi")]).unwrap(), Value::Int(8));
+}
+
+#[test]
+fn weak_references_die_with_their_referent() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/weakrefs.json"), VmConfig::default()).unwrap();
+    assert_globals(&globals, &[
+        ("alive", "True"),
+        ("through_proxy", "'first'"),
+        ("dead", "None"),
+        ("proxy_error", "'weakly-referenced object no longer exists'"),
+    ]);
+}
+
+#[test]
+fn enum_members_are_looked_up_by_value_and_equal_only_to_themselves() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/enums.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/enums.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("red", "<Color.RED: 1>"),
+        ("name", "'RED'"),
+        ("value", "1"),
+        ("same", "True"),
+        ("different", "False"),
+        ("equal_to_value", "False"),
+        ("described", "'GREEN=2'"),
+        ("printed", "'Color.GREEN'"),
+        ("missing", "'3 is not a valid Color'"),
+    ]);
+}
@@ -0,0 +1,158 @@
+use std::rc::Rc;
+
+use super::{assert_every_version, run, run_program, stack_reprs};
+use crate::{FrameBuilder, Instruction, Value, VmConfig};
+
+fn error(builder: FrameBuilder) -> String {
+    run(&builder.build()).unwrap_err().to_string()
+}
+
+#[test]
+fn load_name_of_an_unbound_name_raises_name_error() {
+    let message = error(FrameBuilder::new("<module>").load_name("missing_name"));
+    assert_eq!(message, "NameError: name 'missing_name' is not defined");
+}
+
+#[test]
+fn load_global_of_an_unbound_name_raises_name_error() {
+    let message = error(FrameBuilder::new("<module>").load_global("missing_global"));
+    assert_eq!(message, "NameError: name 'missing_global' is not defined");
+}
+
+#[test]
+fn load_fast_of_an_unbound_local_raises_unbound_local_error() {
+    let function = FrameBuilder::new("f")
+        .load_fast("unbound_local")
+        .push_const(Value::Int(1))
+        .store_fast("unbound_local")
+        .return_value()
+        .build();
+    let message = error(FrameBuilder::new("<module>").push_const(Value::Frame(Rc::new(function))).call_function(0));
+    assert_eq!(message, "UnboundLocalError: local variable 'unbound_local' referenced before assignment");
+}
+
+#[test]
+fn names_bound_in_the_module_are_found() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .store_name("bound")
+        .load_name("bound")
+        .load_global("bound")
+        .build();
+    assert_eq!(run(&frame).unwrap().len(), 2);
+}
+
+#[test]
+fn load_fast_and_clear_pushes_the_local_and_unbinds_it() {
+    let builder = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .store_fast("saved")
+        .instruction(Instruction::LoadFastAndClear(0));
+    assert_eq!(stack_reprs(&builder.build()), ["1"]);
+
+    let builder = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .store_fast("saved")
+        .instruction(Instruction::LoadFastAndClear(0))
+        .load_fast("saved");
+    assert_eq!(error(builder), "UnboundLocalError: local variable 'saved' referenced before assignment");
+}
+
+#[test]
+fn storing_what_load_fast_and_clear_pushed_for_an_unbound_local_unbinds_it_again() {
+    let builder = FrameBuilder::new("<module>")
+        .instruction(Instruction::LoadFastAndClear(0))
+        .push_const(Value::Int(5))
+        .store_fast("saved")
+        .store_fast("saved")
+        .load_fast("saved");
+    assert_eq!(error(builder), "UnboundLocalError: local variable 'saved' referenced before assignment");
+}
+
+#[test]
+fn python_3_12_comprehensions_keep_the_locals_they_shadow() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/comprehensions.3.12.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["kept"], "'kept'");
+    assert_eq!(globals["squares"], "[1, 4, 9]");
+    assert_eq!(globals["pairs"], "[(1, 3), (2, 3)]");
+}
+
+#[test]
+fn assignment_expressions_bind_in_the_enclosing_scope() {
+    assert_every_version(&[
+        ("3.8", include_str!("../../bytecode_gen/tests/walrus.json")),
+        ("3.10", include_str!("../../bytecode_gen/tests/walrus.3.10.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/walrus.3.12.json")),
+    ], &[
+        ("first", "3"),
+        ("size", "'big first'"),
+        ("doubled", "[6, 16, 18]"),
+        ("last_double", "18"),
+        ("total", "11"),
+        ("in_function", "([1, 2], 2)"),
+    ]);
+}
+
+#[test]
+fn annotations_are_collected_in_module_and_class_bodies() {
+    assert_every_version(&[
+        ("3.8", include_str!("../../bytecode_gen/tests/annotations.json")),
+        ("3.10", include_str!("../../bytecode_gen/tests/annotations.3.10.json")),
+        ("3.11", include_str!("../../bytecode_gen/tests/annotations.3.11.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/annotations.3.12.json")),
+    ], &[
+        ("count", "3"),
+        ("module_annotations", "{'count': <class 'int'>, 'name': <class 'str'>}"),
+        ("class_annotations", "{'x': <class 'int'>, 'y': 'float'}"),
+        ("class_default", "0"),
+    ]);
+}
+
+#[test]
+fn store_annotation_adds_to_the_annotations_of_the_body() {
+    let frame = FrameBuilder::new("<module>")
+        .instruction(Instruction::SetupAnnotations)
+        .name("x")
+        .push_const(Value::Str(String::from("int")))
+        .instruction(Instruction::StoreAnnotation(0))
+        .load_name("__annotations__")
+        .build();
+    assert_eq!(stack_reprs(&frame), ["{'x': 'int'}"]);
+}
+
+#[test]
+fn store_annotation_without_setup_annotations_is_a_name_error() {
+    let frame = FrameBuilder::new("<module>")
+        .name("x")
+        .push_const(Value::Str(String::from("int")))
+        .instruction(Instruction::StoreAnnotation(0))
+        .build();
+    assert_eq!(run(&frame).unwrap_err().to_string(), "NameError: name '__annotations__' is not defined");
+}
+
+#[test]
+fn store_global_in_a_function_changes_the_globals_every_function_sees() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/globals.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/globals.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("counter", "2"),
+        ("seen_by_function", "2"),
+        ("after_define", "'late'"),
+        ("deleted", "\"name 'defined' is not defined\""),
+    ]);
+}
+
+#[test]
+fn store_name_binds_in_the_class_body_unless_the_name_is_global() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/store_name.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/store_name.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("mode", "'set in a class body'"),
+        ("level", "'module'"),
+        ("class_level", "'class'"),
+    ]);
+}
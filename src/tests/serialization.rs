@@ -0,0 +1,116 @@
+use std::rc::Rc;
+
+use super::{run_program, stack_reprs};
+use crate::{parse_frame, Frame, FrameBuilder, Instruction, Value, VmConfig};
+
+/// Asserts `first` and `second` are the same code, field by field and through the code objects
+/// in their constants. Other constants are compared by type and repr, as `==` between values
+/// is Python's, where `1 == 1.0 == True`.
+fn assert_same_frame(first: &Frame, second: &Frame) {
+    assert_eq!(first.co_name, second.co_name);
+    assert_eq!(first.co_qualname, second.co_qualname);
+    assert_eq!(format!("{:?}", first.instructions), format!("{:?}", second.instructions));
+    assert_eq!(first.co_names, second.co_names);
+    assert_eq!(first.co_varnames, second.co_varnames);
+    assert_eq!(first.co_argcount, second.co_argcount);
+    assert_eq!(first.co_posonlyargcount, second.co_posonlyargcount);
+    assert_eq!(first.co_kwonlyargcount, second.co_kwonlyargcount);
+    assert_eq!(first.co_flags, second.co_flags);
+    assert_eq!(first.co_cellvars, second.co_cellvars);
+    assert_eq!(first.co_freevars, second.co_freevars);
+    assert_eq!(format!("{:?}", first.co_exceptiontable), format!("{:?}", second.co_exceptiontable));
+    assert_eq!(first.constants.len(), second.constants.len());
+    for (first, second) in first.constants.iter().zip(&second.constants) {
+        match (first, second) {
+            (Value::Frame(first), Value::Frame(second)) => assert_same_frame(first, second),
+            _ => assert_eq!((first.type_name(), first.python_repr()), (second.type_name(), second.python_repr())),
+        }
+    }
+}
+
+#[test]
+fn sets_load_from_a_list_of_items() {
+    let value: Value = serde_json::from_str(r#"{"Set": [{"Int": 2}, {"Int": 1}, {"Float": 1.0}, {"Bool": true}]}"#).unwrap();
+    assert_eq!(value.python_repr(), "{1, 2}");
+    let value: Value = serde_json::from_str(r#"{"FrozenSet": [{"Str": "a"}]}"#).unwrap();
+    assert_eq!(value.python_repr(), "frozenset({'a'})");
+}
+
+#[test]
+fn sets_of_unhashable_items_are_an_error() {
+    let error = serde_json::from_str::<Value>(r#"{"Set": [{"List": []}]}"#).unwrap_err();
+    assert!(error.to_string().contains("unhashable type: 'list'"), "{}", error);
+    let error = serde_json::from_str::<Value>(r#"{"FrozenSet": [{"Tuple": [{"List": []}]}]}"#).unwrap_err();
+    assert!(error.to_string().contains("unhashable type: 'list'"), "{}", error);
+}
+
+#[test]
+fn sets_are_written_in_a_fixed_order() {
+    let value: Value = serde_json::from_str(r#"{"Set": [{"Int": 3}, {"Int": 1}, {"Int": 2}]}"#).unwrap();
+    assert_eq!(serde_json::to_string(&value).unwrap(), r#"{"Set":[{"Int":1},{"Int":2},{"Int":3}]}"#);
+}
+
+#[test]
+fn set_constants_are_loaded() {
+    let frame: Frame = serde_json::from_str(r#"{
+        "instructions": [{"LoadConst": 0}, {"LoadConst": 1}, {"BinaryOr": null}],
+        "constants": [{"Set": [{"Int": 1}]}, {"FrozenSet": [{"Int": 2}]}],
+        "co_names": [],
+        "co_varnames": []
+    }"#).unwrap();
+    assert_eq!(stack_reprs(&frame), ["{1, 2}"]);
+
+    let frame = FrameBuilder::new("<module>")
+        .constant(serde_json::from_str(r#"{"FrozenSet": [{"Int": 1}, {"Int": 3}]}"#).unwrap())
+        .constant(Value::Int(3))
+        .instruction(Instruction::LoadConst(1))
+        .instruction(Instruction::LoadConst(0))
+        .instruction(Instruction::ContainsOp(0))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["True"]);
+}
+
+#[test]
+fn frames_round_trip_through_json() {
+    let function = FrameBuilder::new("f")
+        .parameter("a")
+        .parameter("b")
+        .load_fast("a")
+        .load_global("scale")
+        .instruction(Instruction::BinaryMultiply)
+        .store_fast("product")
+        .load_fast("product")
+        .return_value()
+        .build();
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Frame(Rc::new(function)))
+        .push_const(Value::Str(String::from("f")))
+        .instruction(Instruction::MakeFunction(0))
+        .store_name("f")
+        .push_const(Value::Tuple(vec![Value::Int(1), Value::Float(1.0), Value::Bool(true), Value::Nonetype]))
+        .push_const(Value::Bytes(vec![0, 255]))
+        .push_const(serde_json::from_str(r#"{"FrozenSet": [{"Int": 2}, {"Str": "x"}]}"#).unwrap())
+        .push_const(Value::Float(-0.0))
+        .build_tuple(4)
+        .store_name("values")
+        .build();
+
+    let json = serde_json::to_string(&frame).unwrap();
+    let loaded: Frame = serde_json::from_str(&json).unwrap();
+    assert_same_frame(&loaded, &frame);
+    assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+}
+
+#[test]
+fn emitted_json_loads_as_the_same_frame() {
+    // Python 3.10 counts jumps in instructions, which `--emit-json` writes as 3.8's bytes.
+    let frame = parse_frame(include_str!("../../bytecode_gen/tests/round_trip.3.10.json"), None).unwrap();
+    let emitted = serde_json::to_string_pretty(&frame).unwrap();
+    let loaded = parse_frame(&emitted, None).unwrap();
+    assert_same_frame(&loaded, &frame);
+    assert_eq!(serde_json::to_string_pretty(&loaded).unwrap(), emitted);
+
+    let globals = run_program(include_str!("../../bytecode_gen/tests/round_trip.3.10.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["totals"], "[3, 5]");
+    assert_eq!(run_program(emitted, VmConfig::default()).unwrap(), globals);
+}
@@ -0,0 +1,194 @@
+use super::{assert_every_version, run, stack_reprs};
+use crate::{FrameBuilder, Instruction, Value, VmError};
+
+#[test]
+fn dup_top_two_copies_the_top_two_items_above_them() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .push_const(Value::Int(2))
+        .instruction(Instruction::DupTopTwo)
+        .build();
+    assert_eq!(stack_reprs(&frame), ["1", "2", "1", "2"]);
+}
+
+#[test]
+fn dup_top_two_leaves_the_items_below_alone() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(0))
+        .push_const(Value::Int(1))
+        .push_const(Value::Int(2))
+        .instruction(Instruction::DupTopTwo)
+        .build();
+    assert_eq!(stack_reprs(&frame), ["0", "1", "2", "1", "2"]);
+}
+
+#[test]
+fn rot_three_lifts_the_top_item_below_the_next_two() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(1))
+        .push_const(Value::Int(2))
+        .push_const(Value::Int(3))
+        .instruction(Instruction::RotThree)
+        .build();
+    assert_eq!(stack_reprs(&frame), ["3", "1", "2"]);
+}
+
+#[test]
+fn rot_three_leaves_the_items_below_alone() {
+    let frame = FrameBuilder::new("<module>")
+        .push_const(Value::Int(0))
+        .push_const(Value::Int(1))
+        .push_const(Value::Int(2))
+        .push_const(Value::Int(3))
+        .instruction(Instruction::RotThree)
+        .instruction(Instruction::RotThree)
+        .build();
+    // Two rotations of three items are one rotation the other way.
+    assert_eq!(stack_reprs(&frame), ["0", "2", "3", "1"]);
+}
+
+/// Pushes the ints `0..count`, 0 deepest.
+fn push_ints(count: i64) -> FrameBuilder {
+    (0..count).fold(FrameBuilder::new("<module>"), |builder, number| builder.push_const(Value::Int(number)))
+}
+
+#[test]
+fn copy_1_duplicates_the_top_item() {
+    let frame = push_ints(2).instruction(Instruction::Copy(1)).build();
+    assert_eq!(stack_reprs(&frame), ["0", "1", "1"]);
+}
+
+#[test]
+fn copy_reaches_below_the_top_item() {
+    let frame = push_ints(3)
+        .instruction(Instruction::Copy(2))
+        .instruction(Instruction::Copy(4))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["0", "1", "2", "1", "0"]);
+}
+
+#[test]
+fn copy_pushes_the_same_object() {
+    let frame = FrameBuilder::new("<module>")
+        .build_list(0)
+        .instruction(Instruction::Copy(1))
+        .instruction(Instruction::IsOp(0))
+        .build();
+    assert_eq!(stack_reprs(&frame), ["True"]);
+}
+
+#[test]
+fn copy_below_the_bottom_of_the_stack_is_an_error() {
+    for arg in [0, 3] {
+        let frame = push_ints(2).instruction(Instruction::Copy(arg)).build();
+        assert!(matches!(run(&frame), Err(VmError::InternalError(_))), "COPY {}", arg);
+    }
+}
+
+#[test]
+fn swap_2_is_rot_two() {
+    let frame = push_ints(3).instruction(Instruction::Swap(2)).build();
+    assert_eq!(stack_reprs(&frame), ["0", "2", "1"]);
+}
+
+#[test]
+fn swap_exchanges_the_top_item_with_a_deeper_one() {
+    for (arg, expected) in [(3, ["0", "3", "2", "1"]), (4, ["3", "1", "2", "0"])] {
+        let frame = push_ints(4).instruction(Instruction::Swap(arg)).build();
+        assert_eq!(stack_reprs(&frame), expected, "SWAP {}", arg);
+    }
+}
+
+#[test]
+fn swap_below_the_bottom_of_the_stack_is_an_error() {
+    for arg in [0, 3] {
+        let frame = push_ints(2).instruction(Instruction::Swap(arg)).build();
+        assert!(matches!(run(&frame), Err(VmError::InternalError(_))), "SWAP {}", arg);
+    }
+}
+
+#[test]
+fn starred_items_extend_and_update_the_literal_they_are_in() {
+    assert_every_version(&[
+        ("3.10", include_str!("../../bytecode_gen/tests/unpacking.3.10.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/unpacking.3.12.json")),
+    ], &[
+        ("extended", "[1, 2, 3, 4, 5]"),
+        ("joined", "{1, 2, 3, 4}"),
+        ("merged", "{'a': 1, 'b': 3, 'c': 4}"),
+        ("spread", "(1, 2, 'x', 'y')"),
+        ("called", "((1, 2, 0, 3, 4), {'a': 1, 'b': 2, 'z': 9})"),
+    ]);
+}
+
+#[test]
+fn frozensets_are_dict_keys_set_items_and_give_frozensets() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/frozensets.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/frozensets.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("found", "'found'"),
+        ("nested_count", "1"),
+        ("equal_to_set", "True"),
+        ("union_matches", "True"),
+        ("intersection", "frozenset({2, 3})"),
+        ("difference_matches", "True"),
+        ("symmetric_matches", "True"),
+        ("contains", "True"),
+        ("total", "6"),
+        ("literal", "True"),
+    ]);
+}
+
+#[test]
+fn nop_cache_and_extended_arg_leave_the_stack_alone() {
+    let frame = push_ints(1)
+        .instruction(Instruction::Cache)
+        .instruction(Instruction::ExtendedArg(1))
+        .instruction(Instruction::Nop)
+        .instruction(Instruction::Cache)
+        .push_const(Value::Int(1))
+        .instruction(Instruction::Nop)
+        .build();
+    assert_eq!(stack_reprs(&frame), ["0", "1"]);
+}
+
+#[test]
+fn lists_are_indexed_from_either_end_and_changed_in_place() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/lists.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/lists.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("items", "[1, 20, 30]"),
+        ("first", "1"),
+        ("last", "3"),
+        ("joined", "[1, 20, 30, 4]"),
+        ("repeated", "[0, 0, 0]"),
+        ("equal", "True"),
+        ("ordered", "True"),
+        ("letter", "'l'"),
+        ("out_of_range", "'list index out of range'"),
+    ]);
+}
+
+#[test]
+fn tuples_are_indexed_unpacked_and_refuse_item_assignment() {
+    let programs = [
+        ("3.8", include_str!("../../bytecode_gen/tests/tuples.json")),
+        ("3.12", include_str!("../../bytecode_gen/tests/tuples.3.12.json")),
+    ];
+    assert_every_version(&programs, &[
+        ("point", "(3, 4, 5)"),
+        ("x", "3"),
+        ("z", "5"),
+        ("a", "1"),
+        ("b", "2"),
+        ("c", "6"),
+        ("d", "7"),
+        ("equal", "True"),
+        ("unequal", "False"),
+        ("immutable", "\"'tuple' object does not support item assignment\""),
+    ]);
+}
@@ -0,0 +1,29 @@
+use super::run_program;
+use crate::VmConfig;
+
+#[test]
+fn threads_take_their_arguments_by_keyword() {
+    let globals = run_program(include_str!("../../bytecode_gen/tests/threads.json"), VmConfig::default()).unwrap();
+    assert_eq!(globals["results"], "[('-', 1, 2), ('kw', 3, 4), ('d', 5, 6)]");
+    assert_eq!(globals["name"], "'named'");
+    assert_eq!(globals["default_name"], "'Thread-1'");
+    assert_eq!(globals["is_daemon"], "True");
+    assert_eq!(globals["unexpected"], "\"Thread() got an unexpected keyword argument 'colour'\"");
+    assert_eq!(globals["twice"], "\"Thread() got multiple values for argument 'target'\"");
+    assert_eq!(globals["no_keywords"], "'Lock() takes no keyword arguments'");
+}
+
+#[test]
+fn threads_run_in_parallel_and_share_locks() {
+    let config = VmConfig { threads: true, ..VmConfig::default() };
+    let globals = run_program(include_str!("../../bytecode_gen/tests/threads_parallel.json"), config).unwrap();
+    assert_eq!(globals["alive_while_locked"], "True");
+    assert_eq!(globals["alive_after_release"], "False");
+}
+
+#[test]
+fn threads_share_globals() {
+    let config = VmConfig { threads: true, ..VmConfig::default() };
+    let globals = run_program(include_str!("../../bytecode_gen/tests/threads_counter.json"), config).unwrap();
+    assert_eq!(globals["counter"], "2000");
+}
@@ -0,0 +1,91 @@
+use std::rc::Rc;
+
+use crate::{Frame, FrameBuilder, Instruction, Value};
+
+/// Every issue the verifier finds in `frame`, as `--check` prints them.
+fn issues(frame: &Frame) -> Vec<String> {
+    frame.verify().iter().map(ToString::to_string).collect()
+}
+
+fn module() -> FrameBuilder {
+    FrameBuilder::new("<module>")
+}
+
+#[test]
+fn valid_frames_have_no_issues() {
+    let frame = module().push_const(Value::Int(1)).store_name("x").load_name("x").return_value().build();
+    assert!(issues(&frame).is_empty());
+}
+
+#[test]
+fn empty_code_is_an_error() {
+    assert_eq!(issues(&module().build()), ["<module>: code object has no instructions"]);
+}
+
+#[test]
+fn constant_out_of_range_is_an_error() {
+    let frame = module().push_const(Value::Int(1)).instruction(Instruction::LoadConst(3)).build();
+    assert_eq!(issues(&frame), ["<module>: instruction 1: constant 3 out of range (1 constants)"]);
+}
+
+#[test]
+fn name_out_of_range_is_an_error() {
+    let frame = module().load_name("x").instruction(Instruction::StoreGlobal(1)).instruction(Instruction::LoadAttr(2)).build();
+    assert_eq!(issues(&frame), [
+        "<module>: instruction 1: name 1 out of range (1 co_names)",
+        "<module>: instruction 2: name 2 out of range (1 co_names)",
+    ]);
+}
+
+#[test]
+fn local_variable_out_of_range_is_an_error() {
+    let frame = FrameBuilder::new("f").parameter("a").instruction(Instruction::LoadFast(1)).return_value().build();
+    assert_eq!(issues(&frame), ["<module>: instruction 0: local variable 1 out of range (1 co_varnames)"]);
+}
+
+#[test]
+fn cell_out_of_range_is_an_error() {
+    let frame = module().instruction(Instruction::LoadDeref(0)).build();
+    assert_eq!(issues(&frame), ["<module>: instruction 0: cell variable 0 out of range (0 co_cellvars and co_freevars)"]);
+}
+
+#[test]
+fn jump_out_of_range_is_an_error() {
+    let frame = module()
+        .instruction(Instruction::PopJumpIfTrue(20))
+        .instruction(Instruction::JumpForward(2))
+        .instruction(Instruction::Nop)
+        .build();
+    assert_eq!(issues(&frame), [
+        "<module>: instruction 0: jump target 10 out of range (3 instructions)",
+        "<module>: instruction 1: jump target 3 out of range (3 instructions)",
+        "<module>: instruction 2 is unreachable",
+    ]);
+}
+
+#[test]
+fn unreachable_instructions_are_a_warning() {
+    let frame = module()
+        .push_const(Value::Nonetype)
+        .return_value()
+        .instruction(Instruction::Nop)
+        .instruction(Instruction::Nop)
+        .build();
+    let issues = frame.verify();
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].error.is_warning());
+    assert_eq!(issues[0].to_string(), "<module>: instructions 2 to 3 are unreachable");
+}
+
+#[test]
+fn issues_in_nested_code_give_its_location() {
+    let function = FrameBuilder::new("f").instruction(Instruction::LoadConst(0)).return_value().build();
+    let frame = module().push_const(Value::Int(0)).push_const(Value::Frame(Rc::new(function))).build();
+    assert_eq!(issues(&frame), ["<module>.constants[1]: instruction 0: constant 0 out of range (0 constants)"]);
+}
+
+#[test]
+fn instructions_after_interpreter_exit_are_unreachable() {
+    let frame = module().push_const(Value::Int(1)).instruction(Instruction::InterpreterExit).pop_top().build();
+    assert_eq!(issues(&frame), ["<module>: instruction 2 is unreachable"]);
+}
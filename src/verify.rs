@@ -0,0 +1,221 @@
+//! Checks that a `Frame`'s instruction arguments are in range before it runs, so malformed
+//! bytecode is reported when it is loaded instead of panicking halfway through.
+
+use std::fmt;
+
+use crate::{relative_target, Frame, Instruction, Value};
+
+#[derive(Debug)]
+pub(crate) enum VerifyError {
+    EmptyCode,
+    /// The instruction at `index` uses constant `arg`, but there are only `len`.
+    ConstantOutOfRange { index: usize, arg: usize, len: usize },
+    NameOutOfRange { index: usize, arg: usize, len: usize },
+    VarnameOutOfRange { index: usize, arg: usize, len: usize },
+    /// A cell instruction uses cell or free variable `arg`, but there are only `len`.
+    CellOutOfRange { index: usize, arg: usize, len: usize },
+    /// The instruction at `index` jumps to `target`, which is past the last instruction.
+    JumpOutOfRange { index: usize, target: usize, len: usize },
+    /// Instructions `start..=end` can't be reached from the first one. Only a warning, the
+    /// VM never runs them.
+    Unreachable { start: usize, end: usize },
+}
+
+impl VerifyError {
+    pub(crate) fn is_warning(&self) -> bool {
+        matches!(self, VerifyError::Unreachable { .. })
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::EmptyCode => write!(f, "code object has no instructions"),
+            VerifyError::ConstantOutOfRange { index, arg, len } => {
+                write!(f, "instruction {}: constant {} out of range ({} constants)", index, arg, len)
+            },
+            VerifyError::NameOutOfRange { index, arg, len } => {
+                write!(f, "instruction {}: name {} out of range ({} co_names)", index, arg, len)
+            },
+            VerifyError::VarnameOutOfRange { index, arg, len } => {
+                write!(f, "instruction {}: local variable {} out of range ({} co_varnames)", index, arg, len)
+            },
+            VerifyError::CellOutOfRange { index, arg, len } => {
+                write!(f, "instruction {}: cell variable {} out of range ({} co_cellvars and co_freevars)", index, arg, len)
+            },
+            VerifyError::JumpOutOfRange { index, target, len } => {
+                write!(f, "instruction {}: jump target {} out of range ({} instructions)", index, target, len)
+            },
+            VerifyError::Unreachable { start, end } if start == end => write!(f, "instruction {} is unreachable", start),
+            VerifyError::Unreachable { start, end } => write!(f, "instructions {} to {} are unreachable", start, end),
+        }
+    }
+}
+
+/// A `VerifyError` and the code object it was found in.
+#[derive(Debug)]
+pub(crate) struct Issue {
+    /// The constant indices leading from the module's code to the code object, empty for the
+    /// module itself.
+    pub(crate) location: Vec<usize>,
+    pub(crate) error: VerifyError,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module>")?;
+        for constant in &self.location {
+            write!(f, ".constants[{}]", constant)?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+impl Frame {
+    /// Checks the frame and the code objects in its constants, and returns every issue found.
+    /// Jump arguments are checked in the layout `Frame::decode` produces, so this runs after it.
+    pub(crate) fn verify(&self) -> Vec<Issue> {
+        let mut issues = vec![];
+        self.verify_code(&mut vec![], &mut issues);
+        issues
+    }
+
+    fn verify_code(&self, location: &mut Vec<usize>, issues: &mut Vec<Issue>) {
+        let mut report = |error| issues.push(Issue { location: location.clone(), error });
+        if self.instructions.is_empty() {
+            report(VerifyError::EmptyCode);
+        }
+
+        let len = self.instructions.len();
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            match *instruction {
+                Instruction::LoadConst(arg) | Instruction::KwNames(arg) | Instruction::ReturnConst(arg) if arg >= self.constants.len() => {
+                    report(VerifyError::ConstantOutOfRange { index, arg, len: self.constants.len() });
+                },
+                Instruction::StoreName(arg)
+                | Instruction::LoadName(arg)
+                | Instruction::DeleteName(arg)
+                | Instruction::StoreGlobal(arg)
+                | Instruction::LoadGlobal(arg)
+                | Instruction::LoadGlobalWithNull(arg)
+                | Instruction::DeleteGlobal(arg)
+                | Instruction::ImportName(arg)
+                | Instruction::ImportFrom(arg)
+                | Instruction::LoadAttr(arg)
+                | Instruction::StoreAttr(arg)
+                | Instruction::DeleteAttr(arg)
+                | Instruction::StoreAnnotation(arg)
+                | Instruction::LoadMethod(arg) if arg >= self.co_names.len() => {
+                    report(VerifyError::NameOutOfRange { index, arg, len: self.co_names.len() });
+                },
+                Instruction::LoadSuperAttr(arg) if arg >> 2 >= self.co_names.len() => {
+                    report(VerifyError::NameOutOfRange { index, arg: arg >> 2, len: self.co_names.len() });
+                },
+                Instruction::StoreFast(arg)
+                | Instruction::LoadFast(arg)
+                | Instruction::DeleteFast(arg)
+                | Instruction::LoadFastAndClear(arg)
+                | Instruction::LoadFastCheck(arg) if arg >= self.co_varnames.len() => {
+                    report(VerifyError::VarnameOutOfRange { index, arg, len: self.co_varnames.len() });
+                },
+                Instruction::LoadClosure(arg)
+                | Instruction::LoadDeref(arg)
+                | Instruction::LoadClassDeref(arg)
+                | Instruction::StoreDeref(arg)
+                | Instruction::DeleteDeref(arg) if self.deref_name(arg).is_none() => {
+                    report(VerifyError::CellOutOfRange { index, arg, len: self.co_cellvars.len() + self.co_freevars.len() });
+                },
+                _ => {},
+            }
+            match jump_target(index, *instruction) {
+                Some(target) if target >= len => report(VerifyError::JumpOutOfRange { index, target, len }),
+                _ => {},
+            }
+        }
+        for entry in &self.co_exceptiontable {
+            if entry.target / 2 >= len {
+                report(VerifyError::JumpOutOfRange { index: entry.start / 2, target: entry.target / 2, len });
+            }
+        }
+        let handlers = self.co_exceptiontable.iter().map(|entry| entry.target / 2).collect::<Vec<_>>();
+        for (start, end) in unreachable_ranges(&self.instructions, handlers) {
+            report(VerifyError::Unreachable { start, end });
+        }
+
+        for (constant, value) in self.constants.iter().enumerate() {
+            if let Value::Frame(frame) = value {
+                location.push(constant);
+                frame.verify_code(location, issues);
+                location.pop();
+            }
+        }
+    }
+}
+
+/// The index the instruction at `index` can jump to, if it jumps. The handlers `SETUP_FINALLY`
+/// and `SETUP_WITH` set up count as jumps, Python 3.11's handlers in the exception table don't.
+fn jump_target(index: usize, instruction: Instruction) -> Option<usize> {
+    match instruction {
+        Instruction::PopJumpIfTrue(arg)
+        | Instruction::PopJumpIfFalse(arg)
+        | Instruction::PopJumpIfNone(arg)
+        | Instruction::PopJumpIfNotNone(arg)
+        | Instruction::JumpIfTrueOrPop(arg)
+        | Instruction::JumpIfFalseOrPop(arg)
+        | Instruction::JumpIfNotExcMatch(arg)
+        | Instruction::JumpAbsolute(arg) => Some(arg / 2),
+        Instruction::JumpForward(arg)
+        | Instruction::ForIter(arg)
+        | Instruction::Send(arg)
+        | Instruction::SetupFinally(arg)
+        | Instruction::SetupWith(arg)
+        | Instruction::SetupAsyncWith(arg)
+        | Instruction::CallFinally(arg) => Some(relative_target(index, arg)),
+        _ => None,
+    }
+}
+
+/// Whether execution never continues with the next instruction.
+fn is_unconditional(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::JumpForward(_)
+            | Instruction::JumpAbsolute(_)
+            | Instruction::ReturnValue
+            | Instruction::ReturnConst(_)
+            | Instruction::InterpreterExit
+            | Instruction::RaiseVarargs(_)
+            | Instruction::Reraise(_)
+    )
+}
+
+/// The ranges of instructions no path from the first instruction or one of the exception
+/// `handlers` reaches.
+fn unreachable_ranges(instructions: &[Instruction], handlers: Vec<usize>) -> Vec<(usize, usize)> {
+    let mut reachable = vec![false; instructions.len()];
+    let mut pending = handlers;
+    pending.push(0);
+    while let Some(index) = pending.pop() {
+        if index >= instructions.len() || reachable[index] {
+            continue;
+        }
+        reachable[index] = true;
+        let instruction = instructions[index];
+        pending.extend(jump_target(index, instruction));
+        if !is_unconditional(instruction) {
+            pending.push(index + 1);
+        }
+    }
+
+    // Python 3.12 ends every loop with an `END_FOR` that `FOR_ITER` jumps past, which isn't
+    // worth a warning.
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    let is_unreachable = |&index: &usize| !reachable[index] && !matches!(instructions[index], Instruction::EndFor);
+    for index in (0..instructions.len()).filter(is_unreachable) {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == index => *end = index,
+            _ => ranges.push((index, index)),
+        }
+    }
+    ranges
+}
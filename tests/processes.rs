@@ -0,0 +1,17 @@
+//! Runs the `py_vm` binary itself, since a `Process` starts its child by running the
+//! executable the parent is running, which the unit tests' harness isn't.
+
+use std::process::Command;
+
+#[test]
+fn processes_give_back_what_their_target_returns() {
+    let output = Command::new(env!("CARGO_BIN_EXE_py_vm"))
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/bytecode_gen/tests/processes.json"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("0"));
+    assert_eq!(lines.next(), Some("499500"));
+}